@@ -1,19 +1,37 @@
 use std::sync::{Arc, Mutex};
+use crossbeam_channel::Receiver;
 use eframe;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 use once_cell::sync::Lazy;
 
-use crate::config::ConfigManager;
-use crate::keyboard::{KeyboardHook, KeyboardState};
+use crate::config::{ConfigManager, Settings};
+use crate::config::settings::{Hotkey, WindowGeometry};
+use crate::config::stats::{ExpansionStats, STATS_FILE_NAME};
+use crate::config::watcher::SettingsWatcher;
+use crate::keyboard::{HotkeyAction, HotkeyManager, KeyboardHook, KeyboardState, LayoutKeyTranslator};
 use crate::replacement::ReplacementEngine;
+use crate::replacement::clipboard_history::{clipboard_history, ClipboardWatcher};
+#[cfg(feature = "ipc")]
+use crate::replacement::ipc::IpcServer;
+use crate::replacement::form::{FieldFormRequest, FieldFormSender};
 use crate::ui::app_ui::{AppUi, AppUiState};
+use crate::ui::field_form::{FieldForm, FieldFormOutcome};
+use crate::ui::snippet_picker::{PickerOutcome, SnippetPicker};
+use crate::ui::overlay::ExpansionOverlay;
 use crate::ui::tray::TrayIconState;
 use crate::utils;
 
 // 競合ツール検出時の警告表示フラグ
 static SHOW_CONFLICT_WARNING: AtomicBool = AtomicBool::new(false);
-// 検出された競合ツールの名前
-static CONFLICTING_TOOL_NAMES: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(Vec::new()));
+// 検出された競合ツール
+static CONFLICTING_TOOLS_FOUND: Lazy<Mutex<Vec<utils::ConflictingTool>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// キーボードフックを使わずに起動するコマンドライン引数
+pub const NO_HOOK_ARG: &str = "--no-hook";
+
+/// 展開で変わったスニペットの使用回数を設定ファイルに保存する間隔
+const USAGE_SAVE_INTERVAL: Duration = Duration::from_secs(30);
 
 /// アプリケーション本体
 pub struct App {
@@ -21,23 +39,55 @@ pub struct App {
     ui: AppUi,
     /// トレイアイコンの状態
     tray_state: Option<TrayIconState>,
-    /// キーボードフック
-    _keyboard_hook: KeyboardHook,
+    /// キーボードフック（--no-hook で起動した場合はNone）
+    _keyboard_hook: Option<KeyboardHook>,
+    /// キーボード状態
+    keyboard_state: Arc<Mutex<KeyboardState>>,
+    /// テキスト置換エンジン
+    replacement_engine: Arc<Mutex<ReplacementEngine>>,
+    /// 入力フォームの表示要求の受信器
+    field_form_receiver: Receiver<FieldFormRequest>,
+    /// 表示中の入力フォーム
+    field_form: Option<FieldForm>,
+    /// 入力フォームを表示する前にウィンドウが表示されていたかどうか
+    window_visible_before_form: bool,
+    /// 表示中のスニペットの検索ウィンドウ
+    snippet_picker: Option<SnippetPicker>,
+    /// スニペットの検索ウィンドウを表示する前にウィンドウが表示されていたかどうか
+    window_visible_before_picker: bool,
+    /// グローバルホットキー
+    hotkey_manager: Option<HotkeyManager>,
+    /// 設定マネージャー
+    config_manager: Arc<Mutex<ConfigManager>>,
+    /// 展開で使用回数が変わり、まだ保存していないかどうか
+    usage_changed: Arc<AtomicBool>,
+    /// 最後に使用回数を保存した時刻
+    usage_saved_at: Instant,
+    /// 設定ファイルの変更の監視（手動で編集した設定を再起動せずに反映する）
+    settings_watcher: Option<SettingsWatcher>,
+    /// クリップボードの監視（{clipboard:N}で使う履歴を記録する）
+    _clipboard_watcher: ClipboardWatcher,
+    /// 外部のツールからの要求の待ち受け（設定で有効にした場合のみ）
+    #[cfg(feature = "ipc")]
+    ipc_server: Option<IpcServer>,
+    /// 待ち受けを試みたポート（待ち受けない場合はNone。開始に失敗しても毎フレーム試さないよう記録する）
+    #[cfg(feature = "ipc")]
+    ipc_port: Option<u16>,
+    /// 最後に表示していたウィンドウの位置とサイズ（終了時に保存する）
+    window_geometry: Option<WindowGeometry>,
+    /// EGUIのコンテキスト（ホットキー発生時の再描画要求用）
+    egui_ctx: egui::Context,
 }
 
 impl App {
     /// アプリケーションを初期化する
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Result<Self, Box<dyn std::error::Error>> {
-        // 競合するツールをチェック
-        let conflicting_tools = utils::check_conflicting_tools();
-        if !conflicting_tools.is_empty() {
-            // 競合するツールが見つかった場合の警告メッセージを設定
-            log::warn!("Conflicting text expansion tools found: {:?}", conflicting_tools);
-            
-            // 初期化後、最初のフレーム更新で警告を表示するためのフラグをセット
-            SHOW_CONFLICT_WARNING.store(true, std::sync::atomic::Ordering::SeqCst);
-            CONFLICTING_TOOL_NAMES.lock().unwrap().extend(conflicting_tools);
-        }
+    /// 
+    /// # 引数
+    /// * `cc` - eframeの作成コンテキスト
+    /// * `hook_enabled` - キーボードフックを開始するかどうか（falseの場合は入力を監視せず、編集画面だけを使える）
+    pub fn new(cc: &eframe::CreationContext<'_>, hook_enabled: bool) -> Result<Self, Box<dyn std::error::Error>> {
+        // 日本語のスニペット名などを表示できるようにする
+        crate::ui::install_cjk_font(&cc.egui_ctx);
         
         // 設定を読み込む
         let config_manager = Arc::new(Mutex::new(ConfigManager::new()?));
@@ -49,61 +99,625 @@ impl App {
             Arc::new(Mutex::new(settings))
         };
         
+        // 競合するツールをチェック（設定で追加したツールを含め、警告しないツールは除く）
+        let conflict_tools = settings.lock()
+            .map(|settings| utils::conflict_tool_list(&settings.conflict_tools, &settings.ignored_conflict_tools))
+            .unwrap_or_default();
+        let conflicting_tools = utils::check_conflicting_tools(&conflict_tools);
+        if !conflicting_tools.is_empty() {
+            // 競合するツールが見つかった場合の警告メッセージを設定
+            log::warn!("Conflicting text expansion tools found: {:?}", conflicting_tools);
+            
+            // 初期化後、最初のフレーム更新で警告を表示するためのフラグをセット
+            SHOW_CONFLICT_WARNING.store(true, std::sync::atomic::Ordering::SeqCst);
+            CONFLICTING_TOOLS_FOUND.lock().unwrap().extend(conflicting_tools);
+        }
+        
         // 自動起動の設定を適用
         if let Ok(settings_guard) = settings.lock() {
-            utils::set_auto_startup(settings_guard.start_with_system)?;
+            utils::set_auto_startup(settings_guard.start_with_system, settings_guard.auto_start_method)?;
         }
         
         // キーボード状態を作成
-        let keyboard_state = Arc::new(Mutex::new(KeyboardState::new(100)));
+        let buffer_size = settings.lock().map(|s| s.effective_buffer_size()).unwrap_or(32);
+        let mut keyboard_state = KeyboardState::new(buffer_size);
+        keyboard_state.set_ignore_shortcut_keys(settings.lock().map(|s| s.ignore_shortcut_keys).unwrap_or(true));
+        keyboard_state.set_newline_terminator(settings.lock().map(|s| s.newline_terminates_keyword()).unwrap_or(false));
+        keyboard_state.set_key_translator(Box::new(LayoutKeyTranslator));
+        let keyboard_state = Arc::new(Mutex::new(keyboard_state));
         
         // 置換エンジンを作成
-        let replacement_engine = Arc::new(Mutex::new(ReplacementEngine::new(Arc::clone(&settings))));
+        let mut engine = ReplacementEngine::new(Arc::clone(&settings));
+        let usage_changed = engine.usage_changed_flag();
+        
+        // 展開の記録を読み込む（保持する日数より古い記録は削除する）
+        let stats_path = config_manager.lock().unwrap().config_path().with_file_name(STATS_FILE_NAME);
+        let expansion_stats = ExpansionStats::load(&stats_path, chrono::Local::now());
+        engine.set_expansion_stats(Arc::new(Mutex::new(expansion_stats)));
+        
+        // 展開時のオーバーレイを作成（作成できなくても展開はできるため続行する）
+        match ExpansionOverlay::new() {
+            Ok(overlay) => engine.set_overlay(overlay),
+            Err(e) => log::warn!("Failed to create expansion overlay: {}", e),
+        }
+        
+        // 入力フィールドを含むスニペットの展開時に、UIへフォームの表示を要求する
+        let (field_form_sender, field_form_receiver) = crossbeam_channel::unbounded();
+        let repaint_ctx = cc.egui_ctx.clone();
+        engine.set_field_form_sender(FieldFormSender::new(field_form_sender, move || repaint_ctx.request_repaint()));
+        let replacement_engine = Arc::new(Mutex::new(engine));
+        
+        // 置換はキーボードフックを止めないようワーカースレッドで実行する
+        ReplacementEngine::start_worker(&replacement_engine);
+        
+        // クリップボードの履歴を記録する（置換中の一時的な貼り付けは記録しない）
+        let busy_flag = replacement_engine.lock().ok().and_then(|engine| engine.worker_busy_flag());
+        let clipboard_watcher = ClipboardWatcher::start(clipboard_history(), busy_flag);
         
         // UI状態を作成
-        let ui_state = AppUiState::new(
+        let mut ui_state = AppUiState::new(
             Arc::clone(&config_manager),
             Arc::clone(&settings),
             Arc::clone(&keyboard_state),
             Arc::clone(&replacement_engine),
         );
+        ui_state.hook_enabled = hook_enabled;
         let ui = AppUi::new(ui_state);
         
-        // キーボードフックを作成
-        let keyboard_hook = KeyboardHook::new(
-            Arc::clone(&keyboard_state),
-            Arc::clone(&replacement_engine),
-        )?;
-        
-        // キーボードフックを開始
-        keyboard_hook.start()?;
+        // キーボードフックを作成して開始（無効にした場合は入力を監視しない）
+        let keyboard_hook = if hook_enabled {
+            let keyboard_hook = KeyboardHook::new(
+                Arc::clone(&keyboard_state),
+                Arc::clone(&replacement_engine),
+            )?;
+            keyboard_hook.start()?;
+            Some(keyboard_hook)
+        } else {
+            None
+        };
         
         // トレイアイコンを作成
-        let tray_state = TrayIconState::new(Arc::clone(&settings)).ok();
+        let tray_state = TrayIconState::new(Arc::clone(&settings), Arc::clone(&replacement_engine)).ok();
+        
+        // グローバルホットキーを登録
+        let hotkey_manager = match settings.lock() {
+            Ok(settings_guard) => Self::create_hotkey_manager(&settings_guard, &cc.egui_ctx),
+            Err(_) => None,
+        };
+        
+        // 設定ファイルの変更を監視（監視できなくても動作には影響しないため続行する）
+        let config_path = config_manager.lock().unwrap().config_path().to_path_buf();
+        let repaint_ctx = cc.egui_ctx.clone();
+        let settings_watcher = match SettingsWatcher::start(&config_path, move || repaint_ctx.request_repaint()) {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                log::warn!("Failed to watch settings file: {}", e);
+                None
+            }
+        };
         
         Ok(Self {
             ui,
             tray_state,
             _keyboard_hook: keyboard_hook,
+            keyboard_state,
+            replacement_engine,
+            field_form_receiver,
+            field_form: None,
+            window_visible_before_form: false,
+            snippet_picker: None,
+            window_visible_before_picker: false,
+            hotkey_manager,
+            config_manager,
+            usage_changed,
+            usage_saved_at: Instant::now(),
+            settings_watcher,
+            _clipboard_watcher: clipboard_watcher,
+            #[cfg(feature = "ipc")]
+            ipc_server: None,
+            #[cfg(feature = "ipc")]
+            ipc_port: None,
+            window_geometry: None,
+            egui_ctx: cc.egui_ctx.clone(),
         })
     }
     
+    /// 設定からホットキーの割り当てを取得する
+    fn hotkey_bindings(settings: &Settings) -> Vec<(HotkeyAction, Hotkey)> {
+        let mut bindings = Vec::new();
+        if let Some(hotkey) = settings.toggle_hotkey {
+            bindings.push((HotkeyAction::ToggleEnabled, hotkey));
+        }
+        if let Some(hotkey) = settings.open_window_hotkey {
+            bindings.push((HotkeyAction::ShowWindow, hotkey));
+        }
+        if let Some(hotkey) = settings.capture_hotkey {
+            bindings.push((HotkeyAction::CaptureSelection, hotkey));
+        }
+        if let Some(hotkey) = settings.undo_hotkey {
+            bindings.push((HotkeyAction::UndoExpansion, hotkey));
+        }
+        if let Some(hotkey) = settings.picker_hotkey {
+            bindings.push((HotkeyAction::OpenPicker, hotkey));
+        }
+        bindings
+    }
+    
+    /// 設定に従ってグローバルホットキーを登録する
+    fn create_hotkey_manager(settings: &Settings, ctx: &egui::Context) -> Option<HotkeyManager> {
+        let bindings = Self::hotkey_bindings(settings);
+        if bindings.is_empty() {
+            return None;
+        }
+        
+        let ctx = ctx.clone();
+        match HotkeyManager::new(bindings, move || ctx.request_repaint()) {
+            Ok(manager) => Some(manager),
+            Err(e) => {
+                log::error!("Failed to start hotkey manager: {}", e);
+                None
+            }
+        }
+    }
+    
+    /// ホットキーの設定が変更されていれば登録し直す
+    fn sync_hotkeys(&mut self) {
+        let bindings = match self.ui.settings().lock() {
+            Ok(settings) => Self::hotkey_bindings(&settings),
+            Err(_) => return,
+        };
+        
+        let registered = self.hotkey_manager.as_ref().map(|manager| manager.bindings()).unwrap_or(&[]);
+        if registered != bindings.as_slice() {
+            log::debug!("Hotkey settings changed, re-registering hotkeys");
+            // 古い登録を先に解除してから登録し直す
+            self.hotkey_manager = None;
+            if let Ok(settings) = self.ui.settings().lock() {
+                self.hotkey_manager = Self::create_hotkey_manager(&settings, &self.egui_ctx);
+            }
+        }
+    }
+    
+    /// 外部のツールからの要求の待ち受けの設定が変更されていれば、待ち受けを開始または停止する
+    #[cfg(feature = "ipc")]
+    fn sync_ipc_server(&mut self) {
+        let port = match self.ui.settings().lock() {
+            Ok(settings) => settings.enable_ipc.then_some(settings.ipc_port),
+            Err(_) => return,
+        };
+        if port == self.ipc_port {
+            return;
+        }
+        self.ipc_port = port;
+        
+        // 古い待ち受けを先に止めてから開始し直す
+        if let Some(server) = self.ipc_server.take() {
+            log::info!("Stopped listening for snippet requests on port {}", server.port());
+        }
+        let Some(port) = port else {
+            return;
+        };
+        
+        // トークンのファイルは設定ファイルと同じフォルダに作成する
+        let token_dir = self.config_manager.lock().ok()
+            .and_then(|config_manager| config_manager.config_path().parent().map(|dir| dir.to_path_buf()));
+        let Some(token_dir) = token_dir else {
+            log::error!("Failed to find the folder for the IPC token");
+            return;
+        };
+        match IpcServer::start(Arc::downgrade(&self.replacement_engine), port, &token_dir) {
+            Ok(server) => self.ipc_server = Some(server),
+            Err(e) => log::error!("Failed to listen for snippet requests on port {}: {}", port, e),
+        }
+    }
+    
+    /// バッファのサイズやショートカット・改行の入力の扱いの設定が変更されていればキーボード状態に反映する
+    fn sync_keyboard_settings(&mut self) {
+        let (buffer_size, ignore_shortcut_keys, newline_terminator) = match self.ui.settings().lock() {
+            Ok(settings) => (settings.effective_buffer_size(), settings.ignore_shortcut_keys, settings.newline_terminates_keyword()),
+            Err(_) => return,
+        };
+        
+        if let Ok(mut state) = self.keyboard_state.lock() {
+            if state.buffer_size() != buffer_size {
+                state.set_buffer_size(buffer_size);
+            }
+            if state.ignores_shortcut_keys() != ignore_shortcut_keys {
+                state.set_ignore_shortcut_keys(ignore_shortcut_keys);
+            }
+            if state.is_newline_terminator() != newline_terminator {
+                state.set_newline_terminator(newline_terminator);
+            }
+        }
+    }
+    
+    /// 発生したホットキーを処理する
+    fn process_hotkeys(&mut self, frame: &mut eframe::Frame) {
+        while let Some(action) = self.hotkey_manager.as_ref().and_then(|manager| manager.try_recv()) {
+            match action {
+                HotkeyAction::ToggleEnabled => self.toggle_enabled(),
+                HotkeyAction::ShowWindow => self.show_window(frame),
+                HotkeyAction::CaptureSelection => self.capture_selection_to_editor(frame),
+                HotkeyAction::UndoExpansion => self.undo_last_expansion(),
+                HotkeyAction::OpenPicker => self.open_snippet_picker(frame),
+            }
+        }
+    }
+    
+    /// 展開の有効と無効を切り替える
+    fn toggle_enabled(&mut self) {
+        let enabled = match self.ui.settings().lock() {
+            Ok(settings) => settings.enabled,
+            Err(_) => return,
+        };
+        self.ui.set_enabled(!enabled);
+    }
+    
+    /// 競合ツールを警告しないツールの一覧に加え、警告から取り除く
+    /// 
+    /// # 引数
+    /// * `name` - 競合ツールのプロセス名
+    fn ignore_conflicting_tool(&mut self, name: &str) {
+        if let Ok(mut settings) = self.ui.settings().lock() {
+            if !utils::app_list_contains(&settings.ignored_conflict_tools, name) {
+                settings.ignored_conflict_tools.push(name.to_string());
+            }
+            
+            // 設定のロックを解放して保存
+            drop(settings);
+            
+            if let Ok(mut config_manager) = self.config_manager.lock() {
+                if let Ok(settings) = self.ui.settings().lock() {
+                    let _ = config_manager.update_settings(settings.clone());
+                }
+            }
+        }
+        log::info!("Conflicting tool '{}' will no longer be reported", name);
+        
+        if let Ok(mut conflicting_tools) = CONFLICTING_TOOLS_FOUND.lock() {
+            conflicting_tools.retain(|tool| !tool.name.eq_ignore_ascii_case(name));
+            if conflicting_tools.is_empty() {
+                SHOW_CONFLICT_WARNING.store(false, Ordering::SeqCst);
+            }
+        }
+    }
+    
+    /// 表示中のウィンドウの位置とサイズを記録する
+    /// 
+    /// 最小化・最大化している間は、元に戻したときの位置を保存するため記録しない
+    fn track_window_geometry(&mut self, ctx: &egui::Context, frame: &eframe::Frame) {
+        let window_info = frame.info().window_info;
+        if window_info.minimized || window_info.maximized || window_info.fullscreen {
+            return;
+        }
+        let Some(position) = window_info.position else {
+            return;
+        };
+        
+        // UIスケールを変えても同じ大きさで開くよう、物理ピクセルで記録する
+        let pixels_per_point = ctx.pixels_per_point();
+        self.window_geometry = Some(WindowGeometry {
+            x: position.x * pixels_per_point,
+            y: position.y * pixels_per_point,
+            width: window_info.size.x * pixels_per_point,
+            height: window_info.size.y * pixels_per_point,
+        });
+    }
+    
+    /// 記録したウィンドウの位置とサイズを設定に保存する
+    fn save_window_geometry(&mut self) {
+        let Some(geometry) = self.window_geometry else {
+            return;
+        };
+        
+        if let Ok(mut settings) = self.ui.settings().lock() {
+            if settings.window_geometry == Some(geometry) {
+                return;
+            }
+            settings.window_geometry = Some(geometry);
+            
+            // 設定のロックを解放して保存
+            drop(settings);
+            
+            if let Ok(mut config_manager) = self.config_manager.lock() {
+                if let Ok(settings) = self.ui.settings().lock() {
+                    let _ = config_manager.update_settings(settings.clone());
+                }
+            }
+        }
+    }
+    
+    /// 展開で変わったスニペットの使用回数を、一定の間隔でまとめて設定ファイルに保存する
+    /// 
+    /// 外部で変更された設定ファイルを反映した後にUIスレッドで保存する
+    /// （キーボードフックやワーカースレッドからは設定ファイルを書き込まない）
+    /// 
+    /// # 引数
+    /// * `force` - 間隔を待たずに保存するかどうか
+    fn save_usage_counts(&mut self, force: bool) {
+        if !self.usage_changed.load(Ordering::SeqCst) {
+            return;
+        }
+        if !force && self.usage_saved_at.elapsed() < USAGE_SAVE_INTERVAL {
+            // 操作がなくても保存されるよう、間隔が過ぎたら描画し直す
+            self.egui_ctx.request_repaint_after(USAGE_SAVE_INTERVAL - self.usage_saved_at.elapsed());
+            return;
+        }
+        
+        self.usage_changed.store(false, Ordering::SeqCst);
+        self.usage_saved_at = Instant::now();
+        if let Ok(mut config_manager) = self.config_manager.lock() {
+            if let Ok(settings) = self.ui.settings().lock() {
+                if let Err(e) = config_manager.update_settings(settings.clone()) {
+                    log::error!("Failed to save snippet usage counts: {}", e);
+                }
+            }
+        }
+    }
+    
+    /// 直前の展開を取り消す
+    fn undo_last_expansion(&mut self) {
+        // 取り消し前に入力したキーワードと、入力し直すキーワードが連続して一致しないようにバッファをクリアする
+        if let Ok(mut state) = self.keyboard_state.lock() {
+            state.keyword_replaced();
+        }
+        
+        // キー入力の送信には時間がかかるため、UIを止めないようワーカースレッドで実行する
+        // （実行中に入力されたキーは、キーボードフックがエンジンを待たずに捨てる）
+        if let Ok(engine) = self.replacement_engine.lock() {
+            if !engine.queue_undo() {
+                log::info!("Undo was not started because another expansion is in progress");
+            }
+        }
+    }
+    
+    /// 選択中のテキストから新しいスニペットを作成してエディタを開く
+    fn capture_selection_to_editor(&mut self, frame: &mut eframe::Frame) {
+        // ウィンドウを前面に出す前に、前面のアプリケーションで選択中のテキストを取得する
+        let selection = self.replacement_engine
+            .lock()
+            .ok()
+            .and_then(|engine| engine.capture_selection());
+        
+        match selection {
+            Some(text) => {
+                self.ui.open_editor_with_text(&text);
+                self.show_window(frame);
+            }
+            None => {
+                log::info!("No text selected, snippet was not created");
+            }
+        }
+    }
+    
+    /// 入力フォームの表示要求を受け取り、表示中のフォームを処理する
+    fn process_field_forms(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        if self.field_form.is_none() {
+            if let Ok(request) = self.field_form_receiver.try_recv() {
+                self.window_visible_before_form = self.is_window_visible();
+                self.field_form = Some(FieldForm::new(request));
+                self.show_window(frame);
+            }
+        }
+        
+        let Some(outcome) = self.field_form.as_mut().and_then(|form| form.show(ctx)) else {
+            return;
+        };
+        let Some(form) = self.field_form.take() else {
+            return;
+        };
+        
+        // フォームへの入力中にたまった入力バッファをクリアする
+        if let Ok(mut state) = self.keyboard_state.lock() {
+            state.keyword_replaced();
+        }
+        
+        // フォームを表示する前にウィンドウが隠れていた場合は元に戻す
+        if !self.window_visible_before_form {
+            self.hide_window(frame);
+        }
+        
+        match outcome {
+            FieldFormOutcome::Submit(values) => {
+                // キー入力の送信には時間がかかるため、UIを止めないようワーカースレッドで展開する
+                if let Ok(engine) = self.replacement_engine.lock() {
+                    if !engine.complete_field_form(form.request(), &values) {
+                        log::warn!("Expansion of '{}' was not started", form.request().replacement.snippet_name);
+                    }
+                }
+            }
+            FieldFormOutcome::Cancel => {
+                log::info!("Field form for '{}' was cancelled", form.request().replacement.snippet_name);
+            }
+        }
+    }
+    
+    /// スニペットの検索ウィンドウを開く
+    /// 
+    /// 選んだスニペットを挿入するため、開く前に前面にあったウィンドウを記録する
+    fn open_snippet_picker(&mut self, frame: &mut eframe::Frame) {
+        if self.snippet_picker.is_some() {
+            self.show_window(frame);
+            return;
+        }
+        
+        let target_window = utils::foreground_window();
+        self.window_visible_before_picker = self.is_window_visible();
+        self.snippet_picker = Some(SnippetPicker::new(target_window));
+        self.show_window(frame);
+        frame.set_always_on_top(true);
+    }
+    
+    /// 表示中のスニペットの検索ウィンドウを処理する
+    fn process_snippet_picker(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let Some(picker) = self.snippet_picker.as_mut() else {
+            return;
+        };
+        let outcome = match self.ui.settings().lock() {
+            Ok(settings) => picker.show(ctx, &settings.snippets),
+            Err(_) => Some(PickerOutcome::Cancel),
+        };
+        let Some(outcome) = outcome else {
+            return;
+        };
+        let Some(picker) = self.snippet_picker.take() else {
+            return;
+        };
+        
+        // 検索語の入力中にたまった入力バッファをクリアする
+        if let Ok(mut state) = self.keyboard_state.lock() {
+            state.keyword_replaced();
+        }
+        
+        // 検索ウィンドウを表示する前の状態に戻す
+        frame.set_always_on_top(false);
+        if !self.window_visible_before_picker {
+            self.hide_window(frame);
+        }
+        
+        match outcome {
+            PickerOutcome::Insert(snippet_id) => {
+                // キー入力の送信には時間がかかるため、UIを止めないようワーカースレッドで挿入する
+                if let Ok(engine) = self.replacement_engine.lock() {
+                    if !engine.insert_snippet_into(snippet_id, picker.target_window()) {
+                        log::warn!("Snippet {} was not inserted", snippet_id);
+                    }
+                }
+            }
+            PickerOutcome::Cancel => {
+                log::debug!("Snippet picker was cancelled");
+            }
+        }
+    }
+    
+    /// ウィンドウが表示されているかどうか
+    fn is_window_visible(&self) -> bool {
+        match &self.tray_state {
+            Some(tray_state) => tray_state.show_window.lock().map(|show| *show).unwrap_or(true),
+            None => true,
+        }
+    }
+    
+    /// 閉じるボタンでウィンドウをトレイに隠すかどうか
+    /// 
+    /// トレイアイコンを作成できなかった場合や、トレイメニューから終了する場合は終了する
+    fn should_close_to_tray(&self) -> bool {
+        let Some(tray_state) = &self.tray_state else {
+            return false;
+        };
+        if utils::check_should_exit(&tray_state.should_exit) {
+            return false;
+        }
+        self.ui.settings().lock().map(|settings| settings.close_to_tray).unwrap_or(false)
+    }
+    
+    /// ウィンドウを隠す
+    fn hide_window(&mut self, frame: &mut eframe::Frame) {
+        if let Some(tray_state) = &self.tray_state {
+            if let Ok(mut show_window) = tray_state.show_window.lock() {
+                *show_window = false;
+            }
+        }
+        
+        frame.set_visible(false);
+    }
+    
+    /// ウィンドウを表示して前面に出す
+    fn show_window(&mut self, frame: &mut eframe::Frame) {
+        if let Some(tray_state) = &self.tray_state {
+            if let Ok(mut show_window) = tray_state.show_window.lock() {
+                *show_window = true;
+            }
+        }
+        
+        frame.set_visible(true);
+        frame.focus();
+    }
+    
+    /// 設定ファイルが外部で変更されていれば、読み込み直して反映する
+    fn process_settings_reload(&mut self) {
+        if !self.settings_watcher.as_ref().is_some_and(|watcher| watcher.try_recv()) {
+            return;
+        }
+        
+        let result = match self.config_manager.lock() {
+            Ok(mut config_manager) => config_manager.reload_from_disk(),
+            Err(_) => return,
+        };
+        
+        match result {
+            Ok(Some(new_settings)) => {
+                self.ui.apply_loaded_settings(new_settings);
+                self.ui.show_toast("Settings reloaded from disk");
+            }
+            Ok(None) => {}
+            Err(e) => {
+                log::warn!("Failed to reload settings: {}", e);
+                self.ui.show_toast(format!("Failed to reload settings: {}", e));
+            }
+        }
+    }
+    
     /// 自動起動の設定を更新する
     #[allow(dead_code)]
     pub fn update_auto_startup(&self) -> Result<(), Box<dyn std::error::Error>> {
         if let Ok(settings) = self.ui.settings().lock() {
-            utils::set_auto_startup(settings.start_with_system)?;
+            utils::set_auto_startup(settings.start_with_system, settings.auto_start_method)?;
         }
         Ok(())
     }
 }
 
 impl eframe::App for App {
+    /// ウィンドウを閉じる前に、位置とサイズを保存する
+    /// 
+    /// トレイに隠す設定の場合は終了せずにウィンドウを隠す（トレイメニューの終了だけが実際に終了する）
+    fn on_close_event(&mut self) -> bool {
+        self.save_window_geometry();
+        self.save_usage_counts(true);
+        
+        if self.should_close_to_tray() {
+            log::debug!("Window closed, minimizing to the tray");
+            if let Some(tray_state) = &self.tray_state {
+                if let Ok(mut show_window) = tray_state.show_window.lock() {
+                    *show_window = false;
+                }
+            }
+            // 次のフレームでウィンドウを隠す
+            self.egui_ctx.request_repaint();
+            return false;
+        }
+        
+        true
+    }
+    
     /// フレームを更新する
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        // 外部で変更された設定ファイルを反映してから、展開で変わった使用回数を保存
+        self.process_settings_reload();
+        self.save_usage_counts(false);
+        
+        // グローバルホットキーを処理
+        self.sync_hotkeys();
+        self.process_hotkeys(frame);
+        
+        // キー入力のバッファのサイズとショートカットの入力の扱いを反映
+        self.sync_keyboard_settings();
+        
+        // 外部のツールからの要求の待ち受けを反映
+        #[cfg(feature = "ipc")]
+        self.sync_ipc_server();
+        
+        // 入力フィールドのフォームを処理
+        self.process_field_forms(ctx, frame);
+        
+        // スニペットの検索ウィンドウを処理
+        self.process_snippet_picker(ctx, frame);
+        
         // 競合ツールの警告を表示
         if SHOW_CONFLICT_WARNING.load(Ordering::SeqCst) {
-            if let Ok(conflicting_tools) = CONFLICTING_TOOL_NAMES.lock() {
+            let mut dismissed_tool = None;
+            if let Ok(conflicting_tools) = CONFLICTING_TOOLS_FOUND.lock() {
                 if !conflicting_tools.is_empty() {
                     // 警告ダイアログを表示
                     egui::Window::new("Warning: Conflicting Tools Detected")
@@ -115,7 +729,12 @@ impl eframe::App for App {
                             ui.spacing();
                             
                             for tool in conflicting_tools.iter() {
-                                ui.label(format!("• {}", tool));
+                                ui.horizontal(|ui| {
+                                    ui.label(format!("• {} (PID {})", tool.name, tool.pid));
+                                    if ui.small_button("Don't warn again").clicked() {
+                                        dismissed_tool = Some(tool.name.clone());
+                                    }
+                                });
                             }
                             
                             ui.spacing();
@@ -130,11 +749,20 @@ impl eframe::App for App {
                         });
                 }
             }
+            
+            if let Some(tool) = dismissed_tool {
+                self.ignore_conflicting_tool(&tool);
+            }
         }
         
+        // 終了時に保存するため、ウィンドウの位置とサイズを記録
+        self.track_window_geometry(ctx, frame);
+        
         // トレイアイコンのイベントを処理
         if let Some(tray_state) = &mut self.tray_state {
-            tray_state.process_events();
+            if let Some(enabled) = tray_state.process_events() {
+                self.ui.set_enabled(enabled);
+            }
             
             // 終了フラグをチェック
             if utils::check_should_exit(&tray_state.should_exit) {
@@ -146,6 +774,8 @@ impl eframe::App for App {
             if let Ok(show_window) = tray_state.show_window.lock() {
                 if !*show_window {
                     frame.set_visible(false);
+                    // 隠している間もトレイメニューとホットキーを処理し続ける
+                    ctx.request_repaint_after(std::time::Duration::from_secs(1));
                     return;
                 } else {
                     frame.set_visible(true);
@@ -156,6 +786,11 @@ impl eframe::App for App {
         // UIを更新
         self.ui.update(ctx);
         
+        // 展開を試す前に最小化し、直前に使っていたウィンドウへフォーカスを戻す
+        if self.ui.take_minimize_request() {
+            frame.set_minimized(true);
+        }
+        
         // 自動再描画を設定
         ctx.request_repaint_after(std::time::Duration::from_secs(1));
     }
@@ -0,0 +1,157 @@
+/// AutoHotkeyのホットストリング
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hotstring {
+    /// 置換のきっかけとなる文字列
+    pub trigger: String,
+    /// 置換後のテキスト
+    pub replacement: String,
+    /// 単語の途中でも展開するかどうか（`?` オプション）
+    pub inside_word: bool,
+}
+
+/// AutoHotkeyのスクリプトからホットストリングを読み取る
+/// 
+/// `::btw::by the way` や `:*:btw::by the way` のような行を読み取る。
+/// `?` 以外のオプションは無視し、置換後にコマンドを実行する `X` オプションの行は読み飛ばす。
+/// 置換後のテキストが空の行は、続く `(` から `)` までの行を複数行のテキストとして扱う。
+/// 
+/// # 引数
+/// * `content` - スクリプトの内容
+/// 
+/// # 戻り値
+/// 読み取ったホットストリング（スクリプト内の順序）
+pub fn parse_hotstrings(content: &str) -> Vec<Hotstring> {
+    let mut hotstrings = Vec::new();
+    let mut lines = content.trim_start_matches('\u{feff}').lines();
+    
+    while let Some(line) = lines.next() {
+        let Some((options, trigger, replacement)) = split_hotstring(line.trim_start()) else {
+            continue;
+        };
+        
+        let options = options.to_lowercase();
+        if options.contains('x') {
+            log::debug!("Skipped AutoHotkey hotstring that runs code: '{}'", trigger);
+            continue;
+        }
+        
+        let replacement = if replacement.trim().is_empty() {
+            read_continuation_section(&mut lines)
+        } else {
+            unescape(strip_comment(replacement))
+        };
+        
+        hotstrings.push(Hotstring {
+            trigger: trigger.to_string(),
+            replacement,
+            inside_word: options.contains('?'),
+        });
+    }
+    
+    hotstrings
+}
+
+/// `:オプション:トリガー::置換後` の形式の行をオプション、トリガー、置換後に分ける
+fn split_hotstring(line: &str) -> Option<(&str, &str, &str)> {
+    let rest = line.strip_prefix(':')?;
+    let options_end = rest.find(':')?;
+    let (options, rest) = (&rest[..options_end], &rest[options_end + 1..]);
+    
+    let trigger_end = rest.find("::")?;
+    let trigger = &rest[..trigger_end];
+    if trigger.is_empty() {
+        return None;
+    }
+    
+    Some((options, trigger, &rest[trigger_end + 2..]))
+}
+
+/// `(` から `)` までの複数行のテキストを読み取る
+fn read_continuation_section<'a>(lines: &mut impl Iterator<Item = &'a str>) -> String {
+    let mut section = Vec::new();
+    let mut started = false;
+    
+    for line in lines {
+        let trimmed = line.trim();
+        if !started {
+            if !trimmed.starts_with('(') {
+                break;
+            }
+            started = true;
+            continue;
+        }
+        if trimmed.starts_with(')') {
+            break;
+        }
+        section.push(unescape(line));
+    }
+    
+    section.join("\n")
+}
+
+/// 空白の後の `;` から始まるコメントを取り除く
+fn strip_comment(text: &str) -> &str {
+    let bytes = text.as_bytes();
+    let comment_start = (1..bytes.len())
+        .find(|&i| bytes[i] == b';' && (bytes[i - 1] == b' ' || bytes[i - 1] == b'\t'));
+    
+    match comment_start {
+        Some(i) => text[..i].trim_end(),
+        None => text,
+    }
+}
+
+/// AutoHotkeyのエスケープ文字（`` `n `` など）を変換する
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    
+    while let Some(c) = chars.next() {
+        if c != '`' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some(other) => result.push(other),
+            None => result.push('`'),
+        }
+    }
+    
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_basic_hotstrings() {
+        let script = "\u{feff}; My hotstrings\n::btw::by the way\n:*:afaik::as far as I know ; common\n:?o:ing::ING\n";
+        let hotstrings = parse_hotstrings(script);
+        
+        assert_eq!(hotstrings, vec![
+            Hotstring { trigger: "btw".to_string(), replacement: "by the way".to_string(), inside_word: false },
+            Hotstring { trigger: "afaik".to_string(), replacement: "as far as I know".to_string(), inside_word: false },
+            Hotstring { trigger: "ing".to_string(), replacement: "ING".to_string(), inside_word: true },
+        ]);
+    }
+    
+    #[test]
+    fn test_parse_escapes_and_sections() {
+        let script = "::addr::Line 1`nLine 2 `; not a comment\n::sig::\n(\nBest regards,\n  Taro\n)\n::after::ok\n";
+        let hotstrings = parse_hotstrings(script);
+        
+        assert_eq!(hotstrings.len(), 3);
+        assert_eq!(hotstrings[0].replacement, "Line 1\nLine 2 ; not a comment");
+        assert_eq!(hotstrings[1].replacement, "Best regards,\n  Taro");
+        assert_eq!(hotstrings[2].trigger, "after");
+    }
+    
+    #[test]
+    fn test_parse_skips_other_lines() {
+        let script = "#Hotstring EndChars -()\n^j::Send, hello\n:X:run::MsgBox hi\n::::nothing\nplain text\n";
+        assert!(parse_hotstrings(script).is_empty());
+    }
+}
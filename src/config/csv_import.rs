@@ -0,0 +1,92 @@
+use std::io::Read;
+
+use serde::Deserialize;
+
+use super::settings::{Snippet, SnippetType, DEFAULT_CATEGORY};
+
+/// CSVファイルの1行
+/// 
+/// 列は見出し行の名前で判定するため、順序は問わず、足りない列は空として扱う
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CsvRow {
+    name: String,
+    keyword: String,
+    content: String,
+    category: String,
+    #[serde(rename = "type")]
+    snippet_type: String,
+}
+
+/// `name,keyword,content,category,type` の見出し行を持つCSVからスニペットを読み取る
+/// 
+/// 本文はRFC 4180に従ってダブルクォートで囲むことで改行を含められる。
+/// 種類の列がないか、`Static` と `Dynamic` 以外の場合は静的なスニペットとして扱う。
+/// キーワードの検証は行わないため、空のキーワードも含めて読み取った順に返す。
+/// 
+/// # 引数
+/// * `reader` - CSVの内容
+/// 
+/// # 戻り値
+/// 読み取ったスニペット（CSVの形式が不正な場合はエラー）
+pub fn parse_snippets<R: Read>(reader: R) -> Result<Vec<Snippet>, csv::Error> {
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::Headers)
+        .flexible(true)
+        .from_reader(reader);
+    
+    let mut snippets = Vec::new();
+    for row in csv_reader.deserialize() {
+        let row: CsvRow = row?;
+        
+        let snippet_type = match row.snippet_type.trim().to_lowercase().as_str() {
+            "dynamic" => SnippetType::Dynamic,
+            _ => SnippetType::Static,
+        };
+        let keyword = row.keyword.trim().to_string();
+        let name = match row.name.trim() {
+            "" => keyword.clone(),
+            name => name.to_string(),
+        };
+        let category = match row.category.trim() {
+            "" => DEFAULT_CATEGORY.to_string(),
+            category => category.to_string(),
+        };
+        
+        snippets.push(Snippet::new(name, keyword, row.content, snippet_type, category));
+    }
+    
+    Ok(snippets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_quoted_newlines_and_types() {
+        let csv = "name,keyword,content,category,type\n\
+                   Signature,sig,\"Best regards,\nTaro\",Templates,static\n\
+                   Today,tdy,yyyy/MM/dd,Date,Dynamic\n\
+                   Odd,odd,text,,macro\n";
+        let snippets = parse_snippets(csv.as_bytes()).unwrap();
+        
+        assert_eq!(snippets.len(), 3);
+        assert_eq!(snippets[0].content, "Best regards,\nTaro");
+        assert_eq!(snippets[0].snippet_type, SnippetType::Static);
+        assert_eq!(snippets[1].snippet_type, SnippetType::Dynamic);
+        assert_eq!(snippets[2].snippet_type, SnippetType::Static);
+        assert_eq!(snippets[2].category, DEFAULT_CATEGORY);
+    }
+    
+    #[test]
+    fn test_parse_missing_columns() {
+        let csv = "keyword , content\nbtw,by the way\n,no keyword\n";
+        let snippets = parse_snippets(csv.as_bytes()).unwrap();
+        
+        assert_eq!(snippets.len(), 2);
+        assert_eq!(snippets[0].name, "btw");
+        assert_eq!(snippets[0].snippet_type, SnippetType::Static);
+        assert_eq!(snippets[1].keyword, "");
+    }
+}
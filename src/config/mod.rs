@@ -1,8 +1,29 @@
 pub mod settings;
+pub mod ahk;
+pub mod csv_import;
+pub mod stats;
+pub mod symbols;
+pub mod watcher;
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub use settings::Settings;
+use settings::{Snippet, SnippetType, DEFAULT_PROFILE};
+use crate::replacement::keystrokes::parse_keystrokes;
+
+/// AutoHotkeyのホットストリングから作成したスニペットのカテゴリ
+pub const AHK_IMPORT_CATEGORY: &str = "AutoHotkey";
+
+/// スニペットの読み込み結果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// 追加したスニペットの数
+    pub imported: usize,
+    /// キーワードが既存のスニペットと重複したため追加しなかった数
+    pub skipped_duplicates: usize,
+    /// キーワードが不正なため追加しなかった数
+    pub skipped_invalid: usize,
+}
 
 /// アプリケーションの設定を管理する構造体
 #[derive(Debug, Clone)]
@@ -17,43 +38,22 @@ impl ConfigManager {
         let config_dir = Self::get_config_dir()?;
         std::fs::create_dir_all(&config_dir)?;
         
-        let config_path = config_dir.join("settings.json");
+        Self::with_path(config_dir.join("settings.json"))
+    }
+    
+    /// 指定した設定ファイルを使う設定マネージャーを作成する
+    /// 
+    /// ファイルが存在しない場合はデフォルト設定で作成する
+    /// 
+    /// # 引数
+    /// * `config_path` - 設定ファイルのパス
+    pub fn with_path(config_path: PathBuf) -> Result<Self, Box<dyn std::error::Error>> {
         let settings = if config_path.exists() {
             let content = std::fs::read_to_string(&config_path)?;
-            let mut loaded_settings: Settings = serde_json::from_str(&content)?;
-            
-            // 既存の日本語タイトルやカテゴリを英語に変換
-            for snippet in &mut loaded_settings.snippets {
-                // 日本語タイトルを英語に変換
-                match snippet.name.as_str() {
-                    "今日の日付 (YYYY/MM/DD)" => snippet.name = "Today's Date (YYYY/MM/DD)".to_string(),
-                    "今日の日付 (YYYYMMDD)" => snippet.name = "Today's Date (YYYYMMDD)".to_string(),
-                    "現在時刻" => snippet.name = "Current Time".to_string(),
-                    "タイムスタンプ" => snippet.name = "Timestamp".to_string(),
-                    _ => {}
-                }
-                
-                // 日本語カテゴリを英語に変換
-                match snippet.category.as_str() {
-                    "日付" => snippet.category = "Date".to_string(),
-                    "時間" => snippet.category = "Time".to_string(),
-                    "テンプレート" => snippet.category = "Templates".to_string(),
-                    _ => {}
-                }
-                
-                // 特殊文字を含むキーワードを安全な形式に変換
-                if snippet.keyword.contains('=') || snippet.keyword.contains(';') || snippet.keyword.contains(',') {
-                    let original = snippet.keyword.clone();
-                    snippet.keyword = snippet.keyword.replace('=', "_")
-                                              .replace(';', "_")
-                                              .replace(',', "_");
-                    log::info!("Sanitized keyword from '{}' to '{}'", original, snippet.keyword);
-                }
-            }
-            
-            loaded_settings
+            Self::parse_settings(&content)?
         } else {
-            let default_settings = Settings::default();
+            let mut default_settings = Settings::default();
+            Self::assign_snippet_ids(&mut default_settings);
             let serialized = serde_json::to_string_pretty(&default_settings)?;
             std::fs::write(&config_path, serialized)?;
             default_settings
@@ -65,28 +65,534 @@ impl ConfigManager {
         })
     }
     
+    /// JSON文字列から設定を読み込み、古い形式の設定を変換する
+    /// 
+    /// # 引数
+    /// * `json` - 設定ファイルの内容
+    /// 
+    /// # 戻り値
+    /// 読み込んだ設定（不正なJSONの場合はエラー位置を含むエラー）
+    pub fn parse_settings(json: &str) -> Result<Settings, serde_json::Error> {
+        let mut loaded_settings: Settings = serde_json::from_str(json)?;
+        Self::migrate_settings(&mut loaded_settings);
+        Ok(loaded_settings)
+    }
+    
+    /// 古い形式の設定を現在の形式に変換する
+    fn migrate_settings(loaded_settings: &mut Settings) {
+        // プロファイルがなかった頃の設定ファイルのスニペットは、Defaultプロファイルのスニペットとして扱う
+        if loaded_settings.active_profile.trim().is_empty() {
+            loaded_settings.active_profile = DEFAULT_PROFILE.to_string();
+        }
+        
+        // 手動の編集で選択中のプロファイルが保管側にもある場合は、選択中のスニペットにまとめる
+        if let Some(snippets) = loaded_settings.profiles.remove(&loaded_settings.active_profile) {
+            loaded_settings.snippets.extend(snippets);
+        }
+        
+        // 既存の日本語タイトルやカテゴリを英語に変換
+        let all_snippets = loaded_settings.snippets.iter_mut()
+            .chain(loaded_settings.profiles.values_mut().flatten());
+        for snippet in all_snippets {
+            // 日本語タイトルを英語に変換
+            match snippet.name.as_str() {
+                "今日の日付 (YYYY/MM/DD)" => snippet.name = "Today's Date (YYYY/MM/DD)".to_string(),
+                "今日の日付 (YYYYMMDD)" => snippet.name = "Today's Date (YYYYMMDD)".to_string(),
+                "現在時刻" => snippet.name = "Current Time".to_string(),
+                "タイムスタンプ" => snippet.name = "Timestamp".to_string(),
+                _ => {}
+            }
+            
+            // 日本語カテゴリを英語に変換
+            match snippet.category.as_str() {
+                "日付" => snippet.category = "Date".to_string(),
+                "時間" => snippet.category = "Time".to_string(),
+                "テンプレート" => snippet.category = "Templates".to_string(),
+                _ => {}
+            }
+            
+            // 特殊文字を含むキーワードを安全な形式に変換
+            Self::sanitize_stored_keyword(snippet);
+        }
+        
+        Self::assign_snippet_ids(loaded_settings);
+    }
+    
+    /// IDが未割り当て、または重複しているスニペットに新しいIDを割り当てる
+    /// 
+    /// 保管中のプロファイルのスニペットも含め、すべてのスニペットでIDが重複しないようにする
+    fn assign_snippet_ids(settings: &mut Settings) {
+        let mut next_id = settings.next_snippet_id();
+        let mut used_ids = std::collections::HashSet::new();
+        
+        let all_snippets = settings.snippets.iter_mut()
+            .chain(settings.profiles.values_mut().flatten());
+        for snippet in all_snippets {
+            if snippet.id == 0 || !used_ids.insert(snippet.id) {
+                snippet.id = next_id;
+                used_ids.insert(next_id);
+                next_id += 1;
+            }
+        }
+    }
+    
     /// 設定を取得する
     pub fn get_settings(&self) -> &Settings {
         &self.settings
     }
     
+    /// 設定ファイルのパスを取得する
+    pub fn config_path(&self) -> &Path {
+        &self.config_path
+    }
+    
+    /// 設定ファイルを読み込み直す
+    /// 
+    /// 読み込んだ設定には `with_path` と同じ変換を適用する。
+    /// ファイルの内容がメモリ上の設定と同じ場合（`save` による書き込みなど）は何もしない
+    /// 
+    /// # 戻り値
+    /// 変更された場合は読み込んだ設定、変更がない場合はNone
+    pub fn reload_from_disk(&mut self) -> Result<Option<Settings>, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(&self.config_path)?;
+        let loaded_settings = Self::parse_settings(&content)?;
+        
+        if serde_json::to_value(&loaded_settings)? == serde_json::to_value(&self.settings)? {
+            return Ok(None);
+        }
+        
+        self.settings = loaded_settings.clone();
+        log::info!("Reloaded settings from {:?}", self.config_path);
+        Ok(Some(loaded_settings))
+    }
+    
     /// 設定を変更する
     pub fn update_settings(&mut self, mut settings: Settings) -> Result<(), Box<dyn std::error::Error>> {
         // 保存前に特殊文字を含むキーワードを安全な形式に変換
         for snippet in &mut settings.snippets {
-            if snippet.keyword.contains('=') || snippet.keyword.contains(';') || snippet.keyword.contains(',') {
-                let original = snippet.keyword.clone();
-                snippet.keyword = snippet.keyword.replace('=', "_")
-                                          .replace(';', "_")
-                                          .replace(',', "_");
-                log::info!("Sanitized keyword from '{}' to '{}'", original, snippet.keyword);
-            }
+            Self::sanitize_stored_keyword(snippet);
         }
         
+        Self::assign_snippet_ids(&mut settings);
         self.settings = settings;
         self.save()
     }
     
+    /// 保存せずにメモリ上の設定を置き換える
+    /// 
+    /// UIなど他の場所で変更された設定を、スニペットの操作前に反映するために使う
+    pub fn set_settings(&mut self, settings: Settings) {
+        self.settings = settings;
+    }
+    
+    /// スニペットを検証し、保存できる形に整える
+    /// 
+    /// キーワードの重複は保存を妨げない（保存後に `Settings::find_keyword_conflicts` で警告する）
+    /// 
+    /// # 引数
+    /// * `snippet` - 検証するスニペット（キーワードの特殊文字は置き換えられる）
+    fn validate_snippet(snippet: &mut Snippet) -> Result<(), String> {
+        snippet.name = snippet.name.trim().to_string();
+        snippet.keyword = Self::sanitize_keyword(&snippet.keyword);
+        
+        if snippet.name.is_empty() {
+            return Err("Snippet name must not be empty".to_string());
+        }
+        if snippet.keyword.is_empty() {
+            return Err("Keyword must not be empty".to_string());
+        }
+        if snippet.keyword.chars().any(char::is_whitespace) {
+            return Err(format!("Keyword '{}' must not contain whitespace", snippet.keyword));
+        }
+        
+        // キー入力として送信する場合は、展開時に失敗しないよう保存前に本文を検証する
+        if snippet.send_as_keystrokes {
+            parse_keystrokes(&snippet.content)?;
+        }
+        
+        Ok(())
+    }
+    
+    /// キーワードの前後の空白を取り除き、特殊文字を安全な文字に置き換える
+    fn sanitize_keyword(keyword: &str) -> String {
+        Self::replace_special_chars(keyword.trim())
+    }
+    
+    /// 保存されているスニペットのキーワードの特殊文字を置き換え、置き換えた場合は記録する
+    fn sanitize_stored_keyword(snippet: &mut Snippet) {
+        let sanitized = Self::replace_special_chars(&snippet.keyword);
+        if sanitized != snippet.keyword {
+            log::info!("Sanitized keyword from '{}' to '{}'", snippet.keyword, sanitized);
+            snippet.keyword = sanitized;
+        }
+    }
+    
+    /// キーワードの特殊文字（`=` `;` `,`）を、キー入力のバッファと同じ `_` に置き換える
+    fn replace_special_chars(keyword: &str) -> String {
+        keyword.replace(['=', ';', ','], "_")
+    }
+    
+    /// 指定したキーワードを使っているスニペットを検索する
+    /// 
+    /// 大文字と小文字を区別しない設定では、大文字と小文字だけが異なるキーワードも同じとみなす
+    /// 
+    /// # 引数
+    /// * `keyword` - 検索するキーワード
+    /// * `exclude_id` - 検索から除外するスニペットのID
+    fn find_keyword(&self, keyword: &str, exclude_id: Option<u64>) -> Option<&Snippet> {
+        let case_sensitive = self.settings.case_sensitive;
+        let is_same_keyword = |other: &str| {
+            if case_sensitive {
+                other == keyword
+            } else {
+                other.to_lowercase() == keyword.to_lowercase()
+            }
+        };
+        
+        self.settings.snippets.iter()
+            .filter(|s| Some(s.id) != exclude_id)
+            .find(|s| is_same_keyword(&s.keyword))
+    }
+    
+    /// 設定を変更して保存する（保存に失敗した場合は変更を元に戻す）
+    fn commit(&mut self, settings: Settings) -> Result<(), Box<dyn std::error::Error>> {
+        let previous = std::mem::replace(&mut self.settings, settings);
+        if let Err(e) = self.save() {
+            self.settings = previous;
+            return Err(e);
+        }
+        Ok(())
+    }
+    
+    /// スニペットを変更して保存する
+    fn commit_snippets(&mut self, snippets: Vec<Snippet>) -> Result<(), Box<dyn std::error::Error>> {
+        let mut settings = self.settings.clone();
+        settings.snippets = snippets;
+        self.commit(settings)
+    }
+    
+    /// スニペットを追加する
+    /// 
+    /// # 引数
+    /// * `snippet` - 追加するスニペット（IDは自動で割り当てられる）
+    /// 
+    /// # 戻り値
+    /// 割り当てられたスニペットのID
+    pub fn add_snippet(&mut self, mut snippet: Snippet) -> Result<u64, Box<dyn std::error::Error>> {
+        Self::validate_snippet(&mut snippet)?;
+        
+        snippet.id = self.settings.next_snippet_id();
+        let id = snippet.id;
+        
+        let mut snippets = self.settings.snippets.clone();
+        snippets.push(snippet);
+        self.commit_snippets(snippets)?;
+        
+        log::info!("Added snippet {}", id);
+        Ok(id)
+    }
+    
+    /// スニペットを更新する
+    /// 
+    /// # 引数
+    /// * `id` - 更新するスニペットのID
+    /// * `snippet` - 新しいスニペットの内容（IDは引数の `id` が使われる）
+    pub fn update_snippet(&mut self, id: u64, mut snippet: Snippet) -> Result<(), Box<dyn std::error::Error>> {
+        let index = self.snippet_index(id)?;
+        Self::validate_snippet(&mut snippet)?;
+        snippet.id = id;
+        
+        let mut snippets = self.settings.snippets.clone();
+        snippets[index] = snippet;
+        self.commit_snippets(snippets)?;
+        
+        log::info!("Updated snippet {}", id);
+        Ok(())
+    }
+    
+    /// 複数のスニペットの有効/無効をまとめて変更し、1回だけ保存する
+    /// 
+    /// # 引数
+    /// * `states` - スニペットのIDと変更後の有効/無効
+    /// 
+    /// # 戻り値
+    /// 変更したスニペットのIDと変更前の有効/無効（元に戻すときに渡す）
+    pub fn set_snippets_enabled(&mut self, states: &[(u64, bool)]) -> Result<Vec<(u64, bool)>, Box<dyn std::error::Error>> {
+        let mut snippets = self.settings.snippets.clone();
+        let mut previous = Vec::new();
+        for &(id, enabled) in states {
+            let snippet = &mut snippets[self.snippet_index(id)?];
+            if snippet.enabled != enabled {
+                previous.push((id, snippet.enabled));
+                snippet.enabled = enabled;
+            }
+        }
+        
+        if !previous.is_empty() {
+            self.commit_snippets(snippets)?;
+            log::info!("Changed enabled state of {} snippets", previous.len());
+        }
+        Ok(previous)
+    }
+    
+    /// スニペットを複製する
+    /// 
+    /// 名前に " (copy)" を、キーワードに "_copy" を付けて追加する。
+    /// キーワードが既存のスニペットと重複する場合は "_copy2"、"_copy3" のように番号を付ける。
+    /// 
+    /// # 引数
+    /// * `id` - 複製するスニペットのID
+    /// 
+    /// # 戻り値
+    /// 追加したスニペットのID
+    pub fn duplicate_snippet(&mut self, id: u64) -> Result<u64, Box<dyn std::error::Error>> {
+        let mut copy = self.settings.snippets[self.snippet_index(id)?].clone();
+        copy.name = format!("{} (copy)", copy.name);
+        
+        let base_keyword = Self::sanitize_keyword(&format!("{}_copy", copy.keyword));
+        copy.keyword = base_keyword.clone();
+        let mut suffix = 2;
+        while self.find_keyword(&copy.keyword, None).is_some() {
+            copy.keyword = format!("{}{}", base_keyword, suffix);
+            suffix += 1;
+        }
+        
+        self.add_snippet(copy)
+    }
+    
+    /// スニペットを削除する
+    /// 
+    /// # 引数
+    /// * `id` - 削除するスニペットのID
+    /// 
+    /// # 戻り値
+    /// 削除したスニペット
+    pub fn remove_snippet(&mut self, id: u64) -> Result<Snippet, Box<dyn std::error::Error>> {
+        let index = self.snippet_index(id)?;
+        
+        let mut snippets = self.settings.snippets.clone();
+        let removed = snippets.remove(index);
+        self.commit_snippets(snippets)?;
+        
+        log::info!("Removed snippet {}", id);
+        Ok(removed)
+    }
+    
+    /// 複数のスニペットを追加する
+    /// 
+    /// キーワードが既存のスニペット（または先に追加したスニペット）と重複するものと、
+    /// 不正なものは追加せずに数える
+    /// 
+    /// # 引数
+    /// * `snippets` - 追加するスニペット（IDは自動で割り当てられる）
+    /// 
+    /// # 戻り値
+    /// 追加した数と追加しなかった数
+    fn merge_snippets(&mut self, snippets: Vec<Snippet>) -> Result<ImportSummary, Box<dyn std::error::Error>> {
+        let previous = self.settings.clone();
+        let mut summary = ImportSummary::default();
+        let mut next_id = self.settings.next_snippet_id();
+        
+        for mut snippet in snippets {
+            if self.find_keyword(&Self::sanitize_keyword(&snippet.keyword), None).is_some() {
+                summary.skipped_duplicates += 1;
+                continue;
+            }
+            if let Err(e) = Self::validate_snippet(&mut snippet) {
+                log::warn!("Skipped snippet '{}': {}", snippet.name, e);
+                summary.skipped_invalid += 1;
+                continue;
+            }
+            
+            snippet.id = next_id;
+            next_id += 1;
+            self.settings.snippets.push(snippet);
+            summary.imported += 1;
+        }
+        
+        // 追加したスニペットを保存する（失敗した場合は元の設定に戻る）
+        let merged = std::mem::replace(&mut self.settings, previous);
+        self.commit(merged)?;
+        
+        if summary.skipped_duplicates > 0 {
+            log::info!("Skipped {} snippets with duplicate keywords", summary.skipped_duplicates);
+        }
+        Ok(summary)
+    }
+    
+    /// AutoHotkeyのスクリプトのホットストリングをスニペットとして追加する
+    /// 
+    /// # 引数
+    /// * `path` - `.ahk` ファイルのパス
+    /// 
+    /// # 戻り値
+    /// 追加した数と追加しなかった数
+    pub fn import_ahk(&mut self, path: &Path) -> Result<ImportSummary, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)?;
+        
+        let snippets = ahk::parse_hotstrings(&content).into_iter()
+            .map(|hotstring| {
+                let mut snippet = Snippet::new(
+                    hotstring.trigger.clone(),
+                    hotstring.trigger,
+                    hotstring.replacement,
+                    SnippetType::Static,
+                    AHK_IMPORT_CATEGORY.to_string(),
+                );
+                snippet.word_boundary = !hotstring.inside_word;
+                snippet
+            })
+            .collect();
+        
+        let summary = self.merge_snippets(snippets)?;
+        log::info!("Imported {} snippets from {:?}", summary.imported, path);
+        Ok(summary)
+    }
+    
+    /// CSVファイルのスニペットを追加する
+    /// 
+    /// 見出し行が `name,keyword,content,category,type` のCSVを読み込む（Excelで編集したファイルなど）。
+    /// キーワードが空の行は追加せず、不正なキーワードとして数える。
+    /// 
+    /// # 引数
+    /// * `path` - CSVファイルのパス
+    /// 
+    /// # 戻り値
+    /// 追加した数と追加しなかった数
+    pub fn import_csv(&mut self, path: &Path) -> Result<ImportSummary, Box<dyn std::error::Error>> {
+        let file = std::fs::File::open(path)?;
+        let snippets = csv_import::parse_snippets(file)?;
+        
+        let summary = self.merge_snippets(snippets)?;
+        log::info!("Imported {} snippets from {:?}", summary.imported, path);
+        Ok(summary)
+    }
+    
+    /// 記号の初期セット（`:shrug:` など）をスニペットとして追加する
+    /// 
+    /// 同じキーワードのスニペットがすでにある記号は追加しない
+    /// 
+    /// # 戻り値
+    /// 追加した数と追加しなかった数
+    pub fn add_starter_symbols(&mut self) -> Result<ImportSummary, Box<dyn std::error::Error>> {
+        let summary = self.merge_snippets(symbols::starter_symbol_snippets())?;
+        log::info!("Added {} symbol snippets", summary.imported);
+        Ok(summary)
+    }
+    
+    /// すべてのスニペットをJSONファイルに書き出す
+    /// 
+    /// 他のマシンに持ち出せるように、自動起動などの設定は含めずスニペットの一覧だけを書き出す
+    /// 
+    /// # 引数
+    /// * `path` - 書き出すファイルのパス
+    pub fn export_snippets(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let serialized = serde_json::to_string_pretty(&self.settings.snippets)?;
+        std::fs::write(path, serialized)?;
+        
+        log::info!("Exported {} snippets to {:?}", self.settings.snippets.len(), path);
+        Ok(())
+    }
+    
+    /// `export_snippets` で書き出したファイルのスニペットを追加する
+    /// 
+    /// # 引数
+    /// * `path` - 読み込むファイルのパス
+    /// 
+    /// # 戻り値
+    /// 追加した数と追加しなかった数
+    pub fn import_snippets(&mut self, path: &Path) -> Result<ImportSummary, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)?;
+        let snippets: Vec<Snippet> = serde_json::from_str(&content)?;
+        
+        let summary = self.merge_snippets(snippets)?;
+        log::info!("Imported {} snippets from {:?}", summary.imported, path);
+        Ok(summary)
+    }
+    
+    /// カテゴリの名前を変更し、属するすべてのスニペットを新しいカテゴリに移す
+    /// 
+    /// # 戻り値
+    /// 移動したスニペットの数
+    pub fn rename_category(&mut self, old_name: &str, new_name: &str) -> Result<usize, Box<dyn std::error::Error>> {
+        let new_name = new_name.trim();
+        if new_name.is_empty() {
+            return Err("Category name must not be empty".into());
+        }
+        
+        let mut settings = self.settings.clone();
+        let count = settings.rename_category(old_name, new_name);
+        self.commit(settings)?;
+        
+        log::info!("Renamed category '{}' to '{}' ({} snippets)", old_name, new_name, count);
+        Ok(count)
+    }
+    
+    /// カテゴリを削除し、属するスニペットを未分類に移す
+    /// 
+    /// # 戻り値
+    /// 移動したスニペットの数
+    pub fn delete_category(&mut self, name: &str) -> Result<usize, Box<dyn std::error::Error>> {
+        let mut settings = self.settings.clone();
+        let count = settings.delete_category(name);
+        self.commit(settings)?;
+        
+        log::info!("Deleted category '{}' ({} snippets moved)", name, count);
+        Ok(count)
+    }
+    
+    /// カテゴリの新しいスニペットのデフォルトの種類を設定する
+    /// 
+    /// # 引数
+    /// * `category` - カテゴリ名
+    /// * `snippet_type` - デフォルトの種類（Noneの場合は設定を解除する）
+    pub fn set_category_snippet_type(
+        &mut self,
+        category: &str,
+        snippet_type: Option<SnippetType>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut settings = self.settings.clone();
+        match snippet_type {
+            Some(snippet_type) => {
+                settings.category_snippet_types.insert(category.to_string(), snippet_type);
+            }
+            None => {
+                settings.category_snippet_types.remove(category);
+            }
+        }
+        self.commit(settings)
+    }
+    
+    /// プロファイルを切り替えて保存する
+    /// 
+    /// 存在しないプロファイルを指定した場合は、スニペットのない新しいプロファイルを作成する
+    /// 
+    /// # 引数
+    /// * `name` - 切り替えるプロファイルの名前
+    pub fn switch_profile(&mut self, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Profile name must not be empty".into());
+        }
+        
+        let mut settings = self.settings.clone();
+        if !settings.switch_profile(name) {
+            return Ok(());
+        }
+        Self::assign_snippet_ids(&mut settings);
+        self.commit(settings)?;
+        
+        log::info!("Switched to profile '{}'", name);
+        Ok(())
+    }
+    
+    /// IDからスニペットの位置を取得する
+    fn snippet_index(&self, id: u64) -> Result<usize, String> {
+        self.settings.snippets.iter()
+            .position(|s| s.id == id)
+            .ok_or_else(|| format!("Snippet {} not found", id))
+    }
+    
     /// 設定を保存する
     pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
         let serialized = serde_json::to_string_pretty(&self.settings)?;
@@ -98,8 +604,12 @@ impl ConfigManager {
             }
         }
         
-        // ファイルに書き込み
-        match std::fs::write(&self.config_path, serialized) {
+        // 書き込み途中で終了しても設定ファイルが壊れないように、一時ファイルに書き込んでから置き換える
+        let temp_path = self.config_path.with_extension("json.tmp");
+        let result = std::fs::write(&temp_path, serialized)
+            .and_then(|()| std::fs::rename(&temp_path, &self.config_path));
+        
+        match result {
             Ok(()) => {
                 log::debug!("Settings saved successfully to {:?}", self.config_path);
                 Ok(())
@@ -111,6 +621,99 @@ impl ConfigManager {
         }
     }
     
+    /// 現在の設定をJSON文字列として取得する
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&self.settings)
+    }
+    
+    /// JSON文字列で設定を置き換える
+    /// 
+    /// 置き換える前に現在の設定ファイルを `settings.json.bak` として保存する
+    /// 
+    /// # 引数
+    /// * `json` - 新しい設定のJSON文字列
+    /// 
+    /// # 戻り値
+    /// 適用された設定
+    pub fn replace_with_json(&mut self, json: &str) -> Result<Settings, Box<dyn std::error::Error>> {
+        // 不正なJSONの場合は何も変更しない
+        let settings = Self::parse_settings(json)?;
+        
+        // 現在の設定ファイルをバックアップ
+        if self.config_path.exists() {
+            let backup_path = self.config_path.with_extension("json.bak");
+            std::fs::copy(&self.config_path, &backup_path)?;
+            log::info!("Backed up settings to {:?}", backup_path);
+        }
+        
+        self.update_settings(settings)?;
+        Ok(self.settings.clone())
+    }
+    
+    /// 現在の設定ファイルを、日時を付けたバックアップファイルとして設定ディレクトリに保存する
+    /// 
+    /// ファイル名は `settings.YYYYMMDD-HHMMSS.json.bak` になる。
+    /// 同じ秒に作成したバックアップがある場合は、上書きせずに `settings.YYYYMMDD-HHMMSS-2.json.bak` のように番号を付ける
+    /// 
+    /// # 戻り値
+    /// 作成したバックアップファイルのパス
+    pub fn backup(&self) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        use std::io::Write;
+        
+        // 設定ファイルがまだない場合は、現在の設定を保存してからバックアップする
+        if !self.config_path.exists() {
+            self.save()?;
+        }
+        
+        let stem = self.config_path.file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("settings");
+        let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+        let content = std::fs::read(&self.config_path)?;
+        
+        let mut number = 1;
+        loop {
+            let file_name = if number == 1 {
+                format!("{}.{}.json.bak", stem, timestamp)
+            } else {
+                format!("{}.{}-{}.json.bak", stem, timestamp, number)
+            };
+            let backup_path = self.config_path.with_file_name(file_name);
+            
+            // 既存のバックアップは上書きしない
+            match std::fs::OpenOptions::new().write(true).create_new(true).open(&backup_path) {
+                Ok(mut file) => {
+                    file.write_all(&content)?;
+                    log::info!("Backed up settings to {:?}", backup_path);
+                    return Ok(backup_path);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => number += 1,
+                Err(e) => return Err(Box::new(e)),
+            }
+        }
+    }
+    
+    /// バックアップファイルから設定を復元する
+    /// 
+    /// 古いバックアップも読み込めるよう、設定ファイルの読み込み時と同じ変換を適用する。
+    /// 復元する前に現在の設定をバックアップする
+    /// 
+    /// # 引数
+    /// * `path` - 復元するバックアップファイルのパス
+    /// 
+    /// # 戻り値
+    /// 復元した設定（不正なファイルの場合は何も変更せずにエラーを返す）
+    pub fn restore(&mut self, path: &Path) -> Result<Settings, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)?;
+        let settings = Self::parse_settings(&content)?;
+        
+        self.backup()?;
+        self.update_settings(settings)?;
+        
+        log::info!("Restored settings from {:?}", path);
+        Ok(self.settings.clone())
+    }
+    
     /// 設定ディレクトリのパスを取得する
     fn get_config_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
         let config_dir = dirs::config_dir()
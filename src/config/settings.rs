@@ -1,4 +1,19 @@
+use chrono::{DateTime, Duration, Local};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// 新しいスニペットのデフォルトカテゴリ
+pub const DEFAULT_CATEGORY: &str = "一般";
+
+/// カテゴリを削除したときにスニペットを移すカテゴリ
+pub const UNCATEGORIZED_CATEGORY: &str = "Uncategorized";
+
+/// プロファイルがなかった頃の設定ファイルのスニペットを入れるプロファイル
+pub const DEFAULT_PROFILE: &str = "Default";
+
+/// 選択テキストから作成するスニペット名の最大文字数
+const SELECTION_NAME_MAX_CHARS: usize = 30;
 
 /// スニペットの種類
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -9,9 +24,38 @@ pub enum SnippetType {
     Dynamic,
 }
 
+/// スニペットのテキストを入力する方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Delivery {
+    /// 短いテキストは直接入力し、長いテキストや直接入力に失敗した場合は貼り付ける
+    #[default]
+    Auto,
+    /// 常にクリップボード経由で貼り付ける
+    Paste,
+    /// 常に1文字ずつ直接入力する（貼り付けを受け付けないターミナルやパスワード欄向け）
+    Type,
+}
+
+impl Delivery {
+    /// すべての入力方法
+    pub const ALL: [Delivery; 3] = [Delivery::Auto, Delivery::Paste, Delivery::Type];
+    
+    /// 表示名を取得する
+    pub fn label(self) -> &'static str {
+        match self {
+            Delivery::Auto => "Auto",
+            Delivery::Paste => "Paste",
+            Delivery::Type => "Type",
+        }
+    }
+}
+
 /// スニペットの定義
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Snippet {
+    /// スニペットのID（0は未割り当て）
+    #[serde(default)]
+    pub id: u64,
     /// スニペットの名前
     pub name: String,
     /// スニペットのキーワード（トリガー）
@@ -24,6 +68,43 @@ pub struct Snippet {
     pub category: String,
     /// スニペットの有効/無効
     pub enabled: bool,
+    /// 本文を `{key:...}` を含むキー入力として送信するかどうか
+    #[serde(default)]
+    pub send_as_keystrokes: bool,
+    /// 入力したキーワードの大文字・小文字に合わせて展開するかどうか
+    #[serde(default)]
+    pub match_case: bool,
+    /// 単語の先頭から入力した場合だけ展開するかどうか
+    #[serde(default = "default_word_boundary")]
+    pub word_boundary: bool,
+    /// キーワードを削除せず、キーワードの後ろに本文を入力するかどうか
+    #[serde(default)]
+    pub keep_trigger: bool,
+    /// 展開するアプリのプロセス名（空の場合はすべてのアプリで展開する）
+    #[serde(default)]
+    pub allowed_apps: Vec<String>,
+    /// テキストを入力する方法
+    #[serde(default)]
+    pub delivery: Delivery,
+    /// 展開に成功した回数
+    #[serde(default)]
+    pub usage_count: u64,
+    /// スニペットの説明やメモ（一覧でマウスを重ねると表示する）
+    #[serde(default)]
+    pub description: String,
+    /// 静的な本文の `\t`、`\n`、`\\` をタブ、改行、バックスラッシュとして展開するかどうか
+    #[serde(default)]
+    pub process_escapes: bool,
+    /// キーワードを削除する前の待機時間（ミリ秒）
+    /// 
+    /// 指定した場合は、展開の速さの設定から計算した待機時間の代わりに使う
+    #[serde(default)]
+    pub pre_delay_ms: Option<u64>,
+}
+
+/// 単語の境界を必要とするかどうかのデフォルト値
+fn default_word_boundary() -> bool {
+    true
 }
 
 impl Snippet {
@@ -36,18 +117,67 @@ impl Snippet {
         category: String,
     ) -> Self {
         Self {
+            id: 0,
             name,
             keyword,
             content,
             snippet_type,
             category,
             enabled: true,
+            send_as_keystrokes: false,
+            match_case: false,
+            word_boundary: true,
+            keep_trigger: false,
+            allowed_apps: Vec::new(),
+            delivery: Delivery::Auto,
+            usage_count: 0,
+            description: String::new(),
+            process_escapes: false,
+            pre_delay_ms: None,
         }
     }
+    
+    /// 名前、キーワード、カテゴリ、説明のいずれかに検索語を含むかどうか（大文字と小文字は区別しない）
+    /// 
+    /// # 引数
+    /// * `query` - 検索語（空の場合はすべてのスニペットが一致する）
+    pub fn matches_query(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        [&self.name, &self.keyword, &self.category, &self.description]
+            .iter()
+            .any(|field| field.to_lowercase().contains(&query))
+    }
+    
+    /// 選択中のテキストから静的スニペットを作成する
+    /// 
+    /// 名前には最初の空でない行を使い、キーワードはユーザーが入力するため空にする
+    /// 
+    /// # 引数
+    /// * `text` - 選択中のテキスト
+    pub fn from_selection(text: &str) -> Self {
+        let first_line = text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap_or_default();
+        
+        let mut name: String = first_line.chars().take(SELECTION_NAME_MAX_CHARS).collect();
+        if first_line.chars().count() > SELECTION_NAME_MAX_CHARS {
+            name.push('…');
+        }
+        
+        Self::new(
+            name,
+            String::new(),
+            text.to_string(),
+            SnippetType::Static,
+            DEFAULT_CATEGORY.to_string(),
+        )
+    }
 }
 
 /// ホットキーの定義
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Hotkey {
     /// 修飾キー (Ctrl, Alt, Shift, Win)
     pub modifiers: u32,
@@ -55,21 +185,447 @@ pub struct Hotkey {
     pub key_code: u32,
 }
 
+impl Hotkey {
+    /// Ctrlキーのビット
+    pub const CTRL: u32 = 1;
+    /// Altキーのビット
+    pub const ALT: u32 = 2;
+    /// Shiftキーのビット
+    pub const SHIFT: u32 = 4;
+    /// Winキーのビット
+    pub const WIN: u32 = 8;
+}
+
+impl fmt::Display for Hotkey {
+    /// `Ctrl+Alt+S` のような読みやすい形式で表示する
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modifiers = [(Self::CTRL, "Ctrl"), (Self::ALT, "Alt"), (Self::SHIFT, "Shift"), (Self::WIN, "Win")];
+        for (bit, name) in modifiers {
+            if self.modifiers & bit != 0 {
+                write!(f, "{}+", name)?;
+            }
+        }
+        
+        match self.key_code {
+            0x08 => write!(f, "Backspace"),
+            0x09 => write!(f, "Tab"),
+            0x0D => write!(f, "Enter"),
+            0x1B => write!(f, "Esc"),
+            0x20 => write!(f, "Space"),
+            0x21 => write!(f, "PageUp"),
+            0x22 => write!(f, "PageDown"),
+            0x23 => write!(f, "End"),
+            0x24 => write!(f, "Home"),
+            0x25 => write!(f, "Left"),
+            0x26 => write!(f, "Up"),
+            0x27 => write!(f, "Right"),
+            0x28 => write!(f, "Down"),
+            0x2D => write!(f, "Insert"),
+            0x2E => write!(f, "Delete"),
+            0xBB => write!(f, "="),
+            0xBD => write!(f, "-"),
+            // 数字キーとアルファベットキーの仮想キーコードは文字コードと同じ
+            code @ (0x30..=0x39 | 0x41..=0x5A) => write!(f, "{}", char::from(code as u8)),
+            code @ 0x70..=0x87 => write!(f, "F{}", code - 0x6F),
+            code => write!(f, "0x{:02X}", code),
+        }
+    }
+}
+
+/// ウィンドウのテーマの設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ThemePreference {
+    /// Windowsのアプリのテーマに合わせる
+    #[default]
+    System,
+    /// 常にライト
+    Light,
+    /// 常にダーク
+    Dark,
+}
+
+impl ThemePreference {
+    /// すべての設定
+    pub const ALL: [ThemePreference; 3] = [ThemePreference::System, ThemePreference::Light, ThemePreference::Dark];
+    
+    /// 表示名を取得する
+    pub fn label(self) -> &'static str {
+        match self {
+            ThemePreference::System => "Follow system",
+            ThemePreference::Light => "Light",
+            ThemePreference::Dark => "Dark",
+        }
+    }
+}
+
+/// 自動起動の登録方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AutoStartMethod {
+    /// スタートアップフォルダにショートカットを作成する
+    #[default]
+    StartupFolder,
+    /// レジストリの `HKCU\Software\Microsoft\Windows\CurrentVersion\Run` に登録する
+    /// 
+    /// ショートカットの作成がブロックされたり削除されたりする環境向け
+    Registry,
+}
+
+impl AutoStartMethod {
+    /// すべての登録方法
+    pub const ALL: [AutoStartMethod; 2] = [AutoStartMethod::StartupFolder, AutoStartMethod::Registry];
+    
+    /// 表示名を取得する
+    pub fn label(self) -> &'static str {
+        match self {
+            AutoStartMethod::StartupFolder => "Startup folder shortcut",
+            AutoStartMethod::Registry => "Registry (Run key)",
+        }
+    }
+}
+
+/// 展開したときの通知
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExpandNotification {
+    /// システムの通知音を鳴らすかどうか
+    pub sound: bool,
+    /// スニペット名をトレイの通知に表示するかどうか
+    pub balloon: bool,
+}
+
+/// ウィンドウの位置とサイズ（物理ピクセル）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    /// ウィンドウの左上のX座標
+    pub x: f32,
+    /// ウィンドウの左上のY座標
+    pub y: f32,
+    /// ウィンドウの幅
+    pub width: f32,
+    /// ウィンドウの高さ
+    pub height: f32,
+}
+
+impl WindowGeometry {
+    /// 原点から指定したサイズの領域にウィンドウが収まるよう、位置とサイズを調整する
+    /// 
+    /// # 引数
+    /// * `area_width` - 領域の幅（プライマリモニターの幅など）
+    /// * `area_height` - 領域の高さ
+    pub fn clamped_to(self, area_width: f32, area_height: f32) -> Self {
+        let width = self.width.min(area_width);
+        let height = self.height.min(area_height);
+        Self {
+            x: self.x.clamp(0.0, area_width - width),
+            y: self.y.clamp(0.0, area_height - height),
+            width,
+            height,
+        }
+    }
+}
+
+/// 展開の速さのプリセット
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimingPreset {
+    /// 待機を最小限にする（高速なPC向け）
+    Fast,
+    /// 速さと確実さの中間
+    Balanced,
+    /// 入力の取りこぼしが起きにくい従来の待機時間
+    Safe,
+}
+
+impl TimingPreset {
+    /// すべてのプリセット
+    pub const ALL: [TimingPreset; 3] = [TimingPreset::Fast, TimingPreset::Balanced, TimingPreset::Safe];
+    
+    /// 表示名を取得する
+    pub fn label(self) -> &'static str {
+        match self {
+            TimingPreset::Fast => "Fast",
+            TimingPreset::Balanced => "Balanced",
+            TimingPreset::Safe => "Safe",
+        }
+    }
+}
+
+/// 展開時のキー入力の待機時間（ミリ秒）
+/// 
+/// 短いキーワードなど取りこぼしやすい場合は、これらの値を基準に長めに待機する
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimingProfile {
+    /// キーワードを削除する前の待機時間
+    pub pre_backspace_ms: u64,
+    /// バックスペースを押して離す間、および次のバックスペースまでの待機時間
+    pub between_keys_ms: u64,
+    /// キーワードを削除してからテキストを入力するまでの待機時間
+    pub post_backspace_ms: u64,
+    /// テキストを1文字ずつ入力するときの文字間の待機時間
+    pub char_delay_ms: u64,
+    /// 修飾キーとキーの組み合わせを送信する前の待機時間
+    pub key_combo_ms: u64,
+    /// クリップボードにテキストを設定してから貼り付けるまでの待機時間
+    pub clipboard_settle_ms: u64,
+    /// 貼り付けた後の待機時間
+    pub post_paste_ms: u64,
+    /// 貼り付けのキー入力を送信できなかったときに再試行する回数（プリセットを切り替えても変わらない）
+    pub paste_retries: u32,
+}
+
+impl TimingProfile {
+    /// プリセットの待機時間を取得する
+    pub fn preset(preset: TimingPreset) -> Self {
+        match preset {
+            TimingPreset::Fast => Self {
+                pre_backspace_ms: 30,
+                between_keys_ms: 2,
+                post_backspace_ms: 40,
+                char_delay_ms: 2,
+                key_combo_ms: 30,
+                clipboard_settle_ms: 40,
+                post_paste_ms: 60,
+                paste_retries: DEFAULT_PASTE_RETRIES,
+            },
+            TimingPreset::Balanced => Self {
+                pre_backspace_ms: 100,
+                between_keys_ms: 10,
+                post_backspace_ms: 150,
+                char_delay_ms: 8,
+                key_combo_ms: 80,
+                clipboard_settle_ms: 80,
+                post_paste_ms: 120,
+                paste_retries: DEFAULT_PASTE_RETRIES,
+            },
+            TimingPreset::Safe => Self {
+                pre_backspace_ms: 200,
+                between_keys_ms: 20,
+                post_backspace_ms: 300,
+                char_delay_ms: 15,
+                key_combo_ms: 150,
+                clipboard_settle_ms: 150,
+                post_paste_ms: 200,
+                paste_retries: DEFAULT_PASTE_RETRIES,
+            },
+        }
+    }
+    
+    /// 一致するプリセットを取得する（個別に変更した場合はNone）
+    /// 
+    /// 貼り付けの再試行回数はプリセットとは別に設定するため比較しない
+    pub fn matching_preset(&self) -> Option<TimingPreset> {
+        TimingPreset::ALL.into_iter().find(|&preset| self.with_preset(preset) == *self)
+    }
+    
+    /// 貼り付けの再試行回数はそのままで、待機時間をプリセットの値にしたものを取得する
+    pub fn with_preset(&self, preset: TimingPreset) -> Self {
+        Self {
+            paste_retries: self.paste_retries,
+            ..Self::preset(preset)
+        }
+    }
+}
+
+impl Default for TimingProfile {
+    fn default() -> Self {
+        Self::preset(TimingPreset::Safe)
+    }
+}
+
 /// アプリケーションの設定
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Settings {
     /// アプリケーションの有効/無効
     pub enabled: bool,
-    /// スニペットのリスト
+    /// 展開を一時停止する期限（一時停止していない場合はNone）
+    /// 
+    /// 有効/無効とは別の一時的な状態のため、設定ファイルには保存しない
+    #[serde(skip)]
+    pub paused_until: Option<DateTime<Local>>,
+    /// 選択中のプロファイルのスニペットのリスト（展開や編集の対象）
     pub snippets: Vec<Snippet>,
+    /// 選択中のプロファイルの名前
+    #[serde(default = "default_profile_name")]
+    pub active_profile: String,
+    /// 選択中でないプロファイルのスニペット（プロファイル名ごと）
+    /// 
+    /// プロファイルを切り替えると、`snippets` とここの間でスニペットを入れ替える
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Vec<Snippet>>,
     /// アプリケーションの起動時に自動的に起動するかどうか
     pub start_with_system: bool,
+    /// 自動起動の登録方法
+    #[serde(default)]
+    pub auto_start_method: AutoStartMethod,
     /// 有効にするホットキー
     pub toggle_hotkey: Option<Hotkey>,
     /// ウィンドウを開くホットキー
     pub open_window_hotkey: Option<Hotkey>,
+    /// キーワードの大文字と小文字を区別するかどうか
+    /// 
+    /// 区別しない場合は `USA` のような大文字のキーワードも小文字の入力で展開される
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// キーワードの後に空白や記号（区切り文字）を入力したときだけ展開するかどうか
+    #[serde(default)]
+    pub require_terminator: bool,
+    /// 展開後に区切り文字を入力し直すかどうか（区切り文字が必要な場合のみ）
+    #[serde(default)]
+    pub keep_terminator: bool,
+    /// CTRL・ALT・Winキーを押しながら入力したキーをキーワードの入力として扱わないかどうか
+    #[serde(default = "default_ignore_shortcut_keys")]
+    pub ignore_shortcut_keys: bool,
+    /// 区切り文字として扱う文字（`.` や `/` を加えると、記号の入力でも展開できる）
+    #[serde(default = "default_terminator_chars")]
+    pub terminator_chars: String,
+    /// 選択中のテキストからスニペットを作成するホットキー
+    #[serde(default)]
+    pub capture_hotkey: Option<Hotkey>,
+    /// 直前の展開を取り消すホットキー
+    #[serde(default)]
+    pub undo_hotkey: Option<Hotkey>,
+    /// スニペットを検索して挿入するウィンドウを開くホットキー
+    #[serde(default)]
+    pub picker_hotkey: Option<Hotkey>,
+    /// 展開時にキャレット付近へスニペット名を表示するかどうか
+    #[serde(default)]
+    pub show_expansion_overlay: bool,
+    /// 展開したときに通知音やトレイの通知で知らせるかどうか
+    #[serde(default)]
+    pub notify_on_expand: ExpandNotification,
+    /// 展開に成功するたびに実行するコマンド（キーワードとスニペット名が引数に追加される）
+    #[serde(default)]
+    pub on_expand_command: Option<String>,
+    /// カテゴリごとの新しいスニペットのデフォルトの種類
+    #[serde(default)]
+    pub category_snippet_types: BTreeMap<String, SnippetType>,
+    /// 日付の月名や曜日名に使うロケール（"ja-JP" など。空の場合はシステムのロケール）
+    #[serde(default)]
+    pub locale: String,
+    /// 書式を省略した{date}トークンの書式（"yyyy/MM/dd" など）
+    #[serde(default = "default_date_format")]
+    pub default_date_format: String,
+    /// 展開しないアプリのプロセス名（パスワードマネージャーやゲームなど）
+    #[serde(default)]
+    pub disabled_apps: Vec<String>,
+    /// 起動時に確認する競合ツールのプロセス名（既知のツールに追加する）
+    #[serde(default)]
+    pub conflict_tools: Vec<String>,
+    /// 実行中でも警告しない競合ツールのプロセス名
+    #[serde(default)]
+    pub ignored_conflict_tools: Vec<String>,
+    /// UIの拡大率（システムのDPIスケールに掛け合わせる）
+    #[serde(default = "default_ui_scale")]
+    pub ui_scale: f32,
+    /// 前回終了したときのウィンドウの位置とサイズ（Noneの場合は画面の中央に表示する）
+    #[serde(default)]
+    pub window_geometry: Option<WindowGeometry>,
+    /// ウィンドウの閉じるボタンで終了せず、トレイに隠すかどうか（終了はトレイメニューから行う）
+    #[serde(default = "default_close_to_tray")]
+    pub close_to_tray: bool,
+    /// キー入力のバッファに保持する文字数
+    #[serde(default = "default_buffer_size")]
+    pub buffer_size: usize,
+    /// 展開するテキストの末尾の空白や改行を取り除くかどうか（複数行の本文の末尾に残った改行など）
+    #[serde(default)]
+    pub trim_trailing_whitespace: bool,
+    /// 展開できるテキストの最大文字数（誤ったテンプレートで大量に入力しないための上限）
+    #[serde(default = "default_max_expansion_len")]
+    pub max_expansion_len: usize,
+    /// 展開時のキー入力の待機時間
+    #[serde(default)]
+    pub timing: TimingProfile,
+    /// ウィンドウのテーマ
+    #[serde(default)]
+    pub theme: ThemePreference,
+    /// 入力すると展開を中止して状態を元に戻すキーワード（空の場合は使わない）
+    #[serde(default = "default_abort_keyword")]
+    pub abort_keyword: String,
+    /// 展開の代わりに、入力する内容をログに記録するだけにするかどうか（一致の確認用）
+    #[serde(default)]
+    pub dry_run: bool,
+    /// 外部のツールからキーワードを受け取って展開するかどうか（`ipc` フィーチャーを有効にしてビルドした場合のみ）
+    #[serde(default)]
+    pub enable_ipc: bool,
+    /// 外部のツールからの要求を待ち受けるポート（127.0.0.1にだけバインドする）
+    #[serde(default = "default_ipc_port")]
+    pub ipc_port: u16,
+}
+
+/// 書式を省略した{date}トークンのデフォルトの書式
+pub const DEFAULT_DATE_FORMAT: &str = "yyyy/MM/dd";
+
+/// 選択中のプロファイルのデフォルトの名前
+fn default_profile_name() -> String {
+    DEFAULT_PROFILE.to_string()
+}
+
+/// UIの拡大率のデフォルト値
+fn default_ui_scale() -> f32 {
+    1.0
+}
+
+/// 外部のツールからの要求を待ち受けるデフォルトのポート
+pub const DEFAULT_IPC_PORT: u16 = 47123;
+
+/// 外部のツールからの要求を待ち受けるポートのデフォルト値
+fn default_ipc_port() -> u16 {
+    DEFAULT_IPC_PORT
+}
+
+/// 閉じるボタンでトレイに隠すかどうかのデフォルト値
+fn default_close_to_tray() -> bool {
+    true
+}
+
+/// ショートカットの入力を無視するかどうかのデフォルト値
+fn default_ignore_shortcut_keys() -> bool {
+    true
+}
+
+/// キー入力のバッファのデフォルトの文字数
+fn default_buffer_size() -> usize {
+    32
+}
+
+/// 区切り文字のデフォルト値
+fn default_terminator_chars() -> String {
+    DEFAULT_TERMINATOR_CHARS.to_string()
 }
 
+/// 展開できるテキストのデフォルトの最大文字数
+fn default_max_expansion_len() -> usize {
+    DEFAULT_MAX_EXPANSION_LEN
+}
+
+/// 展開を中止するキーワードのデフォルト値
+fn default_abort_keyword() -> String {
+    "xxstop".to_string()
+}
+
+/// 書式を省略した{date}トークンの書式のデフォルト値
+fn default_date_format() -> String {
+    DEFAULT_DATE_FORMAT.to_string()
+}
+
+/// デフォルトの区切り文字（空白、タブ、改行）
+pub const DEFAULT_TERMINATOR_CHARS: &str = " \t\n";
+
+/// 展開できるテキストのデフォルトの最大文字数
+pub const DEFAULT_MAX_EXPANSION_LEN: usize = 10_000;
+
+/// スニペットごとに指定できる、キーワードを削除する前の待機時間の上限（ミリ秒）
+pub const MAX_PRE_DELAY_MS: u64 = 5_000;
+
+/// 貼り付けのキー入力を送信できなかったときに再試行する回数の既定値
+pub const DEFAULT_PASTE_RETRIES: u32 = 2;
+
+/// 貼り付けを再試行する回数の上限
+pub const MAX_PASTE_RETRIES: u32 = 10;
+
+/// キー入力のバッファの最大文字数
+pub const MAX_BUFFER_SIZE: usize = 256;
+
 impl Default for Settings {
     fn default() -> Self {
         let mut snippets = Vec::new();
@@ -117,10 +673,424 @@ impl Default for Settings {
         
         Self {
             enabled: true,
+            paused_until: None,
             snippets,
+            active_profile: default_profile_name(),
+            profiles: BTreeMap::new(),
             start_with_system: false,
+            auto_start_method: AutoStartMethod::default(),
             toggle_hotkey: None,
             open_window_hotkey: None,
+            case_sensitive: false,
+            require_terminator: false,
+            keep_terminator: false,
+            ignore_shortcut_keys: default_ignore_shortcut_keys(),
+            terminator_chars: default_terminator_chars(),
+            capture_hotkey: None,
+            undo_hotkey: None,
+            picker_hotkey: None,
+            show_expansion_overlay: false,
+            notify_on_expand: ExpandNotification::default(),
+            on_expand_command: None,
+            category_snippet_types: BTreeMap::new(),
+            locale: String::new(),
+            default_date_format: default_date_format(),
+            disabled_apps: Vec::new(),
+            conflict_tools: Vec::new(),
+            ignored_conflict_tools: Vec::new(),
+            ui_scale: default_ui_scale(),
+            window_geometry: None,
+            close_to_tray: default_close_to_tray(),
+            buffer_size: default_buffer_size(),
+            trim_trailing_whitespace: false,
+            max_expansion_len: default_max_expansion_len(),
+            timing: TimingProfile::default(),
+            theme: ThemePreference::default(),
+            abort_keyword: default_abort_keyword(),
+            dry_run: false,
+            enable_ipc: false,
+            ipc_port: default_ipc_port(),
+        }
+    }
+}
+
+impl Settings {
+    /// 最も長いキーワードを検出できるバッファの最小の文字数
+    /// 
+    /// 単語の先頭かどうかを判定できるよう、キーワードの直前の1文字も含める
+    pub fn min_buffer_size(&self) -> usize {
+        self.snippets.iter()
+            .map(|s| s.keyword.chars().count())
+            .max()
+            .unwrap_or(0) + 1
+    }
+    
+    /// キーワードの後に入力して展開を確定させる文字（区切り文字）かどうか
+    /// 
+    /// `,` `;` `=` はキー入力のバッファで `_` に置き換わるため、設定しても区切り文字にならない
+    pub fn is_terminator(&self, c: char) -> bool {
+        self.terminator_chars.contains(c)
+    }
+    
+    /// 改行（エンター）で展開を確定させるかどうか
+    pub fn newline_terminates_keyword(&self) -> bool {
+        self.require_terminator && self.is_terminator('\n')
+    }
+    
+    /// 実際に使うバッファの文字数
+    /// 
+    /// 設定した文字数より長いキーワードが追加された場合も検出できるよう、最小の文字数以上にする
+    pub fn effective_buffer_size(&self) -> usize {
+        self.buffer_size.clamp(self.min_buffer_size(), MAX_BUFFER_SIZE.max(self.min_buffer_size()))
+    }
+    
+    /// キーワードが重複している有効なスニペットの組を取得する
+    /// 
+    /// 同じキーワードのスニペットは一覧で先にあるものだけが展開されるため、保存時の警告に使う。
+    /// 大文字と小文字を区別しない設定では、大文字と小文字だけが異なるキーワードも重複とみなす
+    /// 
+    /// # 戻り値
+    /// 重複しているスニペットの位置の組（組の中では一覧で先にあるものが前）
+    pub fn find_keyword_conflicts(&self) -> Vec<(usize, usize)> {
+        let enabled: Vec<(usize, String)> = self.snippets.iter()
+            .enumerate()
+            .filter(|(_, snippet)| snippet.enabled)
+            .map(|(index, snippet)| {
+                let keyword = if self.case_sensitive { snippet.keyword.clone() } else { snippet.keyword.to_lowercase() };
+                (index, keyword)
+            })
+            .collect();
+        
+        let mut conflicts = Vec::new();
+        for (position, (first, keyword)) in enabled.iter().enumerate() {
+            for (second, other) in &enabled[position + 1..] {
+                if keyword == other {
+                    conflicts.push((*first, *second));
+                }
+            }
+        }
+        conflicts
+    }
+    
+    /// カテゴリの一覧を名前順で取得する
+    /// 
+    /// スニペットが属するカテゴリに加えて、デフォルトの種類だけが設定されたカテゴリも含む
+    pub fn categories(&self) -> Vec<String> {
+        let categories: BTreeSet<&str> = self.snippets.iter()
+            .map(|s| s.category.as_str())
+            .chain(self.category_snippet_types.keys().map(String::as_str))
+            .collect();
+        categories.into_iter().map(str::to_string).collect()
+    }
+    
+    /// カテゴリの新しいスニペットのデフォルトの種類を取得する
+    pub fn default_snippet_type(&self, category: &str) -> Option<SnippetType> {
+        self.category_snippet_types.get(category).cloned()
+    }
+    
+    /// カテゴリの名前を変更する
+    /// 
+    /// 既存のカテゴリと同じ名前にした場合は、そのカテゴリに統合される
+    /// 
+    /// # 戻り値
+    /// 移動したスニペットの数
+    pub fn rename_category(&mut self, old_name: &str, new_name: &str) -> usize {
+        let mut count = 0;
+        for snippet in self.snippets.iter_mut().filter(|s| s.category == old_name) {
+            snippet.category = new_name.to_string();
+            count += 1;
+        }
+        
+        if let Some(snippet_type) = self.category_snippet_types.remove(old_name) {
+            self.category_snippet_types.entry(new_name.to_string()).or_insert(snippet_type);
+        }
+        
+        count
+    }
+    
+    /// カテゴリを削除し、属するスニペットを未分類に移す
+    /// 
+    /// # 戻り値
+    /// 移動したスニペットの数
+    pub fn delete_category(&mut self, name: &str) -> usize {
+        self.category_snippet_types.remove(name);
+        if name == UNCATEGORIZED_CATEGORY {
+            return 0;
+        }
+        self.rename_category(name, UNCATEGORIZED_CATEGORY)
+    }
+    
+    /// プロファイルの一覧を名前順で取得する（選択中のプロファイルを含む）
+    pub fn profile_names(&self) -> Vec<String> {
+        let names: BTreeSet<&str> = self.profiles.keys()
+            .map(String::as_str)
+            .chain(std::iter::once(self.active_profile.as_str()))
+            .collect();
+        names.into_iter().map(str::to_string).collect()
+    }
+    
+    /// 次に追加するスニペットのID
+    /// 
+    /// プロファイルを切り替えてもIDが重複しないよう、保管中のプロファイルのスニペットも含めた最大のIDの次を返す
+    pub fn next_snippet_id(&self) -> u64 {
+        self.snippets.iter()
+            .chain(self.profiles.values().flatten())
+            .map(|s| s.id)
+            .max()
+            .unwrap_or(0) + 1
+    }
+    
+    /// プロファイルを切り替える
+    /// 
+    /// 選択中のプロファイルのスニペットを保管し、指定したプロファイルのスニペットを展開の対象にする。
+    /// 存在しないプロファイルを指定した場合は、スニペットのない新しいプロファイルになる
+    /// 
+    /// # 戻り値
+    /// 切り替えたかどうか（選択中のプロファイルを指定した場合はfalse）
+    pub fn switch_profile(&mut self, name: &str) -> bool {
+        if name == self.active_profile {
+            return false;
+        }
+        
+        let snippets = self.profiles.remove(name).unwrap_or_default();
+        let previous_snippets = std::mem::replace(&mut self.snippets, snippets);
+        let previous_profile = std::mem::replace(&mut self.active_profile, name.to_string());
+        self.profiles.insert(previous_profile, previous_snippets);
+        true
+    }
+    
+    /// 展開を一定時間だけ一時停止する
+    /// 
+    /// # 引数
+    /// * `duration` - 一時停止する時間
+    pub fn pause_for(&mut self, duration: Duration) {
+        self.paused_until = Some(Local::now() + duration);
+    }
+    
+    /// 一時停止を解除する
+    pub fn resume(&mut self) {
+        self.paused_until = None;
+    }
+    
+    /// 一時停止中かどうか（期限が過ぎている場合は一時停止中とみなさない）
+    pub fn is_paused(&self) -> bool {
+        self.paused_until.is_some_and(|until| Local::now() < until)
+    }
+    
+    /// 一時停止の期限が過ぎていれば解除する
+    /// 
+    /// # 戻り値
+    /// 一時停止中かどうか
+    pub fn refresh_pause(&mut self) -> bool {
+        if self.paused_until.is_some() && !self.is_paused() {
+            log::info!("Pause expired, resuming expansion");
+            self.resume();
+        }
+        self.paused_until.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_pause_expires() {
+        let mut settings = Settings::default();
+        assert!(!settings.refresh_pause());
+        
+        settings.pause_for(Duration::minutes(15));
+        assert!(settings.is_paused());
+        assert!(settings.refresh_pause());
+        
+        // 期限が過ぎると次の確認で解除される
+        settings.paused_until = Some(Local::now() - Duration::seconds(1));
+        assert!(!settings.is_paused());
+        assert!(!settings.refresh_pause());
+        assert_eq!(settings.paused_until, None);
+    }
+    
+    #[test]
+    fn test_snippet_from_selection() {
+        let snippet = Snippet::from_selection("\n  Hello team,\nThanks for the update.\n");
+        
+        assert_eq!(snippet.name, "Hello team,");
+        assert_eq!(snippet.keyword, "");
+        assert_eq!(snippet.content, "\n  Hello team,\nThanks for the update.\n");
+        assert_eq!(snippet.snippet_type, SnippetType::Static);
+        assert_eq!(snippet.category, DEFAULT_CATEGORY);
+        assert!(snippet.enabled);
+    }
+    
+    #[test]
+    fn test_effective_buffer_size_fits_longest_keyword() {
+        let mut settings = Settings {
+            snippets: vec![Snippet::new(
+                "Long".to_string(),
+                "averyveryverylongkeyword".to_string(),
+                String::new(),
+                SnippetType::Static,
+                DEFAULT_CATEGORY.to_string(),
+            )],
+            ..Default::default()
+        };
+        
+        assert_eq!(settings.buffer_size, 32);
+        assert_eq!(settings.effective_buffer_size(), 32);
+        
+        settings.buffer_size = 8;
+        assert_eq!(settings.min_buffer_size(), 25);
+        assert_eq!(settings.effective_buffer_size(), 25);
+        
+        settings.buffer_size = 10_000;
+        assert_eq!(settings.effective_buffer_size(), MAX_BUFFER_SIZE);
+    }
+    
+    #[test]
+    fn test_switch_profile_swaps_snippets() {
+        let mut settings = Settings::default();
+        let default_count = settings.snippets.len();
+        assert_eq!(settings.active_profile, DEFAULT_PROFILE);
+        
+        // 新しいプロファイルはスニペットが空の状態で始まる
+        assert!(settings.switch_profile("Work"));
+        assert!(settings.snippets.is_empty());
+        assert_eq!(settings.profile_names(), vec!["Default".to_string(), "Work".to_string()]);
+        
+        settings.snippets.push(Snippet::new(
+            "Meeting".to_string(),
+            "mtg".to_string(),
+            "Let's meet".to_string(),
+            SnippetType::Static,
+            DEFAULT_CATEGORY.to_string(),
+        ));
+        
+        assert!(settings.switch_profile(DEFAULT_PROFILE));
+        assert_eq!(settings.snippets.len(), default_count);
+        assert_eq!(settings.profiles["Work"].len(), 1);
+        assert!(!settings.switch_profile(DEFAULT_PROFILE));
+    }
+    
+    #[test]
+    fn test_theme_preference_defaults_to_system() {
+        // テーマを保存していない設定ファイルはWindowsのテーマに合わせる
+        let mut value = serde_json::to_value(Settings::default()).unwrap();
+        value.as_object_mut().unwrap().remove("theme");
+        let settings: Settings = serde_json::from_value(value).unwrap();
+        assert_eq!(settings.theme, ThemePreference::System);
+    }
+    
+    #[test]
+    fn test_hotkey_display() {
+        let hotkey = Hotkey { modifiers: Hotkey::CTRL | Hotkey::ALT, key_code: 0x53 };
+        assert_eq!(hotkey.to_string(), "Ctrl+Alt+S");
+        
+        let hotkey = Hotkey { modifiers: Hotkey::SHIFT | Hotkey::WIN, key_code: 0x7B };
+        assert_eq!(hotkey.to_string(), "Shift+Win+F12");
+        
+        let hotkey = Hotkey { modifiers: Hotkey::CTRL, key_code: 0x31 };
+        assert_eq!(hotkey.to_string(), "Ctrl+1");
+    }
+    
+    #[test]
+    fn test_timing_profile_presets() {
+        // 既定値は従来の待機時間と同じSafe
+        assert_eq!(Settings::default().timing, TimingProfile::preset(TimingPreset::Safe));
+        
+        for preset in TimingPreset::ALL {
+            assert_eq!(TimingProfile::preset(preset).matching_preset(), Some(preset));
         }
+        
+        let mut custom = TimingProfile::preset(TimingPreset::Fast);
+        custom.post_paste_ms += 1;
+        assert_eq!(custom.matching_preset(), None);
+        
+        // 一部の値だけを指定した場合、残りはSafeの値になる
+        let partial: TimingProfile = serde_json::from_str(r#"{"char_delay_ms": 5}"#).unwrap();
+        assert_eq!(partial.char_delay_ms, 5);
+        assert_eq!(partial.post_paste_ms, 200);
+        assert_eq!(partial.paste_retries, DEFAULT_PASTE_RETRIES);
+    }
+    
+    #[test]
+    fn test_timing_preset_keeps_paste_retries() {
+        let mut timing = TimingProfile::preset(TimingPreset::Safe);
+        timing.paste_retries = 5;
+        
+        // 再試行回数を変えてもプリセットのまま
+        assert_eq!(timing.matching_preset(), Some(TimingPreset::Safe));
+        
+        let fast = timing.with_preset(TimingPreset::Fast);
+        assert_eq!(fast.paste_retries, 5);
+        assert_eq!(fast.matching_preset(), Some(TimingPreset::Fast));
+    }
+    
+    #[test]
+    fn test_snippet_matches_query() {
+        let mut snippet = Snippet::new(
+            "Email Signature".to_string(),
+            "sig".to_string(),
+            "Best regards".to_string(),
+            SnippetType::Static,
+            "Templates".to_string(),
+        );
+        
+        assert!(snippet.matches_query(""));
+        assert!(snippet.matches_query("SIGNATURE"));
+        assert!(snippet.matches_query(" sig "));
+        assert!(snippet.matches_query("templ"));
+        assert!(!snippet.matches_query("regards"));
+        
+        // 説明も検索の対象にする
+        assert!(!snippet.matches_query("work"));
+        snippet.description = "Signature for work email".to_string();
+        assert!(snippet.matches_query("work"));
+    }
+    
+    #[test]
+    fn test_snippet_from_selection_truncates_long_name() {
+        let text = "あ".repeat(40);
+        let snippet = Snippet::from_selection(&text);
+        
+        assert_eq!(snippet.name.chars().count(), SELECTION_NAME_MAX_CHARS + 1);
+        assert!(snippet.name.ends_with('…'));
+        assert_eq!(snippet.content, text);
+    }
+    
+    #[test]
+    fn test_find_keyword_conflicts() {
+        let snippet = |keyword: &str| {
+            Snippet::new(keyword.to_string(), keyword.to_string(), String::new(), SnippetType::Static, "Test".to_string())
+        };
+        let mut settings = Settings {
+            snippets: vec![snippet("sig"), snippet("addr"), snippet("SIG"), snippet("addr"), snippet("sig")],
+            ..Default::default()
+        };
+        
+        // 大文字と小文字を区別しない場合は、大文字だけが異なるキーワードも重複とみなす
+        assert_eq!(settings.find_keyword_conflicts(), vec![(0, 2), (0, 4), (1, 3), (2, 4)]);
+        
+        settings.case_sensitive = true;
+        assert_eq!(settings.find_keyword_conflicts(), vec![(0, 4), (1, 3)]);
+        
+        // 無効なスニペットは展開されないため重複とみなさない
+        settings.snippets[3].enabled = false;
+        assert_eq!(settings.find_keyword_conflicts(), vec![(0, 4)]);
+    }
+    
+    #[test]
+    fn test_window_geometry_clamped_to_screen() {
+        let geometry = WindowGeometry { x: 2500.0, y: -40.0, width: 800.0, height: 600.0 };
+        assert_eq!(
+            geometry.clamped_to(1920.0, 1080.0),
+            WindowGeometry { x: 1120.0, y: 0.0, width: 800.0, height: 600.0 },
+        );
+        
+        // 画面より大きいウィンドウは画面の大きさに縮める
+        let geometry = WindowGeometry { x: 100.0, y: 100.0, width: 3000.0, height: 600.0 };
+        assert_eq!(
+            geometry.clamped_to(1920.0, 1080.0),
+            WindowGeometry { x: 0.0, y: 100.0, width: 1920.0, height: 600.0 },
+        );
     }
 }
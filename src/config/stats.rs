@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+
+/// 展開の記録を保存するファイルの名前（設定ファイルと同じフォルダに作成する）
+pub const STATS_FILE_NAME: &str = "stats.json";
+
+/// 展開の記録を保持する日数（起動時と展開の記録時にこれより古い記録を削除する）
+pub const STATS_RETENTION_DAYS: i64 = 30;
+
+/// 展開の記録の1件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpansionRecord {
+    /// 展開した時刻（UNIX時間の秒）
+    pub timestamp: i64,
+    /// 展開したスニペットのID
+    pub snippet_id: u64,
+    /// 展開したときのスニペットの名前
+    pub snippet_name: String,
+}
+
+/// 展開の記録（Statsタブの集計に使う）
+#[derive(Debug, Clone, Default)]
+pub struct ExpansionStats {
+    /// 記録を保存するファイルのパス（Noneの場合は保存しない）
+    path: Option<PathBuf>,
+    /// 展開の記録（古い順）
+    records: Vec<ExpansionRecord>,
+}
+
+impl ExpansionStats {
+    /// ファイルから展開の記録を読み込み、保持する日数より古い記録を削除する
+    /// 
+    /// ファイルがない場合や読み込めない場合は空の記録にする
+    /// 
+    /// # 引数
+    /// * `path` - 記録を保存するファイルのパス
+    /// * `now` - 現在の時刻
+    pub fn load(path: &Path, now: DateTime<Local>) -> Self {
+        let records = match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                log::warn!("Failed to read expansion statistics, starting over: {}", e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        
+        let mut stats = Self {
+            path: Some(path.to_path_buf()),
+            records,
+        };
+        let purged = stats.purge_older_than(now - Duration::days(STATS_RETENTION_DAYS));
+        if purged > 0 {
+            log::info!("Removed {} expansion records older than {} days", purged, STATS_RETENTION_DAYS);
+            if let Err(e) = stats.save() {
+                log::error!("Failed to save expansion statistics: {}", e);
+            }
+        }
+        stats
+    }
+    
+    /// 展開を記録する
+    /// 
+    /// 起動したまま使い続けても記録が増え続けないよう、保持する日数より古い記録を削除する
+    /// 
+    /// # 引数
+    /// * `snippet_id` - 展開したスニペットのID
+    /// * `snippet_name` - 展開したスニペットの名前
+    /// * `time` - 展開した時刻
+    pub fn record(&mut self, snippet_id: u64, snippet_name: &str, time: DateTime<Local>) {
+        self.purge_older_than(time - Duration::days(STATS_RETENTION_DAYS));
+        self.records.push(ExpansionRecord {
+            timestamp: time.timestamp(),
+            snippet_id,
+            snippet_name: snippet_name.to_string(),
+        });
+    }
+    
+    /// 指定した時刻より古い記録を削除する
+    /// 
+    /// # 戻り値
+    /// 削除した記録の数
+    pub fn purge_older_than(&mut self, cutoff: DateTime<Local>) -> usize {
+        let before = self.records.len();
+        self.records.retain(|record| record.timestamp >= cutoff.timestamp());
+        before - self.records.len()
+    }
+    
+    /// 記録をファイルに保存する
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        
+        // 書き込み途中で終了しても記録が壊れないように、一時ファイルに書き込んでから置き換える
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_string(&self.records)?)?;
+        std::fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+    
+    /// 記録した展開の回数を取得する
+    pub fn total(&self) -> usize {
+        self.records.len()
+    }
+    
+    /// 展開した回数の多いスニペットを取得する
+    /// 
+    /// 名前は最後に展開したときのものを使う。同じ回数の場合は名前順に並べる
+    /// 
+    /// # 引数
+    /// * `limit` - 取得する最大数
+    /// 
+    /// # 戻り値
+    /// スニペットの名前と展開した回数（回数の多い順）
+    pub fn top_snippets(&self, limit: usize) -> Vec<(String, usize)> {
+        let mut counts: HashMap<u64, (String, usize)> = HashMap::new();
+        for record in &self.records {
+            let entry = counts.entry(record.snippet_id).or_insert_with(|| (String::new(), 0));
+            entry.0.clone_from(&record.snippet_name);
+            entry.1 += 1;
+        }
+        
+        let mut top: Vec<(String, usize)> = counts.into_values().collect();
+        top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top.truncate(limit);
+        top
+    }
+    
+    /// 日ごとの展開の回数を取得する
+    /// 
+    /// # 引数
+    /// * `today` - 最後の日
+    /// * `days` - 集計する日数
+    /// 
+    /// # 戻り値
+    /// 日付と展開の回数（古い日から順に `days` 日分。展開していない日は0）
+    pub fn daily_counts(&self, today: NaiveDate, days: usize) -> Vec<(NaiveDate, usize)> {
+        let mut counts: HashMap<NaiveDate, usize> = HashMap::new();
+        for record in &self.records {
+            if let Some(time) = Local.timestamp_opt(record.timestamp, 0).single() {
+                *counts.entry(time.date_naive()).or_default() += 1;
+            }
+        }
+        
+        (0..days)
+            .rev()
+            .filter_map(|offset| today.checked_sub_signed(Duration::days(offset as i64)))
+            .map(|date| (date, counts.get(&date).copied().unwrap_or(0)))
+            .collect()
+    }
+}
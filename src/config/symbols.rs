@@ -0,0 +1,43 @@
+use super::settings::{Snippet, SnippetType};
+
+/// 記号のスニペットのカテゴリ
+pub const SYMBOL_CATEGORY: &str = "Symbols";
+
+/// 記号のスニペットの初期セット（名前、キーワード、本文）
+pub const STARTER_SYMBOLS: &[(&str, &str, &str)] = &[
+    ("Shrug", ":shrug:", "¯\\_(ツ)_/¯"),
+    ("Table flip", ":tableflip:", "(╯°□°)╯︵ ┻━┻"),
+    ("Thumbs up", ":thumbsup:", "👍"),
+    ("Smile", ":smile:", "😄"),
+    ("Joy", ":joy:", "😂"),
+    ("Thinking", ":thinking:", "🤔"),
+    ("Party", ":tada:", "🎉"),
+    ("Rocket", ":rocket:", "🚀"),
+    ("Heart", ":heart:", "❤️"),
+    ("Check mark", ":check:", "✓"),
+    ("Right arrow", ":arrow:", "→"),
+    ("Degree", ":deg:", "°"),
+    ("Copyright", ":copy:", "©"),
+    ("Trademark", ":tm:", "™"),
+    ("Euro", ":euro:", "€"),
+];
+
+/// 記号の初期セットをスニペットとして作成する
+/// 
+/// 記号は単語の途中でも入力できるよう、単語の先頭でなくても展開する
+pub fn starter_symbol_snippets() -> Vec<Snippet> {
+    STARTER_SYMBOLS
+        .iter()
+        .map(|&(name, keyword, content)| {
+            let mut snippet = Snippet::new(
+                name.to_string(),
+                keyword.to_string(),
+                content.to_string(),
+                SnippetType::Static,
+                SYMBOL_CATEGORY.to_string(),
+            );
+            snippet.word_boundary = false;
+            snippet
+        })
+        .collect()
+}
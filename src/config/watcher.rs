@@ -0,0 +1,87 @@
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// 変更を通知するまでに待つ時間
+/// 
+/// エディタの保存では書き込みや名前の変更が続けて発生するため、変更が落ち着いてから1回だけ通知する
+pub const RELOAD_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// 設定ファイルの変更を監視する
+/// 
+/// 保存時に一時ファイルから置き換えられても監視が外れないよう、ファイルではなく親ディレクトリを監視する
+pub struct SettingsWatcher {
+    /// ファイルシステムの監視（破棄すると監視が止まる）
+    _watcher: RecommendedWatcher,
+    /// 変更の通知の受信器
+    receiver: Receiver<()>,
+}
+
+impl SettingsWatcher {
+    /// 設定ファイルの監視を開始する
+    /// 
+    /// # 引数
+    /// * `config_path` - 監視する設定ファイルのパス
+    /// * `on_change` - 変更を通知したときに呼び出す関数（UIの再描画の要求など）
+    pub fn start(
+        config_path: &Path,
+        on_change: impl Fn() + Send + 'static,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let config_dir = config_path.parent()
+            .ok_or_else(|| format!("Settings file {:?} has no parent directory", config_path))?;
+        let target = config_path.to_path_buf();
+        
+        let (event_sender, event_receiver) = crossbeam_channel::unbounded::<()>();
+        let mut watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+            match result {
+                Ok(event) if Self::is_target_event(&event, &target) => {
+                    let _ = event_sender.send(());
+                }
+                Ok(_) => {}
+                Err(e) => log::warn!("Settings watch error: {}", e),
+            }
+        })?;
+        watcher.watch(config_dir, RecursiveMode::NonRecursive)?;
+        
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        thread::spawn(move || {
+            while event_receiver.recv().is_ok() {
+                // 変更が落ち着くまで待ち、続けて発生した変更をまとめる
+                loop {
+                    match event_receiver.recv_timeout(RELOAD_DEBOUNCE) {
+                        Ok(()) => continue,
+                        Err(RecvTimeoutError::Timeout) => break,
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+                
+                if sender.send(()).is_err() {
+                    return;
+                }
+                on_change();
+            }
+        });
+        
+        log::info!("Watching {:?} for changes", config_path);
+        Ok(Self {
+            _watcher: watcher,
+            receiver,
+        })
+    }
+    
+    /// 設定ファイルの変更を示すイベントかどうか（一時ファイルやバックアップの変更は除く）
+    fn is_target_event(event: &Event, target: &Path) -> bool {
+        !event.kind.is_access() && event.paths.iter().any(|path| path == target)
+    }
+    
+    /// 前回の確認以降に設定ファイルが変更されたかどうか
+    pub fn try_recv(&self) -> bool {
+        let mut changed = false;
+        while self.receiver.try_recv().is_ok() {
+            changed = true;
+        }
+        changed
+    }
+}
@@ -1,18 +1,66 @@
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::cell::Cell;
 use once_cell::sync::OnceCell;
 use windows::Win32::Foundation::{LPARAM, LRESULT, WPARAM};
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    GetAsyncKeyState, GetKeyState, GetKeyboardLayout, MapVirtualKeyExW, ToUnicodeEx, MAPVK_VK_TO_VSC,
+    VK_BACK, VK_CAPITAL, VK_CONTROL, VK_LWIN, VK_MENU, VK_RMENU, VK_RWIN, VK_SHIFT,
+};
 use windows::Win32::UI::WindowsAndMessaging::{
-    CallNextHookEx, SetWindowsHookExW, UnhookWindowsHookEx,
+    CallNextHookEx, GetForegroundWindow, GetWindowThreadProcessId, SetWindowsHookExW, UnhookWindowsHookEx,
     WH_KEYBOARD_LL, KBDLLHOOKSTRUCT, LLKHF_INJECTED, HHOOK, KBDLLHOOKSTRUCT_FLAGS,
 };
 
-use crate::keyboard::{KeyboardState, SharedKeyboardState};
+use crate::keyboard::{Key, KeyTranslation, KeyTranslator, KeyboardState, Modifiers, SharedKeyboardState};
 use crate::replacement::ReplacementEngine;
 
 // グローバル状態のためのスレッドセーフなOnceCell
 static GLOBAL_KEYBOARD_STATE: OnceCell<std::sync::Weak<Mutex<KeyboardState>>> = OnceCell::new();
 static GLOBAL_REPLACEMENT_ENGINE: OnceCell<std::sync::Weak<Mutex<ReplacementEngine>>> = OnceCell::new();
+// 置換の実行中かどうか（実行中はワーカースレッドがエンジンをロックしているため、ロックせずに確認する）
+static GLOBAL_REPLACEMENT_BUSY: OnceCell<Arc<AtomicBool>> = OnceCell::new();
+
+/// ToUnicodeExでキーボードの状態を変更しないフラグ（Windows 10 1607以降）
+/// 
+/// 変更すると入力途中のデッドキーが消費され、前面のアプリでアクセント付きの文字が入力できなくなる
+const TO_UNICODE_KEEP_KEYBOARD_STATE: u32 = 0x4;
+
+/// 前面のアプリのキーボード配列に従って、ToUnicodeExでキーを文字に変換する
+#[derive(Debug, Default)]
+pub struct LayoutKeyTranslator;
+
+impl KeyTranslator for LayoutKeyTranslator {
+    fn translate(&self, key: Key, modifiers: Modifiers) -> KeyTranslation {
+        unsafe {
+            // キーボード配列はスレッドごとに異なるため、フックのスレッドではなく前面のウィンドウのスレッドの配列を使う
+            let thread_id = GetWindowThreadProcessId(GetForegroundWindow(), None);
+            let layout = GetKeyboardLayout(thread_id);
+            let scan_code = MapVirtualKeyExW(key.0, MAPVK_VK_TO_VSC, layout);
+            
+            let mut buffer = [0u16; 8];
+            let length = ToUnicodeEx(
+                key.0,
+                scan_code,
+                &modifiers.key_state(),
+                &mut buffer,
+                TO_UNICODE_KEEP_KEYBOARD_STATE,
+                layout,
+            );
+            
+            if length < 0 {
+                return KeyTranslation::DeadKey;
+            }
+            
+            // 1文字になった場合だけ使い、複数の文字になるキーや変換できないキーはUS配列として変換する
+            let mut chars = char::decode_utf16(buffer[..length as usize].iter().copied());
+            match (chars.next(), chars.next()) {
+                (Some(Ok(c)), None) => KeyTranslation::Char(c),
+                _ => KeyTranslation::Unknown,
+            }
+        }
+    }
+}
 
 /// キーボードフックのコールバック関数
 pub extern "system" fn keyboard_hook_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
@@ -68,6 +116,9 @@ impl KeyboardHook {
         // グローバル参照を設定（安全に初期化）
         let _ = GLOBAL_KEYBOARD_STATE.set(Arc::downgrade(&self.keyboard_state));
         let _ = GLOBAL_REPLACEMENT_ENGINE.set(Arc::downgrade(&self.replacement_engine));
+        if let Some(busy) = self.replacement_engine.lock().ok().and_then(|engine| engine.worker_busy_flag()) {
+            let _ = GLOBAL_REPLACEMENT_BUSY.set(busy);
+        }
         
         // キーボードフックを設定
         unsafe {
@@ -105,48 +156,63 @@ fn process_key_event(
     wparam: WPARAM,
     kb: &KBDLLHOOKSTRUCT,
 ) {
+    // 置換の実行中に入力されたキーはバッファに加えずに捨てる
+    // （置換後のテキストと混ざった入力で、誤ってキーワードが一致しないようにする）
+    if GLOBAL_REPLACEMENT_BUSY.get().is_some_and(|busy| busy.load(Ordering::SeqCst)) {
+        log::debug!("Ignoring key input while a replacement is in progress");
+        return;
+    }
+    
     // キーボード状態を更新
     if let Ok(mut state) = keyboard_state.lock() {
+        // ウィンドウを切り替えた場合は、前のウィンドウで入力した文字を捨てる
+        state.track_foreground_window(unsafe { GetForegroundWindow() }.0);
+        
         // キー入力を処理
-        state.process_key_event(wparam.0 as u32, kb.vkCode);
+        state.process_key_event(wparam.0 as u32, kb.vkCode, current_modifiers());
         
         // キーワードの置換を試みる
         if let Ok(engine) = replacement_engine.lock() {
-            if state.should_check_replacement() {
+            // 展開後に文字が入力された場合は、直前の展開を取り消せなくする
+            if is_typing_event(wparam, kb.vkCode) {
+                engine.clear_last_expansion();
+            }
+            
+            // 展開しないアプリが前面にある場合は置換しない
+            // （バッファへの入力は続けるため、許可されたアプリに戻ると展開が再開される）
+            // バックスペースで文字を消した結果がキーワードと一致しても展開しない
+            let is_backspace = kb.vkCode == VK_BACK.0 as u32;
+            if !is_backspace && state.should_check_replacement() && !engine.is_disabled_in_foreground_app() {
                 // バッファから現在のキーワード候補を取得
                 let keyword = state.get_keyword_candidate();
                 
+                // 中止のキーワードが入力された場合は、置換せずにバッファを捨てる
+                if engine.check_abort(&keyword) {
+                    state.clear_buffer();
+                    return;
+                }
+                
                 // キーワードが見つかれば置換
                 if !keyword.is_empty() {
                     log::debug!("Checking for replacement with keyword: '{}'", keyword);
-                    if let Some((replacement, keyword_length)) = engine.check_for_replacements(&keyword) {
-                        log::debug!("Found replacement: '{}' for keyword: '{}'", replacement, keyword);
+                    if let Some(found) = engine.find_replacement(&keyword) {
+                        log::debug!("Found replacement: '{}' for keyword: '{}'", found.text, keyword);
                         
-                        // バッファをクリア (検出されたキーワードを消去)
-                        // 注: これにより連続的な置換を防止する
-                        state.clear_buffer();
+                        // 入力フィールドがある場合は、フォームで値が入力されてから展開する
+                        // （キャンセルされても何も入力しないよう、ここではバッファのクリアだけを行う）
+                        if engine.request_field_form(&found) {
+                            state.keyword_replaced();
+                            return;
+                        }
                         
-                        // ここでmutexをドロップして、置換処理中に他のキー入力が処理できるようにする
-                        drop(state);
-                        drop(engine);
+                        // バッファをクリアし、展開直後のガードを設定する
+                        // 注: これにより連続的な置換や、次のキーワードとの誤った一致を防止する
+                        state.keyword_replaced();
                         
-                        // 再度エンジンを取得して置換実行
-                        if let Ok(engine) = replacement_engine.lock() {
-                            // 置換実行 - 改良されたバックスペースとペースト処理を使用
-                            // キーワードの長さを正確に使用
-                            if engine.perform_replacement_with_backspace(&replacement, keyword_length) {
-                                log::debug!("Successfully replaced '{}' with '{}'", keyword, replacement);
-                            } else {
-                                log::error!("Failed to replace '{}' with '{}'", keyword, replacement);
-                                
-                                // 置換が失敗した場合、キーボード状態を明示的にリセット
-                                if let Ok(mut state) = keyboard_state.lock() {
-                                    state.clear_buffer();
-                                }
-                                
-                                // モディファイアキーをリセットして、キーボードを正常な状態に戻す
-                                engine.reset_modifier_keys();
-                            }
+                        // 置換はワーカースレッドで実行し、フックはすぐに戻る
+                        // （待機を含む置換の間、キー入力の処理が止まらないようにする）
+                        if !engine.queue_replacement(found) {
+                            log::warn!("Replacement for '{}' was not started", keyword);
                         }
                     }
                 }
@@ -154,3 +220,42 @@ fn process_key_event(
         }
     }
 }
+
+/// 現在の修飾キーの状態を取得する
+/// 
+/// 低レベルフックでは押下中のキーの状態がスレッドのキー状態に反映されていないため、
+/// SHIFTは物理的な状態を返すGetAsyncKeyStateで、CAPSLOCKはトグル状態を返すGetKeyStateで取得する
+fn current_modifiers() -> Modifiers {
+    unsafe {
+        Modifiers {
+            shift: GetAsyncKeyState(VK_SHIFT.0 as i32) < 0,
+            caps_lock: GetKeyState(VK_CAPITAL.0 as i32) & 1 != 0,
+            shortcut: is_shortcut_modifier_pressed(),
+            alt_gr: GetAsyncKeyState(VK_RMENU.0 as i32) < 0,
+        }
+    }
+}
+
+/// CTRL・ALT・Winキーのいずれかが押されているかどうか
+fn is_shortcut_modifier_pressed() -> bool {
+    let is_pressed = |vk: u16| unsafe { GetAsyncKeyState(vk as i32) } < 0;
+    [VK_CONTROL, VK_MENU, VK_LWIN, VK_RWIN].iter().any(|key| is_pressed(key.0))
+}
+
+/// 文字の入力にあたるキー押下かどうか
+/// 
+/// 展開の取り消しのホットキーで取り消しが無効にならないよう、修飾キー自体と、
+/// CTRL・ALT・Winキーを押しながらの入力は除く
+fn is_typing_event(wparam: WPARAM, vk_code: u32) -> bool {
+    // WM_KEYDOWN (0x0100) または WM_SYSKEYDOWN (0x0104) の場合
+    if wparam.0 != 0x0100 && wparam.0 != 0x0104 {
+        return false;
+    }
+    
+    // SHIFT、CTRL、ALT（左右を含む）、CAPSLOCK、Winキー
+    if matches!(vk_code, 0x10..=0x12 | 0x14 | 0x5B | 0x5C | 0xA0..=0xA5) {
+        return false;
+    }
+    
+    !is_shortcut_modifier_pressed()
+}
@@ -0,0 +1,157 @@
+use std::thread::{self, JoinHandle};
+use crossbeam_channel::{Receiver, Sender};
+use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+use windows::Win32::System::Threading::GetCurrentThreadId;
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    RegisterHotKey, UnregisterHotKey, HOT_KEY_MODIFIERS, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT, MOD_SHIFT, MOD_WIN,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    GetMessageW, PeekMessageW, PostThreadMessageW, MSG, PM_NOREMOVE, WM_HOTKEY, WM_QUIT,
+};
+
+use crate::config::settings::Hotkey;
+
+/// グローバルホットキーで実行する操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeyAction {
+    /// 展開の有効と無効を切り替える
+    ToggleEnabled,
+    /// ウィンドウを表示する
+    ShowWindow,
+    /// 選択中のテキストからスニペットを作成する
+    CaptureSelection,
+    /// 直前の展開を取り消す
+    UndoExpansion,
+    /// スニペットを検索して挿入するウィンドウを開く
+    OpenPicker,
+}
+
+/// 設定のホットキー修飾キーをRegisterHotKey用の修飾キーに変換する
+/// 
+/// 設定では Ctrl=1, Alt=2, Shift=4, Win=8 のビットで保持している
+fn to_hot_key_modifiers(hotkey: &Hotkey) -> HOT_KEY_MODIFIERS {
+    let mut modifiers = MOD_NOREPEAT;
+    if hotkey.modifiers & Hotkey::CTRL != 0 {
+        modifiers |= MOD_CONTROL;
+    }
+    if hotkey.modifiers & Hotkey::ALT != 0 {
+        modifiers |= MOD_ALT;
+    }
+    if hotkey.modifiers & Hotkey::SHIFT != 0 {
+        modifiers |= MOD_SHIFT;
+    }
+    if hotkey.modifiers & Hotkey::WIN != 0 {
+        modifiers |= MOD_WIN;
+    }
+    modifiers
+}
+
+/// グローバルホットキーを管理する構造体
+/// 
+/// 専用スレッドでホットキーを登録し、WM_HOTKEYを受け取るとチャネル経由で通知する
+pub struct HotkeyManager {
+    /// メッセージループを実行しているスレッドのID
+    thread_id: u32,
+    /// メッセージループのスレッド
+    handle: Option<JoinHandle<()>>,
+    /// ホットキーイベントの受信器
+    receiver: Receiver<HotkeyAction>,
+    /// 登録したホットキー
+    bindings: Vec<(HotkeyAction, Hotkey)>,
+}
+
+impl HotkeyManager {
+    /// ホットキーを登録してメッセージループを開始する
+    /// 
+    /// # 引数
+    /// * `bindings` - 登録する操作とホットキーの組み合わせ
+    /// * `notify` - ホットキーが押されたときに呼ばれる関数（UIの再描画要求など）
+    pub fn new<F>(bindings: Vec<(HotkeyAction, Hotkey)>, notify: F) -> Result<Self, Box<dyn std::error::Error>>
+    where
+        F: Fn() + Send + 'static,
+    {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let (thread_id_sender, thread_id_receiver) = crossbeam_channel::bounded(1);
+        
+        let thread_bindings = bindings.clone();
+        let handle = thread::spawn(move || {
+            run_message_loop(thread_bindings, sender, thread_id_sender, notify);
+        });
+        
+        // メッセージキューが作成されるまで待機
+        let thread_id = thread_id_receiver.recv()?;
+        
+        Ok(Self {
+            thread_id,
+            handle: Some(handle),
+            receiver,
+            bindings,
+        })
+    }
+    
+    /// 登録済みのホットキーを取得する
+    pub fn bindings(&self) -> &[(HotkeyAction, Hotkey)] {
+        &self.bindings
+    }
+    
+    /// 発生したホットキーイベントを取得する
+    pub fn try_recv(&self) -> Option<HotkeyAction> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Drop for HotkeyManager {
+    fn drop(&mut self) {
+        // メッセージループを終了させる
+        unsafe {
+            let _ = PostThreadMessageW(self.thread_id, WM_QUIT, WPARAM(0), LPARAM(0));
+        }
+        
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// ホットキーを登録し、WM_QUITを受け取るまでメッセージを処理する
+fn run_message_loop<F: Fn()>(
+    bindings: Vec<(HotkeyAction, Hotkey)>,
+    sender: Sender<HotkeyAction>,
+    thread_id_sender: Sender<u32>,
+    notify: F,
+) {
+    unsafe {
+        // PostThreadMessageWを受け取れるようにメッセージキューを作成する
+        let mut msg = MSG::default();
+        let _ = PeekMessageW(&mut msg, HWND(0), 0, 0, PM_NOREMOVE);
+        let _ = thread_id_sender.send(GetCurrentThreadId());
+        
+        // ホットキーIDは1から始まる連番にする
+        for (index, (action, hotkey)) in bindings.iter().enumerate() {
+            let id = index as i32 + 1;
+            if RegisterHotKey(HWND(0), id, to_hot_key_modifiers(hotkey), hotkey.key_code).as_bool() {
+                log::info!("Registered hotkey {:?} for {:?}", hotkey, action);
+            } else {
+                log::error!("Failed to register hotkey {:?} for {:?}", hotkey, action);
+            }
+        }
+        
+        // GetMessageWはエラー時に-1を返すため、正の値の間だけループする
+        while GetMessageW(&mut msg, HWND(0), 0, 0).0 > 0 {
+            if msg.message == WM_HOTKEY {
+                let index = msg.wParam.0.wrapping_sub(1);
+                if let Some((action, _)) = bindings.get(index) {
+                    log::debug!("Hotkey pressed: {:?}", action);
+                    let _ = sender.send(*action);
+                    notify();
+                }
+            }
+        }
+        
+        for index in 0..bindings.len() {
+            let _ = UnregisterHotKey(HWND(0), index as i32 + 1);
+        }
+    }
+    
+    log::debug!("Hotkey message loop finished");
+}
@@ -1,3 +1,71 @@
+/// 文字の入力に影響する修飾キーの状態
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    /// SHIFTキーが押されているかどうか
+    pub shift: bool,
+    /// CAPSLOCKが有効かどうか
+    pub caps_lock: bool,
+    /// CTRL・ALT・Winキーのいずれかが押されているかどうか（ショートカットの入力中）
+    pub shortcut: bool,
+    /// AltGr（右ALT）が押されているかどうか（AZERTYやQWERTZなどの配列で記号を入力するときに使う）
+    pub alt_gr: bool,
+}
+
+impl Modifiers {
+    /// ToUnicodeExに渡すキーの状態（256個の仮想キーごとの状態）を作成する
+    /// 
+    /// 押されているキーは上位ビット、CAPSLOCKのトグルは下位ビットを立てる。
+    /// AltGrはWindowsと同じくCTRLとALTを同時に押した状態として表す。
+    /// ショートカットのCTRL・ALTは文字の入力に影響しないよう含めない
+    pub fn key_state(&self) -> [u8; 256] {
+        const PRESSED: u8 = 0x80;
+        const TOGGLED: u8 = 0x01;
+        
+        let mut state = [0u8; 256];
+        if self.shift {
+            state[0x10] = PRESSED; // SHIFT
+            state[0xA0] = PRESSED; // 左SHIFT
+        }
+        if self.caps_lock {
+            state[0x14] = TOGGLED; // CAPSLOCK
+        }
+        if self.alt_gr {
+            state[0x11] = PRESSED; // CTRL
+            state[0xA2] = PRESSED; // 左CTRL
+            state[0x12] = PRESSED; // ALT
+            state[0xA5] = PRESSED; // 右ALT
+        }
+        state
+    }
+}
+
+/// キーを文字に変換した結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyTranslation {
+    /// 入力される文字
+    Char(char),
+    /// デッドキー（次のキーと組み合わせてアクセント付きの文字などを入力する）
+    DeadKey,
+    /// 文字に変換できない
+    Unknown,
+}
+
+/// キーボード配列に従ってキーを文字に変換する
+/// 
+/// Windowsでは前面のアプリのキーボード配列を使って変換する。
+/// テストでは配列の違いを再現するために差し替える
+pub trait KeyTranslator: Send + std::fmt::Debug {
+    /// キーを文字に変換する
+    /// 
+    /// # 引数
+    /// * `key` - 押されたキー
+    /// * `modifiers` - 修飾キーの状態
+    /// 
+    /// # 戻り値
+    /// 変換した結果（変換できない場合は `KeyTranslation::Unknown` を返し、US配列の変換を使う）
+    fn translate(&self, key: Key, modifiers: Modifiers) -> KeyTranslation;
+}
+
 /// キーコードを表す構造体
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Key(pub u32);
@@ -8,12 +76,30 @@ impl Key {
         Self(vk)
     }
     
+    /// 配列によらず同じ扱いにする制御キー（バックスペース、タブ、エンター、ESC）かどうか
+    pub fn is_control_key(&self) -> bool {
+        matches!(self.0, 0x08 | 0x09 | 0x0D | 0x1B)
+    }
+    
     /// キーをキャラクターに変換する
-    pub fn to_char(&self) -> Option<char> {
+    /// 
+    /// 数字と記号のキーはUS配列を前提として、SHIFTを押したときの記号に変換する
+    /// （SHIFT+1 は `!`、SHIFT+; は `:` など）。他の配列では実際に入力される記号と異なる場合があるため、
+    /// キーボード配列に従った変換（`KeyTranslator`）で変換できなかった場合の代わりに使う。
+    /// 
+    /// # 引数
+    /// * `modifiers` - 修飾キーの状態
+    pub fn to_char(&self, modifiers: Modifiers) -> Option<char> {
+        if modifiers.shift {
+            if let Some(c) = self.shifted_symbol() {
+                return Some(c);
+            }
+        }
+        
         // 基本的なASCIIマッピング
         match self.0 {
             0x08 => None, // バックスペース
-            0x09 => None, // タブ
+            0x09 => Some('\t'), // タブ（キーワードの区切り文字として扱う）
             0x0D => Some('\n'), // エンター（改行として扱う）
             0x1B => None, // ESC
             0x20 => Some(' '), // スペース
@@ -21,8 +107,15 @@ impl Key {
             // 数字
             0x30..=0x39 => Some((b'0' + (self.0 - 0x30) as u8) as char),
             
-            // アルファベット (大文字として扱う)
-            0x41..=0x5A => Some((b'a' + (self.0 - 0x41) as u8) as char),
+            // アルファベット（SHIFTとCAPSLOCKのどちらか一方だけが有効な場合は大文字）
+            0x41..=0x5A => {
+                let c = (b'a' + (self.0 - 0x41) as u8) as char;
+                if modifiers.shift != modifiers.caps_lock {
+                    Some(c.to_ascii_uppercase())
+                } else {
+                    Some(c)
+                }
+            }
             
             // テンキー
             0x60..=0x69 => Some((b'0' + (self.0 - 0x60) as u8) as char),
@@ -52,4 +145,33 @@ impl Key {
             _ => None,
         }
     }
+    
+    /// SHIFTを押したときの数字キーと記号キーの記号を取得する（US配列）
+    fn shifted_symbol(&self) -> Option<char> {
+        let c = match self.0 {
+            0x30 => ')',
+            0x31 => '!',
+            0x32 => '@',
+            0x33 => '#',
+            0x34 => '$',
+            0x35 => '%',
+            0x36 => '^',
+            0x37 => '&',
+            0x38 => '*',
+            0x39 => '(',
+            0xBA => ':',
+            0xBB => '+',
+            0xBC => '<',
+            0xBD => '_',
+            0xBE => '>',
+            0xBF => '?',
+            0xC0 => '~',
+            0xDB => '{',
+            0xDC => '|',
+            0xDD => '}',
+            0xDE => '"',
+            _ => return None,
+        };
+        Some(c)
+    }
 } 
\ No newline at end of file
@@ -1,14 +1,43 @@
 pub mod hook;
+pub mod hotkey;
 pub mod key;
 
-pub use hook::KeyboardHook;
-pub use key::Key;
+pub use hook::{KeyboardHook, LayoutKeyTranslator};
+pub use hotkey::{HotkeyAction, HotkeyManager};
+pub use key::{Key, KeyTranslation, KeyTranslator, Modifiers};
 
 use std::sync::{Arc, Mutex};
 
 /// キーボード状態の共有参照型
 pub type SharedKeyboardState = Arc<Mutex<KeyboardState>>;
 
+/// バッファに追加するときの文字を取得する
+/// 
+/// 問題を引き起こす可能性のある特殊文字を安全な文字に置き換える
+fn buffer_char(c: char) -> char {
+    match c {
+        '=' => '_', // '=' を '_' に置き換える
+        ';' => '_', // ';' を '_' に置き換える
+        ',' => '_', // ',' を '_' に置き換える
+        _ => c,
+    }
+}
+
+/// AltGrを押しながら配列で割り当てられた文字を入力したかどうか
+/// 
+/// AltGrはCTRLとALTを押した状態として扱われるが、AZERTYの `@` など文字の入力に使うため、
+/// ショートカットのキーを無視する設定でも文字として扱う
+fn is_alt_gr_char(translation: KeyTranslation, modifiers: Modifiers) -> bool {
+    modifiers.alt_gr && matches!(translation, KeyTranslation::Char(_))
+}
+
+/// 入力された文字列の特殊文字を、バッファに追加するときと同じ文字に置き換える
+/// 
+/// キーボードのバッファを経由しない文字列をキーワードと比較するときに使う
+pub fn normalize_typed_text(text: &str) -> String {
+    text.chars().map(buffer_char).collect()
+}
+
 /// キーボードの状態を管理するクラス
 #[derive(Debug)]
 pub struct KeyboardState {
@@ -16,6 +45,14 @@ pub struct KeyboardState {
     buffer: Vec<char>,
     /// バッファの最大サイズ
     buffer_size: usize,
+    /// 最後にキー入力を受け取ったときの前面のウィンドウのハンドル
+    last_hwnd: Option<isize>,
+    /// CTRL・ALT・Winキーを押しながら入力したキーをバッファに加えないかどうか
+    ignore_shortcut_keys: bool,
+    /// 改行がキーワードの区切り文字かどうか（区切り文字の場合は、改行の前のキーワードを照合できるようバッファに残す）
+    newline_is_terminator: bool,
+    /// キーボード配列に従ってキーを文字に変換する処理（Noneの場合はUS配列として変換する）
+    key_translator: Option<Box<dyn KeyTranslator>>,
 }
 
 impl KeyboardState {
@@ -27,46 +64,122 @@ impl KeyboardState {
         Self {
             buffer: Vec::with_capacity(buffer_size),
             buffer_size,
+            last_hwnd: None,
+            ignore_shortcut_keys: false,
+            newline_is_terminator: false,
+            key_translator: None,
         }
     }
     
+    /// キーボード配列に従ってキーを文字に変換する処理を設定する
+    pub fn set_key_translator(&mut self, key_translator: Box<dyn KeyTranslator>) {
+        self.key_translator = Some(key_translator);
+    }
+    
     /// キー入力を処理する
     /// 
     /// # 引数
     /// * `msg` - Windowsメッセージ（WM_KEYDOWNなど）
     /// * `vk_code` - 仮想キーコード
-    pub fn process_key_event(&mut self, msg: u32, vk_code: u32) {
+    /// * `modifiers` - キーが押されたときの修飾キーの状態
+    pub fn process_key_event(&mut self, msg: u32, vk_code: u32, modifiers: Modifiers) {
         // WM_KEYDOWN (0x0100) または WM_SYSKEYDOWN (0x0104) の場合
         if msg == 0x0100 || msg == 0x0104 {
-            if let Some(c) = Key::from_virtual_key(vk_code).to_char() {
-                self.add_char(c);
+            // バックスペース (0x08) は入力欄の文字と合わせるため、バッファの末尾も削除する
+            if vk_code == 0x08 {
+                self.pop_char();
+                return;
+            }
+            
+            let translation = self.translate_key(vk_code, modifiers);
+            if modifiers.shortcut && self.ignore_shortcut_keys && !is_alt_gr_char(translation, modifiers) {
+                // ショートカットの入力でキーワードが一致しないよう、文字として扱わない
+                log::trace!("Ignoring key 0x{:02X} typed with a shortcut modifier", vk_code);
+                return;
+            }
+            
+            match translation {
+                KeyTranslation::Char(c) => self.add_char(c),
+                KeyTranslation::DeadKey => {
+                    // 次のキーと組み合わせた文字はわからないため、誤って一致しないようバッファを捨てる
+                    log::debug!("Dead key pressed, clearing buffer");
+                    self.clear_buffer();
+                }
+                KeyTranslation::Unknown => {}
+            }
+        }
+    }
+    
+    /// キーを文字に変換する
+    /// 
+    /// キーボード配列に従って変換し、変換できない場合はUS配列として変換する。
+    /// 制御キーと、CTRLの制御文字になった場合もUS配列として変換する。
+    /// AltGrを押している場合は、配列で割り当てられた文字だけを使う（US配列の右ALTでは文字は入力されない）
+    /// 
+    /// # 引数
+    /// * `vk_code` - 仮想キーコード
+    /// * `modifiers` - キーが押されたときの修飾キーの状態
+    fn translate_key(&self, vk_code: u32, modifiers: Modifiers) -> KeyTranslation {
+        let key = Key::from_virtual_key(vk_code);
+        if !key.is_control_key() {
+            if let Some(translator) = &self.key_translator {
+                match translator.translate(key, modifiers) {
+                    KeyTranslation::Char(c) if c.is_control() => {}
+                    KeyTranslation::Unknown => {}
+                    translation => return translation,
+                }
             }
         }
+        if modifiers.alt_gr {
+            return KeyTranslation::Unknown;
+        }
+        
+        key.to_char(modifiers).map_or(KeyTranslation::Unknown, KeyTranslation::Char)
+    }
+    
+    /// 前面のウィンドウを記録し、前回のキー入力から変わっていればバッファをクリアする
+    /// 
+    /// 別のアプリで途中まで入力したキーワードの続きで、切り替え先のアプリで展開されないようにする
+    /// 
+    /// # 引数
+    /// * `hwnd` - 前面のウィンドウのハンドル
+    /// 
+    /// # 戻り値
+    /// バッファをクリアしたかどうか
+    pub fn track_foreground_window(&mut self, hwnd: isize) -> bool {
+        let changed = self.last_hwnd.is_some_and(|last| last != hwnd);
+        self.last_hwnd = Some(hwnd);
+        if changed {
+            log::debug!("Foreground window changed, clearing buffer");
+            self.clear_buffer();
+        }
+        changed
     }
     
     /// 置換チェックを行うべきかを判断
     pub fn should_check_replacement(&self) -> bool {
         // 一定以上の文字が入力されていれば、置換チェックを行う
+        // （展開後はバッファを空にするため、新しい文字が入力されるまで同じ入力で二重に展開しない）
         self.buffer.len() >= 2
     }
     
     /// バッファに文字を追加する
     pub fn add_char(&mut self, c: char) {
+        // 区切り文字として残した改行の後に入力した場合は、前の行の文字を捨てる
+        if self.buffer.last() == Some(&'\n') {
+            self.clear_buffer();
+        }
+        
         // 改行文字の場合はバッファをクリアする
-        if c == '\n' || c == '\r' {
+        // （改行が区切り文字の場合は、改行の前のキーワードを照合できるよう、次の入力まで改行を残す）
+        let c = if c == '\r' { '\n' } else { c };
+        if c == '\n' && (!self.newline_is_terminator || self.buffer.is_empty()) {
             log::debug!("Newline detected, clearing buffer");
             self.clear_buffer();
             return;
         }
         
-        // 特殊文字の場合は特別な処理を行う
-        // 問題を引き起こす可能性のある特殊文字を安全な文字に置き換える
-        let safe_char = match c {
-            '=' => '_', // '=' を '_' に置き換える
-            ';' => '_', // ';' を '_' に置き換える
-            ',' => '_', // ',' を '_' に置き換える
-            _ => c,
-        };
+        let safe_char = buffer_char(c);
         
         self.buffer.push(safe_char);
         log::trace!("Added character '{}' to buffer (stored as '{}'), current buffer: '{}'", 
@@ -78,6 +191,31 @@ impl KeyboardState {
         }
     }
     
+    /// バッファの末尾から1文字削除する（バックスペース）
+    /// 
+    /// バックスペースを押し続けてキーリピートが発生しても、
+    /// 空のバッファに対しては何もしない（パニックやアンダーフローは起きない）
+    /// 
+    /// # 戻り値
+    /// 削除された文字（バッファが空の場合はNone）
+    pub fn pop_char(&mut self) -> Option<char> {
+        let popped = self.buffer.pop();
+        log::trace!("Popped character {:?} from buffer, current buffer: '{}'", 
+                   popped, self.get_keyword_candidate());
+        popped
+    }
+    
+    /// バッファの末尾がキーワードと一致するかを判定する
+    /// 
+    /// バイト数ではなく文字単位で比較する。キーワードの特殊文字は、バッファに追加するときと同じ文字に置き換えて比較する
+    /// 
+    /// # 引数
+    /// * `keyword` - 判定するキーワード
+    pub fn check_keyword(&self, keyword: &str) -> bool {
+        let keyword: Vec<char> = keyword.chars().map(buffer_char).collect();
+        !keyword.is_empty() && self.buffer.ends_with(&keyword)
+    }
+    
     /// バッファをクリアする
     pub fn clear_buffer(&mut self) {
         self.buffer.clear();
@@ -91,7 +229,6 @@ impl KeyboardState {
     /// 
     /// # 戻り値
     /// 置換が成功したかどうか
-    #[allow(dead_code)]
     pub fn replace_keyword(&mut self, keyword: &str, _replacement: &str) -> bool {
         // バッファから特定のキーワードを削除する
         let keyword_len = keyword.chars().count();
@@ -113,8 +250,48 @@ impl KeyboardState {
         }
     }
     
+    /// バッファの最大サイズを取得する
+    pub fn buffer_size(&self) -> usize {
+        self.buffer_size
+    }
+    
+    /// バッファの最大サイズを変更する
+    /// 
+    /// バッファが新しいサイズより長い場合は、新しく入力された文字を残して切り詰める
+    pub fn set_buffer_size(&mut self, buffer_size: usize) {
+        self.buffer_size = buffer_size;
+        if self.buffer.len() > buffer_size {
+            let excess = self.buffer.len() - buffer_size;
+            self.buffer.drain(..excess);
+        }
+        log::debug!("Keyboard buffer size set to {}", buffer_size);
+    }
+    
+    /// CTRL・ALT・Winキーを押しながら入力したキーをバッファに加えないかどうかを取得する
+    pub fn ignores_shortcut_keys(&self) -> bool {
+        self.ignore_shortcut_keys
+    }
+    
+    /// CTRL・ALT・Winキーを押しながら入力したキーをバッファに加えないかどうかを設定する
+    /// 
+    /// SHIFTは文字の入力に使うため、押していても無視しない
+    pub fn set_ignore_shortcut_keys(&mut self, ignore: bool) {
+        self.ignore_shortcut_keys = ignore;
+    }
+    
+    /// 改行がキーワードの区切り文字かどうかを取得する
+    pub fn is_newline_terminator(&self) -> bool {
+        self.newline_is_terminator
+    }
+    
+    /// 改行がキーワードの区切り文字かどうかを設定する
+    /// 
+    /// 区切り文字でない場合は、これまでどおり改行でバッファをクリアする
+    pub fn set_newline_terminator(&mut self, is_terminator: bool) {
+        self.newline_is_terminator = is_terminator;
+    }
+    
     /// バッファの内容を取得する
-    #[allow(dead_code)]
     pub fn get_buffer(&self) -> String {
         self.buffer.iter().collect()
     }
@@ -125,7 +302,8 @@ impl KeyboardState {
     }
     
     /// キーワードが検出され置換された場合、バッファをクリアする
-    #[allow(dead_code)]
+    /// 
+    /// 展開したキーワードの残りが次のキーワードと結合して誤って一致しないように、バッファを空にする
     pub fn keyword_replaced(&mut self) {
         log::debug!("Keyword replacement completed, clearing buffer");
         self.clear_buffer();
@@ -1,9 +1,7 @@
 mod app;
-mod config;
-mod keyboard;
-mod replacement;
-mod ui;
-mod utils;
+
+// アプリ以外のモジュールはライブラリのものを使う（ライブラリだけで使う関数が未使用にならないよう、二重にコンパイルしない）
+use swifttype::{config, keyboard, replacement, ui, utils};
 
 use eframe::egui;
 use log::error;
@@ -12,18 +10,40 @@ use app::App;
 use ui::constants;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // ロガーを初期化（デバッグレベルで詳細なログを表示）
+    // ロガーを初期化（デバッグレベルで詳細なログを表示し、情報以上のログはLogタブにも記録する）
     std::env::set_var("RUST_LOG", "debug");
-    env_logger::init();
+    utils::event_log::init();
     
     log::info!("Starting SwiftType application");
     
+    // --no-hook を指定した場合はキーボードフックを使わずに起動する（他のツールとの競合の調査用）
+    let hook_enabled = !std::env::args().skip(1).any(|arg| arg == app::NO_HOOK_ARG);
+    if !hook_enabled {
+        log::warn!("Started with {}: snippets will not expand while typing", app::NO_HOOK_ARG);
+    }
+    
+    // 保存されているUIスケールをウィンドウサイズに反映する
+    let (ui_scale, window_geometry) = config::ConfigManager::new()
+        .map(|config_manager| {
+            let settings = config_manager.get_settings();
+            (settings.ui_scale, settings.window_geometry)
+        })
+        .unwrap_or((1.0, None));
+    
+    // 前回のウィンドウの位置とサイズを復元する（物理ピクセルで保存しているため、ウィンドウの座標に変換する）
+    let window_geometry = window_geometry.map(utils::fit_window_on_screen);
+    let system_scale = utils::get_system_dpi_scale();
+    
     // アプリケーションの設定を作成
     let options = eframe::NativeOptions {
-        initial_window_size: Some(egui::vec2(
-            constants::DEFAULT_WIDTH,
-            constants::DEFAULT_HEIGHT,
-        )),
+        initial_window_size: Some(match window_geometry {
+            Some(geometry) => egui::vec2(geometry.width, geometry.height) / system_scale,
+            None => ui::scaled_window_size(ui_scale),
+        }),
+        initial_window_pos: window_geometry.map(|geometry| egui::pos2(geometry.x / system_scale, geometry.y / system_scale)),
+        centered: window_geometry.is_none(),
+        // 位置とサイズは設定ファイルに保存するため、eframeの保存には任せない
+        persist_window: false,
         vsync: true,
         icon_data: None, // アイコンを追加したい場合はここで設定
         always_on_top: false,
@@ -39,7 +59,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let result = eframe::run_native(
         constants::APP_TITLE,
         options,
-        Box::new(|cc| Box::new(App::new(cc).expect("Failed to create app"))),
+        Box::new(move |cc| Box::new(App::new(cc, hook_enabled).expect("Failed to create app"))),
     );
     
     if let Err(err) = result {
@@ -0,0 +1,637 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use arboard::Clipboard;
+use windows::Win32::UI::Input::KeyboardAndMouse::{VK_BACK, VK_TAB, VK_V};
+
+use super::clipboard::{self, PasteKeys, PasteOutcome};
+use super::keystrokes::KeyCombo;
+use crate::config::settings::{TimingProfile, MAX_BUFFER_SIZE};
+use crate::utils;
+
+/// キー入力の送信先
+/// 
+/// 置換エンジンはこのトレイトを通してキー入力を送信する。
+/// テストでは送信内容を記録する実装に差し替えられるようにする
+pub trait InputBackend: Send + std::fmt::Debug + std::panic::RefUnwindSafe {
+    /// バックスペースを指定回数送信する
+    /// 
+    /// # 引数
+    /// * `count` - 送信する回数
+    /// * `is_short_keyword` - 短いキーワードの削除かどうか（待機時間を長くする）
+    fn send_backspaces(&self, count: usize, is_short_keyword: bool) -> bool;
+    
+    /// テキストを1文字ずつ直接入力する
+    /// 
+    /// 途中で失敗した場合は、入力済みの文字を削除してから失敗を返す
+    fn type_text(&self, text: &str) -> bool;
+    
+    /// クリップボード経由でテキストを貼り付ける
+    fn paste_text(&self, text: &str) -> bool;
+    
+    /// 修飾キーとキーの組み合わせを送信する
+    fn send_key_combo(&self, combo: KeyCombo) -> bool;
+    
+    /// 修飾キーなしで同じキーを指定回数押す
+    fn send_key_presses(&self, vk: u16, count: usize) -> bool;
+    
+    /// 押されたままになっている修飾キーを解放する
+    fn reset_modifier_keys(&self) -> bool;
+    
+    /// 前面のウィンドウのプロセス名を取得する
+    fn foreground_process_name(&self) -> Option<String>;
+    
+    /// 入力の間に指定した時間だけ待機する
+    fn wait(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+    
+    /// キー入力の待機時間を設定する
+    fn set_timing(&self, _timing: TimingProfile) {}
+}
+
+/// 直接入力で1文字を入力するために押すキー
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharKey {
+    /// UTF-16のコード単位をUnicode文字として送信する
+    Unicode(u16),
+    /// 仮想キーを押す
+    Virtual(u16),
+}
+
+/// 1文字を直接入力するために押すキーを取得する
+/// 
+/// U+FFFFを超える絵文字などは、サロゲートペアの2つのコード単位に分けて送信する。
+/// タブは文字ではなくタブキーとして送信する（表のセルや入力欄を移動できるようにする）
+/// 
+/// # 引数
+/// * `c` - 入力する文字
+pub fn char_keys(c: char) -> Vec<CharKey> {
+    if c == '\t' {
+        return vec![CharKey::Virtual(VK_TAB.0)];
+    }
+    
+    let mut units = [0u16; 2];
+    c.encode_utf16(&mut units).iter().map(|&unit| CharKey::Unicode(unit)).collect()
+}
+
+/// SendInputでキー入力を送信するWindows向けの実装
+#[derive(Debug, Default)]
+pub struct WindowsInputBackend {
+    /// キー入力の待機時間
+    timing: Mutex<TimingProfile>,
+}
+
+impl InputBackend for WindowsInputBackend {
+    
+    fn set_timing(&self, timing: TimingProfile) {
+        if let Ok(mut current) = self.timing.lock() {
+            *current = timing;
+        }
+    }
+    
+    fn send_backspaces(&self, count: usize, is_short_keyword: bool) -> bool {
+        use windows::Win32::UI::Input::KeyboardAndMouse::{
+            INPUT, INPUT_KEYBOARD, KEYBDINPUT, SendInput, KEYEVENTF_KEYUP,
+        };
+        
+        if count == 0 {
+            log::debug!("No backspaces to simulate");
+            return true; // 削除するものがなければ成功と見なす
+        }
+        
+        // バックスペース数をログに記録（デバッグ用）
+        log::debug!("Simulating {} backspaces", count);
+        
+        // 安全のため、バックスペース数に上限を設ける
+        let safe_count = std::cmp::min(count, MAX_BUFFER_SIZE); // バッファの最大サイズまでに制限
+        if safe_count < count {
+            log::warn!("Limiting backspace count from {} to {}", count, safe_count);
+        }
+        
+        // 高リスクの長さに対する特別処理
+        let is_high_risk_length = safe_count >= 5 && safe_count <= 9;
+        
+        // 待機時間はキー間の待機時間を基準にする（Safeでは20ms）
+        let key_wait = self.timing().between_keys_ms;
+        
+        // バックスペース処理前の待機時間
+        // 短いキーワードの場合はより長く待機
+        let initial_wait = if is_short_keyword {
+            key_wait * 5 // 短いキーワードは長めに待機
+        } else if is_high_risk_length {
+            key_wait * 5 / 2
+        } else {
+            key_wait * 2
+        };
+        thread::sleep(Duration::from_millis(initial_wait));
+        
+        // 例外処理を追加
+        let success = match std::panic::catch_unwind(|| {
+            // カーソル位置を安定させるためにバックスペースを丁寧に実行
+            for i in 0..safe_count {
+                // バックスペースキーを押す
+                let mut key_down: INPUT = unsafe { std::mem::zeroed() };
+                key_down.r#type = INPUT_KEYBOARD;
+                key_down.Anonymous.ki = KEYBDINPUT {
+                    wVk: VK_BACK,
+                    wScan: 0,
+                    dwFlags: Default::default(),
+                    time: 0,
+                    dwExtraInfo: 0,
+                };
+                
+                // バックスペースキーを離す
+                let mut key_up: INPUT = unsafe { std::mem::zeroed() };
+                key_up.r#type = INPUT_KEYBOARD;
+                key_up.Anonymous.ki = KEYBDINPUT {
+                    wVk: VK_BACK,
+                    wScan: 0,
+                    dwFlags: KEYEVENTF_KEYUP,
+                    time: 0,
+                    dwExtraInfo: 0,
+                };
+                
+                // バックスペースを押下
+                let sent_down = unsafe {
+                    SendInput(&[key_down], std::mem::size_of::<INPUT>() as i32)
+                };
+                
+                if sent_down != 1 {
+                    log::error!("Failed to send backspace key down event for backspace {}", i + 1);
+                    return false;
+                }
+                
+                // キーの押下を確実に処理してもらうための待機時間
+                // 短いキーワードの場合はより長く待機
+                let key_down_wait = if is_short_keyword { key_wait * 2 } else { key_wait };
+                thread::sleep(Duration::from_millis(key_down_wait));
+                
+                // バックスペースを解放
+                let sent_up = unsafe {
+                    SendInput(&[key_up], std::mem::size_of::<INPUT>() as i32)
+                };
+                
+                if sent_up != 1 {
+                    log::error!("Failed to send backspace key up event for backspace {}", i + 1);
+                    return false;
+                }
+                
+                // 次のバックスペース前の待機時間
+                // 短いキーワードの場合はより長く待機
+                let between_backspace_wait = if is_short_keyword { key_wait * 5 / 2 } else { key_wait };
+                thread::sleep(Duration::from_millis(between_backspace_wait));
+            }
+            
+            // すべて成功
+            true
+        }) {
+            Ok(result) => {
+                log::debug!("Completed sending {} backspace events, success: {}", safe_count, result);
+                result
+            },
+            Err(_) => {
+                log::error!("Panic occurred during backspace simulation");
+                false
+            }
+        };
+        
+        // 最後の操作後の待機時間
+        // 短いキーワードの場合はより長く待機
+        let final_wait = if is_short_keyword {
+            key_wait * 10 // 短いキーワードは長めに待機
+        } else if is_high_risk_length {
+            key_wait * 5
+        } else if safe_count > 5 {
+            key_wait * 4
+        } else {
+            key_wait * 3
+        };
+        thread::sleep(Duration::from_millis(final_wait));
+        
+        success
+    }
+    
+    
+    fn type_text(&self, text: &str) -> bool {
+        use windows::Win32::UI::Input::KeyboardAndMouse::{
+            INPUT, INPUT_KEYBOARD, KEYBDINPUT, SendInput, KEYEVENTF_UNICODE, KEYEVENTF_KEYUP, KEYBD_EVENT_FLAGS,
+            VIRTUAL_KEY,
+        };
+        
+        log::debug!("Simulating direct char input for: '{}'", text);
+        
+        // IMEの状態確認
+        #[cfg(feature = "Win32_UI_Input_Ime")]
+        let ime_active = self.check_ime_status();
+        #[cfg(not(feature = "Win32_UI_Input_Ime"))]
+        let ime_active = false;
+        
+        if ime_active {
+            log::debug!("IME is active, temporarily disabling for direct input");
+            self.toggle_ime(false);
+            
+            // IMEの状態変更が反映されるのを待つ
+            thread::sleep(Duration::from_millis(100));
+        }
+        
+        // 短いテキストの場合は特に慎重に処理
+        let is_short_text = text.len() <= 3;
+        let char_delay = self.timing().char_delay_ms;
+        let char_delay = if is_short_text { char_delay * 2 } else { char_delay };
+        
+        // 入力前に少し待機（特に短いテキストの場合）
+        if is_short_text {
+            thread::sleep(Duration::from_millis(100));
+        }
+        
+        // 入力済みの文字数（途中で失敗した場合に削除する）
+        let mut typed_chars = 0;
+        let key_input = |key: CharKey, key_up: bool| {
+            let (vk, scan, flags) = match key {
+                CharKey::Unicode(unit) => (VIRTUAL_KEY(0), unit, KEYEVENTF_UNICODE),
+                CharKey::Virtual(vk) => (VIRTUAL_KEY(vk), 0, KEYBD_EVENT_FLAGS::default()),
+            };
+            let mut input: INPUT = unsafe { std::mem::zeroed() };
+            input.r#type = INPUT_KEYBOARD;
+            input.Anonymous.ki = KEYBDINPUT {
+                wVk: vk,
+                wScan: scan,
+                dwFlags: if key_up { flags | KEYEVENTF_KEYUP } else { flags },
+                time: 0,
+                dwExtraInfo: 0,
+            };
+            input
+        };
+        
+        for c in text.chars() {
+            // サロゲートペアは2つのコード単位をまとめて押し、まとめて離す
+            let keys = char_keys(c);
+            let inputs_down: Vec<INPUT> = keys.iter().map(|&key| key_input(key, false)).collect();
+            let inputs_up: Vec<INPUT> = keys.iter().map(|&key| key_input(key, true)).collect();
+            
+            // キーダウン入力を送信
+            let sent_down = unsafe {
+                SendInput(&inputs_down, std::mem::size_of::<INPUT>() as i32)
+            };
+            
+            if sent_down as usize != inputs_down.len() {
+                log::error!("Failed to send unicode character down event: '{}'", c);
+                self.abort_direct_input(typed_chars, ime_active);
+                return false;
+            }
+            // キーダウンで文字が入力されるため、ここで入力済みとして数える
+            typed_chars += 1;
+            
+            // キーダウンとキーアップの間に小さな遅延
+            thread::sleep(Duration::from_millis(char_delay));
+            
+            // キーアップ入力を送信
+            let sent_up = unsafe {
+                SendInput(&inputs_up, std::mem::size_of::<INPUT>() as i32)
+            };
+            
+            if sent_up as usize != inputs_up.len() {
+                log::error!("Failed to send unicode character up event: '{}'", c);
+                self.abort_direct_input(typed_chars, ime_active);
+                return false;
+            }
+            
+            // 文字間に小さな遅延
+            thread::sleep(Duration::from_millis(char_delay));
+        }
+        
+        // IMEの状態を元に戻す
+        if ime_active {
+            log::debug!("Restoring IME state");
+            thread::sleep(Duration::from_millis(50));
+            self.toggle_ime(true);
+        }
+        
+        // 入力後に少し待機（特に短いテキストの場合）
+        if is_short_text {
+            thread::sleep(Duration::from_millis(100));
+        }
+        
+        log::debug!("Direct char input completed successfully");
+        return true;
+    }
+    
+    fn paste_text(&self, text: &str) -> bool {
+        // クリップボードを使えない場合は、展開を中止せずに直接入力する
+        let mut clipboard = match Clipboard::new() {
+            Ok(clipboard) => clipboard,
+            Err(e) => {
+                log::warn!("Failed to access clipboard ({}), typing the text instead", e);
+                return self.type_text(text);
+            }
+        };
+        
+        let timing = self.timing();
+        let outcome = clipboard::paste_with_restore(&mut clipboard, text, |_| {
+            // クリップボード設定後に少し待機
+            thread::sleep(Duration::from_millis(timing.clipboard_settle_ms));
+            
+            // CTRL+Vで貼り付ける（Vキーを押せなかった場合は待機してから送り直す）
+            log::debug!("Simulating paste operation (CTRL+V) with improved approach");
+            let sent = clipboard::send_paste_with_retry(
+                timing.paste_retries,
+                Duration::from_millis(timing.key_combo_ms),
+                || {
+                    let (sent, total) = self.send_key_combo_inputs(KeyCombo { ctrl: true, vk: VK_V.0, ..Default::default() });
+                    // CTRLキーを押した後にVキーを押す
+                    PasteKeys::from_sent(sent, total, 1)
+                },
+                thread::sleep,
+            );
+            if !sent {
+                return false;
+            }
+            
+            // 貼り付け先がクリップボードを読み取るまで待機してから元に戻す
+            thread::sleep(Duration::from_millis(timing.post_paste_ms));
+            true
+        });
+        
+        match outcome {
+            PasteOutcome::Pasted => {
+                log::debug!("Replacement completed successfully: '{}'", text);
+                true
+            }
+            PasteOutcome::Failed => false,
+            PasteOutcome::ClipboardUnavailable => {
+                log::warn!("Failed to set clipboard text, typing the text instead");
+                self.type_text(text)
+            }
+        }
+    }
+    
+    fn send_key_combo(&self, combo: KeyCombo) -> bool {
+        let (sent, total) = self.send_key_combo_inputs(combo);
+        sent == total
+    }
+    
+    
+    fn send_key_presses(&self, vk: u16, count: usize) -> bool {
+        use windows::Win32::UI::Input::KeyboardAndMouse::{
+            INPUT, INPUT_KEYBOARD, KEYBDINPUT, SendInput, KEYEVENTF_KEYUP, VIRTUAL_KEY,
+        };
+        
+        // 入力をまとめて準備（押して離すを指定回数）
+        let mut inputs: Vec<INPUT> = Vec::with_capacity(count * 2);
+        for _ in 0..count {
+            for flags in [Default::default(), KEYEVENTF_KEYUP] {
+                let mut input: INPUT = unsafe { std::mem::zeroed() };
+                input.r#type = INPUT_KEYBOARD;
+                input.Anonymous.ki = KEYBDINPUT {
+                    wVk: VIRTUAL_KEY(vk),
+                    wScan: 0,
+                    dwFlags: flags,
+                    time: 0,
+                    dwExtraInfo: 0,
+                };
+                inputs.push(input);
+            }
+        }
+        
+        let sent = unsafe {
+            SendInput(&inputs, std::mem::size_of::<INPUT>() as i32)
+        };
+        
+        if sent as usize != inputs.len() {
+            log::error!("Failed to send key presses, sent only {} of {}", sent, inputs.len());
+            return false;
+        }
+        
+        // 操作後に待機
+        thread::sleep(Duration::from_millis(50));
+        
+        true
+    }
+    
+    fn foreground_process_name(&self) -> Option<String> {
+        utils::get_foreground_process_name()
+    }
+    
+    fn reset_modifier_keys(&self) -> bool {
+        use windows::Win32::UI::Input::KeyboardAndMouse::{
+            INPUT, INPUT_KEYBOARD, KEYBDINPUT, SendInput, KEYEVENTF_KEYUP, 
+            VK_CONTROL, VK_SHIFT, VK_MENU, VK_LWIN, VK_RWIN,
+        };
+        
+        log::debug!("Resetting all modifier keys to released state");
+        
+        let modifiers = [VK_CONTROL, VK_SHIFT, VK_MENU, VK_LWIN, VK_RWIN];
+        let mut inputs: Vec<INPUT> = Vec::with_capacity(modifiers.len());
+        
+        // すべてのモディファイアキーを離す状態にする
+        for &vk in &modifiers {
+            let mut key_up: INPUT = unsafe { std::mem::zeroed() };
+            key_up.r#type = INPUT_KEYBOARD;
+            key_up.Anonymous.ki = KEYBDINPUT {
+                wVk: vk,
+                wScan: 0,
+                dwFlags: KEYEVENTF_KEYUP,
+                time: 0,
+                dwExtraInfo: 0,
+            };
+            inputs.push(key_up);
+        }
+        
+        // モディファイアキーをすべて解放
+        let sent = unsafe {
+            SendInput(&inputs, std::mem::size_of::<INPUT>() as i32)
+        };
+        
+        if sent as usize != inputs.len() {
+            log::error!("Failed to reset modifier keys, sent only {} of {}", sent, inputs.len());
+            return false;
+        }
+        
+        // 少し待機して確実にキー状態が反映されるようにする
+        thread::sleep(Duration::from_millis(50));
+        
+        log::debug!("All modifier keys have been reset");
+        true
+    }
+}
+
+impl WindowsInputBackend {
+    /// 現在のキー入力の待機時間を取得する
+    fn timing(&self) -> TimingProfile {
+        self.timing.lock().map(|timing| *timing).unwrap_or_default()
+    }
+    
+    /// 修飾キーとキーの組み合わせを送信する
+    /// 
+    /// 送信が途中で止まった場合は、押されたままの修飾キーを解放する
+    /// 
+    /// # 戻り値
+    /// 送信できた入力の数と、送信する入力の数
+    fn send_key_combo_inputs(&self, combo: KeyCombo) -> (usize, usize) {
+        use windows::Win32::UI::Input::KeyboardAndMouse::{
+            INPUT, INPUT_KEYBOARD, KEYBDINPUT, SendInput, KEYEVENTF_KEYUP, KEYBD_EVENT_FLAGS,
+            VIRTUAL_KEY, VK_CONTROL, VK_MENU, VK_SHIFT,
+        };
+        
+        // 開始前に修飾キーをリセット（前回の失敗状態から回復するため）
+        self.reset_modifier_keys();
+        
+        // 一貫した時間をおいて処理を実行
+        let combo_wait = self.timing().key_combo_ms;
+        thread::sleep(Duration::from_millis(combo_wait));
+        
+        let key_input = |vk: VIRTUAL_KEY, flags: KEYBD_EVENT_FLAGS| {
+            let mut input: INPUT = unsafe { std::mem::zeroed() };
+            input.r#type = INPUT_KEYBOARD;
+            input.Anonymous.ki = KEYBDINPUT {
+                wVk: vk,
+                wScan: 0,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            };
+            input
+        };
+        
+        // 押す修飾キー
+        let modifiers: Vec<VIRTUAL_KEY> = [(combo.ctrl, VK_CONTROL), (combo.shift, VK_SHIFT), (combo.alt, VK_MENU)]
+            .into_iter()
+            .filter(|(pressed, _)| *pressed)
+            .map(|(_, vk)| vk)
+            .collect();
+        
+        // 入力をまとめて準備（修飾キーを押す → キーを押して離す → 修飾キーを逆順に離す）
+        let mut inputs: Vec<INPUT> = Vec::with_capacity(modifiers.len() * 2 + 2);
+        for &vk in &modifiers {
+            inputs.push(key_input(vk, Default::default()));
+        }
+        inputs.push(key_input(VIRTUAL_KEY(combo.vk), Default::default()));
+        inputs.push(key_input(VIRTUAL_KEY(combo.vk), KEYEVENTF_KEYUP));
+        for &vk in modifiers.iter().rev() {
+            inputs.push(key_input(vk, KEYEVENTF_KEYUP));
+        }
+        
+        // すべての入力をまとめて送信
+        let sent = unsafe {
+            SendInput(&inputs, std::mem::size_of::<INPUT>() as i32)
+        };
+        
+        if sent as usize != inputs.len() {
+            log::error!("Failed to send key sequence, sent only {} of {}", sent, inputs.len());
+            self.reset_modifier_keys();
+            return (sent as usize, inputs.len());
+        }
+        
+        // 操作後に待機
+        thread::sleep(Duration::from_millis(combo_wait * 2 / 3));
+        
+        (inputs.len(), inputs.len())
+    }
+    
+    /// 直接入力が途中で失敗した場合に、入力済みの文字を削除してIMEの状態を元に戻す
+    /// 
+    /// 呼び出し元がクリップボードからの貼り付けに切り替えたときに、同じテキストが重ねて入力されないようにする
+    /// 
+    /// # 引数
+    /// * `typed_chars` - 失敗するまでに入力した文字数
+    /// * `ime_active` - 入力のためにIMEを無効にしたかどうか
+    fn abort_direct_input(&self, typed_chars: usize, ime_active: bool) {
+        if typed_chars > 0 {
+            log::warn!("Direct input failed after {} character(s), removing them before falling back", typed_chars);
+            // send_backspacesはバッファの最大サイズまでしか削除しないため、入力した文字数だけそのまま押す
+            if !self.send_key_presses(VK_BACK.0, typed_chars) {
+                log::error!("Failed to remove partially typed text");
+            }
+        }
+        
+        // IMEの状態を元に戻す
+        if ime_active {
+            self.toggle_ime(true);
+        }
+    }
+    
+    
+    /// IMEの状態を確認する関数
+    #[cfg(feature = "Win32_UI_Input_Ime")]
+    fn check_ime_status(&self) -> bool {
+        use windows::Win32::UI::Input::Ime::{ImmGetContext, ImmGetOpenStatus};
+        use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+        use windows::Win32::Globalization::HIMC;
+        use windows::Win32::Foundation::BOOL;
+        
+        unsafe {
+            let hwnd = GetForegroundWindow();
+            let himc = ImmGetContext(hwnd);
+            
+            if himc.is_invalid() {
+                log::debug!("Failed to get IMM context, assuming IME is not active");
+                return false;
+            }
+            
+            let is_open = ImmGetOpenStatus(himc);
+            log::debug!("IME status: {:?}", is_open);
+            
+            is_open.into()
+        }
+    }
+    
+    
+    /// IMEの状態を切り替える関数
+    #[cfg(feature = "Win32_UI_Input_Ime")]
+    fn toggle_ime(&self, enable: bool) -> bool {
+        use windows::Win32::UI::Input::Ime::{ImmGetContext, ImmSetOpenStatus};
+        use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+        use windows::Win32::Globalization::HIMC;
+        use windows::Win32::Foundation::BOOL;
+        
+        unsafe {
+            let hwnd = GetForegroundWindow();
+            let himc = ImmGetContext(hwnd);
+            
+            if himc.is_invalid() {
+                log::error!("Failed to get IMM context for toggling IME");
+                return false;
+            }
+            
+            let result = ImmSetOpenStatus(himc, enable);
+            log::debug!("Set IME status to {}: {:?}", enable, result);
+            
+            result.into()
+        }
+    }
+    
+    
+    /// IME機能が無効な場合のダミー実装
+    #[cfg(not(feature = "Win32_UI_Input_Ime"))]
+    fn check_ime_status(&self) -> bool {
+        log::debug!("IME feature not enabled, assuming IME is not active");
+        false
+    }
+    
+    
+    /// IME機能が無効な場合のダミー実装
+    #[cfg(not(feature = "Win32_UI_Input_Ime"))]
+    fn toggle_ime(&self, _enable: bool) -> bool {
+        log::debug!("IME feature not enabled, toggle operation ignored");
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_char_keys_split_surrogate_pairs() {
+        let keys: Vec<CharKey> = "a😀é\t".chars().flat_map(char_keys).collect();
+        assert_eq!(keys, vec![
+            CharKey::Unicode(0x0061),
+            // U+1F600 はサロゲートペアの2つのコード単位になる
+            CharKey::Unicode(0xD83D),
+            CharKey::Unicode(0xDE00),
+            CharKey::Unicode(0x00E9),
+            CharKey::Virtual(VK_TAB.0),
+        ]);
+    }
+}
@@ -0,0 +1,451 @@
+use std::thread;
+use std::time::Duration;
+use arboard::{Clipboard, ImageData};
+
+/// クリップボード操作の抽象化
+/// 
+/// テストではメモリ上の実装に差し替えられるようにする
+pub trait ClipboardAccess {
+    /// クリップボードのテキストを取得する
+    fn get_text(&mut self) -> Option<String>;
+    /// クリップボードにテキストを設定する
+    fn set_text(&mut self, text: &str) -> bool;
+    /// クリップボードを空にする
+    fn clear(&mut self) -> bool;
+    /// クリップボードの画像を取得する（画像を扱えない場合はNone）
+    fn get_image(&mut self) -> Option<ImageData<'static>> {
+        None
+    }
+    /// クリップボードに画像を設定する
+    fn set_image(&mut self, _image: ImageData<'static>) -> bool {
+        false
+    }
+}
+
+impl ClipboardAccess for Clipboard {
+    fn get_text(&mut self) -> Option<String> {
+        Clipboard::get_text(self).ok()
+    }
+    
+    fn set_text(&mut self, text: &str) -> bool {
+        Clipboard::set_text(self, text).is_ok()
+    }
+    
+    fn clear(&mut self) -> bool {
+        Clipboard::clear(self).is_ok()
+    }
+    
+    fn get_image(&mut self) -> Option<ImageData<'static>> {
+        Clipboard::get_image(self).ok()
+    }
+    
+    fn set_image(&mut self, image: ImageData<'static>) -> bool {
+        Clipboard::set_image(self, image).is_ok()
+    }
+}
+
+/// 元に戻すために保存したクリップボードの内容
+/// 
+/// 保存できるのはテキストと画像だけで、リッチテキストやファイルなどの
+/// それ以外の形式は保存できない（テキストも持つ場合はテキストだけが戻る）
+#[derive(Debug, Clone)]
+pub enum SavedClipboard {
+    /// テキスト
+    Text(String),
+    /// 画像
+    Image(ImageData<'static>),
+    /// 空、または保存できない形式
+    Unavailable,
+}
+
+/// クリップボードの内容を元に戻すときの試行回数
+/// 
+/// 貼り付け先のアプリケーションがクリップボードを開いたままにしていると失敗するため、少し待って再試行する
+const RESTORE_ATTEMPTS: u32 = 3;
+
+/// 再試行の間の待機時間
+const RESTORE_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+/// クリップボードの内容を保存する
+pub fn save_clipboard<C: ClipboardAccess>(clipboard: &mut C) -> SavedClipboard {
+    if let Some(text) = clipboard.get_text() {
+        return SavedClipboard::Text(text);
+    }
+    match clipboard.get_image() {
+        Some(image) => SavedClipboard::Image(image),
+        None => SavedClipboard::Unavailable,
+    }
+}
+
+/// 保存したクリップボードの内容を元に戻す
+/// 
+/// 保存できなかった場合は、貼り付けたテキストが残らないようにクリップボードを空にする
+/// 
+/// # 戻り値
+/// 元に戻せたかどうか
+pub fn restore_clipboard<C: ClipboardAccess>(clipboard: &mut C, saved: SavedClipboard) -> bool {
+    for attempt in 1..=RESTORE_ATTEMPTS {
+        let restored = match &saved {
+            SavedClipboard::Text(text) => clipboard.set_text(text),
+            SavedClipboard::Image(image) => clipboard.set_image(image.clone()),
+            SavedClipboard::Unavailable => clipboard.clear(),
+        };
+        if restored {
+            return true;
+        }
+        
+        log::debug!("Failed to restore clipboard (attempt {} of {})", attempt, RESTORE_ATTEMPTS);
+        if attempt < RESTORE_ATTEMPTS {
+            thread::sleep(RESTORE_RETRY_DELAY);
+        }
+    }
+    
+    log::warn!("Failed to restore clipboard contents");
+    false
+}
+
+/// クリップボード経由の貼り付けの結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasteOutcome {
+    /// 貼り付けた
+    Pasted,
+    /// 貼り付け操作の送信に失敗した
+    Failed,
+    /// クリップボードにテキストを設定できなかった（何も貼り付けていない）
+    ClipboardUnavailable,
+}
+
+/// クリップボード経由でテキストを貼り付け、クリップボードを元に戻す
+/// 
+/// # 引数
+/// * `clipboard` - クリップボード
+/// * `text` - 貼り付けるテキスト
+/// * `send_paste` - 貼り付け操作（CTRL+V）を送信する関数（貼り付け先が読み取るまで待機してから戻ること）
+/// 
+/// # 戻り値
+/// 貼り付けの結果
+pub fn paste_with_restore<C, F>(clipboard: &mut C, text: &str, send_paste: F) -> PasteOutcome
+where
+    C: ClipboardAccess,
+    F: FnOnce(&mut C) -> bool,
+{
+    // 既存のクリップボード内容を保存（あとで復元するため）
+    let original = save_clipboard(clipboard);
+    
+    log::debug!("Setting clipboard text: '{}'", text);
+    if !clipboard.set_text(text) {
+        log::error!("Failed to set clipboard text");
+        return PasteOutcome::ClipboardUnavailable;
+    }
+    
+    let pasted = send_paste(clipboard);
+    if !pasted {
+        log::error!("Failed to simulate paste operation");
+    }
+    
+    restore_clipboard(clipboard, original);
+    if pasted {
+        PasteOutcome::Pasted
+    } else {
+        PasteOutcome::Failed
+    }
+}
+
+/// 貼り付け操作（CTRL+V）のキー入力を送信した結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasteKeys {
+    /// すべてのキー入力を送信した
+    Sent,
+    /// Vキーを押す前に送信が止まった（貼り付けられていない）
+    NotPressed,
+    /// Vキーを押した後に送信が止まった（貼り付けられている可能性がある）
+    Incomplete,
+}
+
+impl PasteKeys {
+    /// 送信できた入力の数から結果を判定する
+    /// 
+    /// # 引数
+    /// * `sent` - 送信できた入力の数
+    /// * `total` - 送信する入力の数
+    /// * `key_down_index` - Vキーを押す入力の位置（先に押す修飾キーの数）
+    pub fn from_sent(sent: usize, total: usize, key_down_index: usize) -> Self {
+        if sent >= total {
+            PasteKeys::Sent
+        } else if sent <= key_down_index {
+            PasteKeys::NotPressed
+        } else {
+            PasteKeys::Incomplete
+        }
+    }
+}
+
+/// 貼り付け操作を送信し、Vキーを押せなかった場合は待機時間を延ばしながら再試行する
+/// 
+/// 遅延の大きいリモートデスクトップなどで、貼り付け先へのフォーカスの移動が間に合わない場合に備える。
+/// Vキーを押した後に送信が止まった場合は、貼り付けが重複しないよう再試行しない
+/// 
+/// # 引数
+/// * `retries` - 再試行する回数
+/// * `base_delay` - 最初の再試行までの待機時間（再試行のたびにこの時間ずつ延ばす）
+/// * `send_paste` - 貼り付け操作（CTRL+V）を送信する関数
+/// * `wait` - 待機する関数
+/// 
+/// # 戻り値
+/// 貼り付け操作を送信できたかどうか（Vキーを押した後に送信が止まった場合も送信できたとみなす）
+pub fn send_paste_with_retry<F, W>(retries: u32, base_delay: Duration, mut send_paste: F, mut wait: W) -> bool
+where
+    F: FnMut() -> PasteKeys,
+    W: FnMut(Duration),
+{
+    for attempt in 0..=retries {
+        if attempt > 0 {
+            log::warn!("Retrying paste operation (retry {} of {})", attempt, retries);
+            wait(base_delay * attempt);
+        }
+        match send_paste() {
+            PasteKeys::Sent => return true,
+            PasteKeys::Incomplete => {
+                log::warn!("Paste key sequence was cut off after pressing V, not retrying to avoid pasting twice");
+                return true;
+            }
+            PasteKeys::NotPressed => {}
+        }
+    }
+    false
+}
+
+/// 選択中のテキストをコピーして取得し、クリップボードを元に戻す
+/// 
+/// # 引数
+/// * `clipboard` - クリップボード
+/// * `send_copy` - コピー操作（CTRL+C）を送信する関数
+/// 
+/// # 戻り値
+/// 選択中のテキスト（選択がない場合はNone）
+pub fn capture_selection<C, F>(clipboard: &mut C, send_copy: F) -> Option<String>
+where
+    C: ClipboardAccess,
+    F: FnOnce(&mut C) -> bool,
+{
+    // 既存のクリップボード内容を保存（あとで復元するため）
+    let original_text = clipboard.get_text();
+    
+    // コピーされたかどうかを判別できるように一旦空にする
+    clipboard.clear();
+    
+    let selection = if send_copy(clipboard) {
+        clipboard.get_text().filter(|text| !text.is_empty())
+    } else {
+        log::error!("Failed to send copy command");
+        None
+    };
+    
+    // クリップボードを元の状態に戻す
+    match original_text {
+        Some(text) => {
+            if !clipboard.set_text(&text) {
+                log::warn!("Failed to restore clipboard text");
+            }
+        }
+        None => {
+            clipboard.clear();
+        }
+    }
+    
+    log::debug!("Captured selection: {:?}", selection);
+    selection
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    
+    /// テスト用のメモリ上のクリップボード（テキストか画像のどちらかを保持する）
+    #[derive(Debug, Default)]
+    pub(crate) struct MemoryClipboard {
+        pub text: Option<String>,
+        pub image: Option<ImageData<'static>>,
+    }
+    
+    impl ClipboardAccess for MemoryClipboard {
+        fn get_text(&mut self) -> Option<String> {
+            self.text.clone()
+        }
+        
+        fn set_text(&mut self, text: &str) -> bool {
+            self.text = Some(text.to_string());
+            self.image = None;
+            true
+        }
+        
+        fn clear(&mut self) -> bool {
+            self.text = None;
+            self.image = None;
+            true
+        }
+        
+        fn get_image(&mut self) -> Option<ImageData<'static>> {
+            self.image.clone()
+        }
+        
+        fn set_image(&mut self, image: ImageData<'static>) -> bool {
+            self.image = Some(image);
+            self.text = None;
+            true
+        }
+    }
+    
+    /// テキストを保持したクリップボード
+    fn clipboard_with_text(text: &str) -> MemoryClipboard {
+        MemoryClipboard { text: Some(text.to_string()), ..Default::default() }
+    }
+    
+    #[test]
+    fn test_paste_restores_original_text() {
+        let mut clipboard = clipboard_with_text("original");
+        let mut pasted_text = None;
+        
+        // 貼り付け操作の時点では、貼り付けるテキストがクリップボードにある
+        let outcome = paste_with_restore(&mut clipboard, "snippet", |clipboard| {
+            pasted_text = clipboard.get_text();
+            true
+        });
+        
+        assert_eq!(outcome, PasteOutcome::Pasted);
+        assert_eq!(pasted_text.as_deref(), Some("snippet"));
+        assert_eq!(clipboard.text.as_deref(), Some("original"));
+    }
+    
+    #[test]
+    fn test_paste_restores_original_image() {
+        let image = ImageData {
+            width: 2,
+            height: 1,
+            bytes: vec![255, 0, 0, 255, 0, 0, 255, 255].into(),
+        };
+        let mut clipboard = MemoryClipboard { image: Some(image.clone()), ..Default::default() };
+        let mut pasted_text = None;
+        
+        let outcome = paste_with_restore(&mut clipboard, "snippet", |clipboard| {
+            pasted_text = clipboard.get_text();
+            true
+        });
+        
+        assert_eq!(outcome, PasteOutcome::Pasted);
+        assert_eq!(pasted_text.as_deref(), Some("snippet"));
+        assert_eq!(clipboard.text, None);
+        let restored = clipboard.image.expect("image should be restored");
+        assert_eq!((restored.width, restored.height), (image.width, image.height));
+        assert_eq!(restored.bytes, image.bytes);
+    }
+    
+    #[test]
+    fn test_paste_clears_clipboard_when_original_is_unavailable() {
+        // 元の内容を保存できない場合は、貼り付けたテキストを残さない
+        let mut clipboard = MemoryClipboard::default();
+        
+        assert_eq!(paste_with_restore(&mut clipboard, "snippet", |_| false), PasteOutcome::Failed);
+        assert_eq!(clipboard.text, None);
+    }
+    
+    #[test]
+    fn test_paste_reports_unavailable_clipboard() {
+        /// 他のアプリケーションが開いたままで、設定できないクリップボード
+        struct LockedClipboard;
+        
+        impl ClipboardAccess for LockedClipboard {
+            fn get_text(&mut self) -> Option<String> {
+                None
+            }
+            
+            fn set_text(&mut self, _text: &str) -> bool {
+                false
+            }
+            
+            fn clear(&mut self) -> bool {
+                false
+            }
+        }
+        
+        // 貼り付け操作は送信しない
+        let outcome = paste_with_restore(&mut LockedClipboard, "snippet", |_| panic!("paste should not be sent"));
+        assert_eq!(outcome, PasteOutcome::ClipboardUnavailable);
+    }
+    
+    #[test]
+    fn test_send_paste_with_retry_increases_delay() {
+        let mut attempts = 0;
+        let mut waits = Vec::new();
+        
+        let sent = send_paste_with_retry(3, Duration::from_millis(100), || {
+            attempts += 1;
+            if attempts == 3 { PasteKeys::Sent } else { PasteKeys::NotPressed }
+        }, |delay| waits.push(delay));
+        
+        assert!(sent);
+        assert_eq!(attempts, 3);
+        assert_eq!(waits, vec![Duration::from_millis(100), Duration::from_millis(200)]);
+        
+        // 再試行しても送信できない場合は失敗する
+        let mut attempts = 0;
+        assert!(!send_paste_with_retry(2, Duration::ZERO, || { attempts += 1; PasteKeys::NotPressed }, |_| {}));
+        assert_eq!(attempts, 3);
+    }
+    
+    #[test]
+    fn test_send_paste_with_retry_stops_after_v_is_pressed() {
+        // CTRLを押した後に止まった場合は、まだ貼り付けられていない
+        assert_eq!(PasteKeys::from_sent(1, 4, 1), PasteKeys::NotPressed);
+        assert_eq!(PasteKeys::from_sent(0, 4, 1), PasteKeys::NotPressed);
+        assert_eq!(PasteKeys::from_sent(2, 4, 1), PasteKeys::Incomplete);
+        assert_eq!(PasteKeys::from_sent(4, 4, 1), PasteKeys::Sent);
+        
+        // Vキーを押した後に止まった場合は、貼り付けが重複しないよう再試行しない
+        let mut attempts = 0;
+        let mut waits = Vec::new();
+        let sent = send_paste_with_retry(3, Duration::from_millis(100), || {
+            attempts += 1;
+            PasteKeys::from_sent(2, 4, 1)
+        }, |delay| waits.push(delay));
+        
+        assert!(sent);
+        assert_eq!(attempts, 1);
+        assert!(waits.is_empty());
+    }
+    
+    #[test]
+    fn test_capture_selection_restores_clipboard() {
+        let mut clipboard = clipboard_with_text("original");
+        
+        let selection = capture_selection(&mut clipboard, |clipboard| {
+            clipboard.set_text("selected text")
+        });
+        
+        assert_eq!(selection.as_deref(), Some("selected text"));
+        assert_eq!(clipboard.text.as_deref(), Some("original"));
+    }
+    
+    #[test]
+    fn test_capture_selection_without_selection() {
+        let mut clipboard = clipboard_with_text("original");
+        
+        // 何も選択されていない場合はコピーしてもクリップボードが空のまま
+        let selection = capture_selection(&mut clipboard, |_| true);
+        
+        assert_eq!(selection, None);
+        assert_eq!(clipboard.text.as_deref(), Some("original"));
+    }
+    
+    #[test]
+    fn test_capture_selection_with_empty_clipboard() {
+        let mut clipboard = MemoryClipboard::default();
+        
+        let selection = capture_selection(&mut clipboard, |clipboard| {
+            clipboard.set_text("selected text")
+        });
+        
+        assert_eq!(selection.as_deref(), Some("selected text"));
+        assert_eq!(clipboard.text, None);
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+use arboard::Clipboard;
+
+/// クリップボードの履歴に保持する件数
+pub const CLIPBOARD_HISTORY_SIZE: usize = 10;
+
+/// クリップボードを確認する間隔
+const CLIPBOARD_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 最近コピーしたテキストを一定件数だけ保持するリングバッファ
+#[derive(Debug)]
+pub struct ClipboardHistory {
+    /// コピーしたテキスト（新しいものが先頭）
+    entries: Mutex<VecDeque<String>>,
+    /// 保持する件数
+    capacity: usize,
+}
+
+impl ClipboardHistory {
+    /// 新しい履歴を作成する
+    /// 
+    /// # 引数
+    /// * `capacity` - 保持する件数（超えた場合は古いものから捨てる）
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+    
+    /// コピーしたテキストを追加する
+    /// 
+    /// # 戻り値
+    /// 追加したかどうか（空のテキストや最新の履歴と同じテキストは追加しない）
+    pub fn push(&self, text: &str) -> bool {
+        if text.is_empty() {
+            return false;
+        }
+        let Ok(mut entries) = self.entries.lock() else {
+            return false;
+        };
+        if entries.front().is_some_and(|latest| latest == text) {
+            return false;
+        }
+        
+        entries.push_front(text.to_string());
+        entries.truncate(self.capacity.max(1));
+        true
+    }
+    
+    /// 履歴のテキストを取得する
+    /// 
+    /// # 引数
+    /// * `index` - 取得する位置（1が最新）
+    /// 
+    /// # 戻り値
+    /// 履歴のテキスト（その位置の履歴がない場合はNone）
+    pub fn get(&self, index: usize) -> Option<String> {
+        let position = index.checked_sub(1)?;
+        self.entries.lock().ok()?.get(position).cloned()
+    }
+}
+
+/// クリップボードのテキストを、置換の実行中でなければ履歴に追加する
+/// 
+/// 置換の実行中はスニペットの貼り付けのために一時的にクリップボードを書き換えるため、
+/// その間に読み取ったテキストは追加しない
+/// 
+/// # 引数
+/// * `history` - コピーしたテキストを追加する履歴
+/// * `busy` - 置換を実行中かどうかを示すフラグ
+/// * `text` - クリップボードのテキスト
+/// 
+/// # 戻り値
+/// 履歴に追加したかどうか
+pub fn record_clipboard_text(history: &ClipboardHistory, busy: Option<&AtomicBool>, text: &str) -> bool {
+    if busy.is_some_and(|busy| busy.load(Ordering::SeqCst)) {
+        return false;
+    }
+    history.push(text)
+}
+
+/// アプリ全体で共有するクリップボードの履歴
+static CLIPBOARD_HISTORY: OnceLock<ClipboardHistory> = OnceLock::new();
+
+/// アプリ全体で共有するクリップボードの履歴を取得する
+pub fn clipboard_history() -> &'static ClipboardHistory {
+    CLIPBOARD_HISTORY.get_or_init(|| ClipboardHistory::new(CLIPBOARD_HISTORY_SIZE))
+}
+
+/// クリップボードを定期的に確認し、コピーされたテキストを履歴に追加する
+/// 
+/// 破棄すると監視のスレッドも終了する
+#[derive(Debug)]
+pub struct ClipboardWatcher {
+    /// 監視を終了するかどうか
+    stop: Arc<AtomicBool>,
+}
+
+impl ClipboardWatcher {
+    /// クリップボードの監視を開始する
+    /// 
+    /// 置換の実行中に読み取ったテキストは履歴に追加しない（`record_clipboard_text` を参照）
+    /// 
+    /// # 引数
+    /// * `history` - コピーしたテキストを追加する履歴
+    /// * `busy` - 置換を実行中かどうかを示すフラグ
+    pub fn start(history: &'static ClipboardHistory, busy: Option<Arc<AtomicBool>>) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        
+        thread::spawn(move || {
+            let mut clipboard = match Clipboard::new() {
+                Ok(clipboard) => clipboard,
+                Err(e) => {
+                    log::warn!("Failed to open clipboard for history: {}", e);
+                    return;
+                }
+            };
+            
+            while !thread_stop.load(Ordering::SeqCst) {
+                let replacing = busy.as_ref().is_some_and(|busy| busy.load(Ordering::SeqCst));
+                if !replacing {
+                    if let Ok(text) = clipboard.get_text() {
+                        if record_clipboard_text(history, busy.as_deref(), &text) {
+                            log::debug!("Recorded clipboard text in history");
+                        }
+                    }
+                }
+                thread::sleep(CLIPBOARD_POLL_INTERVAL);
+            }
+            log::debug!("Clipboard watcher stopped");
+        });
+        
+        Self { stop }
+    }
+}
+
+impl Drop for ClipboardWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_clipboard_history_keeps_newest_first() {
+        let history = ClipboardHistory::new(3);
+        for text in ["a", "b", "c", "d"] {
+            assert!(history.push(text));
+        }
+        
+        assert_eq!(history.get(1).as_deref(), Some("d"));
+        assert_eq!(history.get(3).as_deref(), Some("b"));
+        assert_eq!(history.get(4), None);
+        assert_eq!(history.get(0), None);
+    }
+    
+    #[test]
+    fn test_clipboard_history_skips_repeated_and_empty_text() {
+        let history = ClipboardHistory::new(3);
+        assert!(history.push("same"));
+        assert!(!history.push("same"));
+        assert!(!history.push(""));
+        assert_eq!(history.get(2), None);
+        
+        // 以前の履歴と同じでも、最新でなければ追加する
+        assert!(history.push("other"));
+        assert!(history.push("same"));
+        assert_eq!(history.get(2).as_deref(), Some("other"));
+    }
+}
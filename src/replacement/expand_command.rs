@@ -0,0 +1,191 @@
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// 展開後に実行する外部コマンド
+/// 
+/// 設定されたコマンドラインの末尾に、展開したキーワードとスニペット名を引数として追加する
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpandCommand {
+    /// 実行するプログラム
+    pub program: String,
+    /// プログラムに渡す引数
+    pub args: Vec<String>,
+}
+
+impl ExpandCommand {
+    /// 設定のコマンドラインから実行するコマンドを作成する
+    /// 
+    /// # 引数
+    /// * `command_line` - 設定されたコマンドライン（ダブルクォートで空白を含むパスを指定できる）
+    /// * `keyword` - 展開したキーワード
+    /// * `snippet_name` - 展開したスニペットの名前
+    /// 
+    /// # 戻り値
+    /// 実行するコマンド（コマンドラインが空の場合はNone）
+    pub fn new(command_line: &str, keyword: &str, snippet_name: &str) -> Option<Self> {
+        let mut parts = split_command_line(command_line).into_iter();
+        let program = parts.next()?;
+        
+        let mut args: Vec<String> = parts.collect();
+        args.push(sanitize_arg(keyword));
+        args.push(sanitize_arg(snippet_name));
+        
+        Some(Self { program, args })
+    }
+    
+    /// ログ表示用に、引数を引用符で囲んだコマンドラインを取得する
+    pub fn display_command_line(&self) -> String {
+        std::iter::once(&self.program)
+            .chain(self.args.iter())
+            .map(|arg| quote_arg(arg))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+    
+    /// コマンドを別スレッドで起動する（終了は待たない）
+    /// 
+    /// キーボードフックを止めないように、起動に失敗してもログに記録するだけにする
+    pub fn spawn_detached(self) {
+        thread::spawn(move || {
+            let mut command = Command::new(&self.program);
+            command
+                .args(&self.args)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null());
+            
+            // コンソールウィンドウを表示しない
+            #[cfg(windows)]
+            {
+                use std::os::windows::process::CommandExt;
+                const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+                command.creation_flags(CREATE_NO_WINDOW);
+            }
+            
+            match command.spawn() {
+                Ok(_) => log::debug!("Started on-expand command: {}", self.display_command_line()),
+                Err(e) => log::error!("Failed to start on-expand command {}: {}", self.display_command_line(), e),
+            }
+        });
+    }
+}
+
+/// コマンドラインを空白で分割する（ダブルクォートで囲んだ部分は1つの引数として扱う）
+fn split_command_line(command_line: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_part = false;
+    
+    for c in command_line.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_part = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_part {
+                    parts.push(std::mem::take(&mut current));
+                    has_part = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_part = true;
+            }
+        }
+    }
+    if has_part {
+        parts.push(current);
+    }
+    
+    parts
+}
+
+/// 引数から制御文字（改行やNULなど）を取り除く
+fn sanitize_arg(arg: &str) -> String {
+    arg.chars().filter(|c| !c.is_control()).collect()
+}
+
+/// Windowsのコマンドライン解析規則に従って引数を引用符で囲む
+/// 
+/// 引用符の直前のバックスラッシュは二重にし、引用符はバックスラッシュでエスケープする
+fn quote_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.chars().any(|c| c.is_whitespace() || c == '"') {
+        return arg.to_string();
+    }
+    
+    let mut quoted = String::from("\"");
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+                quoted.push('"');
+                backslashes = 0;
+            }
+            c => {
+                quoted.push_str(&"\\".repeat(backslashes));
+                quoted.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    // 末尾のバックスラッシュは閉じる引用符をエスケープしないように二重にする
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_new_appends_keyword_and_name() {
+        let command = ExpandCommand::new(
+            r#""C:\Program Files\Logger\log.exe" --source swifttype"#,
+            "ddate",
+            "Today's Date",
+        ).unwrap();
+        
+        assert_eq!(command.program, r"C:\Program Files\Logger\log.exe");
+        assert_eq!(command.args, vec!["--source", "swifttype", "ddate", "Today's Date"]);
+    }
+    
+    #[test]
+    fn test_new_with_empty_command() {
+        assert_eq!(ExpandCommand::new("", "ddate", "Date"), None);
+        assert_eq!(ExpandCommand::new("   ", "ddate", "Date"), None);
+    }
+    
+    #[test]
+    fn test_new_sanitizes_arguments() {
+        let command = ExpandCommand::new("log.exe", "key\0word", "line1\r\nline2").unwrap();
+        assert_eq!(command.args, vec!["keyword", "line1line2"]);
+    }
+    
+    #[test]
+    fn test_display_command_line_quotes_arguments() {
+        let command = ExpandCommand::new(
+            r#""C:\Tools\log.exe""#,
+            "sig",
+            r#"My "quoted" name"#,
+        ).unwrap();
+        
+        assert_eq!(
+            command.display_command_line(),
+            r#"C:\Tools\log.exe sig "My \"quoted\" name""#,
+        );
+    }
+    
+    #[test]
+    fn test_quote_arg() {
+        assert_eq!(quote_arg("plain"), "plain");
+        assert_eq!(quote_arg(""), r#""""#);
+        assert_eq!(quote_arg("two words"), r#""two words""#);
+        assert_eq!(quote_arg(r"C:\dir with space\"), r#""C:\dir with space\\""#);
+        assert_eq!(quote_arg(r#"a\"b"#), r#""a\\\"b""#);
+    }
+}
@@ -0,0 +1,60 @@
+use crossbeam_channel::Sender;
+
+use super::Replacement;
+
+/// 入力フォームの表示要求
+/// 
+/// 入力フィールドを含むスニペットが展開されたときに、UIへ値の入力を依頼する
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldFormRequest {
+    /// フィールドを置き換える前の置換
+    pub replacement: Replacement,
+    /// 展開先のウィンドウ（入力後にフォーカスを戻すため）
+    pub target_window: isize,
+}
+
+/// 入力フォームの表示要求をUIへ送る
+pub struct FieldFormSender {
+    /// 表示要求の送信先
+    sender: Sender<FieldFormRequest>,
+    /// 送信後に呼ばれる関数（UIの再描画要求など）
+    notify: Box<dyn Fn() + Send>,
+}
+
+impl FieldFormSender {
+    /// 新しい送信器を作成する
+    /// 
+    /// # 引数
+    /// * `sender` - 表示要求の送信先
+    /// * `notify` - 送信後に呼ばれる関数
+    pub fn new<F>(sender: Sender<FieldFormRequest>, notify: F) -> Self
+    where
+        F: Fn() + Send + 'static,
+    {
+        Self { sender, notify: Box::new(notify) }
+    }
+    
+    /// 表示要求を送信する
+    /// 
+    /// # 戻り値
+    /// 送信できたかどうか（UIが終了している場合は失敗する）
+    pub fn send(&self, request: FieldFormRequest) -> bool {
+        if self.sender.send(request).is_err() {
+            log::error!("Failed to send field form request");
+            return false;
+        }
+        
+        (self.notify)();
+        true
+    }
+}
+
+impl std::fmt::Debug for FieldFormSender {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FieldFormSender").finish_non_exhaustive()
+    }
+}
+
+// 共有参照から使うのは `send` だけで、送信器の状態は変更しないため、
+// 置換エンジンの `catch_unwind` 内で参照しても不整合な状態は観測されない
+impl std::panic::RefUnwindSafe for FieldFormSender {}
@@ -1,27 +1,160 @@
-use chrono::Local;
+use arboard::Clipboard;
+use chrono::{DateTime, Duration, Local, Locale, Months};
 use regex::Regex;
+use std::collections::HashMap;
 use std::sync::OnceLock;
+use rand::Rng;
+use uuid::Uuid;
+
+use crate::config::settings::DEFAULT_DATE_FORMAT;
+use super::clipboard_history::clipboard_history;
 
 /// 正規表現パターンのキャッシュ
 fn date_pattern() -> &'static Regex {
     static PATTERN: OnceLock<Regex> = OnceLock::new();
-    PATTERN.get_or_init(|| Regex::new(r"\{date:([^}]+)\}").unwrap())
+    // {date:書式} または {date:+3d:書式} のように先頭に日付のずれを指定できる
+    PATTERN.get_or_init(|| Regex::new(r"\{date:(?:([+-][^:}]*):)?([^}]+)\}").unwrap())
+}
+
+/// 大文字・小文字を変換しないプレースホルダーのパターンのキャッシュ
+fn placeholder_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\{(?:cursor|field:[^}]*|key:[^}]*)\}").unwrap())
+}
+
+/// 午前/午後を表す単独の「a」のパターンのキャッシュ
+fn am_pm_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\ba\b").unwrap())
+}
+
+/// UUIDトークンのパターンのキャッシュ
+fn uuid_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\{uuid(:upper)?\}").unwrap())
+}
+
+/// 乱数トークンのパターンのキャッシュ
+fn random_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\{random:([^}]*)\}").unwrap())
+}
+
+/// 乱数の範囲（min-max）のパターンのキャッシュ
+fn random_range_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"^\s*(-?\d+)\s*-\s*(-?\d+)\s*$").unwrap())
+}
+
+/// クリップボードのトークン（{clipboard}、{clipboard:変換}、{clipboard:履歴の位置,変換}）のパターンのキャッシュ
+fn clipboard_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\{clipboard(?::([^}]*))?\}").unwrap())
+}
+
+/// 入力フィールドのパターンのキャッシュ
+fn field_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\{field:([^}]+)\}").unwrap())
+}
+
+/// 書式を省略した日付のトークン（設定の日付の書式でフォーマットする）
+pub const DATE_TOKEN: &str = "{date}";
+
+/// `{{` の代わりに置換の間だけ使う文字（トークンとして扱われないよう私用領域の文字を使う）
+const LITERAL_OPEN_BRACE: char = '\u{E000}';
+/// `}}` の代わりに置換の間だけ使う文字
+const LITERAL_CLOSE_BRACE: char = '\u{E001}';
+
+/// `{{` と `}}` を、トークンの置換の間だけ別の文字にしておく
+fn protect_literal_braces(template: &str) -> String {
+    template
+        .replace("{{", &LITERAL_OPEN_BRACE.to_string())
+        .replace("}}", &LITERAL_CLOSE_BRACE.to_string())
+}
+
+/// トークンの置換後に、`{{` と `}}` を1つの波括弧に戻す
+/// 
+/// スニペットの展開では、入れ子のスニペット・カーソル位置・入力フィールドの処理が終わってから戻す
+/// （`{{snippet:x}}` や `{{cursor}}` がトークンとして扱われないため）
+pub fn restore_literal_braces(text: &str) -> String {
+    text.replace(LITERAL_OPEN_BRACE, "{").replace(LITERAL_CLOSE_BRACE, "}")
 }
 
 /// 動的コンテンツをフォーマットする
 /// 
+/// テンプレート内の日付のトークンは、すべて同じ日時でフォーマットする。
+/// `{{` と `}}` は1つの波括弧として出力し、`{{date:yyyy}}` のようにトークンを文字どおりに入力できる
+/// 
 /// # 引数
 /// * `template` - フォーマットするテンプレート文字列
+/// * `locale` - 月名や曜日名に使うロケール（"ja-JP" など。空の場合はシステムのロケール）
+/// * `default_date_format` - 書式を省略した{date}に使う書式（空の場合は `yyyy/MM/dd`）
+/// * `now` - 現在の日時として使う日時（Noneの場合は現在の日時。テストでは固定の日時を渡す）
 /// 
 /// # 戻り値
 /// フォーマット済みの文字列
-pub fn format_dynamic_content(template: &str) -> String {
+pub fn format_dynamic_content(template: &str, locale: &str, default_date_format: &str, now: Option<DateTime<Local>>) -> String {
+    restore_literal_braces(&format_keeping_literal_braces(template, locale, default_date_format, now))
+}
+
+/// 動的コンテンツをフォーマットし、`{{` と `}}` は置換の間だけ使う文字のまま返す
+/// 
+/// スニペットの展開で、入れ子のスニペットなどを処理した後に `restore_literal_braces` で戻すために使う
+pub(crate) fn format_keeping_literal_braces(template: &str, locale: &str, default_date_format: &str, now: Option<DateTime<Local>>) -> String {
+    let now = now.unwrap_or_else(Local::now);
+    
+    // {clipboard}の置換
+    // 注: 展開時の貼り付けでクリップボードが上書きされる前（キーワードの検出時）に読み取る
+    let template = protect_literal_braces(template);
+    let result = replace_clipboard_token(&format_tokens(&template, locale, default_date_format, now));
+    
+    log::debug!("Final formatted output: '{}'", result);
+    result
+}
+
+/// プレビューで{clipboard}の代わりに表示するテキスト
+pub const CLIPBOARD_PREVIEW: &str = "<clipboard>";
+
+/// エディタのプレビュー用に動的コンテンツをフォーマットする
+/// 
+/// 展開時と同じように日付などのトークンを置き換えるが、クリップボードには触れず、
+/// {clipboard}（変換の指定を含む）は `<clipboard>` と表示する
+/// 
+/// # 引数
+/// * `template` - フォーマットするテンプレート文字列
+/// * `locale` - 月名や曜日名に使うロケール
+/// * `default_date_format` - {date}に使う書式
+/// * `now` - 現在の日時として使う日時（Noneの場合は現在の日時）
+/// 
+/// # 戻り値
+/// プレビューの文字列
+pub fn preview_dynamic_content(template: &str, locale: &str, default_date_format: &str, now: Option<DateTime<Local>>) -> String {
+    let template = protect_literal_braces(template);
+    let now = now.unwrap_or_else(Local::now);
+    let preview = clipboard_pattern()
+        .replace_all(&format_tokens(&template, locale, default_date_format, now), regex::NoExpand(CLIPBOARD_PREVIEW))
+        .to_string();
+    restore_literal_braces(&preview)
+}
+
+/// {clipboard}以外のトークンを置き換える
+fn format_tokens(template: &str, locale: &str, default_date_format: &str, now: DateTime<Local>) -> String {
     log::debug!("Formatting dynamic content with template: '{}'", template);
+    let locale = resolve_locale(locale);
     
     // yyyy/MM/ddのようなパターンが直接指定されている場合は日付として処理
-    if template.contains("yyyy") || template.contains("MM") || template.contains("dd") ||
-       template.contains("HH") || template.contains("mm") || template.contains("ss") {
-        let result = format_date(template);
+    // （{date:...}などのトークンや、文字どおりに入力する波括弧を含む場合は、タグの外側の文字を日付の書式として扱わない）
+    let has_tokens = template.contains("{date:") ||
+                     template.contains(LITERAL_OPEN_BRACE) ||
+                     template.contains(LITERAL_CLOSE_BRACE) ||
+                     template.contains(DATE_TOKEN) ||
+                     template.contains("{snippet:") ||
+                     uuid_pattern().is_match(template) ||
+                     random_pattern().is_match(template);
+    if !has_tokens && (template.contains("yyyy") || template.contains("MM") || template.contains("dd") ||
+       template.contains("HH") || template.contains("mm") || template.contains("ss")) {
+        let result = format_date_at(now, template, locale);
         log::debug!("Formatted date template '{}' to '{}'", template, result);
         return result;
     }
@@ -34,34 +167,470 @@ pub fn format_dynamic_content(template: &str) -> String {
         let date_re = date_pattern();
         
         result = date_re.replace_all(&result, |caps: &regex::Captures| {
-            let format = &caps[1];
+            let format = &caps[2];
             log::debug!("Formatting date pattern: '{}'", format);
-            format_date(format)
+            match caps.get(1) {
+                Some(offset) => format_date_at(shift_date(now, offset.as_str()), format, locale),
+                None => format_date_at(now, format, locale),
+            }
         }).to_string();
         
         log::debug!("Replaced date patterns in template: '{}' -> '{}'", template, result);
     }
     
-    log::debug!("Final formatted output: '{}'", result);
+    // 書式を省略した{date}の置換
+    if result.contains(DATE_TOKEN) {
+        let format = if default_date_format.trim().is_empty() { DEFAULT_DATE_FORMAT } else { default_date_format };
+        result = result.replace(DATE_TOKEN, &format_date_at(now, format, locale));
+    }
+    
+    // {uuid}の置換（トークンごとに新しいUUIDを生成する）
+    if uuid_pattern().is_match(&result) {
+        result = uuid_pattern().replace_all(&result, |caps: &regex::Captures| {
+            let uuid = Uuid::new_v4().to_string();
+            if caps.get(1).is_some() {
+                uuid.to_uppercase()
+            } else {
+                uuid
+            }
+        }).to_string();
+    }
+    
+    // {random:min-max}の置換（トークンごとに別の乱数を生成する）
+    if random_pattern().is_match(&result) {
+        result = random_pattern().replace_all(&result, |caps: &regex::Captures| {
+            match random_in_range(&caps[1]) {
+                Some(value) => value.to_string(),
+                None => {
+                    log::warn!("Invalid random range '{}', leaving the token as is", &caps[1]);
+                    caps[0].to_string()
+                }
+            }
+        }).to_string();
+    }
+    
     result
 }
 
-/// 日付をフォーマットする補助関数
-fn format_date(format: &str) -> String {
-    let now = Local::now();
+/// `1-100` のような範囲から一様な乱数を生成する（両端を含む）
+/// 
+/// # 戻り値
+/// 生成した乱数（数値でない場合や最小値が最大値より大きい場合はNone）
+fn random_in_range(range: &str) -> Option<i64> {
+    let caps = random_range_pattern().captures(range)?;
+    let min: i64 = caps[1].parse().ok()?;
+    let max: i64 = caps[2].parse().ok()?;
+    if min > max {
+        return None;
+    }
+    Some(rand::thread_rng().gen_range(min..=max))
+}
+
+/// クリップボードの内容を挿入するトークン
+pub const CLIPBOARD_TOKEN: &str = "{clipboard}";
+
+/// テキストに含まれる{clipboard}を現在のクリップボードの内容で置き換える
+fn replace_clipboard_token(text: &str) -> String {
+    replace_clipboard_token_with(text, read_system_clipboard)
+}
+
+/// システムのクリップボードからテキストを読み取る
+fn read_system_clipboard() -> Option<String> {
+    let clipboard_text = Clipboard::new().and_then(|mut clipboard| clipboard.get_text());
+    if let Err(e) = &clipboard_text {
+        log::warn!("Failed to read clipboard text for {}: {}", CLIPBOARD_TOKEN, e);
+    }
+    clipboard_text.ok()
+}
+
+/// テキストに含まれる{clipboard}を指定した取得元のテキストで置き換える
+/// 
+/// # 引数
+/// * `text` - トークンを含むテキスト
+/// * `read_clipboard` - クリップボードのテキストを取得する関数（トークンを含む場合だけ1回呼ぶ）
+/// 
+/// # 戻り値
+/// トークンを置き換えたテキスト
+pub fn replace_clipboard_token_with(text: &str, read_clipboard: impl FnOnce() -> Option<String>) -> String {
+    replace_clipboard_tokens(text, read_clipboard, |index| clipboard_history().get(index))
+}
+
+/// {clipboard}をクリップボードのテキストで、{clipboard:N}をクリップボードの履歴のテキストで置き換える
+/// 
+/// `{clipboard:upper}` や `{clipboard:2,upper}` のように変換を指定したトークンは、変換したテキストで置き換える。
+/// 履歴にない位置を指定したトークンは警告を出して空文字列に置き換える
+/// 
+/// # 引数
+/// * `text` - トークンを含むテキスト
+/// * `read_clipboard` - 現在のクリップボードのテキストを取得する関数（{clipboard}を含む場合だけ1回呼ぶ）
+/// * `read_history` - 履歴のテキストを取得する関数（1が最新）
+/// 
+/// # 戻り値
+/// トークンを置き換えたテキスト
+pub fn replace_clipboard_tokens(
+    text: &str,
+    read_clipboard: impl FnOnce() -> Option<String>,
+    read_history: impl Fn(usize) -> Option<String>,
+) -> String {
+    if !clipboard_pattern().is_match(text) {
+        return text.to_string();
+    }
+    
+    let mut read_clipboard = Some(read_clipboard);
+    let mut current_text: Option<String> = None;
+    clipboard_pattern().replace_all(text, |caps: &regex::Captures| {
+        let (index, modifiers) = split_history_index(caps.get(1).map_or("", |m| m.as_str()));
+        let clipboard_text = match index {
+            Some(index) => read_history(index).unwrap_or_else(|| {
+                log::warn!("Clipboard history has no entry {}, it was replaced with an empty string", index);
+                String::new()
+            }),
+            None => current_text.get_or_insert_with(|| {
+                let text = read_clipboard.take().and_then(|read| read()).unwrap_or_default();
+                if text.is_empty() {
+                    log::warn!("Clipboard is empty, {} was replaced with an empty string", CLIPBOARD_TOKEN);
+                }
+                text
+            }).clone(),
+        };
+        transform_clipboard_text(&clipboard_text, modifiers)
+    }).to_string()
+}
+
+/// クリップボードのトークンの指定から、先頭の履歴の位置と残りの変換の指定を分ける
+/// 
+/// # 引数
+/// * `modifiers` - トークンの `:` より後の指定（`2,upper` など）
+/// 
+/// # 戻り値
+/// 履歴の位置（指定がない場合はNone）と変換の指定
+fn split_history_index(modifiers: &str) -> (Option<usize>, &str) {
+    let (first, rest) = modifiers.split_once(',').unwrap_or((modifiers, ""));
+    match first.trim().parse::<usize>() {
+        Ok(index) => (Some(index), rest),
+        Err(_) => (None, modifiers),
+    }
+}
+
+/// クリップボードのテキストに変換を適用する
+/// 
+/// 使える変換は `upper`（大文字）、`lower`（小文字）、`trim`（前後の空白を削除）、
+/// `title`（単語の先頭だけ大文字）。`trim,upper` のようにカンマ区切りで指定すると左から順に適用する。
+/// 不明な変換は警告を出して無視する
+/// 
+/// # 引数
+/// * `text` - クリップボードのテキスト
+/// * `modifiers` - 変換の指定
+/// 
+/// # 戻り値
+/// 変換したテキスト
+pub fn transform_clipboard_text(text: &str, modifiers: &str) -> String {
+    modifiers
+        .split(',')
+        .map(str::trim)
+        .filter(|modifier| !modifier.is_empty())
+        .fold(text.to_string(), |text, modifier| match modifier.to_ascii_lowercase().as_str() {
+            "upper" => text.to_uppercase(),
+            "lower" => text.to_lowercase(),
+            "trim" => text.trim().to_string(),
+            "title" => to_title_case(&text),
+            _ => {
+                log::warn!("Unknown clipboard modifier '{}', inserting the text unchanged", modifier);
+                text
+            }
+        })
+}
+
+/// 空白で区切られた単語の先頭を大文字、残りを小文字にする
+fn to_title_case(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut at_word_start = true;
+    for c in text.chars() {
+        if at_word_start {
+            result.extend(c.to_uppercase());
+        } else {
+            result.extend(c.to_lowercase());
+        }
+        at_word_start = c.is_whitespace();
+    }
+    result
+}
+
+/// 展開後のカーソル位置を示すマーカー
+pub const CURSOR_MARKER: &str = "{cursor}";
+
+/// テキストからカーソル位置のマーカーを取り除く
+/// 
+/// 最初のマーカーの位置だけを使い、2つ目以降のマーカーは削除するだけにする
+/// 
+/// # 引数
+/// * `text` - マーカーを含むテキスト
+/// 
+/// # 戻り値
+/// マーカーを取り除いたテキストと、末尾からマーカーの位置までの文字数
+/// （改行の\r\nは1文字として数える。マーカーがない場合は0）
+pub fn extract_cursor_marker(text: &str) -> (String, usize) {
+    let Some(position) = text.find(CURSOR_MARKER) else {
+        return (text.to_string(), 0);
+    };
+    
+    let before = &text[..position];
+    let after = text[position + CURSOR_MARKER.len()..].replace(CURSOR_MARKER, "");
+    
+    // 左矢印キーの回数はバイト数ではなく文字数で数える
+    let offset = after.chars().filter(|&c| c != '\r').count();
+    
+    (format!("{}{}", before, after), offset)
+}
+
+/// テキストに含まれる入力フィールド（`{field:名前}`）の名前を取得する
+/// 
+/// # 引数
+/// * `text` - フィールドを含むテキスト
+/// 
+/// # 戻り値
+/// 出現順に並べたフィールド名（同じ名前は1つにまとめる）
+pub fn extract_field_names(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for caps in field_pattern().captures_iter(text) {
+        let name = caps[1].trim();
+        if !name.is_empty() && !names.iter().any(|existing| existing == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// 入力フィールドを入力された値で置き換える
+/// 
+/// # 引数
+/// * `text` - フィールドを含むテキスト
+/// * `values` - フィールド名と入力された値（値がないフィールドは空文字列になる）
+pub fn fill_fields(text: &str, values: &HashMap<String, String>) -> String {
+    field_pattern().replace_all(text, |caps: &regex::Captures| {
+        values.get(caps[1].trim()).cloned().unwrap_or_default()
+    }).to_string()
+}
+
+/// テキストのエスケープシーケンスを対応する文字に置き換える
+/// 
+/// `\t` はタブ、`\n` は改行、`\\` はバックスラッシュになる。それ以外の `\` はそのまま残す
+/// 
+/// # 引数
+/// * `text` - エスケープシーケンスを含むテキスト
+pub fn process_escapes(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        
+        let escaped = match chars.peek() {
+            Some('t') => '\t',
+            Some('n') => '\n',
+            Some('\\') => '\\',
+            _ => {
+                result.push(c);
+                continue;
+            }
+        };
+        chars.next();
+        result.push(escaped);
+    }
+    
+    result
+}
+
+/// 入力されたキーワードの大文字・小文字の書き方
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseStyle {
+    /// 登録したとおりに展開する
+    AsAuthored,
+    /// 先頭の文字だけを大文字にする
+    Capitalized,
+    /// すべて大文字にする
+    Upper,
+}
+
+/// 入力されたキーワードから大文字・小文字の書き方を判定する
+/// 
+/// 日本語などの内容を変えないよう、ASCIIの英字を含むキーワードだけを判定する
+/// 
+/// # 引数
+/// * `trigger` - 入力されたキーワード
+/// 
+/// # 戻り値
+/// `Sig` は先頭だけ大文字、`SIG` はすべて大文字、それ以外は登録したとおり
+pub fn detect_case_style(trigger: &str) -> CaseStyle {
+    if !trigger.is_ascii() {
+        return CaseStyle::AsAuthored;
+    }
+    
+    let letters: Vec<char> = trigger.chars().filter(char::is_ascii_alphabetic).collect();
+    match letters.as_slice() {
+        [first, rest @ ..] if first.is_ascii_uppercase() => {
+            if !rest.is_empty() && rest.iter().all(char::is_ascii_uppercase) {
+                CaseStyle::Upper
+            } else {
+                CaseStyle::Capitalized
+            }
+        }
+        _ => CaseStyle::AsAuthored,
+    }
+}
+
+/// テキストを指定した書き方に変換する
+/// 
+/// `{cursor}`、`{field:...}`、`{key:...}` のプレースホルダーは変換しない
+/// 
+/// # 引数
+/// * `text` - 変換するテキスト
+/// * `style` - 大文字・小文字の書き方
+pub fn apply_case_style(text: &str, style: CaseStyle) -> String {
+    if style == CaseStyle::AsAuthored {
+        return text.to_string();
+    }
+    
+    let mut result = String::with_capacity(text.len());
+    let mut capitalized = false;
+    let mut convert = |segment: &str, result: &mut String| {
+        match style {
+            CaseStyle::Upper => result.push_str(&segment.to_uppercase()),
+            _ => {
+                for c in segment.chars() {
+                    // 最初の英字だけを大文字にする
+                    if !capitalized && c.is_alphabetic() {
+                        result.extend(c.to_uppercase());
+                        capitalized = true;
+                    } else {
+                        result.push(c);
+                    }
+                }
+            }
+        }
+    };
+    
+    let mut last = 0;
+    for placeholder in placeholder_pattern().find_iter(text) {
+        convert(&text[last..placeholder.start()], &mut result);
+        result.push_str(placeholder.as_str());
+        last = placeholder.end();
+    }
+    convert(&text[last..], &mut result);
+    
+    result
+}
+
+/// 日付を指定したずれ（`+3d`、`-1w`、`+2m`、`-1y` など）だけ移動する
+/// 
+/// 月と年の移動で日付が存在しない場合は月末に合わせる（1月31日の1か月後は2月末日）
+/// 
+/// # 引数
+/// * `date` - 基準の日時
+/// * `offset` - `+` または `-` に続けて数値と単位（d: 日, w: 週, m: 月, y: 年）
+/// 
+/// # 戻り値
+/// 移動後の日時（ずれの指定が不正な場合は基準の日時）
+fn shift_date(date: DateTime<Local>, offset: &str) -> DateTime<Local> {
+    let shifted = parse_date_offset(offset).and_then(|(amount, unit)| {
+        let months = |count: i64| u32::try_from(count.unsigned_abs()).ok().map(Months::new);
+        match unit {
+            'd' => date.checked_add_signed(Duration::days(amount)),
+            'w' => date.checked_add_signed(Duration::weeks(amount)),
+            'm' | 'y' => {
+                let count = if unit == 'y' { amount.checked_mul(12)? } else { amount };
+                if count >= 0 {
+                    date.checked_add_months(months(count)?)
+                } else {
+                    date.checked_sub_months(months(count)?)
+                }
+            }
+            _ => None,
+        }
+    });
+    
+    shifted.unwrap_or_else(|| {
+        log::debug!("Invalid date offset '{}', using the current date", offset);
+        date
+    })
+}
+
+/// `+3d` のような日付のずれを、符号付きの数値と単位に分解する
+fn parse_date_offset(offset: &str) -> Option<(i64, char)> {
+    let unit = offset.chars().last()?;
+    let amount: i64 = offset[..offset.len() - unit.len_utf8()].parse().ok()?;
+    Some((amount, unit))
+}
+
+/// 設定のロケール名をchronoのロケールに変換する
+/// 
+/// # 引数
+/// * `name` - "ja-JP" や "de_DE" のようなロケール名（空の場合はシステムのロケール）
+/// 
+/// # 戻り値
+/// 対応するロケール（不明な場合はen_US）
+pub fn resolve_locale(name: &str) -> Locale {
+    let name = name.trim();
+    let name = if name.is_empty() {
+        sys_locale::get_locale().unwrap_or_default()
+    } else {
+        name.to_string()
+    };
+    
+    // chronoのロケール名は "ja_JP" の形式
+    Locale::try_from(name.replace('-', "_").as_str()).unwrap_or_else(|_| {
+        log::debug!("Unknown locale '{}', using en_US", name);
+        Locale::en_US
+    })
+}
+
+/// 日付の書式で現在の日時をフォーマットできるか確認する
+/// 
+/// # 引数
+/// * `format` - 確認する書式
+/// * `locale` - 月名や曜日名に使うロケール
+/// * `now` - 現在の日時として使う日時（Noneの場合は現在の日時）
+/// 
+/// # 戻り値
+/// 現在の日時をフォーマットした結果（結果が空になる場合はエラー）
+pub fn check_date_format(format: &str, locale: &str, now: Option<DateTime<Local>>) -> Result<String, String> {
+    let result = format_date_at(now.unwrap_or_else(Local::now), format, resolve_locale(locale));
+    if result.trim().is_empty() {
+        return Err(format!("Date format '{}' produces an empty result", format));
+    }
+    Ok(result)
+}
+
+/// 指定した日時をフォーマットする
+fn format_date_at(now: DateTime<Local>, format: &str, locale: Locale) -> String {
+    // 単独の「a」は午前/午後を表す（ttと同じ）
+    let format = am_pm_pattern().replace_all(format, "tt");
     
     // chrono形式に変換
+    // 注: MMMMがMMとして変換されないよう、長いトークンから順に置き換える
+    // （DDD（年内の通算日）とww（ISO週番号）は大文字・小文字が異なるため、ddやssとは重ならない）
     let chrono_format = format
+        .replace("DDD", "%j")
+        .replace("ww", "%V")
         .replace("yyyy", "%Y")
         .replace("yy", "%y")
+        .replace("MMMM", "%B")
+        .replace("MMM", "%b")
+        .replace("EEEE", "%A")
+        .replace("EEE", "%a")
         .replace("MM", "%m")
         .replace("dd", "%d")
         .replace("HH", "%H")
+        .replace("hh", "%I")
+        .replace("tt", "%p")
         .replace("mm", "%M")
         .replace("ss", "%S");
     
     log::debug!("Converting format '{}' to chrono format '{}'", format, chrono_format);
-    let result = now.format(&chrono_format).to_string();
+    let result = now.format_localized(&chrono_format, locale).to_string();
     log::debug!("Formatted date: '{}'", result);
     
     result
@@ -70,41 +639,387 @@ fn format_date(format: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::Local;
+    use chrono::TimeZone;
+    
+    /// テストで現在の日時として使う日時（2026/01/05は月曜日）
+    fn fixed_now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 1, 5, 15, 45, 30).unwrap()
+    }
+    
+    /// 固定の日時でデフォルトの書式を使って動的コンテンツをフォーマットする
+    fn format_fixed(template: &str) -> String {
+        format_dynamic_content(template, "en-US", DEFAULT_DATE_FORMAT, Some(fixed_now()))
+    }
     
     #[test]
     fn test_format_static_content() {
-        let result = format_dynamic_content("Hello, World!");
+        let result = format_dynamic_content("Hello, World!", "en-US", DEFAULT_DATE_FORMAT, None);
         assert_eq!(result, "Hello, World!");
     }
     
     #[test]
     fn test_format_date() {
-        let now = Local::now();
-        
         // YYYYMMDDフォーマット
-        let result = format_dynamic_content("{date:yyyyMMdd}");
-        let expected = now.format("%Y%m%d").to_string();
-        assert_eq!(result, expected);
+        assert_eq!(format_fixed("{date:yyyyMMdd}"), "20260105");
         
         // YYYY/MM/DDフォーマット
-        let result = format_dynamic_content("{date:yyyy/MM/dd}");
-        let expected = now.format("%Y/%m/%d").to_string();
-        assert_eq!(result, expected);
+        assert_eq!(format_fixed("{date:yyyy/MM/dd}"), "2026/01/05");
+        
+        // 時刻を含むフォーマット
+        assert_eq!(format_fixed("{date:yyyy-MM-dd HH:mm:ss}"), "2026-01-05 15:45:30");
+    }
+    
+    #[test]
+    fn test_bare_date_uses_default_format() {
+        assert_eq!(format_fixed("Today: {date}"), "Today: 2026/01/05");
+        
+        let result = format_dynamic_content("{date} {date:HH}", "en-US", "dd.MM.yyyy", Some(fixed_now()));
+        assert_eq!(result, "05.01.2026 15");
+        
+        // 書式が空の場合はデフォルトの書式を使う
+        let result = format_dynamic_content("{date}", "en-US", " ", Some(fixed_now()));
+        assert_eq!(result, "2026/01/05");
+    }
+    
+    #[test]
+    fn test_check_date_format() {
+        assert_eq!(check_date_format("yyyyMMdd", "en-US", Some(fixed_now())), Ok("20260105".to_string()));
+        assert!(check_date_format("", "en-US", Some(fixed_now())).is_err());
+        assert!(check_date_format("   ", "en-US", Some(fixed_now())).is_err());
+    }
+    
+    #[test]
+    fn test_preview_does_not_read_clipboard() {
+        let result = preview_dynamic_content("{date:yyyyMMdd} {clipboard}", "en-US", DEFAULT_DATE_FORMAT, Some(fixed_now()));
+        assert_eq!(result, format!("20260105 {}", CLIPBOARD_PREVIEW));
     }
     
     #[test]
     fn test_format_time() {
-        let template = "{date:HH:mm:ss}";
-        let result = format_dynamic_content(template);
-        assert!(result.len() == 8); // HH:MM:SS形式で8文字
-        assert!(result.contains(":"));
+        assert_eq!(format_fixed("{date:HH:mm:ss}"), "15:45:30");
+        // 日付のトークンを含まない書式だけのテンプレート
+        assert_eq!(format_fixed("HH:mm:ss"), "15:45:30");
+    }
+    
+    #[test]
+    fn test_extract_cursor_marker() {
+        assert_eq!(extract_cursor_marker("Hello, World!"), ("Hello, World!".to_string(), 0));
+        assert_eq!(extract_cursor_marker("Dear {cursor},\nThanks"), ("Dear ,\nThanks".to_string(), 8));
+        assert_eq!(extract_cursor_marker("End{cursor}"), ("End".to_string(), 0));
+    }
+    
+    #[test]
+    fn test_extract_cursor_marker_counts_chars() {
+        // 日本語は1文字を複数バイトで表すが、矢印キーの回数は文字数になる
+        assert_eq!(extract_cursor_marker("「{cursor}」です"), ("「」です".to_string(), 3));
+        // \r\nは1回の矢印キーで移動する
+        assert_eq!(extract_cursor_marker("{cursor}a\r\nb"), ("a\r\nb".to_string(), 3));
+    }
+    
+    #[test]
+    fn test_extract_only_first_cursor_marker() {
+        assert_eq!(extract_cursor_marker("a{cursor}b{cursor}c"), ("abc".to_string(), 2));
+    }
+    
+    #[test]
+    fn test_doubled_braces_are_literal() {
+        assert_eq!(format_fixed("{{date:yyyy}}"), "{date:yyyy}");
+        assert_eq!(format_fixed("Type {{date:yyyy}} to get {date:yyyy}"), "Type {date:yyyy} to get 2026");
+        assert_eq!(format_fixed("{{date}} {{uuid}} {{random:1-6}}"), "{date} {uuid} {random:1-6}");
+        
+        // 文字どおりの{clipboard}はクリップボードを読み取らない
+        assert_eq!(
+            replace_clipboard_token_with(&protect_literal_braces("{{clipboard}}"), || panic!("clipboard should not be read")),
+            protect_literal_braces("{{clipboard}}"),
+        );
+        assert_eq!(format_fixed("{{clipboard}}"), "{clipboard}");
+        
+        // プレビューでも同じように表示する
+        assert_eq!(preview_dynamic_content("{{clipboard}} {clipboard}", "en-US", DEFAULT_DATE_FORMAT, Some(fixed_now())), "{clipboard} <clipboard>");
+    }
+    
+    #[test]
+    fn test_doubled_braces_skip_bare_date_pattern() {
+        // 日付の書式だけのテンプレートでも、波括弧で囲むと文字どおりに出力する
+        assert_eq!(format_fixed("yyyy/MM/dd"), "2026/01/05");
+        assert_eq!(format_fixed("{{yyyy/MM/dd}}"), "{yyyy/MM/dd}");
+    }
+    
+    #[test]
+    fn test_process_escapes() {
+        assert_eq!(process_escapes(r"Name\tPrice\nApple\t100"), "Name\tPrice\nApple\t100");
+        assert_eq!(process_escapes(r"C:\\temp"), r"C:\temp");
+        
+        // \\t はバックスラッシュの後に t が続く
+        assert_eq!(process_escapes(r"\\t"), r"\t");
+    }
+    
+    #[test]
+    fn test_process_escapes_keeps_unknown_sequences() {
+        assert_eq!(process_escapes(r"C:\Users\x"), r"C:\Users\x");
+        assert_eq!(process_escapes(r"end\"), r"end\");
+        assert_eq!(process_escapes("no escapes"), "no escapes");
+    }
+    
+    #[test]
+    fn test_extract_field_names() {
+        assert!(extract_field_names("No fields here").is_empty());
+        assert_eq!(
+            extract_field_names("Hi {field:Name}, ticket {field:Ticket#} for {field: Name }? {field:Name}"),
+            vec!["Name".to_string(), "Ticket#".to_string()],
+        );
+    }
+    
+    #[test]
+    fn test_fill_fields() {
+        let values: HashMap<String, String> = [
+            ("Name".to_string(), "太郎".to_string()),
+            ("Ticket#".to_string(), "42".to_string()),
+        ].into();
+        
+        assert_eq!(
+            fill_fields("{field:Name} / #{field:Ticket#} / {field:Name} / {field:Missing}", &values),
+            "太郎 / #42 / 太郎 / ",
+        );
+    }
+    
+    #[test]
+    fn test_detect_case_style() {
+        assert_eq!(detect_case_style("sig"), CaseStyle::AsAuthored);
+        assert_eq!(detect_case_style("Sig"), CaseStyle::Capitalized);
+        assert_eq!(detect_case_style("SIG"), CaseStyle::Upper);
+        assert_eq!(detect_case_style("S"), CaseStyle::Capitalized);
+        assert_eq!(detect_case_style("SIG2"), CaseStyle::Upper);
+        assert_eq!(detect_case_style("123"), CaseStyle::AsAuthored);
+        assert_eq!(detect_case_style("Aあ"), CaseStyle::AsAuthored);
+    }
+    
+    #[test]
+    fn test_apply_case_style() {
+        assert_eq!(apply_case_style("best regards", CaseStyle::AsAuthored), "best regards");
+        assert_eq!(apply_case_style("best regards", CaseStyle::Capitalized), "Best regards");
+        assert_eq!(apply_case_style("best regards", CaseStyle::Upper), "BEST REGARDS");
+        
+        // プレースホルダーは変換しない
+        assert_eq!(
+            apply_case_style("{field:name} hi{cursor}", CaseStyle::Upper),
+            "{field:name} HI{cursor}",
+        );
+        assert_eq!(apply_case_style("{cursor}hi there", CaseStyle::Capitalized), "{cursor}Hi there");
+    }
+    
+    #[test]
+    fn test_insert_clipboard_text() {
+        assert_eq!(
+            replace_clipboard_token_with("<code>{clipboard}</code>", || Some("let x = 1;".to_string())),
+            "<code>let x = 1;</code>",
+        );
+        assert_eq!(replace_clipboard_token_with("[{clipboard}|{clipboard}]", || Some("あ".to_string())), "[あ|あ]");
+        assert_eq!(replace_clipboard_token_with("<code>{clipboard}</code>", || None), "<code></code>");
+    }
+    
+    #[test]
+    fn test_clipboard_history_tokens() {
+        let history = |index: usize| ["latest", "older"].get(index.wrapping_sub(1)).map(|text| text.to_string());
+        assert_eq!(
+            replace_clipboard_tokens("{clipboard:1} / {clipboard:2}", || panic!("clipboard should not be read"), history),
+            "latest / older",
+        );
+        assert_eq!(replace_clipboard_tokens("[{clipboard: 2 ,upper}]", || None, history), "[OLDER]");
+        assert_eq!(
+            replace_clipboard_tokens("{clipboard} {clipboard:1}", || Some("now".to_string()), history),
+            "now latest",
+        );
+        
+        // 履歴にない位置は空文字列にする
+        assert_eq!(replace_clipboard_tokens("[{clipboard:3}|{clipboard:0}]", || None, history), "[|]");
+    }
+    
+    #[test]
+    fn test_clipboard_modifiers() {
+        let source = || Some("  hello WORLD  ".to_string());
+        assert_eq!(replace_clipboard_token_with("[{clipboard:upper}]", source), "[  HELLO WORLD  ]");
+        assert_eq!(replace_clipboard_token_with("[{clipboard:lower}]", source), "[  hello world  ]");
+        assert_eq!(replace_clipboard_token_with("[{clipboard:trim}]", source), "[hello WORLD]");
+        assert_eq!(replace_clipboard_token_with("[{clipboard:trim,title}]", source), "[Hello World]");
+        assert_eq!(
+            replace_clipboard_token_with("{clipboard} / {clipboard:trim}", source),
+            "  hello WORLD   / hello WORLD",
+        );
+    }
+    
+    #[test]
+    fn test_unknown_clipboard_modifier_keeps_text() {
+        let source = || Some("Mixed Case".to_string());
+        assert_eq!(replace_clipboard_token_with("{clipboard:reverse}", source), "Mixed Case");
+        assert_eq!(replace_clipboard_token_with("{clipboard:reverse,upper}", source), "MIXED CASE");
+    }
+    
+    #[test]
+    fn test_clipboard_source_is_read_only_when_needed() {
+        let result = replace_clipboard_token_with("no token", || panic!("clipboard should not be read"));
+        assert_eq!(result, "no token");
+        assert_eq!(replace_clipboard_token_with("[{clipboard:upper}]", || None), "[]");
+    }
+    
+    #[test]
+    fn test_preview_hides_clipboard_modifiers() {
+        assert_eq!(
+            preview_dynamic_content("{clipboard:upper} {clipboard}", "", DEFAULT_DATE_FORMAT, Some(fixed_now())),
+            format!("{} {}", CLIPBOARD_PREVIEW, CLIPBOARD_PREVIEW),
+        );
+    }
+    
+    #[test]
+    fn test_relative_date_tokens() {
+        assert_eq!(format_fixed("{date:+3d:yyyy/MM/dd}"), "2026/01/08");
+        assert_eq!(format_fixed("Last week: {date:-1w:yyyyMMdd}"), "Last week: 20251229");
+    }
+    
+    #[test]
+    fn test_shift_date() {
+        let base = Local.with_ymd_and_hms(2024, 1, 31, 9, 30, 0).unwrap();
+        let format = |offset: &str| format_date_at(shift_date(base, offset), "yyyy/MM/dd HH:mm", Locale::en_US);
+        
+        assert_eq!(format("+3d"), "2024/02/03 09:30");
+        assert_eq!(format("-10d"), "2024/01/21 09:30");
+        assert_eq!(format("+2w"), "2024/02/14 09:30");
+        // 存在しない日付は月末に合わせる
+        assert_eq!(format("+1m"), "2024/02/29 09:30");
+        assert_eq!(format("-2m"), "2023/11/30 09:30");
+        assert_eq!(format("+1y"), "2025/01/31 09:30");
+        assert_eq!(format("-1y"), "2023/01/31 09:30");
+    }
+    
+    #[test]
+    fn test_invalid_date_offset_uses_current_date() {
+        let base = Local.with_ymd_and_hms(2024, 1, 31, 9, 30, 0).unwrap();
+        
+        assert_eq!(shift_date(base, "+3x"), base);
+        assert_eq!(shift_date(base, "+d"), base);
+        assert_eq!(shift_date(base, "+"), base);
+        assert_eq!(shift_date(base, "+99999999999y"), base);
+        
+        assert_eq!(format_fixed("{date:+abc:yyyyMMdd}"), "20260105");
+    }
+    
+    #[test]
+    fn test_month_and_weekday_names() {
+        // 2026/01/05は月曜日
+        let base = Local.with_ymd_and_hms(2026, 1, 5, 9, 30, 0).unwrap();
+        
+        assert_eq!(format_date_at(base, "EEEE, MMMM dd", Locale::en_US), "Monday, January 05");
+        assert_eq!(format_date_at(base, "EEE, dd MMM yyyy", Locale::en_US), "Mon, 05 Jan 2026");
+        assert_eq!(format_date_at(base, "MMMM/MM", Locale::en_US), "January/01");
+        
+        assert_eq!(format_fixed("{date:EEEE, MMMM dd}"), "Monday, January 05");
+    }
+    
+    #[test]
+    fn test_twelve_hour_clock() {
+        let afternoon = Local.with_ymd_and_hms(2026, 1, 5, 15, 45, 0).unwrap();
+        let morning = Local.with_ymd_and_hms(2026, 1, 5, 0, 5, 0).unwrap();
+        
+        assert_eq!(format_date_at(afternoon, "hh:mm tt", Locale::en_US), "03:45 PM");
+        assert_eq!(format_date_at(morning, "hh:mm tt", Locale::en_US), "12:05 AM");
+        assert_eq!(format_date_at(afternoon, "hh:mm a", Locale::en_US), "03:45 PM");
+        // 24時間表記は変わらない
+        assert_eq!(format_date_at(afternoon, "HH:mm", Locale::en_US), "15:45");
+        
+        assert_eq!(format_fixed("{date:hh:mm tt}"), "03:45 PM");
+    }
+    
+    #[test]
+    fn test_week_number_and_day_of_year() {
+        // 2026/01/01は木曜日のため、ISO週の第1週に含まれる
+        let new_year = Local.with_ymd_and_hms(2026, 1, 1, 9, 0, 0).unwrap();
+        assert_eq!(format_date_at(new_year, "yyyy-Www", Locale::en_US), "2026-W01");
+        assert_eq!(format_date_at(new_year, "DDD", Locale::en_US), "001");
+        
+        // 2027/01/01は金曜日のため、前年の第53週になる
+        let next_year = Local.with_ymd_and_hms(2027, 1, 1, 9, 0, 0).unwrap();
+        assert_eq!(format_date_at(next_year, "ww", Locale::en_US), "53");
+        
+        let base = Local.with_ymd_and_hms(2026, 12, 31, 23, 59, 58).unwrap();
+        assert_eq!(format_date_at(base, "DDD dd ss", Locale::en_US), "365 31 58");
+        
+        // 対応していない文字はそのまま残す
+        assert_eq!(format_date_at(base, "W-D", Locale::en_US), "W-D");
+    }
+    
+    #[test]
+    fn test_localized_names() {
+        let base = Local.with_ymd_and_hms(2026, 1, 5, 15, 45, 0).unwrap();
+        
+        assert_eq!(format_date_at(base, "EEEE, dd. MMMM", resolve_locale("de-DE")), "Montag, 05. Januar");
+        assert_eq!(format_date_at(base, "EEEE", resolve_locale("ja-JP")), "月曜日");
+        assert_eq!(format_date_at(base, "yyyy/MM/dd", resolve_locale("ja_JP")), "2026/01/05");
+    }
+    
+    #[test]
+    fn test_resolve_locale() {
+        assert_eq!(resolve_locale("de-DE"), Locale::de_DE);
+        assert_eq!(resolve_locale(" ja-JP "), Locale::ja_JP);
+        assert_eq!(resolve_locale("xx-YY"), Locale::en_US);
+    }
+    
+    #[test]
+    fn test_uuid_tokens() {
+        let template = "id: {uuid} added";
+        let first = format_dynamic_content(template, "en-US", DEFAULT_DATE_FORMAT, None);
+        let second = format_dynamic_content(template, "en-US", DEFAULT_DATE_FORMAT, None);
+        
+        // 毎回新しいUUIDが生成される
+        assert_ne!(first, second);
+        
+        let uuid = first.strip_prefix("id: ").and_then(|rest| rest.strip_suffix(" added")).unwrap();
+        let parsed = Uuid::parse_str(uuid).unwrap();
+        assert_eq!(parsed.get_version_num(), 4);
+        assert_eq!(uuid, uuid.to_lowercase());
+        
+        // 大文字指定と、同じテンプレート内の複数のトークン
+        let result = format_dynamic_content("{uuid:upper}/{uuid:upper}", "en-US", DEFAULT_DATE_FORMAT, None);
+        let (a, b) = result.split_once('/').unwrap();
+        assert_eq!(a, a.to_uppercase());
+        assert_ne!(a, b);
+    }
+    
+    #[test]
+    fn test_random_tokens_stay_in_range() {
+        for _ in 0..1000 {
+            let result = format_dynamic_content("{random:1-6},{random:-3-3}", "en-US", DEFAULT_DATE_FORMAT, None);
+            let (die, signed) = result.split_once(',').unwrap();
+            let die: i64 = die.parse().unwrap();
+            let signed: i64 = signed.parse().unwrap();
+            assert!((1..=6).contains(&die), "out of range: {}", die);
+            assert!((-3..=3).contains(&signed), "out of range: {}", signed);
+        }
+        
+        assert_eq!(format_dynamic_content("{random:7-7}", "en-US", DEFAULT_DATE_FORMAT, None), "7");
+    }
+    
+    #[test]
+    fn test_random_tokens_are_independent() {
+        // 同じテンプレート内の各トークンは別々に生成される（1000回すべて一致することはまずない）
+        let all_same = (0..1000).all(|_| {
+            let result = format_dynamic_content("{random:1-1000000}/{random:1-1000000}", "en-US", DEFAULT_DATE_FORMAT, None);
+            let (a, b) = result.split_once('/').unwrap();
+            a == b
+        });
+        assert!(!all_same);
+    }
+    
+    #[test]
+    fn test_invalid_random_ranges_left_untouched() {
+        assert_eq!(format_dynamic_content("{random:10-1}", "en-US", DEFAULT_DATE_FORMAT, None), "{random:10-1}");
+        assert_eq!(format_dynamic_content("{random:a-b}", "en-US", DEFAULT_DATE_FORMAT, None), "{random:a-b}");
+        assert_eq!(format_dynamic_content("x {random:} y", "en-US", DEFAULT_DATE_FORMAT, None), "x {random:} y");
     }
     
     #[test]
     fn test_multiple_replacements() {
         let template = "Date: {date:yyyy/MM/dd} Time: {date:HH:mm:ss}";
-        let result = format_dynamic_content(template);
+        let result = format_dynamic_content(template, "en-US", DEFAULT_DATE_FORMAT, None);
         assert!(result.starts_with("Date: "));
         assert!(result.contains(" Time: "));
     }
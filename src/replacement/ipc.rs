@@ -0,0 +1,323 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{Ipv4Addr, SocketAddrV4, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::ReplacementEngine;
+
+/// セッションごとのトークンを書き込むファイルの名前（設定ファイルと同じフォルダに作成する）
+pub const IPC_TOKEN_FILE: &str = "ipc_token";
+
+/// スニペットを展開する要求のパス
+pub const IPC_EXPAND_PATH: &str = "/expand";
+
+/// 受け付ける要求の最大サイズ
+const MAX_REQUEST_SIZE: usize = 64 * 1024;
+
+/// 接続を待つ間隔（終了の要求を確認するため、待ち受けはブロックしない）
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 要求の受信を待つ最大時間
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 外部のツールから受け取った要求
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IpcRequest {
+    /// HTTPメソッド（`POST` など）
+    pub method: String,
+    /// 要求のパス
+    pub path: String,
+    /// `Authorization: Bearer` で指定されたトークン
+    pub token: Option<String>,
+    /// 要求の本文（展開するキーワード）
+    pub body: String,
+}
+
+/// 外部のツールへ返す応答
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IpcResponse {
+    /// HTTPのステータスコード
+    pub status: u16,
+    /// 応答の本文（展開したテキストまたはエラーメッセージ）
+    pub body: String,
+}
+
+impl IpcResponse {
+    /// 展開したテキストを返す応答を作成する
+    pub fn ok(body: impl Into<String>) -> Self {
+        Self { status: 200, body: body.into() }
+    }
+    
+    /// エラーを返す応答を作成する
+    pub fn error(status: u16, message: impl Into<String>) -> Self {
+        Self { status, body: message.into() }
+    }
+    
+    /// HTTPの応答に変換する
+    pub fn to_http(&self) -> String {
+        let reason = match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            413 => "Payload Too Large",
+            422 => "Unprocessable Entity",
+            _ => "Internal Server Error",
+        };
+        format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status, reason, self.body.len(), self.body,
+        )
+    }
+}
+
+/// 受信したデータからHTTPの要求を読み取る
+/// 
+/// # 引数
+/// * `data` - 受信したデータ
+/// 
+/// # 戻り値
+/// 要求（ヘッダーや本文がまだ揃っていない場合はNone、形式が正しくない場合はエラーの応答）
+pub fn parse_request(data: &[u8]) -> Result<Option<IpcRequest>, IpcResponse> {
+    let Some(header_end) = data.windows(4).position(|window| window == b"\r\n\r\n") else {
+        return Ok(None);
+    };
+    let header = std::str::from_utf8(&data[..header_end])
+        .map_err(|_| IpcResponse::error(400, "Request headers must be UTF-8"))?;
+    
+    let mut lines = header.split("\r\n");
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let (Some(method), Some(path)) = (request_line.next(), request_line.next()) else {
+        return Err(IpcResponse::error(400, "Malformed request line"));
+    };
+    
+    let mut content_length = 0;
+    let mut token = None;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.trim().eq_ignore_ascii_case("content-length") {
+            content_length = value.parse::<usize>()
+                .map_err(|_| IpcResponse::error(400, "Invalid Content-Length"))?;
+        } else if name.trim().eq_ignore_ascii_case("authorization") {
+            token = value.strip_prefix("Bearer ").map(|token| token.trim().to_string());
+        }
+    }
+    
+    let body_start = header_end + 4;
+    if body_start + content_length > MAX_REQUEST_SIZE {
+        return Err(IpcResponse::error(413, "Request is too large"));
+    }
+    if data.len() < body_start + content_length {
+        return Ok(None);
+    }
+    let body = std::str::from_utf8(&data[body_start..body_start + content_length])
+        .map_err(|_| IpcResponse::error(400, "Request body must be UTF-8"))?;
+    
+    Ok(Some(IpcRequest {
+        method: method.to_string(),
+        path: path.to_string(),
+        token,
+        body: body.to_string(),
+    }))
+}
+
+/// 要求を検証し、キーワードのスニペットを展開する
+/// 
+/// # 引数
+/// * `request` - 受け取った要求
+/// * `token` - このセッションのトークン
+/// * `expand` - キーワードのスニペットを展開し、展開したテキストを返す関数（検証に成功した場合だけ呼ぶ）
+/// 
+/// # 戻り値
+/// 外部のツールへ返す応答
+pub fn handle_request(
+    request: &IpcRequest,
+    token: &str,
+    expand: impl FnOnce(&str) -> Result<String, String>,
+) -> IpcResponse {
+    if request.path != IPC_EXPAND_PATH {
+        return IpcResponse::error(404, format!("Unknown path '{}'", request.path));
+    }
+    if request.method != "POST" {
+        return IpcResponse::error(405, "Use POST to expand a snippet");
+    }
+    if request.token.as_deref() != Some(token) {
+        return IpcResponse::error(401, "Missing or invalid token");
+    }
+    
+    let keyword = request.body.trim();
+    if keyword.is_empty() {
+        return IpcResponse::error(400, "Send the keyword to expand as the request body");
+    }
+    
+    match expand(keyword) {
+        Ok(text) => IpcResponse::ok(text),
+        Err(message) => IpcResponse::error(422, message),
+    }
+}
+
+/// 外部のツールからキーワードを受け取り、スニペットを展開する待ち受け
+/// 
+/// 同じPCのツールだけが使えるよう127.0.0.1にだけバインドし、セッションごとのトークンを要求する。
+/// トークンは設定ファイルと同じフォルダの `ipc_token` に書き込み、破棄すると待ち受けを止めてファイルを削除する
+#[derive(Debug)]
+pub struct IpcServer {
+    /// 待ち受けを終了するかどうか
+    stop: Arc<AtomicBool>,
+    /// 待ち受けているポート
+    port: u16,
+    /// トークンを書き込んだファイルのパス
+    token_path: PathBuf,
+}
+
+impl IpcServer {
+    /// 待ち受けを開始する
+    /// 
+    /// # 引数
+    /// * `engine` - スニペットを展開するエンジン（エンジンが破棄されると待ち受けも終了する）
+    /// * `port` - 待ち受けるポート
+    /// * `token_dir` - トークンのファイルを作成するフォルダ
+    pub fn start(engine: Weak<Mutex<ReplacementEngine>>, port: u16, token_dir: &Path) -> io::Result<Self> {
+        let listener = TcpListener::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port))?;
+        listener.set_nonblocking(true)?;
+        
+        let token = Uuid::new_v4().simple().to_string();
+        let token_path = token_dir.join(IPC_TOKEN_FILE);
+        fs::write(&token_path, &token)?;
+        
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        thread::spawn(move || {
+            while !thread_stop.load(Ordering::SeqCst) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        if let Err(e) = Self::serve(stream, &token, &engine) {
+                            log::warn!("Failed to answer an IPC request: {}", e);
+                        }
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL_INTERVAL),
+                    Err(e) => {
+                        log::warn!("Failed to accept an IPC connection: {}", e);
+                        thread::sleep(ACCEPT_POLL_INTERVAL);
+                    }
+                }
+                if engine.strong_count() == 0 {
+                    break;
+                }
+            }
+            log::debug!("IPC server stopped");
+        });
+        
+        log::info!("Listening for snippet requests on 127.0.0.1:{}", port);
+        Ok(Self { stop, port, token_path })
+    }
+    
+    /// 待ち受けているポートを取得する
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+    
+    /// 1つの接続の要求を読み取り、応答を返す
+    fn serve(mut stream: TcpStream, token: &str, engine: &Weak<Mutex<ReplacementEngine>>) -> io::Result<()> {
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        
+        let response = match Self::read_request(&mut stream)? {
+            Ok(request) => handle_request(&request, token, |keyword| {
+                let engine = engine.upgrade().ok_or_else(|| "SwiftType is shutting down".to_string())?;
+                let engine = engine.lock().map_err(|_| "The replacement engine is not available".to_string())?;
+                engine.expand_keyword(keyword)
+            }),
+            Err(response) => response,
+        };
+        
+        if response.status == 200 {
+            log::info!("Expanded a snippet requested over IPC");
+        } else {
+            log::warn!("Rejected an IPC request ({}): {}", response.status, response.body);
+        }
+        stream.write_all(response.to_http().as_bytes())
+    }
+    
+    /// 要求がすべて届くまで読み取る
+    fn read_request(stream: &mut TcpStream) -> io::Result<Result<IpcRequest, IpcResponse>> {
+        let mut data = Vec::new();
+        let mut chunk = [0u8; 4096];
+        loop {
+            let read = stream.read(&mut chunk)?;
+            if read == 0 {
+                return Ok(Err(IpcResponse::error(400, "Incomplete request")));
+            }
+            data.extend_from_slice(&chunk[..read]);
+            
+            match parse_request(&data) {
+                Ok(Some(request)) => return Ok(Ok(request)),
+                Ok(None) if data.len() > MAX_REQUEST_SIZE => {
+                    return Ok(Err(IpcResponse::error(413, "Request is too large")));
+                }
+                Ok(None) => {}
+                Err(response) => return Ok(Err(response)),
+            }
+        }
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        let _ = fs::remove_file(&self.token_path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn request(token: Option<&str>, body: &str) -> IpcRequest {
+        IpcRequest {
+            method: "POST".to_string(),
+            path: IPC_EXPAND_PATH.to_string(),
+            token: token.map(str::to_string),
+            body: body.to_string(),
+        }
+    }
+    
+    #[test]
+    fn test_parse_request_waits_for_the_whole_body() {
+        let data = b"POST /expand HTTP/1.1\r\nHost: 127.0.0.1\r\nAuthorization: Bearer abc\r\nContent-Length: 4\r\n\r\n;sig";
+        assert_eq!(parse_request(data), Ok(Some(request(Some("abc"), ";sig"))));
+        assert_eq!(parse_request(&data[..data.len() - 1]), Ok(None));
+        assert_eq!(parse_request(b"POST /expand HTTP/1.1\r\n"), Ok(None));
+        assert_eq!(parse_request(b"\r\n\r\n").map_err(|response| response.status), Err(400));
+    }
+    
+    #[test]
+    fn test_handle_request_requires_the_session_token() {
+        let expand = |keyword: &str| Ok(format!("expanded {}", keyword));
+        
+        assert_eq!(handle_request(&request(Some("secret"), " sig\n"), "secret", expand), IpcResponse::ok("expanded sig"));
+        assert_eq!(handle_request(&request(None, "sig"), "secret", expand).status, 401);
+        assert_eq!(handle_request(&request(Some("guess"), "sig"), "secret", expand).status, 401);
+        assert_eq!(handle_request(&request(Some("secret"), "  "), "secret", expand).status, 400);
+        
+        let mut get = request(Some("secret"), "sig");
+        get.method = "GET".to_string();
+        assert_eq!(handle_request(&get, "secret", expand).status, 405);
+    }
+    
+    #[test]
+    fn test_handle_request_reports_expansion_errors() {
+        let response = handle_request(&request(Some("secret"), "nope"), "secret", |_| Err("No snippet matches 'nope'".to_string()));
+        assert_eq!(response, IpcResponse::error(422, "No snippet matches 'nope'"));
+        assert!(response.to_http().starts_with("HTTP/1.1 422 Unprocessable Entity\r\n"));
+    }
+}
@@ -0,0 +1,235 @@
+/// キー入力シーケンスの開始記号
+const KEY_TAG_PREFIX: &str = "{key:";
+
+/// 1つのスニペットで送信できるキーの組み合わせの最大数
+pub const MAX_KEY_COMBOS: usize = 50;
+
+/// 仮想キーコード
+mod vk {
+    pub const BACK: u16 = 0x08;
+    pub const TAB: u16 = 0x09;
+    pub const RETURN: u16 = 0x0D;
+    pub const ESCAPE: u16 = 0x1B;
+    pub const SPACE: u16 = 0x20;
+    pub const PRIOR: u16 = 0x21;
+    pub const NEXT: u16 = 0x22;
+    pub const END: u16 = 0x23;
+    pub const HOME: u16 = 0x24;
+    pub const LEFT: u16 = 0x25;
+    pub const UP: u16 = 0x26;
+    pub const RIGHT: u16 = 0x27;
+    pub const DOWN: u16 = 0x28;
+    pub const INSERT: u16 = 0x2D;
+    pub const DELETE: u16 = 0x2E;
+    pub const F1: u16 = 0x70;
+    pub const F4: u16 = 0x73;
+}
+
+/// 修飾キーと仮想キーの組み合わせ
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KeyCombo {
+    /// CTRLキー
+    pub ctrl: bool,
+    /// SHIFTキー
+    pub shift: bool,
+    /// ALTキー
+    pub alt: bool,
+    /// 仮想キーコード
+    pub vk: u16,
+}
+
+/// スニペットの本文を構成する要素
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeystrokeToken {
+    /// そのまま入力するテキスト
+    Text(String),
+    /// 送信するキーの組み合わせ
+    Key(KeyCombo),
+}
+
+/// スニペットの本文をテキストとキーの組み合わせに分解する
+/// 
+/// 本文中の `{key:ctrl+a}` のような記述をキーの組み合わせとして、それ以外の部分をテキストとして扱う。
+/// 誤操作を防ぐため、Winキーを含む組み合わせ（`win+r` でコマンドを実行できるなど）と、
+/// ウィンドウを閉じたりセキュリティ画面を開いたりする組み合わせは使用できない。
+/// 
+/// # 引数
+/// * `content` - スニペットの本文
+/// 
+/// # 戻り値
+/// 先頭から順に実行する要素（不正なキーや禁止された組み合わせがある場合はエラー）
+pub fn parse_keystrokes(content: &str) -> Result<Vec<KeystrokeToken>, String> {
+    let mut tokens = Vec::new();
+    let mut key_count = 0;
+    let mut rest = content;
+    
+    while let Some(start) = rest.find(KEY_TAG_PREFIX) {
+        if start > 0 {
+            tokens.push(KeystrokeToken::Text(rest[..start].to_string()));
+        }
+        
+        let after_prefix = &rest[start + KEY_TAG_PREFIX.len()..];
+        let end = after_prefix
+            .find('}')
+            .ok_or_else(|| format!("Unclosed key sequence: '{}'", &rest[start..]))?;
+        
+        key_count += 1;
+        if key_count > MAX_KEY_COMBOS {
+            return Err(format!("Too many key sequences (max {})", MAX_KEY_COMBOS));
+        }
+        
+        tokens.push(KeystrokeToken::Key(parse_key_combo(&after_prefix[..end])?));
+        rest = &after_prefix[end + 1..];
+    }
+    
+    if !rest.is_empty() {
+        tokens.push(KeystrokeToken::Text(rest.to_string()));
+    }
+    
+    Ok(tokens)
+}
+
+/// `ctrl+shift+end` のような記述をキーの組み合わせに変換する
+/// 
+/// # 引数
+/// * `spec` - `+` で区切った修飾キーとキーの名前（大文字と小文字は区別しない）
+pub fn parse_key_combo(spec: &str) -> Result<KeyCombo, String> {
+    let parts: Vec<String> = spec.split('+').map(|part| part.trim().to_lowercase()).collect();
+    let (key, modifiers) = parts
+        .split_last()
+        .filter(|(key, _)| !key.is_empty())
+        .ok_or_else(|| format!("Missing key in '{}'", spec))?;
+    
+    let mut combo = KeyCombo::default();
+    for modifier in modifiers {
+        match modifier.as_str() {
+            "ctrl" | "control" => combo.ctrl = true,
+            "shift" => combo.shift = true,
+            "alt" => combo.alt = true,
+            "win" => return Err(format!("The Windows key is not allowed in '{}'", spec)),
+            other => return Err(format!("Unknown modifier '{}' in '{}'", other, spec)),
+        }
+    }
+    
+    combo.vk = key_name_to_vk(key).ok_or_else(|| format!("Unknown key '{}' in '{}'", key, spec))?;
+    
+    if is_dangerous(&combo) {
+        return Err(format!("The key combination '{}' is not allowed", spec));
+    }
+    
+    Ok(combo)
+}
+
+/// キーの名前を仮想キーコードに変換する
+fn key_name_to_vk(name: &str) -> Option<u16> {
+    let code = match name {
+        "enter" | "return" => vk::RETURN,
+        "tab" => vk::TAB,
+        "esc" | "escape" => vk::ESCAPE,
+        "space" => vk::SPACE,
+        "backspace" | "bs" => vk::BACK,
+        "del" | "delete" => vk::DELETE,
+        "ins" | "insert" => vk::INSERT,
+        "home" => vk::HOME,
+        "end" => vk::END,
+        "pgup" | "pageup" => vk::PRIOR,
+        "pgdn" | "pagedown" => vk::NEXT,
+        "left" => vk::LEFT,
+        "up" => vk::UP,
+        "right" => vk::RIGHT,
+        "down" => vk::DOWN,
+        _ => {
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                // 英数字1文字は仮想キーコードが大文字のASCIIコードと同じ
+                (Some(c), None) if c.is_ascii_alphanumeric() => c.to_ascii_uppercase() as u16,
+                // F1〜F24
+                (Some('f'), Some(_)) => match name[1..].parse::<u16>() {
+                    Ok(number @ 1..=24) => vk::F1 + number - 1,
+                    _ => return None,
+                },
+                _ => return None,
+            }
+        }
+    };
+    Some(code)
+}
+
+/// 送信を禁止するキーの組み合わせかどうか
+fn is_dangerous(combo: &KeyCombo) -> bool {
+    // ALT+F4（ウィンドウやWindowsの終了）
+    let closes_window = combo.alt && combo.vk == vk::F4;
+    // CTRL+ALT+DELETE（セキュリティ画面）
+    let secure_attention = combo.ctrl && combo.alt && combo.vk == vk::DELETE;
+    // CTRL+SHIFT+ESC（タスクマネージャー）
+    let task_manager = combo.ctrl && combo.shift && combo.vk == vk::ESCAPE;
+    
+    closes_window || secure_attention || task_manager
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_ctrl_shift_end() {
+        let combo = parse_key_combo("ctrl+shift+end").unwrap();
+        assert_eq!(combo, KeyCombo { ctrl: true, shift: true, alt: false, vk: vk::END });
+    }
+    
+    #[test]
+    fn test_parse_key_names() {
+        assert_eq!(parse_key_combo("a").unwrap().vk, 0x41);
+        assert_eq!(parse_key_combo("Ctrl+A").unwrap(), KeyCombo { ctrl: true, vk: 0x41, ..Default::default() });
+        assert_eq!(parse_key_combo("7").unwrap().vk, 0x37);
+        assert_eq!(parse_key_combo("f5").unwrap().vk, 0x74);
+        assert_eq!(parse_key_combo("F24").unwrap().vk, 0x87);
+        assert_eq!(parse_key_combo(" alt + tab ").unwrap(), KeyCombo { alt: true, vk: vk::TAB, ..Default::default() });
+    }
+    
+    #[test]
+    fn test_parse_invalid_keys() {
+        assert!(parse_key_combo("").is_err());
+        assert!(parse_key_combo("ctrl+").is_err());
+        assert!(parse_key_combo("hyper+a").is_err());
+        assert!(parse_key_combo("f25").is_err());
+        assert!(parse_key_combo("ab").is_err());
+    }
+    
+    #[test]
+    fn test_dangerous_combos_rejected() {
+        assert!(parse_key_combo("win+r").is_err());
+        assert!(parse_key_combo("alt+f4").is_err());
+        assert!(parse_key_combo("ctrl+alt+del").is_err());
+        assert!(parse_key_combo("ctrl+shift+esc").is_err());
+    }
+    
+    #[test]
+    fn test_parse_keystrokes_interleaved() {
+        let tokens = parse_keystrokes("{key:ctrl+a}{key:del}Hello{key:enter}World").unwrap();
+        assert_eq!(tokens, vec![
+            KeystrokeToken::Key(KeyCombo { ctrl: true, vk: 0x41, ..Default::default() }),
+            KeystrokeToken::Key(KeyCombo { vk: vk::DELETE, ..Default::default() }),
+            KeystrokeToken::Text("Hello".to_string()),
+            KeystrokeToken::Key(KeyCombo { vk: vk::RETURN, ..Default::default() }),
+            KeystrokeToken::Text("World".to_string()),
+        ]);
+    }
+    
+    #[test]
+    fn test_parse_keystrokes_plain_text() {
+        assert_eq!(parse_keystrokes("").unwrap(), vec![]);
+        assert_eq!(
+            parse_keystrokes("{date} stays {text}").unwrap(),
+            vec![KeystrokeToken::Text("{date} stays {text}".to_string())],
+        );
+    }
+    
+    #[test]
+    fn test_parse_keystrokes_errors() {
+        assert!(parse_keystrokes("before {key:ctrl+a").is_err());
+        assert!(parse_keystrokes("{key:win+r}").is_err());
+        assert!(parse_keystrokes(&"{key:tab}".repeat(MAX_KEY_COMBOS + 1)).is_err());
+        assert!(parse_keystrokes(&"{key:tab}".repeat(MAX_KEY_COMBOS)).is_ok());
+    }
+}
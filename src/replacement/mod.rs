@@ -1,111 +1,1349 @@
+pub mod backend;
+pub mod clipboard;
+pub mod clipboard_history;
+pub mod expand_command;
+pub mod form;
 pub mod formatter;
+#[cfg(feature = "ipc")]
+pub mod ipc;
+pub mod keystrokes;
+pub mod worker;
 
-use std::sync::{Arc, Mutex};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
 use arboard::Clipboard;
-use std::thread;
-use std::time::Duration;
+use chrono::{DateTime, Local};
+use regex::Regex;
+use std::time::{Duration, Instant};
+use windows::Win32::Foundation::HWND;
+use windows::Win32::UI::Input::KeyboardAndMouse::{VK_BACK, VK_C, VK_LEFT, VK_RIGHT};
+use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, SetForegroundWindow};
 
 use crate::config::Settings;
-use crate::config::settings::SnippetType;
-use formatter::format_dynamic_content;
+use crate::config::stats::ExpansionStats;
+use crate::config::settings::{Delivery, Snippet, SnippetType, TimingProfile, MAX_BUFFER_SIZE, MAX_PRE_DELAY_MS};
+use crate::keyboard::normalize_typed_text;
+use crate::ui::notification;
+use crate::ui::overlay::ExpansionOverlay;
+use crate::utils;
+use backend::{InputBackend, WindowsInputBackend};
+use expand_command::ExpandCommand;
+use form::{FieldFormRequest, FieldFormSender};
+use formatter::{
+    apply_case_style, detect_case_style, extract_cursor_marker, extract_field_names, fill_fields,
+    format_keeping_literal_braces, process_escapes, restore_literal_braces,
+};
+use keystrokes::{parse_keystrokes, KeyCombo, KeystrokeToken};
+use worker::{ReplacementJob, ReplacementWorker};
+
+/// バッファの末尾がキーワードと一致するかを判定する
+/// 
+/// # 引数
+/// * `buffer` - 入力バッファ
+/// * `keyword` - キーワード
+/// * `case_sensitive` - 大文字と小文字を区別するかどうか
+fn ends_with_keyword(buffer: &str, keyword: &str, case_sensitive: bool) -> bool {
+    if case_sensitive {
+        buffer.ends_with(keyword)
+    } else {
+        buffer.to_lowercase().ends_with(&keyword.to_lowercase())
+    }
+}
+
+/// 単語を構成する文字かどうか
+/// 
+/// 日本語の文章の直後でも展開できるように、ASCIIの英数字とアンダースコアだけを単語の文字とみなす
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// 区切り文字の入力で確定した置換に、区切り文字の分の削除と入力を加える
+/// 
+/// # 引数
+/// * `replacement` - キーワードに一致した置換
+/// * `terminator` - キーワードの後に入力された区切り文字
+/// * `keep` - 展開後に区切り文字を入力し直すかどうか
+fn attach_terminator(mut replacement: Replacement, terminator: char, keep: bool) -> Replacement {
+    replacement.keyword_length += 1;
+    replacement.trigger.push(terminator);
+    if keep {
+        replacement.kept_terminator = Some(terminator);
+    }
+    replacement
+}
+
+/// バッファの末尾のキーワードが単語の先頭から始まっているかを判定する
+/// 
+/// キーワードの直前がバッファの先頭、空白、記号などの場合に単語の境界とみなす。
+/// キーワードが記号で始まる場合は、直前の文字に関係なく境界とみなす
+/// 
+/// # 引数
+/// * `buffer` - 入力バッファ（末尾がキーワードと一致していること）
+/// * `keyword` - キーワード
+fn starts_at_word_boundary(buffer: &str, keyword: &str) -> bool {
+    if !keyword.chars().next().is_some_and(is_word_char) {
+        return true;
+    }
+    
+    let before = keyword_start(buffer, keyword)
+        .map(|start| &buffer[..start])
+        .unwrap_or("");
+    !before.chars().next_back().is_some_and(is_word_char)
+}
+
+/// バッファの末尾から、入力されたキーワードの部分を取り出す
+fn typed_keyword<'a>(buffer: &'a str, keyword: &'a str) -> &'a str {
+    keyword_start(buffer, keyword)
+        .map(|start| &buffer[start..])
+        .unwrap_or(keyword)
+}
+
+/// バッファの末尾のキーワードの部分が始まる位置（バイト単位）
+/// 
+/// 大文字と小文字を区別しない場合は入力とキーワードのバイト数が異なることがあるため、文字数で数える
+/// 
+/// # 戻り値
+/// 開始位置（バッファがキーワードより短い場合はNone）
+fn keyword_start(buffer: &str, keyword: &str) -> Option<usize> {
+    match keyword.chars().count() {
+        0 => Some(buffer.len()),
+        count => buffer.char_indices().nth_back(count - 1).map(|(start, _)| start),
+    }
+}
+
+/// 入れ子のスニペットの最大の深さ
+pub const MAX_SNIPPET_NESTING: usize = 10;
+
+/// 入れ子のスニペット（`{snippet:キーワード}`）のパターンのキャッシュ
+fn nested_snippet_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\{snippet:([^}]+)\}").unwrap())
+}
+
+/// スニペットの内容を展開するテキストに変換する
+/// 
+/// 動的スニペットをフォーマットしたうえで、`{snippet:キーワード}` を参照先のスニペットの内容で置き換える。
+/// 参照先は無効なスニペットでもよい（部品としてだけ使うスニペットのため）。
+/// 循環参照や深すぎる入れ子はエラーをログに記録し、トークンをそのまま残す
+/// 
+/// # 引数
+/// * `snippet` - 変換するスニペット
+/// * `settings` - スニペットの一覧とロケールを含む設定
+/// * `visited` - 展開中のスニペットのキーワード（循環参照の検出用）
+fn render_snippet(snippet: &Snippet, settings: &Settings, visited: &mut Vec<String>) -> String {
+    let text = match snippet.snippet_type {
+        // エスケープシーケンスはタブや改行の文字にする（直接入力ではタブキーとして送信する）
+        SnippetType::Static if snippet.process_escapes => process_escapes(&snippet.content),
+        SnippetType::Static => snippet.content.clone(),
+        SnippetType::Dynamic => {
+            let result = format_keeping_literal_braces(&snippet.content, &settings.locale, &settings.default_date_format, None);
+            log::debug!("Formatted dynamic content: '{}' -> '{}'", 
+                       snippet.content, result);
+            result
+        }
+    };
+    
+    if !text.contains("{snippet:") {
+        return text;
+    }
+    
+    let same_keyword = |a: &str, b: &str| {
+        if settings.case_sensitive { a == b } else { a.to_lowercase() == b.to_lowercase() }
+    };
+    
+    visited.push(snippet.keyword.clone());
+    let result = nested_snippet_pattern().replace_all(&text, |caps: &regex::Captures| {
+        let keyword = caps[1].trim();
+        
+        if visited.iter().any(|v| same_keyword(v, keyword)) {
+            log::error!("Circular snippet reference: {} -> {}", visited.join(" -> "), keyword);
+            return caps[0].to_string();
+        }
+        if visited.len() >= MAX_SNIPPET_NESTING {
+            log::error!("Snippet nesting is deeper than {}: {} -> {}", MAX_SNIPPET_NESTING, visited.join(" -> "), keyword);
+            return caps[0].to_string();
+        }
+        
+        match settings.snippets.iter().find(|s| same_keyword(&s.keyword, keyword)) {
+            Some(nested) => render_snippet(nested, settings, visited),
+            None => {
+                log::warn!("Referenced snippet '{}' was not found", keyword);
+                caps[0].to_string()
+            }
+        }
+    }).to_string();
+    visited.pop();
+    
+    result
+}
+
+/// スニペットを展開したときのテキストを取得する（キー入力や貼り付けは行わない）
+/// 
+/// エディタで、ほかのアプリで展開した場合の結果を確かめるために使う。
+/// 動的スニペットのクリップボードは展開時と同じく実際に読み取る
+/// 
+/// # 引数
+/// * `snippet` - 展開するスニペット
+/// * `settings` - 入れ子のスニペットの参照先とロケールを含む設定
+/// 
+/// # 戻り値
+/// 展開したテキスト（入力フィールドやカーソル位置の指定はそのまま残る）
+pub fn expand_snippet_text(snippet: &Snippet, settings: &Settings) -> String {
+    restore_literal_braces(&render_text(snippet, settings))
+}
+
+/// スニペットを展開したときのテキストを、`{{` と `}}` を置換の間だけ使う文字のまま取得する
+fn render_text(snippet: &Snippet, settings: &Settings) -> String {
+    trim_trailing_whitespace(render_snippet(snippet, settings, &mut Vec::new()), snippet, settings)
+}
+
+/// 設定に従って、展開するテキストの末尾の空白や改行を取り除く
+/// 
+/// テキストの途中の空白や改行はそのまま残す。キー入力として送信するスニペットは、
+/// 本文がキーの記述のため取り除かない
+fn trim_trailing_whitespace(text: String, snippet: &Snippet, settings: &Settings) -> String {
+    if settings.trim_trailing_whitespace && !snippet.send_as_keystrokes {
+        text.trim_end().to_string()
+    } else {
+        text
+    }
+}
+
+/// スニペットから置換を作成する
+/// 
+/// # 引数
+/// * `snippet` - 一致したスニペット
+/// * `trigger` - 入力されたキーワード（大文字・小文字の判定に使う）
+/// * `settings` - 入れ子のスニペットの参照先とロケールを含む設定
+fn build_replacement(snippet: &Snippet, trigger: &str, settings: &Settings) -> Replacement {
+    let text = render_text(snippet, settings);
+    
+    // 入力したキーワードの大文字・小文字に合わせる
+    let text = if snippet.match_case {
+        apply_case_style(&text, detect_case_style(trigger))
+    } else {
+        text
+    };
+    
+    // 入力フィールドがある場合は、値を埋めるまでカーソル位置を決められない
+    let fields = extract_field_names(&text);
+    let (text, cursor_offset) = if fields.is_empty() {
+        finish_text(text, snippet.send_as_keystrokes)
+    } else {
+        (text, 0)
+    };
+    
+    // キーワードの文字数を返す（バックスペースは1文字ごとに1回必要なため、バイト数ではなく文字数）
+    // キーワードを残すスニペットは削除しないため0にする
+    Replacement {
+        snippet_id: snippet.id,
+        trigger: trigger.to_string(),
+        text,
+        keyword_length: if snippet.keep_trigger { 0 } else { snippet.keyword.chars().count() },
+        keyword: snippet.keyword.clone(),
+        snippet_name: snippet.name.clone(),
+        send_as_keystrokes: snippet.send_as_keystrokes,
+        delivery: snippet.delivery,
+        cursor_offset,
+        fields,
+        kept_terminator: None,
+        pre_delay_ms: snippet.pre_delay_ms.map(|ms| ms.min(MAX_PRE_DELAY_MS)),
+    }
+}
+
+/// 入力するテキストからカーソル位置のマーカーを取り除き、文字どおりに入力する波括弧を戻す
+fn finish_text(text: String, send_as_keystrokes: bool) -> (String, usize) {
+    // キー入力として送信するスニペットは、矢印キーで自由にカーソルを動かせるためマーカーを扱わない
+    let (text, cursor_offset) = if send_as_keystrokes {
+        (text, 0)
+    } else {
+        extract_cursor_marker(&text)
+    };
+    
+    // `{{` と `}}` は、入れ子のスニペット・カーソル位置・入力フィールドの処理がすべて終わってから戻す
+    (restore_literal_braces(&text), cursor_offset)
+}
+
+/// テキストバッファから置換対象のキーワードを検索する
+/// 
+/// # 引数
+/// * `settings` - スニペットの一覧を含む設定
+/// * `buffer` - キー入力のバッファ
+/// * `require_terminator` - バッファの末尾に区切り文字が必要かどうか
+/// * `read_foreground_app` - 前面のアプリのプロセス名を取得する関数（アプリを限定したスニペットがある場合だけ呼ぶ）
+/// 
+/// # 戻り値
+/// 一致したスニペットの置換（一致しない場合や展開が無効・一時停止中の場合はNone）
+fn match_replacement(
+    settings: &Settings,
+    buffer: &str,
+    require_terminator: bool,
+    mut read_foreground_app: impl FnMut() -> Option<String>,
+) -> Option<Replacement> {
+    if !settings.enabled || settings.is_paused() {
+        return None;
+    }
+    
+    // バッファ内容をログに記録（デバッグ用）
+    log::debug!("Checking buffer for replacements: '{}'", buffer);
+    
+    // 区切り文字が必要な場合は、末尾の区切り文字を除いた部分でキーワードを探す
+    let (buffer, terminator) = if require_terminator {
+        let mut chars = buffer.chars();
+        match chars.next_back() {
+            Some(c) if settings.is_terminator(c) => (chars.as_str(), Some(c)),
+            _ => return None,
+        }
+    } else {
+        (buffer, None)
+    };
+    
+    // 保存したキーワードの特殊文字は `_` に置き換わっているため、入力された文字列も同じように置き換えて比較する
+    let buffer = normalize_typed_text(buffer);
+    let buffer = buffer.as_str();
+    let finish = |replacement: Replacement| match terminator {
+        Some(terminator) => attach_terminator(replacement, terminator, settings.keep_terminator),
+        None => replacement,
+    };
+    
+    // 前面のアプリは、アプリを限定したスニペットがある場合だけ1回取得する
+    let mut foreground_app: Option<Option<String>> = None;
+    
+    // 一致したキーワードのうち最も長いものを使う（同じ長さの場合はリストで先にあるもの）
+    // 注: "st" と "test" のように末尾が重なる場合に、並び順によって "test" が展開されなくなるのを防ぐ。
+    //     "te" と "test" のように先頭が重なる場合は、区切り文字が不要だと "te" の入力で先に展開されるため、
+    //     "test" を展開できるのは区切り文字が必要な場合だけ
+    let mut best_match: Option<&Snippet> = None;
+    
+    // 有効なスニペットだけを検索
+    for snippet in settings.snippets.iter().filter(|s| s.enabled) {
+        // 展開するアプリが限定されている場合は、前面のアプリが含まれるか確認する
+        if !snippet.allowed_apps.is_empty() {
+            let app = foreground_app.get_or_insert_with(&mut read_foreground_app);
+            if !app.as_deref().is_some_and(|app| utils::app_list_contains(&snippet.allowed_apps, app)) {
+                continue;
+            }
+        }
+        
+        // 単語の途中で一致した場合は展開しない（"contest1" で "test1" を展開しないため）
+        let at_boundary = !snippet.word_boundary || starts_at_word_boundary(buffer, &snippet.keyword);
+        
+        if at_boundary && ends_with_keyword(buffer, &snippet.keyword, settings.case_sensitive) {
+            log::debug!("Found matching keyword: '{}' for snippet: '{}'", 
+                       snippet.keyword, snippet.name);
+            
+            let longer = best_match
+                .is_none_or(|best| snippet.keyword.chars().count() > best.keyword.chars().count());
+            if longer {
+                best_match = Some(snippet);
+            }
+        }
+    }
+    
+    best_match.map(|snippet| {
+        let replacement = build_replacement(snippet, typed_keyword(buffer, &snippet.keyword), settings);
+        // キーワードを残すスニペットは、区切り文字も消さずにその後ろへ入力する
+        if snippet.keep_trigger {
+            replacement
+        } else {
+            finish(replacement)
+        }
+    })
+}
+
+/// キーワードの検索結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchResult {
+    /// 置換後のテキスト
+    pub text: String,
+    /// 一致したスニペットのキーワード
+    pub keyword: String,
+    /// 削除するキーワードの文字数（区切り文字が必要な場合は区切り文字を含む）
+    pub keyword_length: usize,
+}
+
+impl From<Replacement> for MatchResult {
+    fn from(replacement: Replacement) -> Self {
+        Self {
+            text: replacement.text,
+            keyword: replacement.keyword,
+            keyword_length: replacement.keyword_length,
+        }
+    }
+}
+
+/// テキストバッファから置換対象のキーワードを検索する
+/// 
+/// キー入力の送信やクリップボードへの貼り付けは行わないため、Windowsの入力を使わずに照合だけを確認できる。
+/// 前面のアプリを取得しないため、展開するアプリを限定したスニペットは一致しない
+/// 
+/// # 引数
+/// * `settings` - スニペットの一覧を含む設定
+/// * `buffer` - キー入力のバッファ
+/// 
+/// # 戻り値
+/// 一致したスニペットの置換後のテキストとキーワード（一致しない場合はNone）
+pub fn match_snippet(settings: &Settings, buffer: &str) -> Option<MatchResult> {
+    match_replacement(settings, buffer, settings.require_terminator, || None).map(MatchResult::from)
+}
+
+/// 置換を実行した結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplacementOutcome {
+    /// キーワードを削除してテキストを入力した
+    Expanded,
+    /// ドライランのため、入力せずに記録だけを行った
+    Recorded,
+    /// 入力に失敗した
+    Failed,
+}
+
+impl ReplacementOutcome {
+    /// 失敗しなかったかどうか
+    pub fn succeeded(self) -> bool {
+        self != Self::Failed
+    }
+}
+
+/// テキストを入力した方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertMethod {
+    /// 1文字ずつ直接入力した
+    Typed,
+    /// クリップボード経由で貼り付けた
+    Pasted,
+}
+
+impl InsertMethod {
+    /// ログに表示する名前を取得する
+    pub fn label(self) -> &'static str {
+        match self {
+            InsertMethod::Typed => "typed",
+            InsertMethod::Pasted => "pasted",
+        }
+    }
+}
+
+/// 検出された置換
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replacement {
+    /// 一致したスニペットのID
+    pub snippet_id: u64,
+    /// 入力されたキーワード（大文字と小文字は入力どおり）
+    pub trigger: String,
+    /// 置換後のテキスト
+    pub text: String,
+    /// 削除するキーワードの文字数
+    pub keyword_length: usize,
+    /// 一致したスニペットのキーワード
+    pub keyword: String,
+    /// 一致したスニペットの名前
+    pub snippet_name: String,
+    /// 本文をキー入力として送信するかどうか
+    pub send_as_keystrokes: bool,
+    /// テキストを入力する方法
+    pub delivery: Delivery,
+    /// 入力後にカーソルを左へ戻す文字数（`{cursor}` の位置）
+    pub cursor_offset: usize,
+    /// 展開前に入力が必要なフィールドの名前
+    pub fields: Vec<String>,
+    /// 展開後に入力し直す区切り文字
+    pub kept_terminator: Option<char>,
+    /// キーワードを削除する前の待機時間（スニペットで指定した場合のみ）
+    pub pre_delay_ms: Option<u64>,
+}
+
+impl Replacement {
+    /// キーワードを入力せずに展開するための置換にする（削除するキーワードがないため、キーワードの長さは0になる）
+    fn without_keyword(mut self) -> Self {
+        self.keyword_length = 0;
+        self.trigger = String::new();
+        self
+    }
+    
+    /// 入力フィールドを入力された値で置き換えた置換を作成する
+    /// 
+    /// # 引数
+    /// * `values` - フィールド名と入力された値
+    pub fn with_field_values(&self, values: &HashMap<String, String>) -> Self {
+        let (text, cursor_offset) = finish_text(fill_fields(&self.text, values), self.send_as_keystrokes);
+        Self {
+            text,
+            cursor_offset,
+            fields: Vec::new(),
+            ..self.clone()
+        }
+    }
+    
+    /// 入力後にカーソルを左へ戻す文字数（入力し直した区切り文字を含む）
+    /// 
+    /// `{cursor}` がない場合は、入力し直した区切り文字の後ろにカーソルを置いたままにする
+    pub fn caret_offset(&self) -> usize {
+        if self.cursor_offset == 0 {
+            0
+        } else {
+            self.cursor_offset + usize::from(self.kept_terminator.is_some())
+        }
+    }
+}
+
+/// 入力せずに記録した展開
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DryRunEvent {
+    /// 展開しようとした時刻
+    pub time: DateTime<Local>,
+    /// 入力されたキーワード
+    pub trigger: String,
+    /// 一致したスニペットの名前
+    pub snippet_name: String,
+    /// 送信するはずだったバックスペースの回数
+    pub backspaces: usize,
+    /// 入力するはずだったテキスト（キー入力として送信するスニペットは本文）
+    pub text: String,
+}
+
+/// 入力せずに記録した展開の一覧（新しいものが末尾）
+pub type DryRunLog = Arc<Mutex<VecDeque<DryRunEvent>>>;
+
+/// 入力せずに記録した展開を保持する件数
+pub const MAX_DRY_RUN_EVENTS: usize = 50;
 
 /// テキスト置換エンジン
 #[derive(Debug)]
 pub struct ReplacementEngine {
     settings: Arc<Mutex<Settings>>,
+    /// 展開時に表示するオーバーレイ
+    overlay: Option<ExpansionOverlay>,
+    /// キー入力の送信先
+    backend: Box<dyn InputBackend>,
+    /// 入力フォームの表示要求の送信先
+    field_form_sender: Option<FieldFormSender>,
+    /// 展開で使用回数が変わり、まだ設定ファイルに保存していないかどうか（保存はUIスレッドで行う）
+    usage_changed: Arc<AtomicBool>,
+    /// 展開の記録（Statsタブの集計に使う）
+    expansion_stats: Option<Arc<Mutex<ExpansionStats>>>,
+    /// 取り消しできる直前の展開
+    last_expansion: Mutex<Option<LastExpansion>>,
+    /// 置換を実行するワーカースレッド
+    worker: Option<ReplacementWorker>,
+    /// 中止のキーワードで展開を止めている期限
+    aborted_until: Mutex<Option<Instant>>,
+    /// 入力せずに記録した展開
+    dry_run_log: DryRunLog,
+}
+
+/// 直前の展開を取り消せる時間
+pub const UNDO_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 中止のキーワードを入力した後、展開を止めておく時間
+pub const ABORT_COOLDOWN: Duration = Duration::from_secs(1);
+
+/// 入力方法が自動の場合に、直接入力を試すテキストの最大バイト数
+pub const AUTO_DIRECT_INPUT_MAX_LEN: usize = 50;
+
+/// 取り消しのために記録する直前の展開
+#[derive(Debug)]
+struct LastExpansion {
+    /// 入力されたキーワード
+    trigger: String,
+    /// 入力したテキストの文字数
+    text_length: usize,
+    /// 入力後にカーソルを左へ戻した文字数
+    cursor_offset: usize,
+    /// 展開した時刻
+    expanded_at: Instant,
 }
 
 impl ReplacementEngine {
     /// 新しい置換エンジンを作成する
     pub fn new(settings: Arc<Mutex<Settings>>) -> Self {
-        Self { settings }
+        Self::with_backend(settings, Box::new(WindowsInputBackend::default()))
+    }
+    
+    /// キー入力の送信先を指定して置換エンジンを作成する
+    pub fn with_backend(settings: Arc<Mutex<Settings>>, backend: Box<dyn InputBackend>) -> Self {
+        Self {
+            settings,
+            overlay: None,
+            backend,
+            field_form_sender: None,
+            usage_changed: Arc::new(AtomicBool::new(false)),
+            expansion_stats: None,
+            last_expansion: Mutex::new(None),
+            worker: None,
+            aborted_until: Mutex::new(None),
+            dry_run_log: DryRunLog::default(),
+        }
+    }
+    
+    /// 置換を実行するワーカースレッドを開始する
+    /// 
+    /// 開始後は `queue_replacement` で依頼した置換がワーカースレッドで実行される
+    pub fn start_worker(engine: &Arc<Mutex<Self>>) {
+        let worker = ReplacementWorker::start(Arc::downgrade(engine));
+        if let Ok(mut engine) = engine.lock() {
+            engine.worker = Some(worker);
+        }
+    }
+    
+    /// 置換を実行中かどうかを示すフラグを取得する（ワーカースレッドがない場合はNone）
+    pub fn worker_busy_flag(&self) -> Option<Arc<AtomicBool>> {
+        self.worker.as_ref().map(ReplacementWorker::busy_flag)
+    }
+    
+    /// 置換の実行を依頼する
+    /// 
+    /// ワーカースレッドがあればそちらで実行してすぐに戻り、なければこのスレッドで実行する
+    /// 
+    /// # 戻り値
+    /// 置換を受け付けたかどうか（ワーカースレッドがない場合は置換が成功したかどうか）
+    pub fn queue_replacement(&self, replacement: Replacement) -> bool {
+        self.queue_job(ReplacementJob::Replace(replacement))
+    }
+    
+    /// 直前の展開の取り消しを依頼する
+    /// 
+    /// # 戻り値
+    /// 取り消しを受け付けたかどうか（ワーカースレッドがない場合は取り消したかどうか）
+    pub fn queue_undo(&self) -> bool {
+        self.queue_job(ReplacementJob::Undo)
+    }
+    
+    /// 編集中のスニペットで展開を試すよう依頼する
+    /// 
+    /// # 引数
+    /// * `snippet` - 試すスニペット
+    /// * `result` - テキストを入力した方法の送信先
+    /// 
+    /// # 戻り値
+    /// 依頼を受け付けたかどうか
+    pub fn queue_test_expansion(&self, snippet: Snippet, result: crossbeam_channel::Sender<Option<InsertMethod>>) -> bool {
+        self.queue_job(ReplacementJob::TestExpansion(snippet, result))
+    }
+    
+    /// キー入力を送信する処理を依頼する
+    /// 
+    /// ワーカースレッドがあればそちらで実行してすぐに戻り、なければこのスレッドで実行する
+    fn queue_job(&self, job: ReplacementJob) -> bool {
+        match &self.worker {
+            Some(worker) => worker.submit(job),
+            None => self.run_job(job),
+        }
+    }
+    
+    /// キー入力を送信する処理を実行する
+    /// 
+    /// # 戻り値
+    /// 処理が成功したかどうか
+    pub fn run_job(&self, job: ReplacementJob) -> bool {
+        match job {
+            ReplacementJob::Replace(replacement) => self.run_replacement(&replacement),
+            ReplacementJob::ReplaceInWindow(replacement, target_window) => {
+                self.restore_focus(target_window);
+                self.run_replacement(&replacement)
+            }
+            ReplacementJob::Undo => self.undo_last_expansion(),
+            ReplacementJob::TestExpansion(snippet, result) => {
+                let method = self.test_expansion(&snippet);
+                let succeeded = method.is_some();
+                let _ = result.send(method);
+                succeeded
+            }
+        }
+    }
+    
+    /// 置換を実行し、成功した場合は展開を通知する
+    /// 
+    /// ドライランで記録だけを行った場合は、通知や展開後のコマンドの実行もしない。
+    /// 失敗した場合は、キーボードを正常な状態に戻すため修飾キーを解放する
+    pub fn run_replacement(&self, replacement: &Replacement) -> bool {
+        match self.perform_replacement(replacement) {
+            ReplacementOutcome::Expanded => {
+                log::debug!("Successfully replaced '{}' with '{}'", replacement.trigger, replacement.text);
+                self.notify_expansion(&replacement.keyword, &replacement.snippet_name);
+                true
+            }
+            ReplacementOutcome::Recorded => true,
+            ReplacementOutcome::Failed => {
+                log::error!("Failed to replace '{}' with '{}'", replacement.trigger, replacement.text);
+                self.backend.reset_modifier_keys();
+                false
+            }
+        }
+    }
+    
+    /// 使用回数が変わり、まだ保存していないかどうかのフラグを取得する（UIスレッドでまとめて保存するために共有する）
+    pub fn usage_changed_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.usage_changed)
+    }
+    
+    /// 展開した時刻を記録する展開の記録を設定する
+    pub fn set_expansion_stats(&mut self, expansion_stats: Arc<Mutex<ExpansionStats>>) {
+        self.expansion_stats = Some(expansion_stats);
+    }
+    
+    /// 展開時に表示するオーバーレイを設定する
+    pub fn set_overlay(&mut self, overlay: ExpansionOverlay) {
+        self.overlay = Some(overlay);
+    }
+    
+    /// 入力フォームの表示要求の送信先を設定する
+    pub fn set_field_form_sender(&mut self, sender: FieldFormSender) {
+        self.field_form_sender = Some(sender);
+    }
+    
+    /// 入力フィールドを含む置換であれば、展開せずにUIへ入力フォームの表示を要求する
+    /// 
+    /// # 戻り値
+    /// 表示を要求したかどうか（要求した場合、キーワードはまだ削除されていない）
+    pub fn request_field_form(&self, replacement: &Replacement) -> bool {
+        if replacement.fields.is_empty() {
+            return false;
+        }
+        
+        // 入力後にフォーカスを戻すため、展開先のウィンドウを記録する
+        let target_window = unsafe { GetForegroundWindow() }.0;
+        self.request_field_form_for(replacement, target_window)
+    }
+    
+    /// 入力フィールドを含む置換であれば、展開先のウィンドウを指定して入力フォームの表示を要求する
+    /// 
+    /// # 引数
+    /// * `replacement` - 検出された置換
+    /// * `target_window` - 入力後にフォーカスを戻す展開先のウィンドウのハンドル
+    /// 
+    /// # 戻り値
+    /// 表示を要求したかどうか
+    fn request_field_form_for(&self, replacement: &Replacement, target_window: isize) -> bool {
+        if replacement.fields.is_empty() {
+            return false;
+        }
+        
+        let Some(sender) = &self.field_form_sender else {
+            log::warn!("Snippet '{}' has fields but no form is available", replacement.snippet_name);
+            return false;
+        };
+        
+        log::debug!("Requesting field form for snippet '{}': {:?}", replacement.snippet_name, replacement.fields);
+        
+        sender.send(FieldFormRequest {
+            replacement: replacement.clone(),
+            target_window,
+        })
+    }
+    
+    /// 入力フォームに入力された値で展開するよう依頼する
+    /// 
+    /// ワーカースレッドで展開先のウィンドウにフォーカスを戻してから、キーワードを削除して入力する
+    /// 
+    /// # 引数
+    /// * `request` - 入力フォームの表示要求
+    /// * `values` - フィールド名と入力された値
+    /// 
+    /// # 戻り値
+    /// 展開を受け付けたかどうか（ワーカースレッドがない場合は置換が成功したかどうか）
+    pub fn complete_field_form(&self, request: &FieldFormRequest, values: &HashMap<String, String>) -> bool {
+        let replacement = request.replacement.with_field_values(values);
+        self.queue_job(ReplacementJob::ReplaceInWindow(replacement, request.target_window))
+    }
+    
+    /// 展開先のウィンドウにフォーカスを戻し、フォーカスが移るまで待機する
+    /// 
+    /// # 引数
+    /// * `target_window` - 展開先のウィンドウのハンドル
+    fn restore_focus(&self, target_window: isize) {
+        if !unsafe { SetForegroundWindow(HWND(target_window)) }.as_bool() {
+            log::warn!("Failed to restore focus to the target window");
+        }
+        self.backend.wait(Duration::from_millis(300));
+    }
+    
+    /// 展開が成功したことを通知する
+    /// 
+    /// 設定に応じて、オーバーレイにスニペット名を表示し、通知音やトレイの通知で知らせ、展開後のコマンドを実行する
+    /// 
+    /// # 引数
+    /// * `keyword` - 展開したキーワード
+    /// * `snippet_name` - 展開したスニペットの名前
+    pub fn notify_expansion(&self, keyword: &str, snippet_name: &str) {
+        let (show_overlay, notification, on_expand_command) = match self.settings.lock() {
+            Ok(settings) => (settings.show_expansion_overlay, settings.notify_on_expand, settings.on_expand_command.clone()),
+            Err(_) => return,
+        };
+        
+        if show_overlay {
+            if let Some(overlay) = &self.overlay {
+                overlay.show(snippet_name);
+            }
+        }
+        
+        // 通知音とトレイの通知は別スレッドで行い、展開を待たせない
+        if notification.sound {
+            notification::play_expand_sound();
+        }
+        if notification.balloon {
+            notification::show_expand_balloon(snippet_name);
+        }
+        
+        // コマンドの完了は待たない（キーボードフックを止めないため）
+        if let Some(command) = on_expand_command
+            .and_then(|command_line| ExpandCommand::new(&command_line, keyword, snippet_name))
+        {
+            command.spawn_detached();
+        }
+    }
+    
+    /// 前面のアプリが展開しないアプリの一覧に含まれるかどうか
+    pub fn is_disabled_in_foreground_app(&self) -> bool {
+        let disabled_apps = match self.settings.lock() {
+            Ok(settings) if !settings.disabled_apps.is_empty() => settings.disabled_apps.clone(),
+            _ => return false,
+        };
+        
+        match self.backend.foreground_process_name() {
+            Some(app) if utils::app_list_contains(&disabled_apps, &app) => {
+                log::debug!("Expansion is disabled in foreground process: {}", app);
+                true
+            }
+            _ => false,
+        }
+    }
+    
+    /// テキストバッファから置換対象のキーワードを検索する
+    /// 
+    /// # 戻り値
+    /// 置換後のテキストと、削除するキーワードの文字数
+    pub fn check_for_replacements(&self, buffer: &str) -> Option<(String, usize)> {
+        self.find_replacement(buffer)
+            .map(|replacement| (replacement.text, replacement.keyword_length))
+    }
+    
+    /// キーワードを入力せずに展開するための置換を作成する
+    /// 
+    /// 削除するキーワードがないため、キーワードの長さは0になる
+    /// 
+    /// # 引数
+    /// * `snippet_id` - 展開するスニペットのID
+    /// 
+    /// # 戻り値
+    /// 置換（スニペットが見つからない場合はNone）
+    pub fn replacement_for_snippet(&self, snippet_id: u64) -> Option<Replacement> {
+        let settings = self.settings.lock().ok()?;
+        let snippet = settings.snippets.iter().find(|snippet| snippet.id == snippet_id)?;
+        
+        Some(build_replacement(snippet, &snippet.keyword, &settings).without_keyword())
+    }
+    
+    /// キーワードを入力せずにスニペットを前面のウィンドウへ挿入する
+    /// 
+    /// 入力フィールドを含むスニペットは、入力フォームで値が入力されてから挿入する
+    /// 
+    /// # 戻り値
+    /// 挿入を開始できたかどうか
+    pub fn insert_snippet(&self, snippet_id: u64) -> bool {
+        let Some(replacement) = self.replacement_for_snippet(snippet_id) else {
+            log::warn!("Snippet {} to insert was not found", snippet_id);
+            return false;
+        };
+        
+        if self.request_field_form(&replacement) {
+            return true;
+        }
+        self.queue_replacement(replacement)
+    }
+    
+    /// 指定したウィンドウにフォーカスを戻してから、キーワードを入力せずにスニペットを挿入する
+    /// 
+    /// スニペットの検索ウィンドウなど、アプリのウィンドウで選んだスニペットを元のウィンドウへ挿入するために使う
+    /// 
+    /// # 引数
+    /// * `snippet_id` - 挿入するスニペットのID
+    /// * `target_window` - 挿入先のウィンドウのハンドル
+    /// 
+    /// # 戻り値
+    /// 挿入を開始できたかどうか
+    pub fn insert_snippet_into(&self, snippet_id: u64, target_window: isize) -> bool {
+        let Some(replacement) = self.replacement_for_snippet(snippet_id) else {
+            log::warn!("Snippet {} to insert was not found", snippet_id);
+            return false;
+        };
+        
+        // 入力フィールドを含む場合は、フォームで値が入力されてから挿入先のウィンドウへ戻す
+        if self.request_field_form_for(&replacement, target_window) {
+            return true;
+        }
+        self.queue_job(ReplacementJob::ReplaceInWindow(replacement, target_window))
+    }
+    
+    /// 外部のツールから指定されたキーワードのスニペットを、キーワードを入力せずに前面のウィンドウへ挿入する
+    /// 
+    /// 区切り文字が必要な設定でも、キーワードだけで一致させる。キーワードの一部だけが一致する場合は展開しない
+    /// 
+    /// # 引数
+    /// * `keyword` - 展開するスニペットのキーワード
+    /// 
+    /// # 戻り値
+    /// 挿入を開始したテキスト（入力フィールドを含む場合はフォームで値を入力する前のテキスト）、
+    /// または展開できなかった理由
+    pub fn expand_keyword(&self, keyword: &str) -> Result<String, String> {
+        let keyword = keyword.trim();
+        if self.is_aborted() {
+            return Err("Expansion is stopped by the abort keyword".to_string());
+        }
+        
+        // 一致したときに作成した置換をそのまま使う（{clipboard}や{random}が返すテキストと入力するテキストで変わらないよう、1回だけ展開する）
+        let replacement = {
+            let settings = self.settings.lock().map_err(|_| "Settings are not available".to_string())?;
+            // 区切り文字は入力されないため、キーワードだけで照合する
+            match_replacement(&settings, keyword, false, || self.backend.foreground_process_name())
+                .filter(|found| found.keyword.chars().count() == keyword.chars().count())
+                .ok_or_else(|| format!("No enabled snippet has the keyword '{}'", keyword))?
+                .without_keyword()
+        };
+        
+        if self.is_disabled_in_foreground_app() {
+            return Err("Expansion is turned off in the focused app".to_string());
+        }
+        
+        let text = restore_literal_braces(&replacement.text);
+        if self.request_field_form(&replacement) || self.queue_replacement(replacement) {
+            Ok(text)
+        } else {
+            Err("Another expansion is in progress".to_string())
+        }
+    }
+    
+    /// 押されたままになっている修飾キーを解放する
+    /// 
+    /// 展開を無効にしている間に押した修飾キーが、再び有効にした後の最初の展開に残らないようにするために使う
+    /// 
+    /// # 戻り値
+    /// 成功したかどうか
+    pub fn reset_modifier_keys(&self) -> bool {
+        self.backend.reset_modifier_keys()
+    }
+    
+    /// バッファの末尾が中止のキーワードであれば、展開の状態を元に戻して少しの間展開を止める
+    /// 
+    /// 押されたままの修飾キーを解放し、取り消しできる展開を破棄する。
+    /// キーボードのバッファは呼び出し元でクリアする
+    /// 
+    /// # 引数
+    /// * `buffer` - キー入力のバッファ
+    /// 
+    /// # 戻り値
+    /// 中止のキーワードが入力されたかどうか
+    pub fn check_abort(&self, buffer: &str) -> bool {
+        let (abort_keyword, case_sensitive) = match self.settings.lock() {
+            Ok(settings) => (normalize_typed_text(settings.abort_keyword.trim()), settings.case_sensitive),
+            Err(_) => return false,
+        };
+        if abort_keyword.is_empty() || !ends_with_keyword(&normalize_typed_text(buffer), &abort_keyword, case_sensitive) {
+            return false;
+        }
+        
+        log::info!("Abort keyword '{}' typed, resetting state and pausing expansion for {:?}", abort_keyword, ABORT_COOLDOWN);
+        self.clear_last_expansion();
+        self.backend.reset_modifier_keys();
+        if let Ok(mut aborted_until) = self.aborted_until.lock() {
+            *aborted_until = Some(Instant::now() + ABORT_COOLDOWN);
+        }
+        true
+    }
+    
+    /// 展開の記録を取得する（UIで集計を表示するために共有する）
+    pub fn expansion_stats(&self) -> Option<Arc<Mutex<ExpansionStats>>> {
+        self.expansion_stats.clone()
+    }
+    
+    /// 入力せずに記録した展開の一覧を取得する（UIで表示するために共有する）
+    pub fn dry_run_log(&self) -> DryRunLog {
+        Arc::clone(&self.dry_run_log)
+    }
+    
+    /// 展開の代わりに、入力するはずだった内容をログと一覧に記録する
+    fn record_dry_run(&self, replacement: &Replacement) {
+        log::info!("Dry run: would send {} backspace(s) and {} {:?} for '{}' (snippet '{}')",
+                   replacement.keyword_length,
+                   if replacement.send_as_keystrokes { "keystrokes" } else { "text" },
+                   replacement.text, replacement.trigger, replacement.snippet_name);
+        
+        if let Ok(mut events) = self.dry_run_log.lock() {
+            if events.len() >= MAX_DRY_RUN_EVENTS {
+                events.pop_front();
+            }
+            events.push_back(DryRunEvent {
+                time: Local::now(),
+                trigger: replacement.trigger.clone(),
+                snippet_name: replacement.snippet_name.clone(),
+                backspaces: replacement.keyword_length,
+                text: replacement.text.clone(),
+            });
+        }
+    }
+    
+    /// 中止のキーワードで展開を止めている間かどうか
+    fn is_aborted(&self) -> bool {
+        self.aborted_until.lock().ok()
+            .and_then(|aborted_until| *aborted_until)
+            .is_some_and(|until| Instant::now() < until)
+    }
+    
+    /// テキストバッファから置換対象のキーワードを検索し、一致したスニペットの情報を返す
+    pub fn find_replacement(&self, buffer: &str) -> Option<Replacement> {
+        if self.is_aborted() {
+            log::debug!("Expansion is stopped by the abort keyword");
+            return None;
+        }
+        
+        let mut settings = self.settings.lock().ok()?;
+        
+        // 一時停止の期限が過ぎていれば、ここで展開を再開する
+        settings.refresh_pause();
+        match_replacement(&settings, buffer, settings.require_terminator, || {
+            let app = self.backend.foreground_process_name();
+            log::debug!("Foreground process: {:?}", app);
+            app
+        })
+    }
+    
+    /// キーワードを置換しようと試みる
+    /// 
+    /// # 引数
+    /// * `buffer` - 置換対象のバッファ文字列
+    /// 
+    /// # 戻り値
+    /// 置換が成功したかどうか
+    pub fn try_replace(&mut self, buffer: &str) -> bool {
+        if let Some(replacement) = self.find_replacement(buffer) {
+            self.perform_replacement(&replacement).succeeded()
+        } else {
+            false
+        }
+    }
+    
+    /// キーワードを削除してテキストを入力する
+    /// 
+    /// # 引数
+    /// * `text` - 入力するテキスト
+    /// * `keyword_length` - 削除するキーワードの文字数
+    /// * `delivery` - テキストを入力する方法
+    /// * `pre_delay_ms` - キーワードを削除する前の待機時間（Noneの場合は展開の速さの設定から決める）
+    /// 
+    /// # 戻り値
+    /// テキストを入力した方法（失敗した場合はNone）
+    fn replace_with_backspace(
+        &self,
+        text: &str,
+        keyword_length: usize,
+        delivery: Delivery,
+        pre_delay_ms: Option<u64>,
+    ) -> Option<InsertMethod> {
+        // キーワード削除前にログ記録
+        log::debug!("Replacing keyword (length: {}) with text: '{}'", keyword_length, text);
+        
+        if !self.check_expansion_length(text) {
+            return None;
+        }
+        
+        if !self.delete_keyword(keyword_length, pre_delay_ms) {
+            return None;
+        }
+        
+        self.insert_text(text, delivery)
+    }
+    
+    /// 編集中のスニペットを前面のウィンドウに入力して、展開を試す
+    /// 
+    /// キーワードは入力されていないためバックスペースは送らない。
+    /// 入力フィールドは空のまま入力し、使用回数や取り消しの対象には含めない
+    /// 
+    /// # 引数
+    /// * `snippet` - 試すスニペット（保存されていなくてもよい）
+    /// 
+    /// # 戻り値
+    /// テキストを入力した方法（失敗した場合はNone）
+    pub fn test_expansion(&self, snippet: &Snippet) -> Option<InsertMethod> {
+        let mut replacement = {
+            let settings = self.settings.lock().ok()?;
+            build_replacement(snippet, &snippet.keyword, &settings)
+        };
+        if !replacement.fields.is_empty() {
+            replacement = replacement.with_field_values(&HashMap::new());
+        }
+        
+        log::debug!("Testing expansion of snippet '{}'", snippet.name);
+        if replacement.send_as_keystrokes {
+            replacement.keyword_length = 0;
+            return self.send_replacement(&replacement).then_some(InsertMethod::Typed);
+        }
+        
+        let method = self.replace_with_backspace(&replacement.text, 0, replacement.delivery, replacement.pre_delay_ms)?;
+        self.move_caret_left(replacement.cursor_offset).then_some(method)
+    }
+    
+    /// スニペットの設定に従って置換を実行する
+    /// 
+    /// キー入力として送信するスニペットは、キーワードを削除する前に本文を解析し、
+    /// 不正な場合は何も入力せずに失敗とする。
+    /// ドライランの設定では何も入力せず、入力するはずだった内容を記録する
+    /// 
+    /// # 引数
+    /// * `replacement` - 検出された置換
+    /// 
+    /// # 戻り値
+    /// 置換を実行した結果
+    pub fn perform_replacement(&self, replacement: &Replacement) -> ReplacementOutcome {
+        if self.settings.lock().is_ok_and(|settings| settings.dry_run) {
+            self.record_dry_run(replacement);
+            return ReplacementOutcome::Recorded;
+        }
+        
+        let succeeded = self.send_replacement(replacement);
+        if succeeded {
+            self.record_usage(replacement.snippet_id);
+        }
+        
+        // キー入力として送信したスニペットは、送信したキーの操作を元に戻せないため取り消しの対象にしない
+        if let Ok(mut last_expansion) = self.last_expansion.lock() {
+            // キーワードを削除していない場合は、取り消すときに入力し直さない
+            *last_expansion = (succeeded && !replacement.send_as_keystrokes).then(|| LastExpansion {
+                trigger: if replacement.keyword_length > 0 { replacement.trigger.clone() } else { String::new() },
+                text_length: replacement.text.chars().filter(|&c| c != '\r').count()
+                    + usize::from(replacement.kept_terminator.is_some()),
+                cursor_offset: replacement.caret_offset(),
+                expanded_at: Instant::now(),
+            });
+        }
+        
+        if succeeded {
+            ReplacementOutcome::Expanded
+        } else {
+            ReplacementOutcome::Failed
+        }
     }
     
-    /// テキストバッファから置換対象のキーワードを検索する
-    pub fn check_for_replacements(&self, buffer: &str) -> Option<(String, usize)> {
-        if let Ok(settings) = self.settings.lock() {
-            if !settings.enabled {
-                return None;
+    /// 直前の展開を取り消し、展開したテキストを削除してキーワードを入力し直す
+    /// 
+    /// 展開から `UNDO_TIMEOUT` が経過した場合と、展開後に文字が入力された場合は取り消せない
+    /// 
+    /// # 戻り値
+    /// 取り消したかどうか
+    pub fn undo_last_expansion(&self) -> bool {
+        let last = match self.last_expansion.lock() {
+            Ok(mut last_expansion) => last_expansion.take(),
+            Err(_) => return false,
+        };
+        let Some(last) = last else {
+            log::info!("No expansion to undo");
+            return false;
+        };
+        if last.expanded_at.elapsed() > UNDO_TIMEOUT {
+            log::info!("Last expansion is too old to undo");
+            return false;
+        }
+        
+        log::debug!("Undoing expansion of '{}' ({} characters)", last.trigger, last.text_length);
+        
+        // ホットキーの修飾キーが押されたままだと入力がショートカットとして扱われるため、解放してから入力する
+        self.apply_timing();
+        self.backend.wait(Duration::from_millis(100));
+        self.backend.reset_modifier_keys();
+        
+        // `{cursor}` でカーソルを戻した分だけ右へ移動してから、展開したテキストを削除する
+        if last.cursor_offset > 0 && !self.backend.send_key_presses(VK_RIGHT.0, last.cursor_offset) {
+            log::error!("Failed to move caret to the end of the expansion");
+            return false;
+        }
+        if !self.backend.send_key_presses(VK_BACK.0, last.text_length) {
+            log::error!("Failed to delete the expanded text");
+            return false;
+        }
+        
+        // キーワードを削除せずに展開した場合は、入力し直すものがない
+        last.trigger.is_empty() || self.backend.type_text(&last.trigger)
+    }
+    
+    /// 直前の展開を取り消せなくする（展開後に文字が入力された場合など）
+    pub fn clear_last_expansion(&self) {
+        if let Ok(mut last_expansion) = self.last_expansion.lock() {
+            *last_expansion = None;
+        }
+    }
+    
+    /// スニペットの使用回数を増やして展開の記録に追加し、別スレッドで記録を保存する
+    /// 
+    /// 設定ファイルはここでは書き込まず、変更のフラグを立ててUIスレッドでまとめて保存する
+    /// （手動で編集した設定ファイルを、読み込み直す前に展開で上書きしないため）
+    fn record_usage(&self, snippet_id: u64) {
+        let snippet_name = match self.settings.lock() {
+            Ok(mut settings) => {
+                let snippet = settings.snippets.iter_mut().find(|s| s.id == snippet_id);
+                snippet.map(|snippet| {
+                    snippet.usage_count += 1;
+                    snippet.name.clone()
+                })
             }
-            
-            // バッファ内容をログに記録（デバッグ用）
-            log::debug!("Checking buffer for replacements: '{}'", buffer);
-            
-            // 有効なスニペットだけを検索
-            for snippet in settings.snippets.iter().filter(|s| s.enabled) {
-                // まず元のキーワードで直接比較
-                if buffer.ends_with(&snippet.keyword) {
-                    log::debug!("Found matching keyword (direct): '{}' for snippet: '{}'", 
-                               snippet.keyword, snippet.name);
-                    
-                    let replacement = match snippet.snippet_type {
-                        SnippetType::Static => snippet.content.clone(),
-                        SnippetType::Dynamic => {
-                            let result = format_dynamic_content(&snippet.content);
-                            log::debug!("Formatted dynamic content: '{}' -> '{}'", 
-                                       snippet.content, result);
-                            result
-                        }
-                    };
-                    
-                    // キーワードの長さを返す（正確なバックスペース数のため）
-                    return Some((replacement, snippet.keyword.len()));
-                }
-                
-                // 元の比較で見つからない場合のみ、正規化して比較
-                let normalized_buffer = buffer.replace('=', "_")
-                                             .replace(';', "_")
-                                             .replace(',', "_");
-                let normalized_keyword = snippet.keyword.replace('=', "_")
-                                                      .replace(';', "_")
-                                                      .replace(',', "_");
-                
-                if normalized_buffer.ends_with(&normalized_keyword) {
-                    log::debug!("Found matching keyword (normalized): '{}' for snippet: '{}'", 
-                               snippet.keyword, snippet.name);
-                    
-                    let replacement = match snippet.snippet_type {
-                        SnippetType::Static => snippet.content.clone(),
-                        SnippetType::Dynamic => {
-                            let result = format_dynamic_content(&snippet.content);
-                            log::debug!("Formatted dynamic content: '{}' -> '{}'", 
-                                       snippet.content, result);
-                            result
-                        }
-                    };
-                    
-                    // キーワードの長さを返す（正確なバックスペース数のため）
-                    return Some((replacement, snippet.keyword.len()));
+            Err(_) => return,
+        };
+        if snippet_name.is_some() {
+            self.usage_changed.store(true, Ordering::SeqCst);
+        }
+        
+        if let (Some(stats), Some(snippet_name)) = (&self.expansion_stats, snippet_name) {
+            let stats = Arc::clone(stats);
+            let expanded_at = Local::now();
+            std::thread::spawn(move || {
+                if let Ok(mut stats) = stats.lock() {
+                    stats.record(snippet_id, &snippet_name, expanded_at);
+                    if let Err(e) = stats.save() {
+                        log::error!("Failed to save expansion statistics: {}", e);
+                    }
                 }
+            });
+        }
+    }
+    
+    /// 置換を実行する（使用回数は記録しない）
+    fn send_replacement(&self, replacement: &Replacement) -> bool {
+        if !replacement.send_as_keystrokes {
+            let Some(method) = self.replace_with_backspace(
+                &replacement.text,
+                replacement.keyword_length,
+                replacement.delivery,
+                replacement.pre_delay_ms,
+            ) else {
+                return false;
+            };
+            log::info!("Expanded '{}' with snippet '{}' ({}): {:?}",
+                       replacement.trigger, replacement.snippet_name, method.label(), replacement.text);
+            if !self.insert_kept_terminator(replacement) {
+                return false;
+            }
+            return self.move_caret_left(replacement.caret_offset());
+        }
+        
+        let tokens = match parse_keystrokes(&replacement.text) {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                log::error!("Invalid keystrokes in snippet '{}': {}", replacement.snippet_name, e);
+                return false;
             }
+        };
+        
+        log::debug!("Replacing keyword (length: {}) with {} keystroke tokens",
+                   replacement.keyword_length, tokens.len());
+        
+        if !self.check_expansion_length(&replacement.text) {
+            return false;
+        }
+        
+        if !self.delete_keyword(replacement.keyword_length, replacement.pre_delay_ms) {
+            return false;
         }
         
-        None
+        if !self.send_keystrokes(&tokens, replacement.delivery) {
+            return false;
+        }
+        log::info!("Expanded '{}' with snippet '{}' (keystrokes): {:?}",
+                   replacement.trigger, replacement.snippet_name, replacement.text);
+        self.insert_kept_terminator(replacement)
     }
     
-    /// キーワードを置換しようと試みる
+    /// 展開するテキストが設定の最大文字数以内かを確認する
     /// 
-    /// # 引数
-    /// * `buffer` - 置換対象のバッファ文字列
+    /// 超えている場合は、キーワードを削除する前に展開をやめ、文字数と上限をログとトレイの通知で知らせる
     /// 
     /// # 戻り値
-    /// 置換が成功したかどうか
-    #[allow(dead_code)]
-    pub fn try_replace(&mut self, buffer: &str) -> bool {
-        if let Some((replacement, keyword_length)) = self.check_for_replacements(buffer) {
-            self.perform_replacement_with_backspace(&replacement, keyword_length)
-        } else {
-            false
+    /// 展開してよいかどうか
+    fn check_expansion_length(&self, text: &str) -> bool {
+        let Ok(max_length) = self.settings.lock().map(|settings| settings.max_expansion_len) else {
+            return true;
+        };
+        let length = text.chars().count();
+        if length <= max_length {
+            return true;
         }
+        
+        let message = format!(
+            "Expansion blocked: the text is {} characters long, over the limit of {}",
+            length, max_length,
+        );
+        log::warn!("{}", message);
+        notification::show_warning_balloon(&message);
+        false
     }
     
-    /// 置換を実行する（キーワードの長さを指定してバックスペース）
-    pub fn perform_replacement_with_backspace(&self, text: &str, keyword_length: usize) -> bool {
-        // キーワード削除前にログ記録
-        log::debug!("Replacing keyword (length: {}) with text: '{}'", keyword_length, text);
+    /// 展開後に区切り文字を入力し直す
+    /// 
+    /// # 戻り値
+    /// 入力に成功したかどうか（入力し直す区切り文字がない場合も成功とみなす）
+    fn insert_kept_terminator(&self, replacement: &Replacement) -> bool {
+        match replacement.kept_terminator {
+            Some(terminator) => self.insert_text(&terminator.to_string(), Delivery::Auto).is_some(),
+            None => true,
+        }
+    }
+    
+    /// テキストとキーの組み合わせを先頭から順に送信する
+    fn send_keystrokes(&self, tokens: &[KeystrokeToken], delivery: Delivery) -> bool {
+        for token in tokens {
+            let sent = match token {
+                KeystrokeToken::Text(text) => self.insert_text(text, delivery).is_some(),
+                KeystrokeToken::Key(combo) => self.backend.send_key_combo(*combo),
+            };
+            
+            if !sent {
+                log::error!("Failed to send keystroke token: {:?}", token);
+                self.backend.reset_modifier_keys();
+                return false;
+            }
+        }
+        
+        true
+    }
+    
+    /// 入力後のカーソルを `{cursor}` の位置まで左へ移動する
+    fn move_caret_left(&self, count: usize) -> bool {
+        if count == 0 {
+            return true;
+        }
+        
+        log::debug!("Moving caret {} characters to the left", count);
+        if !self.backend.send_key_presses(VK_LEFT.0, count) {
+            log::error!("Failed to move caret to the cursor marker");
+            self.backend.reset_modifier_keys();
+            return false;
+        }
+        
+        true
+    }
+    
+    /// 設定の待機時間を取得し、キー入力の送信先にも反映する
+    fn apply_timing(&self) -> TimingProfile {
+        let timing = self.settings.lock()
+            .map(|settings| settings.timing)
+            .unwrap_or_default();
+        self.backend.set_timing(timing);
+        timing
+    }
+    
+    /// 入力済みのキーワードをバックスペースで削除する
+    /// 
+    /// # 引数
+    /// * `keyword_length` - 削除するキーワードの文字数
+    /// * `pre_delay_ms` - 削除する前の待機時間（スニペットで指定した場合は、展開の速さの設定から計算した値の代わりに使う）
+    fn delete_keyword(&self, keyword_length: usize, pre_delay_ms: Option<u64>) -> bool {
+        // キーワードを入力せずに展開する場合は、フォーカスが戻るまで待つだけにする
+        if keyword_length == 0 {
+            let wait = pre_delay_ms.unwrap_or(self.apply_timing().pre_backspace_ms);
+            self.backend.wait(Duration::from_millis(wait));
+            return true;
+        }
         
-        // 安全のため、キーワード長に上限を設ける
-        let safe_length = std::cmp::min(keyword_length, 20); // 最大20文字に制限
+        // 安全のため、キーワード長に上限を設ける（バッファに収まらないキーワードは入力されることがない）
+        let safe_length = std::cmp::min(keyword_length, MAX_BUFFER_SIZE);
         if safe_length < keyword_length {
             log::warn!("Limiting keyword length from {} to {}", keyword_length, safe_length);
         }
@@ -116,21 +1354,25 @@ impl ReplacementEngine {
         // 短いキーワードの場合は特別な処理
         let is_short_keyword = safe_length <= 2;
         
+        let timing = self.apply_timing();
+        
         // バックスペース処理の前に少し待機
-        // 短いキーワードの場合はより長く待機
-        let pre_backspace_wait = if is_short_keyword {
-            400 // 短いキーワードは長めに待機
+        // 短いキーワードの場合はより長く待機（スニペットで待機時間を指定した場合はその値を使う）
+        let pre_backspace_wait = if let Some(pre_delay_ms) = pre_delay_ms {
+            pre_delay_ms
+        } else if is_short_keyword {
+            timing.pre_backspace_ms * 2 // 短いキーワードは長めに待機
         } else if is_high_risk_length {
-            300
+            timing.pre_backspace_ms * 3 / 2
         } else {
-            200
+            timing.pre_backspace_ms
         };
-        thread::sleep(Duration::from_millis(pre_backspace_wait));
+        self.backend.wait(Duration::from_millis(pre_backspace_wait));
         
         // 例外処理を追加
         let backspace_result = std::panic::catch_unwind(|| {
             // キーワードを削除（キーワードの長さに基づいてバックスペース）
-            if !self.simulate_backspace(safe_length, is_short_keyword) {
+            if !self.backend.send_backspaces(safe_length, is_short_keyword) {
                 log::error!("Failed to simulate backspace for keyword of length {}", safe_length);
                 return false;
             }
@@ -155,22 +1397,43 @@ impl ReplacementEngine {
         // バックスペースと入力操作の間の遅延
         // 短いキーワードの場合はより長く待機
         let wait_time = if is_short_keyword {
-            600 // 短いキーワードは長めに待機
+            timing.post_backspace_ms * 2 // 短いキーワードは長めに待機
         } else if safe_length > 7 {
-            400
+            timing.post_backspace_ms * 4 / 3
         } else {
-            300
+            timing.post_backspace_ms
+        };
+        self.backend.wait(Duration::from_millis(wait_time));
+        
+        true
+    }
+    
+    /// テキストを入力する
+    /// 
+    /// 自動の場合は短いテキストだけ直接入力を試み、長いテキストや直接入力に失敗した場合はクリップボード経由で貼り付ける。
+    /// 直接入力を指定した場合は、貼り付けを受け付けないアプリのために失敗しても貼り付けない
+    /// 
+    /// # 引数
+    /// * `text` - 入力するテキスト
+    /// * `delivery` - テキストを入力する方法
+    /// 
+    /// # 戻り値
+    /// テキストを入力した方法（失敗した場合はNone）
+    fn insert_text(&self, text: &str, delivery: Delivery) -> Option<InsertMethod> {
+        let try_direct_input = match delivery {
+            // テキストが短い場合は直接文字入力を試みる (より高い成功率)
+            Delivery::Auto => text.len() <= AUTO_DIRECT_INPUT_MAX_LEN,
+            Delivery::Type => true,
+            Delivery::Paste => false,
         };
-        thread::sleep(Duration::from_millis(wait_time));
         
-        // テキストが短い場合は直接文字入力を試みる (より高い成功率)
-        if text.len() <= 50 {
+        if try_direct_input {
             log::debug!("Attempting direct text input for text: '{}'", text);
             
             // 例外処理を追加
             let input_result = std::panic::catch_unwind(|| {
                 // 改良された直接文字入力メソッドを使用（日本語文字にも対応）
-                let direct_input_result = self.simulate_direct_char_input(text);
+                let direct_input_result = self.backend.type_text(text);
                 
                 if direct_input_result {
                     log::debug!("Direct text input completed successfully");
@@ -183,477 +1446,62 @@ impl ReplacementEngine {
             
             // 直接入力が成功した場合は終了
             match input_result {
-                Ok(true) => return true,
+                Ok(true) => return Some(InsertMethod::Typed),
                 Ok(false) => {}, // クリップボード方式にフォールバック
                 Err(_) => {
                     log::error!("Panic occurred during direct text input");
                     // クリップボード方式にフォールバック
                 }
             }
+            
+            if delivery == Delivery::Type {
+                log::error!("Direct text input failed and pasting is disabled for this snippet");
+                return None;
+            }
         }
         
         // クリップボード操作を例外処理で囲む
-        let clipboard_result = std::panic::catch_unwind(|| {
-            // クリップボードにテキストを設定
-            if let Ok(mut clipboard) = Clipboard::new() {
-                // 既存のクリップボード内容を保存（あとで復元するため）
-                let original_clipboard = clipboard.get_text().ok();
-                
-                log::debug!("Setting clipboard text: '{}'", text);
-                if let Err(e) = clipboard.set_text(text) {
-                    log::error!("Failed to set clipboard text: {}", e);
-                    return false;
-                }
-                
-                // クリップボード設定後に少し待機
-                thread::sleep(Duration::from_millis(150));
-                
-                // CTRL+Vで貼り付ける
-                let paste_result = self.simulate_paste_simple();
-                
-                if !paste_result {
-                    log::error!("Failed to simulate paste operation");
-                    
-                    // クリップボードを元の状態に戻す (エラー無視)
-                    if let Some(original_text) = original_clipboard {
-                        let _ = clipboard.set_text(&original_text);
-                    }
-                    
-                    return false;
-                }
-                
-                // 操作完了後に少し待機
-                thread::sleep(Duration::from_millis(200));
-                
-                log::debug!("Replacement completed successfully: '{}'", text);
-                return true;
-            } else {
-                log::error!("Failed to access clipboard");
-                return false;
-            }
-        });
+        let clipboard_result = std::panic::catch_unwind(|| self.backend.paste_text(text));
         
         match clipboard_result {
-            Ok(result) => result,
+            Ok(result) => result.then_some(InsertMethod::Pasted),
             Err(_) => {
                 log::error!("Panic occurred during clipboard operation");
-                false
+                None
             }
         }
     }
     
-    /// バックスペースキーを自動で入力する
-    fn simulate_backspace(&self, count: usize, is_short_keyword: bool) -> bool {
-        use windows::Win32::UI::Input::KeyboardAndMouse::{
-            INPUT, INPUT_KEYBOARD, KEYBDINPUT, SendInput, KEYEVENTF_KEYUP, VK_BACK,
-        };
-        
-        if count == 0 {
-            log::debug!("No backspaces to simulate");
-            return true; // 削除するものがなければ成功と見なす
-        }
-        
-        // バックスペース数をログに記録（デバッグ用）
-        log::debug!("Simulating {} backspaces", count);
-        
-        // 安全のため、バックスペース数に上限を設ける
-        let safe_count = std::cmp::min(count, 20); // 最大20回に制限
-        if safe_count < count {
-            log::warn!("Limiting backspace count from {} to {}", count, safe_count);
-        }
-        
-        // 高リスクの長さに対する特別処理
-        let is_high_risk_length = safe_count >= 5 && safe_count <= 9;
-        
-        // バックスペース処理前の待機時間
-        // 短いキーワードの場合はより長く待機
-        let initial_wait = if is_short_keyword {
-            100 // 短いキーワードは長めに待機
-        } else if is_high_risk_length {
-            50
-        } else {
-            40
-        };
-        thread::sleep(Duration::from_millis(initial_wait));
-        
-        // 例外処理を追加
-        let success = match std::panic::catch_unwind(|| {
-            // カーソル位置を安定させるためにバックスペースを丁寧に実行
-            for i in 0..safe_count {
-                // バックスペースキーを押す
-                let mut key_down: INPUT = unsafe { std::mem::zeroed() };
-                key_down.r#type = INPUT_KEYBOARD;
-                key_down.Anonymous.ki = KEYBDINPUT {
-                    wVk: VK_BACK,
-                    wScan: 0,
-                    dwFlags: Default::default(),
-                    time: 0,
-                    dwExtraInfo: 0,
-                };
-                
-                // バックスペースキーを離す
-                let mut key_up: INPUT = unsafe { std::mem::zeroed() };
-                key_up.r#type = INPUT_KEYBOARD;
-                key_up.Anonymous.ki = KEYBDINPUT {
-                    wVk: VK_BACK,
-                    wScan: 0,
-                    dwFlags: KEYEVENTF_KEYUP,
-                    time: 0,
-                    dwExtraInfo: 0,
-                };
-                
-                // バックスペースを押下
-                let sent_down = unsafe {
-                    SendInput(&[key_down], std::mem::size_of::<INPUT>() as i32)
-                };
-                
-                if sent_down != 1 {
-                    log::error!("Failed to send backspace key down event for backspace {}", i + 1);
-                    return false;
-                }
-                
-                // キーの押下を確実に処理してもらうための待機時間
-                // 短いキーワードの場合はより長く待機
-                let key_down_wait = if is_short_keyword { 40 } else { 20 };
-                thread::sleep(Duration::from_millis(key_down_wait));
-                
-                // バックスペースを解放
-                let sent_up = unsafe {
-                    SendInput(&[key_up], std::mem::size_of::<INPUT>() as i32)
-                };
-                
-                if sent_up != 1 {
-                    log::error!("Failed to send backspace key up event for backspace {}", i + 1);
-                    return false;
-                }
-                
-                // 次のバックスペース前の待機時間
-                // 短いキーワードの場合はより長く待機
-                let between_backspace_wait = if is_short_keyword { 50 } else { 20 };
-                thread::sleep(Duration::from_millis(between_backspace_wait));
-            }
-            
-            // すべて成功
-            true
-        }) {
-            Ok(result) => {
-                log::debug!("Completed sending {} backspace events, success: {}", safe_count, result);
-                result
-            },
-            Err(_) => {
-                log::error!("Panic occurred during backspace simulation");
-                false
-            }
-        };
-        
-        // 最後の操作後の待機時間
-        // 短いキーワードの場合はより長く待機
-        let final_wait = if is_short_keyword {
-            200 // 短いキーワードは長めに待機
-        } else if is_high_risk_length {
-            100
-        } else if safe_count > 5 {
-            80
-        } else {
-            60
-        };
-        thread::sleep(Duration::from_millis(final_wait));
-        
-        success
-    }
-
-    /// シンプルなテキスト貼り付け操作 (CTRL+V)
-    fn simulate_paste_simple(&self) -> bool {
-        use windows::Win32::UI::Input::KeyboardAndMouse::{
-            INPUT, INPUT_KEYBOARD, KEYBDINPUT, SendInput, KEYEVENTF_KEYUP, VK_CONTROL, VK_V,
-        };
-        
-        log::debug!("Simulating paste operation (CTRL+V) with improved approach");
-        
-        // 開始前に修飾キーをリセット（前回の失敗状態から回復するため）
-        self.reset_modifier_keys();
-        
-        // 一貫した時間をおいて貼り付け処理を実行
-        thread::sleep(Duration::from_millis(150));
-        
-        // 入力をまとめて準備
-        let mut inputs: Vec<INPUT> = Vec::with_capacity(4);
-        
-        // CTRL キーを押す
-        let mut ctrl_down: INPUT = unsafe { std::mem::zeroed() };
-        ctrl_down.r#type = INPUT_KEYBOARD;
-        ctrl_down.Anonymous.ki = KEYBDINPUT {
-            wVk: VK_CONTROL,
-            wScan: 0,
-            dwFlags: Default::default(),
-            time: 0,
-            dwExtraInfo: 0,
-        };
-        inputs.push(ctrl_down);
-        
-        // V キーを押す
-        let mut v_down: INPUT = unsafe { std::mem::zeroed() };
-        v_down.r#type = INPUT_KEYBOARD;
-        v_down.Anonymous.ki = KEYBDINPUT {
-            wVk: VK_V,
-            wScan: 0,
-            dwFlags: Default::default(),
-            time: 0,
-            dwExtraInfo: 0,
-        };
-        inputs.push(v_down);
-        
-        // V キーを離す
-        let mut v_up: INPUT = unsafe { std::mem::zeroed() };
-        v_up.r#type = INPUT_KEYBOARD;
-        v_up.Anonymous.ki = KEYBDINPUT {
-            wVk: VK_V,
-            wScan: 0,
-            dwFlags: KEYEVENTF_KEYUP,
-            time: 0,
-            dwExtraInfo: 0,
-        };
-        inputs.push(v_up);
-        
-        // CTRL キーを離す
-        let mut ctrl_up: INPUT = unsafe { std::mem::zeroed() };
-        ctrl_up.r#type = INPUT_KEYBOARD;
-        ctrl_up.Anonymous.ki = KEYBDINPUT {
-            wVk: VK_CONTROL,
-            wScan: 0,
-            dwFlags: KEYEVENTF_KEYUP,
-            time: 0,
-            dwExtraInfo: 0,
-        };
-        inputs.push(ctrl_up);
-        
-        // すべての入力をまとめて送信
-        let sent = unsafe {
-            SendInput(&inputs, std::mem::size_of::<INPUT>() as i32)
-        };
+    /// 選択中のテキストのコピー操作 (CTRL+C)
+    fn simulate_copy(&self) -> bool {
+        log::debug!("Simulating copy operation (CTRL+C)");
         
-        if sent as usize != inputs.len() {
-            log::error!("Failed to send paste key sequence, sent only {} of {}", sent, inputs.len());
-            self.reset_modifier_keys();
+        if !self.backend.send_key_combo(KeyCombo { ctrl: true, vk: VK_C.0, ..Default::default() }) {
+            log::error!("Failed to send copy key sequence");
             return false;
         }
         
-        // 操作後に待機
-        thread::sleep(Duration::from_millis(100));
-        
-        log::debug!("Paste operation completed via improved approach");
+        // クリップボードに反映されるまで待機
+        self.backend.wait(Duration::from_millis(self.apply_timing().clipboard_settle_ms));
         
         true
     }
-
-    /// 直接文字入力（Unicode文字対応）
-    fn simulate_direct_char_input(&self, text: &str) -> bool {
-        use windows::Win32::UI::Input::KeyboardAndMouse::{
-            INPUT, INPUT_KEYBOARD, KEYBDINPUT, SendInput, KEYEVENTF_UNICODE, KEYEVENTF_KEYUP, VIRTUAL_KEY,
-        };
-        
-        log::debug!("Simulating direct char input for: '{}'", text);
-        
-        // IMEの状態確認
-        #[cfg(feature = "Win32_UI_Input_Ime")]
-        let ime_active = self.check_ime_status();
-        #[cfg(not(feature = "Win32_UI_Input_Ime"))]
-        let ime_active = false;
-        
-        if ime_active {
-            log::debug!("IME is active, temporarily disabling for direct input");
-            self.toggle_ime(false);
-            
-            // IMEの状態変更が反映されるのを待つ
-            thread::sleep(Duration::from_millis(100));
-        }
-        
-        // 短いテキストの場合は特に慎重に処理
-        let is_short_text = text.len() <= 3;
-        let char_delay = if is_short_text { 30 } else { 15 };
-        
-        // 入力前に少し待機（特に短いテキストの場合）
-        if is_short_text {
-            thread::sleep(Duration::from_millis(100));
-        }
-        
-        for c in text.chars() {
-            // キーダウン入力を表すINPUT構造体を作成
-            let mut input_down: INPUT = unsafe { std::mem::zeroed() };
-            input_down.r#type = INPUT_KEYBOARD;
-            input_down.Anonymous.ki = KEYBDINPUT {
-                wVk: VIRTUAL_KEY(0),
-                wScan: c as u16,
-                dwFlags: KEYEVENTF_UNICODE,
-                time: 0,
-                dwExtraInfo: 0,
-            };
-            
-            // キーアップ入力を表すINPUT構造体を作成
-            let mut input_up: INPUT = unsafe { std::mem::zeroed() };
-            input_up.r#type = INPUT_KEYBOARD;
-            input_up.Anonymous.ki = KEYBDINPUT {
-                wVk: VIRTUAL_KEY(0),
-                wScan: c as u16,
-                dwFlags: KEYEVENTF_UNICODE | KEYEVENTF_KEYUP,
-                time: 0,
-                dwExtraInfo: 0,
-            };
-            
-            // キーダウン入力を送信
-            let sent_down = unsafe {
-                SendInput(&[input_down], std::mem::size_of::<INPUT>() as i32)
-            };
-            
-            if sent_down != 1 {
-                log::error!("Failed to send unicode character down event: '{}'", c);
-                // IMEの状態を元に戻す
-                if ime_active {
-                    self.toggle_ime(true);
-                }
-                return false;
-            }
-            
-            // キーダウンとキーアップの間に小さな遅延
-            thread::sleep(Duration::from_millis(char_delay));
-            
-            // キーアップ入力を送信
-            let sent_up = unsafe {
-                SendInput(&[input_up], std::mem::size_of::<INPUT>() as i32)
-            };
-            
-            if sent_up != 1 {
-                log::error!("Failed to send unicode character up event: '{}'", c);
-                // IMEの状態を元に戻す
-                if ime_active {
-                    self.toggle_ime(true);
-                }
-                return false;
-            }
-            
-            // 文字間に小さな遅延
-            let between_char_delay = if is_short_text { 30 } else { 15 };
-            thread::sleep(Duration::from_millis(between_char_delay));
-        }
-        
-        // IMEの状態を元に戻す
-        if ime_active {
-            log::debug!("Restoring IME state");
-            thread::sleep(Duration::from_millis(50));
-            self.toggle_ime(true);
-        }
-        
-        // 入力後に少し待機（特に短いテキストの場合）
-        if is_short_text {
-            thread::sleep(Duration::from_millis(100));
-        }
-        
-        log::debug!("Direct char input completed successfully");
-        return true;
-    }
-
-    /// IMEの状態を確認する関数
-    #[cfg(feature = "Win32_UI_Input_Ime")]
-    fn check_ime_status(&self) -> bool {
-        use windows::Win32::UI::Input::Ime::{ImmGetContext, ImmGetOpenStatus};
-        use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
-        use windows::Win32::Globalization::HIMC;
-        use windows::Win32::Foundation::BOOL;
-        
-        unsafe {
-            let hwnd = GetForegroundWindow();
-            let himc = ImmGetContext(hwnd);
-            
-            if himc.is_invalid() {
-                log::debug!("Failed to get IMM context, assuming IME is not active");
-                return false;
-            }
-            
-            let is_open = ImmGetOpenStatus(himc);
-            log::debug!("IME status: {:?}", is_open);
-            
-            is_open.into()
-        }
-    }
     
-    /// IMEの状態を切り替える関数
-    #[cfg(feature = "Win32_UI_Input_Ime")]
-    fn toggle_ime(&self, enable: bool) -> bool {
-        use windows::Win32::UI::Input::Ime::{ImmGetContext, ImmSetOpenStatus};
-        use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
-        use windows::Win32::Globalization::HIMC;
-        use windows::Win32::Foundation::BOOL;
-        
-        unsafe {
-            let hwnd = GetForegroundWindow();
-            let himc = ImmGetContext(hwnd);
-            
-            if himc.is_invalid() {
-                log::error!("Failed to get IMM context for toggling IME");
-                return false;
+    /// 前面のアプリケーションで選択中のテキストを取得する
+    /// 
+    /// CTRL+Cでコピーしてクリップボードから読み取り、クリップボードは元の内容に戻す
+    /// 
+    /// # 戻り値
+    /// 選択中のテキスト（選択がない場合や取得に失敗した場合はNone）
+    pub fn capture_selection(&self) -> Option<String> {
+        match Clipboard::new() {
+            Ok(mut clipboard) => {
+                clipboard::capture_selection(&mut clipboard, |_| self.simulate_copy())
+            }
+            Err(e) => {
+                log::error!("Failed to access clipboard: {}", e);
+                None
             }
-            
-            let result = ImmSetOpenStatus(himc, enable);
-            log::debug!("Set IME status to {}: {:?}", enable, result);
-            
-            result.into()
-        }
-    }
-    
-    /// IME機能が無効な場合のダミー実装
-    #[cfg(not(feature = "Win32_UI_Input_Ime"))]
-    fn check_ime_status(&self) -> bool {
-        log::debug!("IME feature not enabled, assuming IME is not active");
-        false
-    }
-    
-    /// IME機能が無効な場合のダミー実装
-    #[cfg(not(feature = "Win32_UI_Input_Ime"))]
-    fn toggle_ime(&self, _enable: bool) -> bool {
-        log::debug!("IME feature not enabled, toggle operation ignored");
-        true
-    }
-
-    /// モディファイアキーを強制的に解放する関数
-    pub fn reset_modifier_keys(&self) -> bool {
-        use windows::Win32::UI::Input::KeyboardAndMouse::{
-            INPUT, INPUT_KEYBOARD, KEYBDINPUT, SendInput, KEYEVENTF_KEYUP, 
-            VK_CONTROL, VK_SHIFT, VK_MENU, VK_LWIN, VK_RWIN,
-        };
-        
-        log::debug!("Resetting all modifier keys to released state");
-        
-        let modifiers = [VK_CONTROL, VK_SHIFT, VK_MENU, VK_LWIN, VK_RWIN];
-        let mut inputs: Vec<INPUT> = Vec::with_capacity(modifiers.len());
-        
-        // すべてのモディファイアキーを離す状態にする
-        for &vk in &modifiers {
-            let mut key_up: INPUT = unsafe { std::mem::zeroed() };
-            key_up.r#type = INPUT_KEYBOARD;
-            key_up.Anonymous.ki = KEYBDINPUT {
-                wVk: vk,
-                wScan: 0,
-                dwFlags: KEYEVENTF_KEYUP,
-                time: 0,
-                dwExtraInfo: 0,
-            };
-            inputs.push(key_up);
-        }
-        
-        // モディファイアキーをすべて解放
-        let sent = unsafe {
-            SendInput(&inputs, std::mem::size_of::<INPUT>() as i32)
-        };
-        
-        if sent as usize != inputs.len() {
-            log::error!("Failed to reset modifier keys, sent only {} of {}", sent, inputs.len());
-            return false;
         }
-        
-        // 少し待機して確実にキー状態が反映されるようにする
-        thread::sleep(Duration::from_millis(50));
-        
-        log::debug!("All modifier keys have been reset");
-        true
     }
-}
+}
\ No newline at end of file
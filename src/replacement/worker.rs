@@ -0,0 +1,101 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use crossbeam_channel::Sender;
+
+use crate::config::settings::Snippet;
+use super::{InsertMethod, Replacement, ReplacementEngine};
+
+/// ワーカースレッドで実行する、キー入力を送信する処理
+#[derive(Debug)]
+pub enum ReplacementJob {
+    /// 置換を実行する
+    Replace(Replacement),
+    /// 指定したウィンドウにフォーカスを戻してから置換を実行する
+    ReplaceInWindow(Replacement, isize),
+    /// 直前の展開を取り消す
+    Undo,
+    /// 編集中のスニペットで展開を試し、テキストを入力した方法を送り返す
+    TestExpansion(Snippet, Sender<Option<InsertMethod>>),
+}
+
+impl ReplacementJob {
+    /// ログに表示する処理の名前
+    fn label(&self) -> &str {
+        match self {
+            Self::Replace(replacement) | Self::ReplaceInWindow(replacement, _) => &replacement.keyword,
+            Self::Undo => "undo",
+            Self::TestExpansion(snippet, _) => &snippet.name,
+        }
+    }
+}
+
+/// 置換を順番に実行するワーカースレッド
+/// 
+/// キーボードフックのコールバックで待機を含む置換を実行すると、
+/// 置換が終わるまでキー入力の処理が止まるため、置換の実行はこのスレッドに任せる。
+/// 取り消しや展開のテストなど、キー入力を送信する他の処理もこのスレッドで実行し、
+/// 実行中はキーボードフックがエンジンをロックせずに入力を捨てられるようにする。
+/// 一度に実行する処理は1つだけで、実行中は次の処理を受け付けない
+#[derive(Debug)]
+pub struct ReplacementWorker {
+    /// 処理の送信先
+    sender: Sender<ReplacementJob>,
+    /// 置換を実行中かどうか
+    busy: Arc<AtomicBool>,
+}
+
+impl ReplacementWorker {
+    /// ワーカースレッドを開始する
+    /// 
+    /// # 引数
+    /// * `engine` - 置換を実行するエンジン（エンジンが破棄されるとスレッドも終了する）
+    pub fn start(engine: Weak<Mutex<ReplacementEngine>>) -> Self {
+        let (sender, receiver) = crossbeam_channel::unbounded::<ReplacementJob>();
+        let busy = Arc::new(AtomicBool::new(false));
+        let thread_busy = Arc::clone(&busy);
+        
+        thread::spawn(move || {
+            for job in receiver {
+                let Some(engine) = engine.upgrade() else {
+                    break;
+                };
+                
+                if let Ok(engine) = engine.lock() {
+                    engine.run_job(job);
+                }
+                
+                thread_busy.store(false, Ordering::SeqCst);
+            }
+            log::debug!("Replacement worker stopped");
+        });
+        
+        Self { sender, busy }
+    }
+    
+    /// 置換の実行中かどうかを示すフラグを取得する
+    /// 
+    /// エンジンをロックせずに実行中かどうかを確認するために使う
+    pub fn busy_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.busy)
+    }
+    
+    /// 処理の実行を依頼する
+    /// 
+    /// # 戻り値
+    /// 受け付けたかどうか（別の処理を実行中の場合やスレッドが終了している場合は失敗する）
+    pub fn submit(&self, job: ReplacementJob) -> bool {
+        if self.busy.swap(true, Ordering::SeqCst) {
+            log::debug!("Replacement is already in progress, dropping '{}'", job.label());
+            return false;
+        }
+        
+        if self.sender.send(job).is_err() {
+            log::error!("Replacement worker is not running");
+            self.busy.store(false, Ordering::SeqCst);
+            return false;
+        }
+        
+        true
+    }
+}
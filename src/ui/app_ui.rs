@@ -1,11 +1,42 @@
+use crossbeam_channel::Receiver;
 use egui::{self, CentralPanel, ScrollArea, TopBottomPanel, Ui};
+use log::{Level, LevelFilter};
+use std::collections::{BTreeMap, BTreeSet};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use crate::config::{ConfigManager, Settings};
+use crate::config::stats::{ExpansionStats, STATS_RETENTION_DAYS};
+use crate::config::settings::{
+    AutoStartMethod, Snippet, SnippetType, ThemePreference, TimingPreset, DEFAULT_CATEGORY,
+    DEFAULT_MAX_EXPANSION_LEN, DEFAULT_PASTE_RETRIES, MAX_BUFFER_SIZE, MAX_PASTE_RETRIES, UNCATEGORIZED_CATEGORY,
+};
 use crate::keyboard::KeyboardState;
-use crate::replacement::ReplacementEngine;
-use super::{ThemeMode, constants, snippet_editor};
+use crate::replacement::{self, DryRunLog, InsertMethod, ReplacementEngine};
+use crate::replacement::formatter;
+use super::{ThemeMode, constants, settings_view, snippet_editor};
 use crate::utils;
+use crate::utils::event_log;
+
+/// Windowsのテーマに合わせる場合に、テーマの変更を確認する間隔
+const SYSTEM_THEME_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// トーストを表示する時間
+const TOAST_DURATION: Duration = Duration::from_secs(3);
+
+/// アプリ内のキーボードショートカット（ヘルプに表示するキーと説明）
+const SHORTCUT_HELP: &[(&str, &str)] = &[
+    ("Ctrl+N", "Create a new snippet"),
+    ("Ctrl+S", "Save the snippet in the editor"),
+    ("Ctrl+F", "Search snippets"),
+    ("Esc", "Go back to the snippet list"),
+];
+
+/// Statsタブに表示する、よく使うスニペットの数
+const STATS_TOP_SNIPPETS: usize = 10;
+
+/// 展開を試すまでのカウントダウンの時間
+const TEST_EXPANSION_COUNTDOWN: Duration = Duration::from_secs(3);
 
 /// アプリケーションのUI状態
 #[derive(Debug)]
@@ -15,17 +46,81 @@ pub struct AppUiState {
     /// アプリケーションの設定
     pub settings: Arc<Mutex<Settings>>,
     /// キーボードの状態
-    #[allow(dead_code)]
     pub keyboard_state: Arc<Mutex<KeyboardState>>,
     /// テキスト置換エンジン
-    #[allow(dead_code)]
     pub replacement_engine: Arc<Mutex<ReplacementEngine>>,
     /// テーマモード
     pub theme: ThemeMode,
+    /// Windowsのテーマを最後に確認した時刻（Noneの場合は次の描画で確認する）
+    pub system_theme_checked_at: Option<Instant>,
     /// 選択中のタブ
     pub selected_tab: Tab,
     /// 選択中のスニペットのインデックス
     pub selected_snippet_index: Option<usize>,
+    /// システムのDPIスケール
+    pub system_scale: f32,
+    /// ドラッグ中で未確定のUIスケール
+    pub pending_ui_scale: Option<f32>,
+    /// エディタで編集中のスニペット（未保存の下書き）
+    pub editor_draft: Option<Snippet>,
+    /// スニペットの保存結果（成功時はメッセージ、失敗時はエラー）
+    pub editor_status: Option<Result<String, String>>,
+    /// スニペット一覧の検索語
+    pub search_query: String,
+    /// スニペット一覧で表示するカテゴリ（Noneの場合はすべて表示する）
+    pub category_filter: Option<String>,
+    /// スニペット一覧を使用回数の多い順に並べるかどうか
+    pub sort_by_usage: bool,
+    /// スニペット一覧で折りたたんでいるカテゴリ
+    pub collapsed_categories: BTreeSet<String>,
+    /// 削除の確認中のスニペットのインデックス
+    pub pending_delete: Option<usize>,
+    /// 保存したスニペットのIDと、キーワードが重複している他のスニペット（IDと名前）
+    pub keyword_conflict: Option<(u64, Vec<(u64, String)>)>,
+    /// スニペットの読み込み結果（成功時はメッセージ、失敗時はエラー）
+    pub snippets_status: Option<Result<String, String>>,
+    /// 名前を変更中のカテゴリと新しい名前
+    pub category_rename: Option<(String, String)>,
+    /// カテゴリ操作の結果（成功時はメッセージ、失敗時はエラー）
+    pub category_status: Option<Result<String, String>>,
+    /// 編集中の設定ファイルの内容
+    pub raw_config_text: String,
+    /// 設定ファイルの検証・適用結果（成功時はメッセージ、失敗時はエラー）
+    pub raw_config_status: Option<Result<String, String>>,
+    /// 設定のバックアップ・復元の結果（成功時はメッセージ、失敗時はエラー）
+    pub backup_status: Option<Result<String, String>>,
+    /// 表示中のトーストのメッセージと表示を開始した時刻
+    pub toast: Option<(String, Instant)>,
+    /// トーストの [Undo] で元に戻すスニペットの有効/無効（IDと変更前の状態）
+    pub toast_undo: Option<Vec<(u64, bool)>>,
+    /// 作成中のプロファイルの名前（Noneの場合は作成していない）
+    pub new_profile_name: Option<String>,
+    /// 自動起動の設定に失敗した場合のエラー
+    pub startup_error: Option<String>,
+    /// 展開を試すスニペットとカウントダウンを開始した時刻
+    pub test_countdown: Option<(Snippet, Instant)>,
+    /// 展開を試した結果の受信器（入力が終わるまで保持する）
+    pub test_result: Option<Receiver<Option<InsertMethod>>>,
+    /// エディタ内で展開を確かめた結果（スニペットを開き直すと消える）
+    pub editor_sandbox: Option<String>,
+    /// ウィンドウの最小化の要求（展開を試す前に、直前に使っていたウィンドウへフォーカスを戻す）
+    pub minimize_requested: bool,
+    /// ドライランで記録した展開（置換エンジンと共有する）
+    pub dry_run_log: DryRunLog,
+    /// 展開の記録（置換エンジンと共有する）
+    pub expansion_stats: Option<Arc<Mutex<ExpansionStats>>>,
+    /// キーボードフックが動作しているかどうか（--no-hook で起動した場合はfalse）
+    pub hook_enabled: bool,
+    /// Logタブに表示する最も詳細なレベル
+    pub log_level_filter: LevelFilter,
+    /// 次のフレームでスニペット一覧の検索欄にフォーカスを移すかどうか
+    pub focus_search: bool,
+    /// ショートカットでエディタのスニペットの保存が要求されたかどうか
+    pub save_requested: bool,
+    /// 前のフレームで入力欄にフォーカスがあったかどうか（Escで入力欄から抜けるときはタブを戻さない）
+    pub text_input_focused: bool,
+    /// キーボードショートカットのヘルプを表示するかどうか
+    pub show_shortcut_help: bool,
 }
 
 /// アプリケーションのタブ
@@ -37,6 +132,23 @@ pub enum Tab {
     Settings,
     /// スニペットエディタ
     Editor,
+    /// 設定ファイルの直接編集
+    RawConfig,
+    /// カテゴリの管理
+    Categories,
+    /// アプリ内のログ
+    Log,
+    /// 展開の統計
+    Stats,
+}
+
+/// Logタブのレベルの絞り込みの表示名を取得する
+fn log_filter_label(filter: LevelFilter) -> &'static str {
+    match filter {
+        LevelFilter::Warn => "Warnings and errors",
+        LevelFilter::Error => "Errors only",
+        _ => "All",
+    }
 }
 
 impl AppUiState {
@@ -47,14 +159,51 @@ impl AppUiState {
         keyboard_state: Arc<Mutex<KeyboardState>>,
         replacement_engine: Arc<Mutex<ReplacementEngine>>,
     ) -> Self {
+        let theme_preference = settings.lock().map(|settings| settings.theme).unwrap_or_default();
+        let dry_run_log = replacement_engine.lock().map(|engine| engine.dry_run_log()).unwrap_or_default();
+        let expansion_stats = replacement_engine.lock().ok().and_then(|engine| engine.expansion_stats());
+        
         Self {
             config_manager,
             settings,
             keyboard_state,
             replacement_engine,
-            theme: ThemeMode::Dark,
+            theme: ThemeMode::from_preference(theme_preference),
+            system_theme_checked_at: Some(Instant::now()),
             selected_tab: Tab::Snippets,
             selected_snippet_index: None,
+            system_scale: utils::get_system_dpi_scale(),
+            pending_ui_scale: None,
+            editor_draft: None,
+            editor_status: None,
+            search_query: String::new(),
+            category_filter: None,
+            sort_by_usage: false,
+            collapsed_categories: BTreeSet::new(),
+            pending_delete: None,
+            keyword_conflict: None,
+            snippets_status: None,
+            category_rename: None,
+            category_status: None,
+            raw_config_text: String::new(),
+            raw_config_status: None,
+            backup_status: None,
+            toast: None,
+            toast_undo: None,
+            new_profile_name: None,
+            startup_error: None,
+            test_countdown: None,
+            test_result: None,
+            editor_sandbox: None,
+            minimize_requested: false,
+            dry_run_log,
+            expansion_stats,
+            hook_enabled: true,
+            log_level_filter: LevelFilter::Info,
+            focus_search: false,
+            save_requested: false,
+            text_input_focused: false,
+            show_shortcut_help: false,
         }
     }
     
@@ -64,8 +213,95 @@ impl AppUiState {
     }
     
     /// テーマを切り替える
+    /// 
+    /// 切り替えた後はWindowsのテーマに合わせず、選んだテーマを保存する
     pub fn toggle_theme(&mut self) {
         self.theme.toggle();
+        let preference = match self.theme {
+            ThemeMode::Light => ThemePreference::Light,
+            ThemeMode::Dark => ThemePreference::Dark,
+        };
+        self.set_theme_preference(preference);
+    }
+    
+    /// テーマの設定を変更して保存する
+    pub fn set_theme_preference(&mut self, preference: ThemePreference) {
+        if let Ok(mut settings) = self.settings.lock() {
+            settings.theme = preference;
+            
+            // 設定のロックを解放して保存
+            drop(settings);
+            
+            if let Ok(mut config_manager) = self.config_manager.lock() {
+                if let Ok(settings) = self.settings.lock() {
+                    let _ = config_manager.update_settings(settings.clone());
+                }
+            }
+        }
+        
+        // Windowsのテーマに合わせる場合は、次の描画ですぐに確認する
+        self.system_theme_checked_at = None;
+    }
+    
+    /// 設定に従ってテーマモードを更新する
+    /// 
+    /// Windowsのテーマに合わせる場合は、テーマの変更に追従するため一定間隔で確認する
+    fn refresh_theme(&mut self, ctx: &egui::Context) {
+        let preference = self.settings.lock().map(|settings| settings.theme).unwrap_or_default();
+        if preference != ThemePreference::System {
+            self.theme = ThemeMode::from_preference(preference);
+            return;
+        }
+        
+        let due = self.system_theme_checked_at
+            .is_none_or(|checked_at| checked_at.elapsed() >= SYSTEM_THEME_CHECK_INTERVAL);
+        if due {
+            self.theme = ThemeMode::from_system();
+            self.system_theme_checked_at = Some(Instant::now());
+        }
+        ctx.request_repaint_after(SYSTEM_THEME_CHECK_INTERVAL);
+    }
+    
+    /// 展開の有効と無効を切り替える
+    /// 
+    /// トレイ、設定タブ、ホットキーのどこから切り替えた場合もこのメソッドを通す。
+    /// 無効にしたときは入力途中のキーワードが残らないようにバッファをクリアし、
+    /// 有効にしたときは押されたままの修飾キーが最初の展開に影響しないように解放する
+    /// 
+    /// # 引数
+    /// * `enabled` - 展開を有効にするかどうか
+    pub fn set_enabled(&mut self, enabled: bool) {
+        match self.settings.lock() {
+            Ok(mut settings) if settings.enabled != enabled => settings.enabled = enabled,
+            _ => return,
+        }
+        log::info!("Expansion {}", if enabled { "enabled" } else { "disabled" });
+        
+        if enabled {
+            if let Ok(engine) = self.replacement_engine.lock() {
+                engine.reset_modifier_keys();
+            }
+        } else if let Ok(mut state) = self.keyboard_state.lock() {
+            state.clear_buffer();
+        }
+    }
+    
+    /// 指定したスニペットを下書きとしてエディタを開く
+    pub fn start_new_snippet(&mut self, snippet: Snippet) {
+        self.selected_snippet_index = None;
+        self.editor_draft = Some(snippet);
+        self.editor_status = None;
+        self.editor_sandbox = None;
+        self.switch_tab(Tab::Editor);
+    }
+    
+    /// 既存のスニペットを下書きとしてエディタを開く
+    pub fn start_editing_snippet(&mut self, index: usize, snippet: Snippet) {
+        self.selected_snippet_index = Some(index);
+        self.editor_draft = Some(snippet);
+        self.editor_status = None;
+        self.editor_sandbox = None;
+        self.switch_tab(Tab::Editor);
     }
 }
 
@@ -81,18 +317,430 @@ impl AppUi {
     }
     
     /// 設定へのアクセスを提供する
-    #[allow(dead_code)]
     pub fn settings(&self) -> &Arc<Mutex<Settings>> {
         &self.state.settings
     }
     
+    /// 展開の有効と無効を切り替える
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.state.set_enabled(enabled);
+    }
+    
+    /// ウィンドウの最小化が要求されたかどうか（要求は取り出すと消える）
+    pub fn take_minimize_request(&mut self) -> bool {
+        std::mem::take(&mut self.state.minimize_requested)
+    }
+    
+    /// 画面の右下に短いメッセージを表示する
+    pub fn show_toast(&mut self, message: impl Into<String>) {
+        self.state.toast = Some((message.into(), Instant::now()));
+        self.state.toast_undo = None;
+    }
+    
+    /// 指定したテキストを内容とする新しいスニペットをエディタで開く
+    pub fn open_editor_with_text(&mut self, text: &str) {
+        self.state.start_new_snippet(Snippet::from_selection(text));
+    }
+    
+    /// 設定を変更して設定ファイルに保存する
+    /// 
+    /// # 引数
+    /// * `change` - 設定を変更する関数
+    fn update_setting<F>(&self, change: F)
+    where
+        F: FnOnce(&mut Settings),
+    {
+        if let Ok(mut settings) = self.state.settings.lock() {
+            change(&mut settings);
+            
+            // 設定のロックを解放して保存
+            drop(settings);
+            
+            if let Ok(mut config_manager) = self.state.config_manager.lock() {
+                if let Ok(settings) = self.state.settings.lock() {
+                    let _ = config_manager.update_settings(settings.clone());
+                }
+            }
+        }
+    }
+    
+    /// 設定ファイルから読み込んだ設定をアプリケーションの設定に反映する
+    /// 
+    /// 一時停止は設定ファイルに保存しないため、現在の状態を引き継ぐ。
+    /// 自動起動の設定が変わった場合は、自動起動も設定し直す
+    /// 
+    /// # 引数
+    /// * `new_settings` - 読み込んだ設定
+    pub fn apply_loaded_settings(&self, new_settings: Settings) {
+        let startup_changed = if let Ok(mut settings) = self.state.settings.lock() {
+            let startup_changed = settings.start_with_system != new_settings.start_with_system
+                || settings.auto_start_method != new_settings.auto_start_method;
+            let paused_until = settings.paused_until;
+            *settings = new_settings.clone();
+            settings.paused_until = paused_until;
+            startup_changed
+        } else {
+            false
+        };
+        
+        // 自動起動の設定を変更した場合は、自動起動を設定
+        if startup_changed {
+            let _ = utils::set_auto_startup(new_settings.start_with_system, new_settings.auto_start_method);
+        }
+    }
+    
+    /// 設定マネージャーでスニペットやカテゴリを操作し、結果をアプリケーションの設定に反映する
+    /// 
+    /// 操作の前に、UIやトレイで変更された最新の設定を設定マネージャーに反映する
+    fn modify_config<T, F>(&self, operation: F) -> Result<T, String>
+    where
+        F: FnOnce(&mut ConfigManager) -> Result<T, Box<dyn std::error::Error>>,
+    {
+        let mut config_manager = self.state.config_manager.lock()
+            .map_err(|_| "Failed to lock config manager".to_string())?;
+        
+        if let Ok(settings) = self.state.settings.lock() {
+            config_manager.set_settings(settings.clone());
+        }
+        
+        let result = operation(&mut config_manager).map_err(|e| e.to_string())?;
+        
+        if let Ok(mut settings) = self.state.settings.lock() {
+            *settings = config_manager.get_settings().clone();
+        }
+        
+        Ok(result)
+    }
+    
+    /// 指定したカテゴリの空のスニペットを作成する（カテゴリのデフォルトの種類を使う）
+    fn empty_snippet(&self, category: &str) -> Snippet {
+        let snippet_type = self.state.settings.lock().ok()
+            .and_then(|settings| settings.default_snippet_type(category))
+            .unwrap_or(SnippetType::Static);
+        
+        Snippet::new(
+            String::new(),
+            String::new(),
+            String::new(),
+            snippet_type,
+            category.to_string(),
+        )
+    }
+    
     /// UIを更新する
     pub fn update(&mut self, ctx: &egui::Context) {
-        super::setup_context(ctx, self.state.theme);
+        let ui_scale = self.state.settings.lock().map(|s| s.ui_scale).unwrap_or(1.0);
+        let pixels_per_point = super::compute_pixels_per_point(self.state.system_scale, ui_scale);
+        self.state.refresh_theme(ctx);
+        super::setup_context(ctx, self.state.theme, pixels_per_point);
+        
+        self.process_shortcuts(ctx);
         
         self.render_top_panel(ctx);
         self.render_central_panel(ctx);
         self.render_bottom_panel(ctx);
+        self.render_toast(ctx);
+        self.render_keyword_conflict(ctx);
+        self.render_shortcut_help(ctx);
+        self.process_test_expansion(ctx);
+        
+        self.state.text_input_focused = ctx.wants_keyboard_input();
+    }
+    
+    /// アプリ内のキーボードショートカットを処理する
+    /// 
+    /// 入力欄の描画より先に処理し、ショートカットのキーが入力欄に渡らないようにする
+    fn process_shortcuts(&mut self, ctx: &egui::Context) {
+        let ctrl = |key| egui::KeyboardShortcut::new(egui::Modifiers::CTRL, key);
+        
+        if ctx.input_mut(|i| i.consume_shortcut(&ctrl(egui::Key::N))) {
+            let snippet = self.empty_snippet(DEFAULT_CATEGORY);
+            self.state.start_new_snippet(snippet);
+        }
+        
+        if ctx.input_mut(|i| i.consume_shortcut(&ctrl(egui::Key::S))) && self.state.selected_tab == Tab::Editor {
+            self.state.save_requested = true;
+        }
+        
+        if ctx.input_mut(|i| i.consume_shortcut(&ctrl(egui::Key::F))) {
+            self.state.switch_tab(Tab::Snippets);
+            self.state.focus_search = true;
+        }
+        
+        // 入力欄でEscを押した場合はフォーカスが外れるだけにする（EGUIはフレームの開始時にフォーカスを外す）
+        // 確認ウィンドウやヘルプの表示中は、先にそちらを閉じる
+        let dialog_open = self.state.pending_delete.is_some() || self.state.keyword_conflict.is_some();
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) && !self.state.text_input_focused && !dialog_open {
+            if self.state.show_shortcut_help {
+                self.state.show_shortcut_help = false;
+            } else if self.state.selected_tab != Tab::Snippets {
+                self.state.switch_tab(Tab::Snippets);
+            }
+        }
+    }
+    
+    /// 外部のツールからの要求を待ち受ける設定を描画する
+    #[cfg(feature = "ipc")]
+    fn render_ipc_settings(&mut self, ui: &mut Ui) {
+        let Some((mut enable_ipc, mut ipc_port)) = self.state.settings.lock().ok()
+            .map(|settings| (settings.enable_ipc, settings.ipc_port))
+        else {
+            return;
+        };
+        
+        let mut ipc_changed = false;
+        ui.horizontal(|ui| {
+            ipc_changed |= ui.checkbox(&mut enable_ipc, "Allow other tools to expand snippets")
+                .on_hover_text(format!(
+                    "POST a keyword to http://127.0.0.1:<port>{} with the token from the '{}' file next to the settings file as a Bearer token",
+                    replacement::ipc::IPC_EXPAND_PATH,
+                    replacement::ipc::IPC_TOKEN_FILE,
+                ))
+                .changed();
+            ui.label("Port:");
+            ipc_changed |= ui.add_enabled(enable_ipc, egui::DragValue::new(&mut ipc_port).clamp_range(1024..=u16::MAX))
+                .changed();
+        });
+        
+        if ipc_changed {
+            self.update_setting(|settings| {
+                settings.enable_ipc = enable_ipc;
+                settings.ipc_port = ipc_port;
+            });
+        }
+    }
+    
+    /// キーボードショートカットのヘルプを描画する
+    fn render_shortcut_help(&mut self, ctx: &egui::Context) {
+        let mut open = self.state.show_shortcut_help;
+        egui::Window::new("Keyboard shortcuts")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::RIGHT_TOP, [-12.0, 40.0])
+            .show(ctx, |ui| {
+                egui::Grid::new("shortcut_help").num_columns(2).spacing([20.0, 4.0]).show(ui, |ui| {
+                    for (keys, description) in SHORTCUT_HELP {
+                        ui.strong(*keys);
+                        ui.label(*description);
+                        ui.end_row();
+                    }
+                });
+            });
+        self.state.show_shortcut_help &= open;
+    }
+    
+    /// 保存したスニペットとキーワードが重複している有効なスニペットを記録する（重複がなければ何もしない）
+    fn check_keyword_conflicts(&mut self, saved_id: u64) {
+        let conflicting = self.state.settings.lock().ok().and_then(|settings| {
+            let saved_index = settings.snippets.iter().position(|s| s.id == saved_id)?;
+            let others: Vec<(u64, String)> = settings.find_keyword_conflicts().into_iter()
+                .filter_map(|(first, second)| match saved_index {
+                    index if index == first => Some(second),
+                    index if index == second => Some(first),
+                    _ => None,
+                })
+                .map(|index| (settings.snippets[index].id, settings.snippets[index].name.clone()))
+                .collect();
+            (!others.is_empty()).then_some(others)
+        });
+        
+        if let Some(others) = conflicting {
+            log::warn!("Snippet {} shares its keyword with {} other snippets", saved_id, others.len());
+            self.state.keyword_conflict = Some((saved_id, others));
+        }
+    }
+    
+    /// キーワードの重複の警告を描画する
+    /// 
+    /// 重複しているスニペットを無効にするか、保存したスニペットのキーワードを変更するかを選べる
+    fn render_keyword_conflict(&mut self, ctx: &egui::Context) {
+        let Some((saved_id, others)) = self.state.keyword_conflict.clone() else {
+            return;
+        };
+        let saved = self.state.settings.lock().ok().and_then(|settings| {
+            settings.snippets.iter()
+                .position(|s| s.id == saved_id)
+                .map(|index| (index, settings.snippets[index].clone()))
+        });
+        let Some((saved_index, saved)) = saved else {
+            self.state.keyword_conflict = None;
+            return;
+        };
+        
+        let mut disable_id = None;
+        let mut rename = false;
+        let mut keep_both = false;
+        egui::Window::new("Duplicate keyword")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("The keyword '{}' of '{}' is also used by:", saved.keyword, saved.name));
+                for (id, name) in &others {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("• {}", name));
+                        if ui.small_button("Disable").clicked() {
+                            disable_id = Some(*id);
+                        }
+                    });
+                }
+                ui.label("Only the snippet that comes first in the list expands.");
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    rename = ui.button(format!("Rename '{}'", saved.name)).clicked();
+                    keep_both = ui.button("Keep both").clicked();
+                });
+            });
+        
+        if let Some(id) = disable_id {
+            match self.modify_config(|config_manager| config_manager.set_snippets_enabled(&[(id, false)])) {
+                Ok(_) => {
+                    let remaining: Vec<(u64, String)> = others.into_iter().filter(|(other, _)| *other != id).collect();
+                    self.state.keyword_conflict = (!remaining.is_empty()).then_some((saved_id, remaining));
+                }
+                Err(e) => {
+                    self.state.keyword_conflict = None;
+                    self.show_toast(format!("Failed to disable snippet: {}", e));
+                }
+            }
+        }
+        if rename {
+            self.state.keyword_conflict = None;
+            self.state.start_editing_snippet(saved_index, saved);
+        } else if keep_both {
+            self.state.keyword_conflict = None;
+        }
+    }
+    
+    /// 展開を試すカウントダウンを描画し、終わったら入力を開始して結果を受け取る
+    fn process_test_expansion(&mut self, ctx: &egui::Context) {
+        if let Some((snippet, started_at)) = &self.state.test_countdown {
+            let elapsed = started_at.elapsed();
+            if elapsed < TEST_EXPANSION_COUNTDOWN {
+                let remaining = (TEST_EXPANSION_COUNTDOWN - elapsed).as_secs() + 1;
+                let mut cancelled = false;
+                egui::Area::new("test_expansion_countdown")
+                    .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                    .order(egui::Order::Foreground)
+                    .show(ctx, |ui| {
+                        egui::Frame::popup(ui.style()).show(ui, |ui| {
+                            ui.heading(format!("Testing '{}' in {}...", snippet.name, remaining));
+                            ui.label("SwiftType will minimize and type into the window you used before it");
+                            cancelled = ui.button("Cancel").clicked();
+                        });
+                    });
+                
+                if cancelled {
+                    self.state.test_countdown = None;
+                } else {
+                    ctx.request_repaint_after(Duration::from_millis(100));
+                }
+                return;
+            }
+            
+            // 最小化して直前のウィンドウにフォーカスを戻し、キーボードフックを止めないようワーカースレッドで入力する
+            let Some((snippet, _)) = self.state.test_countdown.take() else {
+                return;
+            };
+            let (sender, receiver) = crossbeam_channel::bounded(1);
+            let queued = self.state.replacement_engine.lock()
+                .is_ok_and(|engine| engine.queue_test_expansion(snippet, sender));
+            if !queued {
+                self.state.editor_status = Some(Err("Test expansion could not start while another expansion is running".to_string()));
+                return;
+            }
+            
+            self.state.test_result = Some(receiver);
+            self.state.minimize_requested = true;
+            return;
+        }
+        
+        let Some(receiver) = &self.state.test_result else {
+            return;
+        };
+        let Ok(result) = receiver.try_recv() else {
+            // 入力が終わるまで結果を確認し続ける
+            ctx.request_repaint_after(Duration::from_millis(100));
+            return;
+        };
+        self.state.test_result = None;
+        self.state.editor_status = Some(match result {
+            Some(InsertMethod::Typed) => Ok("Test expansion typed the content directly".to_string()),
+            Some(InsertMethod::Pasted) => Ok("Test expansion fell back to pasting via the clipboard".to_string()),
+            None => Err("Test expansion failed".to_string()),
+        });
+    }
+    
+    /// トーストを描画する（表示時間を過ぎたら消す）
+    fn render_toast(&mut self, ctx: &egui::Context) {
+        let Some((message, shown_at)) = &self.state.toast else {
+            return;
+        };
+        
+        let elapsed = shown_at.elapsed();
+        if elapsed >= TOAST_DURATION {
+            self.state.toast = None;
+            self.state.toast_undo = None;
+            return;
+        }
+        
+        let can_undo = self.state.toast_undo.is_some();
+        let mut undo_clicked = false;
+        egui::Area::new("toast")
+            .anchor(egui::Align2::RIGHT_BOTTOM, [-12.0, -40.0])
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(message.as_str());
+                        if can_undo {
+                            undo_clicked = ui.button("Undo").clicked();
+                        }
+                    });
+                });
+            });
+        ctx.request_repaint_after(TOAST_DURATION - elapsed);
+        
+        if undo_clicked {
+            self.undo_toast();
+        }
+    }
+    
+    /// トーストの [Undo] で、まとめて変更したスニペットの有効/無効を元に戻す
+    fn undo_toast(&mut self) {
+        let Some(previous) = self.state.toast_undo.take() else {
+            return;
+        };
+        match self.modify_config(|config_manager| config_manager.set_snippets_enabled(&previous)) {
+            Ok(_) => self.show_toast("Restored the previous state"),
+            Err(e) => self.show_toast(format!("Failed to undo: {}", e)),
+        }
+    }
+    
+    /// 絞り込み中のカテゴリのスニペットをまとめて有効/無効にし、元に戻せるトーストを表示する
+    /// 
+    /// # 引数
+    /// * `snippets` - このフレームで表示しているスニペット
+    /// * `enabled` - 変更後の有効/無効
+    fn set_category_enabled(&mut self, snippets: &[Snippet], enabled: bool) {
+        let category_filter = self.state.category_filter.clone();
+        let states: Vec<(u64, bool)> = snippets.iter()
+            .filter(|s| category_filter.as_ref().is_none_or(|category| &s.category == category))
+            .map(|s| (s.id, enabled))
+            .collect();
+        
+        match self.modify_config(|config_manager| config_manager.set_snippets_enabled(&states)) {
+            Ok(previous) if previous.is_empty() => {}
+            Ok(previous) => {
+                let action = if enabled { "Enabled" } else { "Disabled" };
+                self.show_toast(format!("{} {} snippets", action, previous.len()));
+                self.state.toast_undo = Some(previous);
+            }
+            Err(e) => {
+                self.state.snippets_status = Some(Err(format!("Failed to update snippets: {}", e)));
+            }
+        }
     }
     
     /// 上部パネルを描画する
@@ -106,10 +754,24 @@ impl AppUi {
                     self.state.switch_tab(Tab::Snippets);
                 }
                 
+                if ui.selectable_label(self.state.selected_tab == Tab::Categories, "Categories").clicked() {
+                    self.state.category_rename = None;
+                    self.state.category_status = None;
+                    self.state.switch_tab(Tab::Categories);
+                }
+                
                 if ui.selectable_label(self.state.selected_tab == Tab::Settings, "Settings").clicked() {
                     self.state.switch_tab(Tab::Settings);
                 }
                 
+                if ui.selectable_label(self.state.selected_tab == Tab::Log, "Log").clicked() {
+                    self.state.switch_tab(Tab::Log);
+                }
+                
+                if ui.selectable_label(self.state.selected_tab == Tab::Stats, "Stats").clicked() {
+                    self.state.switch_tab(Tab::Stats);
+                }
+                
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                     let theme_label = match self.state.theme {
                         ThemeMode::Light => "🌙 Dark",
@@ -119,11 +781,82 @@ impl AppUi {
                     if ui.button(theme_label).clicked() {
                         self.state.toggle_theme();
                     }
+                    
+                    if ui.button("⌨").on_hover_text("Keyboard shortcuts").clicked() {
+                        self.state.show_shortcut_help = !self.state.show_shortcut_help;
+                    }
+                    
+                    self.render_profile_selector(ui);
                 });
             });
         });
     }
     
+    /// プロファイルの選択欄を描画する（右から左に並べるレイアウトの中で呼ぶ）
+    fn render_profile_selector(&mut self, ui: &mut Ui) {
+        // 新しいプロファイルの名前を入力中
+        if let Some(mut name) = self.state.new_profile_name.take() {
+            let cancelled = ui.button("Cancel").clicked();
+            let created = ui.add_enabled(!name.trim().is_empty(), egui::Button::new("Create")).clicked();
+            let response = ui.add(egui::TextEdit::singleline(&mut name).hint_text("Profile name").desired_width(120.0));
+            if name.is_empty() && !response.has_focus() {
+                response.request_focus();
+            }
+            
+            if created || (response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) && !name.trim().is_empty()) {
+                self.switch_profile(&name);
+            } else if !cancelled && !ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                self.state.new_profile_name = Some(name);
+            }
+            return;
+        }
+        
+        let Some((active_profile, profile_names)) = self.state.settings.lock().ok()
+            .map(|settings| (settings.active_profile.clone(), settings.profile_names()))
+        else {
+            return;
+        };
+        
+        let mut selected_profile = active_profile.clone();
+        egui::ComboBox::from_id_source("profile_selector")
+            .selected_text(&selected_profile)
+            .show_ui(ui, |ui| {
+                for name in &profile_names {
+                    ui.selectable_value(&mut selected_profile, name.clone(), name);
+                }
+                ui.separator();
+                if ui.selectable_label(false, "New profile...").clicked() {
+                    self.state.new_profile_name = Some(String::new());
+                }
+            })
+            .response
+            .on_hover_text("Only the snippets of the selected profile are expanded");
+        ui.label("Profile:");
+        
+        if selected_profile != active_profile {
+            self.switch_profile(&selected_profile);
+        }
+    }
+    
+    /// プロファイルを切り替える（存在しないプロファイルの場合は作成する）
+    fn switch_profile(&mut self, name: &str) {
+        match self.modify_config(|config_manager| config_manager.switch_profile(name)) {
+            Ok(()) => {
+                // 別のプロファイルのスニペットを指さないよう、選択や編集中の内容を破棄する
+                self.state.selected_snippet_index = None;
+                self.state.editor_draft = None;
+                self.state.editor_status = None;
+                self.state.pending_delete = None;
+                self.state.category_filter = None;
+                if self.state.selected_tab == Tab::Editor {
+                    self.state.switch_tab(Tab::Snippets);
+                }
+                self.show_toast(format!("Switched to profile '{}'", name.trim()));
+            }
+            Err(e) => self.show_toast(format!("Failed to switch profile: {}", e)),
+        }
+    }
+    
     /// 中央パネルを描画する
     fn render_central_panel(&mut self, ctx: &egui::Context) {
         CentralPanel::default().show(ctx, |ui| {
@@ -131,6 +864,10 @@ impl AppUi {
                 Tab::Snippets => self.render_snippets_tab(ui),
                 Tab::Settings => self.render_settings_tab(ui),
                 Tab::Editor => self.render_editor_tab(ui),
+                Tab::RawConfig => self.render_raw_config_tab(ui),
+                Tab::Categories => self.render_categories_tab(ui),
+                Tab::Log => self.render_log_tab(ui),
+                Tab::Stats => self.render_stats_tab(ui),
             }
         });
     }
@@ -141,12 +878,110 @@ impl AppUi {
             ui.heading("Snippets");
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                 if ui.button("Add Snippet").clicked() {
-                    self.state.selected_snippet_index = None;
-                    self.state.switch_tab(Tab::Editor);
+                    let snippet = self.empty_snippet(DEFAULT_CATEGORY);
+                    self.state.start_new_snippet(snippet);
+                }
+                
+                if ui.button("Add Symbols")
+                    .on_hover_text("Adds snippets for symbols and emoji, such as :shrug: and :thumbsup:")
+                    .clicked()
+                {
+                    self.add_starter_symbols();
+                }
+                
+                if ui.button("Import AHK").clicked() {
+                    self.import_ahk();
+                }
+                
+                if ui.button("Import CSV").clicked() {
+                    self.import_csv();
+                }
+                
+                if ui.button("Import").clicked() {
+                    self.import_snippets();
+                }
+                
+                if ui.button("Export").clicked() {
+                    self.export_snippets();
                 }
             });
         });
         
+        match &self.state.snippets_status {
+            Some(Ok(message)) => {
+                ui.colored_label(egui::Color32::from_rgb(80, 180, 80), message);
+            }
+            Some(Err(error)) => {
+                ui.colored_label(egui::Color32::from_rgb(220, 80, 80), error);
+            }
+            None => {}
+        }
+        
+        // スニペットが属するカテゴリの一覧（名前順）
+        let categories: Vec<String> = self.state.settings.lock().ok()
+            .map(|settings| {
+                settings.snippets.iter()
+                    .map(|s| s.category.clone())
+                    .collect::<BTreeSet<_>>()
+                    .into_iter()
+                    .collect()
+            })
+            .unwrap_or_default();
+        
+        // 選択中のカテゴリが名前の変更や削除でなくなった場合は、すべて表示に戻す
+        if self.state.category_filter.as_ref().is_some_and(|category| !categories.contains(category)) {
+            self.state.category_filter = None;
+        }
+        
+        ui.horizontal(|ui| {
+            ui.label("Search:");
+            let search = ui.text_edit_singleline(&mut self.state.search_query)
+                .on_hover_text("Filters by name, keyword, category or description (Ctrl+F)");
+            if std::mem::take(&mut self.state.focus_search) {
+                search.request_focus();
+            }
+            if !self.state.search_query.is_empty() && ui.button("Clear").clicked() {
+                self.state.search_query.clear();
+            }
+            
+            ui.label("Category:");
+            egui::ComboBox::from_id_source("snippet_category_filter")
+                .selected_text(self.state.category_filter.as_deref().unwrap_or("All"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.state.category_filter, None, "All");
+                    for category in &categories {
+                        ui.selectable_value(&mut self.state.category_filter, Some(category.clone()), category);
+                    }
+                });
+            
+            ui.checkbox(&mut self.state.sort_by_usage, "Most used first");
+        });
+        
+        // 絞り込み中のカテゴリ（すべて表示の場合はすべて）のスニペットをまとめて有効/無効にする
+        let mut bulk_enabled = None;
+        ui.horizontal(|ui| {
+            let scope = match &self.state.category_filter {
+                Some(category) => format!(" in '{}'", category),
+                None => String::new(),
+            };
+            if ui.button(format!("Enable all{}", scope)).clicked() {
+                bulk_enabled = Some(true);
+            }
+            if ui.button(format!("Disable all{}", scope)).clicked() {
+                bulk_enabled = Some(false);
+            }
+            
+            ui.separator();
+            
+            // カテゴリの見出しをまとめて開く/折りたたむ
+            if ui.button("Expand all").clicked() {
+                self.state.collapsed_categories.clear();
+            }
+            if ui.button("Collapse all").clicked() {
+                self.state.collapsed_categories = categories.iter().cloned().collect();
+            }
+        });
+        
         ui.add_space(10.0);
         
         ScrollArea::vertical().show(ui, |ui| {
@@ -159,129 +994,850 @@ impl AppUi {
                 }
             };
             
-            // 更新するスニペットを格納する
-            let mut updated_snippets = snippets.clone();
-            let mut is_updated = false;
+            // 有効/無効を切り替えたスニペットを格納する
+            let mut toggled_snippet = None;
+            // 複製するスニペットのID
+            let mut duplicate_id = None;
             
-            // UI表示処理
-            for (index, snippet) in snippets.iter().enumerate() {
-                ui.horizontal(|ui| {
-                    if ui.checkbox(
-                        &mut updated_snippets[index].enabled,
-                        &snippet.name,
-                    ).changed() {
-                        is_updated = true;
-                    }
-                    
-                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        if ui.button("Edit").clicked() {
-                            let tab_switch = {
-                                self.state.selected_snippet_index = Some(index);
-                                true
-                            };
-                            if tab_switch {
-                                self.state.switch_tab(Tab::Editor);
-                            }
+            // UI表示処理（Editボタンが正しいスニペットを開くよう、元の位置を保持したまま絞り込む）
+            let query = self.state.search_query.clone();
+            let category_filter = self.state.category_filter.clone();
+            let mut visible: Vec<(usize, &Snippet)> = snippets.iter().enumerate()
+                .filter(|(_, s)| {
+                    s.matches_query(&query)
+                        && category_filter.as_ref().is_none_or(|category| &s.category == category)
+                })
+                .collect();
+            if self.state.sort_by_usage {
+                visible.sort_by_key(|(_, s)| std::cmp::Reverse(s.usage_count));
+            }
+            
+            // カテゴリごとにまとめる（カテゴリは名前順、カテゴリ内は上で並べた順）
+            let mut groups: BTreeMap<&str, Vec<(usize, &Snippet)>> = BTreeMap::new();
+            for (index, snippet) in visible {
+                groups.entry(snippet.category.as_str()).or_default().push((index, snippet));
+            }
+            
+            for (category, group) in groups {
+                // 開閉の状態はAppUiStateで保持し、見出しのクリックで切り替える
+                let collapsed = self.state.collapsed_categories.contains(category);
+                let enabled_count = group.iter().filter(|(_, s)| s.enabled).count();
+                let header = egui::CollapsingHeader::new(format!("{} ({}/{} enabled)", category, enabled_count, group.len()))
+                    .id_source(("snippet_category", category))
+                    .open(Some(!collapsed))
+                    .show(ui, |ui| {
+                        for (index, snippet) in group {
+                            ui.horizontal(|ui| {
+                                let mut enabled = snippet.enabled;
+                                let mut response = ui.checkbox(&mut enabled, &snippet.name);
+                                if !snippet.description.trim().is_empty() {
+                                    response = response.on_hover_text(&snippet.description);
+                                }
+                                if response.changed() {
+                                    let mut updated = snippet.clone();
+                                    updated.enabled = enabled;
+                                    toggled_snippet = Some(updated);
+                                }
+                                
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                    if ui.button("Delete").clicked() {
+                                        self.state.pending_delete = Some(index);
+                                    }
+                                    
+                                    if ui.button("Duplicate").clicked() {
+                                        duplicate_id = Some(snippet.id);
+                                    }
+                                    
+                                    if ui.button("Edit").clicked() {
+                                        self.state.start_editing_snippet(index, snippet.clone());
+                                    }
+                                });
+                            });
+                            
+                            ui.horizontal(|ui| {
+                                ui.label(format!("Keyword: {}", snippet.keyword));
+                                ui.label(format!("Used: {}", snippet.usage_count));
+                            });
+                            
+                            ui.separator();
                         }
                     });
-                });
-                
-                ui.horizontal(|ui| {
-                    ui.label(format!("Keyword: {}", snippet.keyword));
-                    ui.label(format!("Category: {}", snippet.category));
-                });
-                
-                ui.separator();
+                if header.header_response.clicked() {
+                    if collapsed {
+                        self.state.collapsed_categories.remove(category);
+                    } else {
+                        self.state.collapsed_categories.insert(category.to_string());
+                    }
+                }
             }
             
-            // 設定を更新
-            if is_updated {
-                // 設定のロックを取得して更新
-                if let Ok(mut settings) = self.state.settings.lock() {
-                    settings.snippets = updated_snippets;
-                    
-                    // 設定ファイルに保存
-                    // 別のロックを取得するため、一時的にdroptさせる
-                    drop(settings);
-                    
-                    if let Ok(mut config_manager) = self.state.config_manager.lock() {
-                        // 再度設定を取得
-                        if let Ok(settings) = self.state.settings.lock() {
-                            let _ = config_manager.update_settings(settings.clone());
+            // 設定を更新（まとめて変更した場合は、同じフレームの個別の切り替えで上書きしないよう1回だけ保存する）
+            if let Some(enabled) = bulk_enabled {
+                self.set_category_enabled(&snippets, enabled);
+            } else if let Some(snippet) = toggled_snippet {
+                let id = snippet.id;
+                if let Err(e) = self.modify_config(|config_manager| config_manager.update_snippet(id, snippet)) {
+                    log::error!("Failed to update snippet {}: {}", id, e);
+                }
+            }
+            
+            // 複製したスニペットをエディタで開く
+            if let Some(id) = duplicate_id {
+                match self.modify_config(|config_manager| config_manager.duplicate_snippet(id)) {
+                    Ok(new_id) => {
+                        let copy = self.state.settings.lock().ok().and_then(|settings| {
+                            settings.snippets.iter()
+                                .position(|s| s.id == new_id)
+                                .map(|index| (index, settings.snippets[index].clone()))
+                        });
+                        if let Some((index, snippet)) = copy {
+                            self.state.start_editing_snippet(index, snippet);
                         }
                     }
+                    Err(e) => {
+                        self.state.snippets_status = Some(Err(format!("Failed to duplicate snippet: {}", e)));
+                    }
                 }
             }
         });
+        
+        self.render_delete_confirmation(ui.ctx());
     }
     
-    /// 設定タブを描画する
-    fn render_settings_tab(&mut self, ui: &mut Ui) {
-        ui.heading("Settings");
-        ui.add_space(10.0);
+    /// スニペットの削除の確認ウィンドウを描画する
+    fn render_delete_confirmation(&mut self, ctx: &egui::Context) {
+        let Some(index) = self.state.pending_delete else {
+            return;
+        };
+        let snippet = self.state.settings.lock().ok()
+            .and_then(|settings| settings.snippets.get(index).cloned());
+        let Some(snippet) = snippet else {
+            self.state.pending_delete = None;
+            return;
+        };
         
-        // 元の設定値を取得
-        let (mut enabled, mut start_with_system) = {
-            if let Ok(settings) = self.state.settings.lock() {
-                (settings.enabled, settings.start_with_system)
+        let mut confirmed = false;
+        let mut cancelled = false;
+        egui::Window::new("Delete snippet")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("Delete snippet '{}'?", snippet.name));
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    cancelled = ui.button("Cancel").clicked();
+                    confirmed = ui.button("Delete").clicked();
+                });
+            });
+        
+        if cancelled {
+            self.state.pending_delete = None;
+        }
+        if confirmed {
+            self.state.pending_delete = None;
+            let id = snippet.id;
+            match self.modify_config(|config_manager| config_manager.remove_snippet(id)) {
+                Ok(removed) => {
+                    // 削除した位置以降を指すインデックスは別のスニペットを指してしまうため、選択を解除する
+                    if self.state.selected_snippet_index.is_some_and(|selected| selected >= index) {
+                        self.state.selected_snippet_index = None;
+                        self.state.editor_draft = None;
+                    }
+                    self.state.snippets_status = Some(Ok(format!("Deleted '{}'", removed.name)));
+                }
+                Err(e) => {
+                    self.state.snippets_status = Some(Err(format!("Failed to delete '{}': {}", snippet.name, e)));
+                }
+            }
+        }
+    }
+    
+    /// 記号の初期セットをスニペットとして追加する
+    fn add_starter_symbols(&mut self) {
+        let result = self.modify_config(|config_manager| config_manager.add_starter_symbols());
+        self.state.snippets_status = Some(result.map(|summary| {
+            format!(
+                "Added {} symbol snippets ({} already existed)",
+                summary.imported, summary.skipped_duplicates,
+            )
+        }));
+    }
+    
+    /// AutoHotkeyのスクリプトを選択し、ホットストリングをスニペットとして追加する
+    fn import_ahk(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("AutoHotkey script", &["ahk"])
+            .pick_file()
+        else {
+            return;
+        };
+        
+        let result = self.modify_config(|config_manager| config_manager.import_ahk(&path));
+        self.state.snippets_status = Some(result.map(|summary| {
+            format!(
+                "Imported {} snippets ({} duplicate keywords and {} invalid keywords skipped)",
+                summary.imported, summary.skipped_duplicates, summary.skipped_invalid,
+            )
+        }));
+    }
+    
+    /// CSVファイルを選択し、スニペットを追加する
+    fn import_csv(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .pick_file()
+        else {
+            return;
+        };
+        
+        let result = self.modify_config(|config_manager| config_manager.import_csv(&path));
+        self.state.snippets_status = Some(result.map(|summary| {
+            format!(
+                "Imported {} snippets ({} duplicate keywords and {} empty or invalid keywords skipped)",
+                summary.imported, summary.skipped_duplicates, summary.skipped_invalid,
+            )
+        }));
+    }
+    
+    /// 保存先を選択し、すべてのスニペットをJSONファイルに書き出す
+    fn export_snippets(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .set_file_name("snippets.json")
+            .save_file()
+        else {
+            return;
+        };
+        
+        let result = self.modify_config(|config_manager| config_manager.export_snippets(&path));
+        self.state.snippets_status = Some(result.map(|()| format!("Exported snippets to {}", path.display())));
+    }
+    
+    /// 書き出したJSONファイルを選択し、スニペットを追加する
+    fn import_snippets(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .pick_file()
+        else {
+            return;
+        };
+        
+        let result = self.modify_config(|config_manager| config_manager.import_snippets(&path));
+        self.state.snippets_status = Some(result.map(|summary| {
+            format!(
+                "Imported {} snippets ({} duplicate keywords and {} invalid keywords skipped)",
+                summary.imported, summary.skipped_duplicates, summary.skipped_invalid,
+            )
+        }));
+    }
+    
+    /// 現在の設定をバックアップする
+    fn backup_settings(&mut self) {
+        let result = self.modify_config(|config_manager| config_manager.backup());
+        self.state.backup_status = Some(result.map(|path| format!("Backed up settings to {}", path.display())));
+    }
+    
+    /// バックアップファイルを選択し、設定を復元する
+    fn restore_settings(&mut self) {
+        let config_dir = self.state.config_manager.lock().ok()
+            .and_then(|config_manager| config_manager.config_path().parent().map(|dir| dir.to_path_buf()));
+        
+        let mut dialog = rfd::FileDialog::new().add_filter("Settings backup", &["bak", "json"]);
+        if let Some(config_dir) = config_dir {
+            dialog = dialog.set_directory(config_dir);
+        }
+        let Some(path) = dialog.pick_file() else {
+            return;
+        };
+        
+        // 復元の前に、最新の設定をバックアップできるよう設定マネージャーに反映する
+        let result = match self.state.config_manager.lock() {
+            Ok(mut config_manager) => {
+                if let Ok(settings) = self.state.settings.lock() {
+                    config_manager.set_settings(settings.clone());
+                }
+                config_manager.restore(&path).map_err(|e| e.to_string())
+            }
+            Err(_) => Err("Failed to lock config manager".to_string()),
+        };
+        
+        self.state.backup_status = Some(match result {
+            Ok(restored) => {
+                self.apply_loaded_settings(restored);
+                Ok(format!("Restored settings from {}", path.display()))
+            }
+            Err(e) => Err(format!("Settings were not restored: {}", e)),
+        });
+    }
+    
+    /// ドライランで記録した展開の一覧を描画する（記録がない場合は何も表示しない）
+    fn render_dry_run_events(&self, ui: &mut Ui) {
+        let Ok(mut events) = self.state.dry_run_log.lock() else {
+            return;
+        };
+        if events.is_empty() {
+            return;
+        }
+        
+        ui.collapsing(format!("Dry-run events ({})", events.len()), |ui| {
+            ScrollArea::vertical()
+                .id_source("dry_run_events")
+                .max_height(150.0)
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for event in events.iter() {
+                        ui.label(format!(
+                            "{}  '{}' -> {}: {} backspace(s), {:?}",
+                            event.time.format("%H:%M:%S"),
+                            event.trigger,
+                            event.snippet_name,
+                            event.backspaces,
+                            event.text,
+                        ));
+                    }
+                });
+            if ui.button("Clear").clicked() {
+                events.clear();
+            }
+        });
+    }
+    
+    /// カンマ区切りのプロセス名の一覧を編集する行を描画し、変更されたら保存する
+    /// 
+    /// 入力途中のカンマが消えないよう、編集中はテキストをそのまま保持する
+    /// 
+    /// # 引数
+    /// * `id_source` - 編集中のテキストを保持するID
+    /// * `label` - 行のラベル
+    /// * `hover_text` - 入力欄のツールチップ
+    /// * `field` - 編集する設定の一覧
+    fn render_app_list_setting(
+        &mut self,
+        ui: &mut Ui,
+        id_source: &str,
+        label: &str,
+        hover_text: &str,
+        field: fn(&mut Settings) -> &mut Vec<String>,
+    ) {
+        let id = ui.make_persistent_id(id_source);
+        let mut text = ui.data_mut(|data| data.get_temp::<String>(id))
+            .or_else(|| self.state.settings.lock().ok().map(|mut settings| field(&mut settings).join(", ")))
+            .unwrap_or_default();
+        let response = ui.horizontal(|ui| {
+            ui.label(label);
+            ui.text_edit_singleline(&mut text).on_hover_text(hover_text)
+        }).inner;
+        
+        if response.changed() {
+            self.update_setting(|settings| *field(settings) = utils::parse_app_list(&text));
+        }
+        if response.has_focus() {
+            ui.data_mut(|data| data.insert_temp(id, text));
+        } else {
+            ui.data_mut(|data| data.remove::<String>(id));
+        }
+    }
+    
+    /// 1行のテキストの設定を編集する行を描画する
+    /// 
+    /// 入力途中の空白が消えないよう、編集中はテキストをそのまま保持し、
+    /// フォーカスが外れたときに前後の空白を除いて保存する値を返す
+    /// 
+    /// # 引数
+    /// * `id_source` - 編集中のテキストを保持するID
+    /// * `label` - 行のラベル
+    /// * `hover_text` - 入力欄のツールチップ
+    /// * `field` - 保存されている値を取得する関数
+    /// 
+    /// # 戻り値
+    /// 入力欄のテキストと、値が変わったままフォーカスが外れた場合は保存する値
+    fn render_text_setting(
+        &self,
+        ui: &mut Ui,
+        id_source: &str,
+        label: &str,
+        hover_text: &str,
+        field: fn(&Settings) -> String,
+    ) -> (String, Option<String>) {
+        let id = ui.make_persistent_id(id_source);
+        let saved = self.state.settings.lock().ok()
+            .map(|settings| field(&settings))
+            .unwrap_or_default();
+        let mut text = ui.data_mut(|data| data.get_temp::<String>(id))
+            .unwrap_or_else(|| saved.clone());
+        let response = ui.horizontal(|ui| {
+            ui.label(label);
+            ui.text_edit_singleline(&mut text).on_hover_text(hover_text)
+        }).inner;
+        
+        let trimmed = text.trim().to_string();
+        let save = (response.lost_focus() && trimmed != saved).then_some(trimmed);
+        if response.has_focus() {
+            ui.data_mut(|data| data.insert_temp(id, text.clone()));
+        } else {
+            ui.data_mut(|data| data.remove::<String>(id));
+        }
+        (text, save)
+    }
+    
+    /// 設定タブを描画する
+    fn render_settings_tab(&mut self, ui: &mut Ui) {
+        ui.heading("Settings");
+        ui.add_space(10.0);
+        
+        // 元の設定値を取得
+        let (mut enabled, mut start_with_system, mut auto_start_method, mut close_to_tray, mut case_sensitive, mut ignore_shortcut_keys, mut require_terminator, mut keep_terminator, mut show_overlay, mut notification, current_ui_scale) = {
+            if let Ok(settings) = self.state.settings.lock() {
+                (
+                    settings.enabled,
+                    settings.start_with_system,
+                    settings.auto_start_method,
+                    settings.close_to_tray,
+                    settings.case_sensitive,
+                    settings.ignore_shortcut_keys,
+                    settings.require_terminator,
+                    settings.keep_terminator,
+                    settings.show_expansion_overlay,
+                    settings.notify_on_expand,
+                    settings.ui_scale,
+                )
             } else {
                 return;
             }
         };
         
+        let mut terminator_chars = self.state.settings.lock()
+            .map(|settings| settings.terminator_chars.clone())
+            .unwrap_or_default();
+        
         // UI要素の表示
         let enabled_changed = ui.checkbox(&mut enabled, "Enable SwiftType").changed();
-        let startup_changed = ui.checkbox(&mut start_with_system, "Start with system").changed();
+        let mut startup_changed = ui.checkbox(&mut start_with_system, "Start with system").changed();
+        ui.horizontal(|ui| {
+            ui.label("Startup method:");
+            egui::ComboBox::from_id_source("auto_start_method")
+                .selected_text(auto_start_method.label())
+                .show_ui(ui, |ui| {
+                    for method in AutoStartMethod::ALL {
+                        startup_changed |= ui.selectable_value(&mut auto_start_method, method, method.label()).changed();
+                    }
+                })
+                .response
+                .on_hover_text("Use the registry if startup shortcuts are blocked or removed on your PC");
+        });
+        if let Some(error) = &self.state.startup_error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+        let close_changed = ui.checkbox(&mut close_to_tray, "Minimize to the tray when the window is closed")
+            .on_hover_text("Snippets keep expanding in the background. Use Exit in the tray menu to quit")
+            .changed();
+        let case_changed = ui.checkbox(&mut case_sensitive, "Case-sensitive keywords")
+            .on_hover_text("When off, keywords such as 'USA' also expand when typed in lowercase")
+            .changed();
+        let shortcut_changed = ui.checkbox(&mut ignore_shortcut_keys, "Ignore keys pressed with Ctrl, Alt or Win")
+            .on_hover_text("Keyboard shortcuts are not counted as typing a keyword. Shift still types characters")
+            .changed();
+        let mut terminator_changed = ui.checkbox(&mut require_terminator, "Expand only after a terminator")
+            .on_hover_text("Snippets expand when the keyword is followed by one of the terminators below")
+            .changed();
+        ui.add_enabled_ui(require_terminator, |ui| {
+            terminator_changed |= ui.checkbox(&mut keep_terminator, "Keep the terminator after the expansion")
+                .changed();
+            
+            // 空白の区切り文字はチェックボックスで、記号は入力欄で選ぶ
+            let mut symbols: String = terminator_chars.chars().filter(|c| !c.is_whitespace()).collect();
+            let mut whitespace: Vec<char> = terminator_chars.chars().filter(|c| c.is_whitespace()).collect();
+            let mut chars_changed = false;
+            ui.horizontal(|ui| {
+                ui.label("Terminators:");
+                for (c, label) in [(' ', "Space"), ('\t', "Tab"), ('\n', "Enter")] {
+                    let mut included = whitespace.contains(&c);
+                    if ui.checkbox(&mut included, label).changed() {
+                        whitespace.retain(|&w| w != c);
+                        if included {
+                            whitespace.push(c);
+                        }
+                        chars_changed = true;
+                    }
+                }
+                ui.label("Symbols:");
+                chars_changed |= ui.add(egui::TextEdit::singleline(&mut symbols).desired_width(80.0))
+                    .on_hover_text("Symbols such as . or / that also end a keyword. , ; and = cannot be used")
+                    .changed();
+            });
+            if chars_changed {
+                terminator_chars = whitespace.into_iter()
+                    .chain(symbols.chars().filter(|c| !c.is_whitespace()))
+                    .collect();
+                terminator_changed = true;
+            }
+        });
+        let overlay_changed = ui.checkbox(&mut show_overlay, "Show snippet name on expansion")
+            .on_hover_text("Briefly shows the expanded snippet's name near the text cursor")
+            .changed();
+        let mut notification_changed = false;
+        ui.horizontal(|ui| {
+            ui.label("On expansion:");
+            notification_changed |= ui.checkbox(&mut notification.sound, "Play a sound").changed();
+            notification_changed |= ui.checkbox(&mut notification.balloon, "Show a tray notification")
+                .on_hover_text("Shows the expanded snippet's name as a Windows notification")
+                .changed();
+        });
+        
+        // UIスケール（ドラッグ中に拡大率が変わると操作しづらいため、ドラッグ終了時に確定する）
+        let mut ui_scale = self.state.pending_ui_scale.unwrap_or(current_ui_scale);
+        let scale_response = ui.add(
+            egui::Slider::new(&mut ui_scale, constants::MIN_UI_SCALE..=constants::MAX_UI_SCALE)
+                .step_by(0.05)
+                .text("UI scale"),
+        );
+        if scale_response.changed() {
+            self.state.pending_ui_scale = Some(ui_scale);
+        }
+        let scale_changed = match self.state.pending_ui_scale {
+            Some(pending) if !scale_response.dragged() => {
+                self.state.pending_ui_scale = None;
+                ui_scale = super::clamp_ui_scale(pending);
+                ui_scale != current_ui_scale
+            }
+            _ => false,
+        };
+        
+        if enabled_changed {
+            self.state.set_enabled(enabled);
+        }
         
         // 変更があれば設定を更新
-        if enabled_changed || startup_changed {
-            if let Ok(mut settings) = self.state.settings.lock() {
-                settings.enabled = enabled;
+        if enabled_changed || startup_changed || close_changed || case_changed || shortcut_changed || terminator_changed || overlay_changed || notification_changed || scale_changed {
+            self.update_setting(|settings| {
                 settings.start_with_system = start_with_system;
-                
-                // 設定のロックを解放して保存
-                drop(settings);
-                
-                if let Ok(mut config_manager) = self.state.config_manager.lock() {
-                    if let Ok(settings) = self.state.settings.lock() {
-                        let _ = config_manager.update_settings(settings.clone());
-                        
-                        // 自動起動の設定を変更した場合は、自動起動を設定
-                        if startup_changed {
-                            let _ = utils::set_auto_startup(start_with_system);
-                        }
+                settings.auto_start_method = auto_start_method;
+                settings.close_to_tray = close_to_tray;
+                settings.case_sensitive = case_sensitive;
+                settings.ignore_shortcut_keys = ignore_shortcut_keys;
+                settings.require_terminator = require_terminator;
+                settings.keep_terminator = keep_terminator;
+                settings.terminator_chars = terminator_chars;
+                settings.show_expansion_overlay = show_overlay;
+                settings.notify_on_expand = notification;
+                settings.ui_scale = ui_scale;
+            });
+            
+            // 自動起動の設定を変更した場合は、自動起動を設定
+            if startup_changed {
+                self.state.startup_error = utils::set_auto_startup(start_with_system, auto_start_method)
+                    .err()
+                    .map(|e| format!("Failed to update startup: {}", e));
+            }
+        }
+        
+        // ウィンドウのテーマ
+        let current_theme = self.state.settings.lock().map(|settings| settings.theme).unwrap_or_default();
+        let mut selected_theme = current_theme;
+        ui.horizontal(|ui| {
+            ui.label("Theme:");
+            egui::ComboBox::from_id_source("theme_preference")
+                .selected_text(selected_theme.label())
+                .show_ui(ui, |ui| {
+                    for preference in ThemePreference::ALL {
+                        ui.selectable_value(&mut selected_theme, preference, preference.label());
                     }
-                }
+                });
+        });
+        if selected_theme != current_theme {
+            self.state.set_theme_preference(selected_theme);
+        }
+        
+        ui.separator();
+        ui.heading("Hotkeys");
+        ui.add_space(10.0);
+        
+        // グローバルホットキー（変更はアプリケーションが登録し直す）
+        let Some(mut hotkeys) = self.state.settings.lock().ok().map(|settings| [
+            settings.toggle_hotkey,
+            settings.open_window_hotkey,
+            settings.capture_hotkey,
+            settings.undo_hotkey,
+            settings.picker_hotkey,
+        ]) else {
+            return;
+        };
+        let labels = [
+            "Enable/disable SwiftType:",
+            "Show window:",
+            "Snippet from selection:",
+            "Undo last expansion:",
+            "Search and insert snippet:",
+        ];
+        
+        let mut hotkeys_changed = false;
+        egui::Grid::new("hotkeys_grid").num_columns(2).show(ui, |ui| {
+            for (label, hotkey) in labels.into_iter().zip(hotkeys.iter_mut()) {
+                ui.label(label);
+                hotkeys_changed |= settings_view::render_hotkey_editor(ui, label, hotkey);
+                ui.end_row();
+            }
+        });
+        
+        if hotkeys_changed {
+            self.update_setting(|settings| {
+                let [toggle, open_window, capture, undo, picker] = hotkeys;
+                settings.toggle_hotkey = toggle;
+                settings.open_window_hotkey = open_window;
+                settings.capture_hotkey = capture;
+                settings.undo_hotkey = undo;
+                settings.picker_hotkey = picker;
+            });
+        }
+        
+        ui.separator();
+        ui.heading("Advanced");
+        ui.add_space(10.0);
+        
+        // 日付のロケール（書式の例には編集中のロケールを使う）
+        let (locale, saved_locale) = self.render_text_setting(
+            ui,
+            "locale",
+            "Date locale:",
+            "Locale for month and weekday names, e.g. en-US, ja-JP or de-DE. Leave empty to use the system locale",
+            |settings| settings.locale.clone(),
+        );
+        let locale = locale.trim();
+        
+        if let Some(saved_locale) = saved_locale {
+            self.update_setting(|settings| settings.locale = saved_locale);
+        }
+        
+        // 書式を省略した{date}の書式
+        let mut date_format = self.state.settings.lock().ok()
+            .map(|settings| settings.default_date_format.clone())
+            .unwrap_or_default();
+        let date_format_changed = ui.horizontal(|ui| {
+            ui.label("Default date format:");
+            ui.text_edit_singleline(&mut date_format)
+                .on_hover_text("Format used by {date} without an explicit format, e.g. yyyy/MM/dd or dd.MM.yyyy")
+                .changed()
+        }).inner;
+        match formatter::check_date_format(&date_format, locale, None) {
+            Ok(example) => {
+                ui.label(format!("Example: {}", example));
+            }
+            Err(e) => {
+                ui.colored_label(egui::Color32::from_rgb(230, 150, 0), e);
+            }
+        }
+        
+        if date_format_changed {
+            if let Err(e) = formatter::check_date_format(&date_format, locale, None) {
+                log::warn!("{}", e);
             }
+            
+            self.update_setting(|settings| settings.default_date_format = date_format);
+        }
+        
+        // キー入力のバッファのサイズ（最も長いキーワードより短くはできない）
+        let (mut buffer_size, min_buffer_size) = self.state.settings.lock().ok()
+            .map(|settings| (settings.effective_buffer_size(), settings.min_buffer_size()))
+            .unwrap_or((32, 1));
+        let buffer_size_changed = ui.horizontal(|ui| {
+            ui.label("Keyboard buffer size:");
+            ui.add(egui::DragValue::new(&mut buffer_size).clamp_range(min_buffer_size..=MAX_BUFFER_SIZE.max(min_buffer_size)))
+                .on_hover_text(format!(
+                    "Number of recent characters kept for matching. Must be at least {} to fit the longest keyword",
+                    min_buffer_size,
+                ))
+                .changed()
+        }).inner;
+        
+        if buffer_size_changed {
+            self.update_setting(|settings| settings.buffer_size = buffer_size);
+        }
+        
+        // 展開できるテキストの最大文字数
+        let mut max_expansion_len = self.state.settings.lock()
+            .map(|settings| settings.max_expansion_len)
+            .unwrap_or(DEFAULT_MAX_EXPANSION_LEN);
+        let max_length_changed = ui.horizontal(|ui| {
+            ui.label("Maximum expansion length:");
+            ui.add(egui::DragValue::new(&mut max_expansion_len).clamp_range(1..=usize::MAX).speed(100.0))
+                .on_hover_text("Longer expansions are blocked instead of being typed, to stop runaway templates")
+                .changed()
+        }).inner;
+        
+        if max_length_changed {
+            self.update_setting(|settings| settings.max_expansion_len = max_expansion_len);
+        }
+        
+        // 展開の速さ（設定ファイルで個別に変更した場合はCustomと表示する）
+        let current_preset = self.state.settings.lock().ok()
+            .and_then(|settings| settings.timing.matching_preset());
+        let mut selected_preset = current_preset;
+        ui.horizontal(|ui| {
+            ui.label("Expansion speed:");
+            egui::ComboBox::from_id_source("expansion_speed")
+                .selected_text(selected_preset.map_or("Custom", TimingPreset::label))
+                .show_ui(ui, |ui| {
+                    for preset in TimingPreset::ALL {
+                        ui.selectable_value(&mut selected_preset, Some(preset), preset.label());
+                    }
+                })
+                .response
+                .on_hover_text("Use Safe if characters are dropped or expanded text is garbled in some applications");
+        });
+        
+        if let Some(preset) = selected_preset.filter(|_| selected_preset != current_preset) {
+            self.update_setting(|settings| settings.timing = settings.timing.with_preset(preset));
+        }
+        
+        // 貼り付けのキー入力を送信できなかったときの再試行回数
+        let mut paste_retries = self.state.settings.lock().ok()
+            .map(|settings| settings.timing.paste_retries)
+            .unwrap_or(DEFAULT_PASTE_RETRIES);
+        let paste_retries_changed = ui.horizontal(|ui| {
+            ui.label("Paste retries:");
+            ui.add(egui::DragValue::new(&mut paste_retries).clamp_range(0..=MAX_PASTE_RETRIES))
+                .on_hover_text("How many times to resend the paste keys, waiting a little longer each time, if they could not be sent. Raise this on slow remote desktop sessions")
+                .changed()
+        }).inner;
+        
+        if paste_retries_changed {
+            self.update_setting(|settings| settings.timing.paste_retries = paste_retries);
+        }
+        
+        // 展開するテキストの末尾の空白や改行
+        let mut trim_trailing = self.state.settings.lock().map(|settings| settings.trim_trailing_whitespace).unwrap_or(false);
+        let trim_trailing_changed = ui.checkbox(&mut trim_trailing, "Remove trailing spaces and new lines from expansions")
+            .on_hover_text("Stops a stray new line at the end of a snippet from adding a blank line. Spaces and new lines inside the text are kept")
+            .changed();
+        
+        if trim_trailing_changed {
+            self.update_setting(|settings| settings.trim_trailing_whitespace = trim_trailing);
+        }
+        
+        // 展開を中止するキーワード（入力途中のキーワードで保存しないよう、フォーカスが外れたときに保存する）
+        let (_, abort_keyword) = self.render_text_setting(
+            ui,
+            "abort_keyword",
+            "Abort keyword:",
+            "Typing this clears the typed text buffer, releases stuck modifier keys and stops expansion for a second. Leave empty to turn it off",
+            |settings| settings.abort_keyword.clone(),
+        );
+        
+        if let Some(abort_keyword) = abort_keyword {
+            self.update_setting(|settings| settings.abort_keyword = abort_keyword);
+        }
+        
+        // ドライラン（入力せずに、入力する内容を記録する）
+        let mut dry_run = self.state.settings.lock().map(|settings| settings.dry_run).unwrap_or(false);
+        let dry_run_changed = ui.checkbox(&mut dry_run, "Dry run: log expansions instead of typing them")
+            .on_hover_text("Matching keywords are not removed and nothing is typed. Use this to find out which snippet fires and when")
+            .changed();
+        
+        if dry_run_changed {
+            self.update_setting(|settings| settings.dry_run = dry_run);
+        }
+        self.render_dry_run_events(ui);
+        
+        // 外部のツールからの要求の待ち受け
+        #[cfg(feature = "ipc")]
+        self.render_ipc_settings(ui);
+        
+        // 展開後に実行するコマンド（引数の区切りの空白が消えないよう、編集中はテキストをそのまま保持する）
+        let (_, on_expand_command) = self.render_text_setting(
+            ui,
+            "on_expand_command",
+            "Run after expansion:",
+            "Runs without waiting for it to finish. The keyword and snippet name are appended as arguments",
+            |settings| settings.on_expand_command.clone().unwrap_or_default(),
+        );
+        
+        if let Some(command) = on_expand_command {
+            self.update_setting(|settings| {
+                settings.on_expand_command = if command.is_empty() {
+                    None
+                } else {
+                    Some(command)
+                };
+            });
+        }
+        
+        // 展開しないアプリ（入力途中のカンマが消えないよう、編集中はテキストをそのまま保持する）
+        let disabled_apps_id = ui.make_persistent_id("disabled_apps");
+        let mut disabled_apps = ui.data_mut(|data| data.get_temp::<String>(disabled_apps_id))
+            .or_else(|| self.state.settings.lock().ok().map(|settings| settings.disabled_apps.join(", ")))
+            .unwrap_or_default();
+        let disabled_apps_response = ui.horizontal(|ui| {
+            ui.label("Never expand in:");
+            ui.text_edit_singleline(&mut disabled_apps)
+                .on_hover_text("Comma-separated process names such as KeePass.exe, game. Typing is still tracked so expansion resumes in other apps")
+        }).inner;
+        
+        if disabled_apps_response.changed() {
+            self.update_setting(|settings| settings.disabled_apps = utils::parse_app_list(&disabled_apps));
+        }
+        if disabled_apps_response.has_focus() {
+            ui.data_mut(|data| data.insert_temp(disabled_apps_id, disabled_apps));
+        } else {
+            ui.data_mut(|data| data.remove::<String>(disabled_apps_id));
+        }
+        
+        // 起動時に確認する競合ツール（次回の起動から反映する）
+        self.render_app_list_setting(
+            ui,
+            "conflict_tools",
+            "Also warn about:",
+            "Comma-separated process names of other text expanders to check for at startup, in addition to the built-in list",
+            |settings| &mut settings.conflict_tools,
+        );
+        self.render_app_list_setting(
+            ui,
+            "ignored_conflict_tools",
+            "Never warn about:",
+            "Comma-separated process names that are not reported as conflicting tools at startup",
+            |settings| &mut settings.ignored_conflict_tools,
+        );
+        
+        ui.add_space(10.0);
+        
+        ui.horizontal(|ui| {
+            if ui.button("Edit raw config").clicked() {
+                self.load_raw_config();
+                self.state.switch_tab(Tab::RawConfig);
+            }
+            
+            if ui.button("Back up settings").on_hover_text("Saves a timestamped copy of settings.json next to it").clicked() {
+                self.backup_settings();
+            }
+            
+            if ui.button("Restore from backup...").clicked() {
+                self.restore_settings();
+            }
+        });
+        
+        match &self.state.backup_status {
+            Some(Ok(message)) => {
+                ui.colored_label(egui::Color32::from_rgb(80, 180, 80), message);
+            }
+            Some(Err(message)) => {
+                ui.colored_label(egui::Color32::RED, message);
+            }
+            None => {}
         }
     }
     
     /// エディタタブを描画する
     fn render_editor_tab(&mut self, ui: &mut Ui) {
-        // スニペットの取得
-        let snippet_to_edit = if let Some(index) = self.state.selected_snippet_index {
-            // 既存のスニペットを編集
-            if let Ok(settings) = self.state.settings.lock() {
-                if index < settings.snippets.len() {
-                    Some((settings.snippets[index].clone(), true, index))
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        } else {
-            // 新しいスニペットを作成
-            Some((crate::config::settings::Snippet::new(
-                String::new(),
-                String::new(),
-                String::new(),
-                crate::config::settings::SnippetType::Static,
-                "一般".to_string(),
-            ), false, 0))
+        // 編集中の下書きを取得（なければ選択中のスニペットか新しいスニペットから作成する）
+        let is_editing = self.state.selected_snippet_index.is_some();
+        let draft = match (self.state.editor_draft.clone(), self.state.selected_snippet_index) {
+            (Some(draft), _) => Some(draft),
+            (None, Some(index)) => self.state.settings.lock().ok()
+                .and_then(|settings| settings.snippets.get(index).cloned()),
+            (None, None) => Some(self.empty_snippet(DEFAULT_CATEGORY)),
         };
         
-        if let Some((mut snippet, is_editing, index)) = snippet_to_edit {
+        if let Some(mut snippet) = draft {
             // UI表示
             if is_editing {
                 ui.heading("Edit Snippet");
@@ -289,40 +1845,415 @@ impl AppUi {
                 ui.heading("Create New Snippet");
             }
             
-            let edited = snippet_editor::render_snippet_editor(ui, &mut snippet);
+            let previous_category = snippet.category.clone();
+            let (locale, date_format) = self.state.settings.lock().ok()
+                .map(|settings| (settings.locale.clone(), settings.default_date_format.clone()))
+                .unwrap_or_default();
+            let response = snippet_editor::render_snippet_editor(
+                ui,
+                &mut snippet,
+                &locale,
+                &date_format,
+                self.state.editor_sandbox.as_deref(),
+            );
             
-            // 編集されたスニペットの保存
-            if edited && (!is_editing || !snippet.name.is_empty() && !snippet.keyword.is_empty()) {
-                let switch_to_snippets = {
-                    if let Ok(mut settings) = self.state.settings.lock() {
-                        if is_editing && index < settings.snippets.len() {
-                            // 既存のスニペットを更新
-                            settings.snippets[index] = snippet;
-                        } else if !is_editing {
-                            // 新しいスニペットを追加
-                            settings.snippets.push(snippet);
-                        }
-                        
-                        // 設定のロックを解放して保存
-                        drop(settings);
-                        
-                        if let Ok(mut config_manager) = self.state.config_manager.lock() {
-                            if let Ok(settings) = self.state.settings.lock() {
-                                let _ = config_manager.update_settings(settings.clone());
+            // グローバルなキーボードフックを通さず、保存前の内容で展開結果を確かめる
+            if response.try_clicked {
+                self.state.editor_sandbox = self.state.settings.lock().ok()
+                    .map(|settings| replacement::expand_snippet_text(&snippet, &settings));
+            }
+            
+            // 新規作成中にカテゴリを変更した場合は、そのカテゴリのデフォルトの種類にする
+            if !is_editing && snippet.category != previous_category {
+                if let Some(snippet_type) = self.state.settings.lock().ok()
+                    .and_then(|settings| settings.default_snippet_type(&snippet.category))
+                {
+                    snippet.snippet_type = snippet_type;
+                }
+            }
+            
+            // 編集中の内容は保存ボタンが押されるまで下書きとして保持する
+            self.state.editor_draft = Some(snippet.clone());
+            if response.changed {
+                self.state.editor_status = None;
+            }
+            
+            match &self.state.editor_status {
+                Some(Ok(message)) => {
+                    ui.colored_label(egui::Color32::from_rgb(80, 180, 80), message);
+                }
+                Some(Err(error)) => {
+                    ui.colored_label(egui::Color32::from_rgb(220, 80, 80), error);
+                }
+                None => {}
+            }
+            
+            if response.test_clicked && self.state.test_result.is_none() {
+                self.state.editor_status = None;
+                self.state.test_countdown = Some((snippet.clone(), Instant::now()));
+            }
+            
+            // 保存ボタンとCtrl+Sのどちらでも保存する
+            if response.save_clicked || std::mem::take(&mut self.state.save_requested) {
+                let result = if is_editing {
+                    let id = snippet.id;
+                    self.modify_config(|config_manager| config_manager.update_snippet(id, snippet).map(|_| id))
+                } else {
+                    self.modify_config(|config_manager| config_manager.add_snippet(snippet))
+                };
+                
+                // 重複したキーワードでも保存し、保存後に警告する
+                if let Ok(id) = result {
+                    self.check_keyword_conflicts(id);
+                }
+                
+                match result {
+                    Ok(_) if is_editing => {
+                        self.state.editor_status = Some(Ok("Snippet saved".to_string()));
+                    }
+                    Ok(_) => {
+                        // 新規作成時のみスニペット一覧に戻る
+                        self.state.editor_draft = None;
+                        self.state.switch_tab(Tab::Snippets);
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to save snippet: {}", e);
+                        self.state.editor_status = Some(Err(e));
+                    }
+                }
+            }
+        }
+    }
+    
+    /// カテゴリ管理タブを描画する
+    fn render_categories_tab(&mut self, ui: &mut Ui) {
+        ui.heading("Categories");
+        ui.label(format!(
+            "Deleting a category moves its snippets to '{}'. The default type is used for new snippets in the category.",
+            UNCATEGORIZED_CATEGORY,
+        ));
+        ui.add_space(10.0);
+        
+        match &self.state.category_status {
+            Some(Ok(message)) => {
+                ui.colored_label(egui::Color32::from_rgb(80, 180, 80), message);
+            }
+            Some(Err(error)) => {
+                ui.colored_label(egui::Color32::from_rgb(220, 80, 80), error);
+            }
+            None => {}
+        }
+        
+        // カテゴリとスニペット数、デフォルトの種類を取得
+        let categories: Vec<(String, usize, Option<SnippetType>)> = {
+            if let Ok(settings) = self.state.settings.lock() {
+                settings.categories().into_iter()
+                    .map(|category| {
+                        let count = settings.snippets.iter().filter(|s| s.category == category).count();
+                        let snippet_type = settings.default_snippet_type(&category);
+                        (category, count, snippet_type)
+                    })
+                    .collect()
+            } else {
+                return;
+            }
+        };
+        
+        let mut start_rename = None;
+        let mut confirm_rename = false;
+        let mut cancel_rename = false;
+        let mut delete = None;
+        let mut type_change = None;
+        
+        ScrollArea::vertical().show(ui, |ui| {
+            for (category, count, default_type) in &categories {
+                ui.horizontal(|ui| {
+                    match &mut self.state.category_rename {
+                        Some((renaming, new_name)) if renaming == category => {
+                            let response = ui.text_edit_singleline(new_name);
+                            if ui.button("OK").clicked()
+                                || (response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)))
+                            {
+                                confirm_rename = true;
+                            }
+                            if ui.button("Cancel").clicked() {
+                                cancel_rename = true;
                             }
                         }
+                        _ => {
+                            ui.label(format!("{} ({})", category, count));
+                        }
+                    }
+                    
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.button("Delete").clicked() {
+                            delete = Some(category.clone());
+                        }
+                        if ui.button("Rename").clicked() {
+                            start_rename = Some(category.clone());
+                        }
                         
-                        // 新規作成時のみスニペット一覧に戻る
-                        !is_editing
-                    } else {
-                        false
+                        let mut selected = default_type.clone();
+                        egui::ComboBox::from_id_source(("category_type", category.as_str()))
+                            .selected_text(match &selected {
+                                Some(SnippetType::Static) => "Static",
+                                Some(SnippetType::Dynamic) => "Dynamic",
+                                None => "No default",
+                            })
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut selected, None, "No default");
+                                ui.selectable_value(&mut selected, Some(SnippetType::Static), "Static");
+                                ui.selectable_value(&mut selected, Some(SnippetType::Dynamic), "Dynamic");
+                            });
+                        if selected != *default_type {
+                            type_change = Some((category.clone(), selected));
+                        }
+                    });
+                });
+                ui.separator();
+            }
+        });
+        
+        if let Some(category) = start_rename {
+            self.state.category_rename = Some((category.clone(), category));
+            self.state.category_status = None;
+        }
+        if cancel_rename {
+            self.state.category_rename = None;
+        }
+        if confirm_rename {
+            if let Some((old_name, new_name)) = self.state.category_rename.clone() {
+                let result = self.modify_config(|config_manager| config_manager.rename_category(&old_name, &new_name));
+                self.state.category_status = Some(result.map(|count| {
+                    format!("Renamed '{}' to '{}' ({} snippets)", old_name, new_name.trim(), count)
+                }));
+                if matches!(self.state.category_status, Some(Ok(_))) {
+                    self.state.category_rename = None;
+                }
+            }
+        }
+        if let Some(category) = delete {
+            let result = self.modify_config(|config_manager| config_manager.delete_category(&category));
+            self.state.category_status = Some(result.map(|count| {
+                format!("Deleted '{}' and moved {} snippets to '{}'", category, count, UNCATEGORIZED_CATEGORY)
+            }));
+        }
+        if let Some((category, snippet_type)) = type_change {
+            if let Err(e) = self.modify_config(|config_manager| {
+                config_manager.set_category_snippet_type(&category, snippet_type)
+            }) {
+                self.state.category_status = Some(Err(e));
+            }
+        }
+    }
+    
+    /// Logタブを描画する
+    fn render_log_tab(&mut self, ui: &mut Ui) {
+        ui.heading("Log");
+        ui.add_space(10.0);
+        
+        let Some(event_log) = event_log::event_log() else {
+            ui.label("The log is not available.");
+            return;
+        };
+        let entries = event_log.entries(self.state.log_level_filter);
+        
+        ui.horizontal(|ui| {
+            ui.label("Show:");
+            egui::ComboBox::from_id_source("log_level_filter")
+                .selected_text(log_filter_label(self.state.log_level_filter))
+                .show_ui(ui, |ui| {
+                    for filter in [LevelFilter::Info, LevelFilter::Warn, LevelFilter::Error] {
+                        ui.selectable_value(&mut self.state.log_level_filter, filter, log_filter_label(filter));
                     }
-                };
-                
-                // スニペット一覧に戻る
-                if switch_to_snippets {
-                    self.state.switch_tab(Tab::Snippets);
+                });
+            
+            // 不具合を報告するときに貼り付けられるよう、表示中のログをコピーする
+            if ui.add_enabled(!entries.is_empty(), egui::Button::new("Copy")).clicked() {
+                let text = entries.iter()
+                    .map(|entry| format!("{} [{}] {}", entry.time.format("%Y-%m-%d %H:%M:%S"), entry.level, entry.message))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                ui.output_mut(|output| output.copied_text = text);
+            }
+            if ui.button("Clear").clicked() {
+                event_log.clear();
+            }
+        });
+        ui.separator();
+        
+        if entries.is_empty() {
+            ui.label("No log entries yet.");
+            return;
+        }
+        
+        ScrollArea::vertical()
+            .id_source("event_log")
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for entry in &entries {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label(entry.time.format("%H:%M:%S").to_string());
+                        match entry.level {
+                            Level::Error => ui.colored_label(egui::Color32::RED, "ERROR"),
+                            Level::Warn => ui.colored_label(egui::Color32::from_rgb(230, 150, 0), "WARN"),
+                            level => ui.label(level.as_str()),
+                        };
+                        ui.label(&entry.message);
+                    });
                 }
+            });
+    }
+    
+    /// Statsタブを描画する
+    fn render_stats_tab(&mut self, ui: &mut Ui) {
+        ui.heading("Stats");
+        ui.add_space(10.0);
+        
+        let Some((total, top, daily)) = self.state.expansion_stats.as_ref()
+            .and_then(|stats| stats.lock().ok())
+            .map(|stats| {
+                let today = chrono::Local::now().date_naive();
+                (stats.total(), stats.top_snippets(STATS_TOP_SNIPPETS), stats.daily_counts(today, STATS_RETENTION_DAYS as usize))
+            })
+        else {
+            ui.label("Statistics are not available.");
+            return;
+        };
+        
+        ui.label(format!("{} expansions in the last {} days", total, STATS_RETENTION_DAYS));
+        ui.add_space(10.0);
+        
+        // 日ごとの展開の回数（右端が今日）
+        let bars: Vec<egui::plot::Bar> = daily.iter().enumerate()
+            .map(|(index, (date, count))| {
+                egui::plot::Bar::new(index as f64, *count as f64)
+                    .width(0.8)
+                    .name(date.format("%Y-%m-%d"))
+            })
+            .collect();
+        let dates: Vec<String> = daily.iter().map(|(date, _)| date.format("%m/%d").to_string()).collect();
+        egui::plot::Plot::new("daily_expansions")
+            .height(180.0)
+            .allow_zoom(false)
+            .allow_drag(false)
+            .allow_scroll(false)
+            .include_y(0.0)
+            .x_axis_formatter(move |x, _| {
+                // 整数の位置にだけ日付を表示する
+                let index = x.round();
+                if (x - index).abs() > f64::EPSILON || index < 0.0 {
+                    return String::new();
+                }
+                dates.get(index as usize).cloned().unwrap_or_default()
+            })
+            .show(ui, |plot_ui| plot_ui.bar_chart(egui::plot::BarChart::new(bars)));
+        
+        ui.add_space(10.0);
+        ui.strong("Most used snippets");
+        if top.is_empty() {
+            ui.label("No expansions yet.");
+            return;
+        }
+        egui::Grid::new("top_snippets").num_columns(3).striped(true).show(ui, |ui| {
+            for (rank, (name, count)) in top.iter().enumerate() {
+                ui.label(format!("{}.", rank + 1));
+                ui.label(name);
+                ui.label(count.to_string());
+                ui.end_row();
+            }
+        });
+    }
+    
+    /// 設定ファイル編集タブを描画する
+    fn render_raw_config_tab(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.heading("Advanced: Edit raw config");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button("Back").clicked() {
+                    self.state.switch_tab(Tab::Settings);
+                }
+            });
+        });
+        
+        ui.label("Invalid settings are rejected. The current settings.json is backed up before applying.");
+        ui.add_space(10.0);
+        
+        ui.horizontal(|ui| {
+            if ui.button("Reload").clicked() {
+                self.load_raw_config();
+            }
+            
+            if ui.button("Validate").clicked() {
+                self.state.raw_config_status = Some(
+                    match ConfigManager::parse_settings(&self.state.raw_config_text) {
+                        Ok(_) => Ok("Settings JSON is valid".to_string()),
+                        Err(e) => Err(format!("Invalid settings JSON: {}", e)),
+                    },
+                );
+            }
+            
+            if ui.button("Apply").clicked() {
+                self.apply_raw_config();
+            }
+        });
+        
+        // 検証・適用結果の表示
+        match &self.state.raw_config_status {
+            Some(Ok(message)) => {
+                ui.colored_label(egui::Color32::GREEN, message);
+            }
+            Some(Err(message)) => {
+                ui.colored_label(egui::Color32::RED, message);
+            }
+            None => {}
+        }
+        
+        ui.add_space(10.0);
+        
+        ScrollArea::vertical().show(ui, |ui| {
+            if ui.add(
+                egui::TextEdit::multiline(&mut self.state.raw_config_text)
+                    .code_editor()
+                    .desired_width(f32::INFINITY)
+                    .desired_rows(20),
+            ).changed() {
+                self.state.raw_config_status = None;
+            }
+        });
+    }
+    
+    /// 現在の設定を編集用のテキストとして読み込む
+    fn load_raw_config(&mut self) {
+        if let Ok(config_manager) = self.state.config_manager.lock() {
+            match config_manager.to_json() {
+                Ok(json) => {
+                    self.state.raw_config_text = json;
+                    self.state.raw_config_status = None;
+                }
+                Err(e) => {
+                    self.state.raw_config_status = Some(Err(format!("Failed to serialize settings: {}", e)));
+                }
+            }
+        }
+    }
+    
+    /// 編集した設定を検証して適用する
+    fn apply_raw_config(&mut self) {
+        let result = if let Ok(mut config_manager) = self.state.config_manager.lock() {
+            config_manager.replace_with_json(&self.state.raw_config_text)
+        } else {
+            return;
+        };
+        
+        match result {
+            Ok(new_settings) => {
+                self.apply_loaded_settings(new_settings);
+                self.load_raw_config();
+                self.state.raw_config_status = Some(Ok("Settings applied".to_string()));
+            }
+            Err(e) => {
+                self.state.raw_config_status = Some(Err(format!("Settings were not applied: {}", e)));
             }
         }
     }
@@ -341,6 +2272,17 @@ impl AppUi {
                     };
                     
                     ui.label(status);
+                    
+                    // 一時停止中は再開する時刻を表示する
+                    if let Some(until) = settings.paused_until.filter(|_| settings.is_paused()) {
+                        ui.colored_label(egui::Color32::from_rgb(230, 150, 0), format!("Paused until {}", until.format("%H:%M")));
+                    }
+                }
+                
+                // キーボードフックを使わずに起動した場合は、入力しても展開されないことを示す
+                if !self.state.hook_enabled {
+                    ui.colored_label(egui::Color32::from_rgb(220, 50, 50), "Keyboard hook off (--no-hook)")
+                        .on_hover_text("Typed keywords are not expanded. Restart without --no-hook to enable expansion.");
                 }
                 
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use egui::Context;
+
+use crate::replacement::form::FieldFormRequest;
+
+/// 入力フォームの操作結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldFormOutcome {
+    /// 入力された値で展開する
+    Submit(HashMap<String, String>),
+    /// 展開を取り消す
+    Cancel,
+}
+
+/// 展開前にフィールドの値を入力するフォーム
+#[derive(Debug)]
+pub struct FieldForm {
+    /// 入力フォームの表示要求
+    request: FieldFormRequest,
+    /// フィールド名と入力中の値（同じ名前のフィールドは1つの入力欄を共有する）
+    values: Vec<(String, String)>,
+    /// 最初の入力欄にフォーカスを移したかどうか
+    focused: bool,
+}
+
+impl FieldForm {
+    /// 表示要求から入力フォームを作成する
+    pub fn new(request: FieldFormRequest) -> Self {
+        let values = request.replacement.fields.iter()
+            .map(|name| (name.clone(), String::new()))
+            .collect();
+        
+        Self { request, values, focused: false }
+    }
+    
+    /// 入力フォームの表示要求を取得する
+    pub fn request(&self) -> &FieldFormRequest {
+        &self.request
+    }
+    
+    /// 入力フォームを描画する
+    /// 
+    /// # 引数
+    /// * `ctx` - EGUIのコンテキスト
+    /// 
+    /// # 戻り値
+    /// 展開または取り消しが選ばれた場合はその結果
+    pub fn show(&mut self, ctx: &Context) -> Option<FieldFormOutcome> {
+        let mut outcome = None;
+        
+        egui::Window::new(format!("Fill in: {}", self.request.replacement.snippet_name))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                egui::Grid::new("field_form_grid")
+                    .num_columns(2)
+                    .spacing([8.0, 6.0])
+                    .show(ui, |ui| {
+                        for (index, (name, value)) in self.values.iter_mut().enumerate() {
+                            ui.label(format!("{}:", name));
+                            let response = ui.text_edit_singleline(value);
+                            if index == 0 && !self.focused {
+                                response.request_focus();
+                                self.focused = true;
+                            }
+                            ui.end_row();
+                        }
+                    });
+                
+                ui.separator();
+                
+                ui.horizontal(|ui| {
+                    if ui.button("Expand").clicked() {
+                        outcome = Some(self.submit());
+                    }
+                    if ui.button("Cancel").clicked() {
+                        outcome = Some(FieldFormOutcome::Cancel);
+                    }
+                });
+            });
+        
+        // Enterで展開し、Escで取り消す
+        if outcome.is_none() {
+            if ctx.input(|i| i.key_pressed(egui::Key::Enter)) {
+                outcome = Some(self.submit());
+            } else if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+                outcome = Some(FieldFormOutcome::Cancel);
+            }
+        }
+        
+        outcome
+    }
+    
+    /// 入力された値をまとめる
+    fn submit(&self) -> FieldFormOutcome {
+        FieldFormOutcome::Submit(self.values.iter().cloned().collect())
+    }
+}
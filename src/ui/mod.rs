@@ -1,10 +1,17 @@
 pub mod app_ui;
+pub mod field_form;
+pub mod notification;
+pub mod overlay;
 pub mod settings_view;
 pub mod snippet_editor;
+pub mod snippet_picker;
 pub mod tray;
 
 use egui::{Context, Visuals};
 
+use crate::config::settings::ThemePreference;
+use crate::utils;
+
 /// UI関連の定数
 pub mod constants {
     /// ウィンドウのタイトル
@@ -13,6 +20,10 @@ pub mod constants {
     pub const DEFAULT_WIDTH: f32 = 800.0;
     /// ウィンドウの高さ
     pub const DEFAULT_HEIGHT: f32 = 600.0;
+    /// UIスケールの下限
+    pub const MIN_UI_SCALE: f32 = 0.5;
+    /// UIスケールの上限
+    pub const MAX_UI_SCALE: f32 = 3.0;
 }
 
 /// テーマモード
@@ -33,6 +44,23 @@ impl ThemeMode {
         }
     }
     
+    /// Windowsのアプリのテーマ設定からテーマモードを取得する（取得できない場合はダーク）
+    pub fn from_system() -> Self {
+        match utils::apps_use_light_theme() {
+            Some(true) => ThemeMode::Light,
+            _ => ThemeMode::Dark,
+        }
+    }
+    
+    /// 設定のテーマからテーマモードを取得する
+    pub fn from_preference(preference: ThemePreference) -> Self {
+        match preference {
+            ThemePreference::System => Self::from_system(),
+            ThemePreference::Light => ThemeMode::Light,
+            ThemePreference::Dark => ThemeMode::Dark,
+        }
+    }
+    
     /// 現在のテーマモードを切り替える
     pub fn toggle(&mut self) {
         *self = match self {
@@ -43,11 +71,97 @@ impl ThemeMode {
 }
 
 /// EGUIのコンテキストを設定する
-pub fn setup_context(ctx: &Context, theme: ThemeMode) {
+/// 
+/// # 引数
+/// * `ctx` - EGUIのコンテキスト
+/// * `theme` - テーマモード
+/// * `pixels_per_point` - 1ポイントあたりのピクセル数
+pub fn setup_context(ctx: &Context, theme: ThemeMode, pixels_per_point: f32) {
     ctx.set_visuals(theme.to_visuals());
+    ctx.set_pixels_per_point(pixels_per_point);
     setup_fonts(ctx);
 }
 
+/// システムのDPIスケールとユーザー指定のスケールからpixels_per_pointを計算する
+/// 
+/// # 引数
+/// * `system_scale` - システムのDPIスケール（96DPIを1.0とする）
+/// * `ui_scale` - ユーザーが設定したUIスケール
+/// 
+/// # 戻り値
+/// 妥当な範囲に制限されたpixels_per_point
+pub fn compute_pixels_per_point(system_scale: f32, ui_scale: f32) -> f32 {
+    // 不正な値（0、負数、NaNなど）は等倍として扱う
+    let system_scale = if system_scale.is_finite() && system_scale > 0.0 { system_scale } else { 1.0 };
+    let ui_scale = clamp_ui_scale(ui_scale);
+    
+    (system_scale * ui_scale).clamp(constants::MIN_UI_SCALE, constants::MAX_UI_SCALE * 2.0)
+}
+
+/// ユーザー指定のUIスケールを妥当な範囲に制限する
+pub fn clamp_ui_scale(ui_scale: f32) -> f32 {
+    if ui_scale.is_finite() {
+        ui_scale.clamp(constants::MIN_UI_SCALE, constants::MAX_UI_SCALE)
+    } else {
+        1.0
+    }
+}
+
+/// UIスケールを適用したデフォルトのウィンドウサイズを取得する
+pub fn scaled_window_size(ui_scale: f32) -> egui::Vec2 {
+    let scale = clamp_ui_scale(ui_scale);
+    egui::vec2(constants::DEFAULT_WIDTH * scale, constants::DEFAULT_HEIGHT * scale)
+}
+
+/// 日本語を表示するためのフォントの名前
+const CJK_FONT_NAME: &str = "cjk";
+
+/// 日本語を表示できるフォントを、既定のフォントのフォールバックとして追加する
+/// 
+/// `bundled-cjk-font` フィーチャーが有効な場合は埋め込んだフォントを使い、
+/// 無効な場合はWindowsに付属する日本語フォントを読み込む。
+/// フォントの定義の比較は重いため、描画ごとではなく起動時に一度だけ呼び出す
+/// 
+/// # 引数
+/// * `ctx` - EGUIのコンテキスト
+pub fn install_cjk_font(ctx: &Context) {
+    use egui::{FontDefinitions, FontFamily};
+    
+    let Some(font_data) = load_cjk_font() else {
+        log::warn!("No Japanese font was found, Japanese text may not be displayed");
+        return;
+    };
+    
+    let mut fonts = FontDefinitions::default();
+    fonts.font_data.insert(CJK_FONT_NAME.to_string(), font_data);
+    for family in [FontFamily::Proportional, FontFamily::Monospace] {
+        fonts.families.entry(family).or_default().push(CJK_FONT_NAME.to_string());
+    }
+    ctx.set_fonts(fonts);
+}
+
+/// 埋め込んだ日本語フォントを読み込む
+#[cfg(feature = "bundled-cjk-font")]
+fn load_cjk_font() -> Option<egui::FontData> {
+    Some(egui::FontData::from_static(include_bytes!("../../assets/fonts/NotoSansJP-Regular.ttf")))
+}
+
+/// Windowsに付属する日本語フォントを読み込む
+#[cfg(not(feature = "bundled-cjk-font"))]
+fn load_cjk_font() -> Option<egui::FontData> {
+    // 游ゴシック、メイリオ、MSゴシックの順に探す（.ttcはコレクションの最初のフォントを使う）
+    const CANDIDATES: [&str; 3] = ["YuGothM.ttc", "meiryo.ttc", "msgothic.ttc"];
+    
+    let windows_dir = std::env::var_os("WINDIR").unwrap_or_else(|| "C:\\Windows".into());
+    let fonts_dir = std::path::Path::new(&windows_dir).join("Fonts");
+    
+    CANDIDATES.iter().find_map(|name| {
+        let data = std::fs::read(fonts_dir.join(name)).ok()?;
+        log::debug!("Loaded Japanese font: {}", name);
+        Some(egui::FontData::from_owned(data))
+    })
+}
+
 /// フォントを設定する
 fn setup_fonts(ctx: &Context) {
     use egui::{FontFamily, FontId, TextStyle};
@@ -62,4 +176,34 @@ fn setup_fonts(ctx: &Context) {
     ].into();
     
     ctx.set_style(style);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_compute_pixels_per_point() {
+        // 等倍
+        assert_eq!(compute_pixels_per_point(1.0, 1.0), 1.0);
+        // 150%のディスプレイ
+        assert_eq!(compute_pixels_per_point(1.5, 1.0), 1.5);
+        // システムスケールとユーザースケールの積
+        assert_eq!(compute_pixels_per_point(1.5, 2.0), 3.0);
+    }
+    
+    #[test]
+    fn test_compute_pixels_per_point_clamping() {
+        // ユーザースケールは上下限に制限される
+        assert_eq!(compute_pixels_per_point(1.0, 0.1), constants::MIN_UI_SCALE);
+        assert_eq!(compute_pixels_per_point(1.0, 10.0), constants::MAX_UI_SCALE);
+        // 極端な組み合わせでも上限を超えない
+        assert_eq!(compute_pixels_per_point(4.0, 3.0), constants::MAX_UI_SCALE * 2.0);
+        
+        // 不正なシステムスケールは等倍として扱う
+        assert_eq!(compute_pixels_per_point(0.0, 1.0), 1.0);
+        assert_eq!(compute_pixels_per_point(-2.0, 1.0), 1.0);
+        assert_eq!(compute_pixels_per_point(f32::NAN, 1.0), 1.0);
+        assert_eq!(compute_pixels_per_point(1.0, f32::NAN), 1.0);
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,160 @@
+use std::thread;
+use std::time::Duration;
+use windows::core::w;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::Media::Audio::{PlaySoundW, SND_ALIAS, SND_ASYNC, SND_NODEFAULT};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::UI::Shell::{
+    Shell_NotifyIconW, NIF_ICON, NIF_INFO, NIF_TIP, NIIF_INFO, NIIF_NOSOUND, NIIF_WARNING, NIM_ADD, NIM_DELETE, NOTIFYICONDATAW,
+    NOTIFY_ICON_INFOTIP_FLAGS,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DestroyWindow, LoadIconW, HWND_MESSAGE, IDI_APPLICATION, WINDOW_EX_STYLE, WINDOW_STYLE,
+};
+
+/// 展開したときに鳴らすシステムの通知音
+const EXPAND_SOUND_ALIAS: windows::core::PCWSTR = w!("SystemAsterisk");
+/// トレイの通知を表示しておく時間
+const BALLOON_DURATION: Duration = Duration::from_secs(5);
+/// トレイの通知のタイトル
+const BALLOON_TITLE: &str = "SwiftType";
+
+/// 展開したときのシステムの通知音を鳴らす（再生の完了は待たない）
+pub fn play_expand_sound() {
+    thread::spawn(|| unsafe {
+        if !PlaySoundW(EXPAND_SOUND_ALIAS, None, SND_ALIAS | SND_ASYNC | SND_NODEFAULT).as_bool() {
+            log::warn!("Failed to play the expansion sound");
+        }
+    });
+}
+
+/// 展開したスニペットの名前をトレイの通知に表示する（表示の完了は待たない）
+/// 
+/// トレイアイコンのライブラリは通知を表示できないため、通知の間だけ別の通知領域のアイコンを追加する
+/// 
+/// # 引数
+/// * `snippet_name` - 展開したスニペットの名前
+pub fn show_expand_balloon(snippet_name: &str) {
+    let message = balloon_message(snippet_name);
+    thread::spawn(move || unsafe {
+        if !show_balloon(&message, NIIF_INFO) {
+            log::warn!("Failed to show the expansion notification");
+        }
+    });
+}
+
+/// 展開できなかった理由を警告としてトレイの通知に表示する（表示の完了は待たない）
+/// 
+/// 展開時の通知の設定にかかわらず表示する
+/// 
+/// # 引数
+/// * `message` - 通知の本文
+pub fn show_warning_balloon(message: &str) {
+    let message = message.to_string();
+    thread::spawn(move || unsafe {
+        if !show_balloon(&message, NIIF_WARNING) {
+            log::warn!("Failed to show the warning notification");
+        }
+    });
+}
+
+/// トレイの通知に表示する本文を作成する
+pub fn balloon_message(snippet_name: &str) -> String {
+    format!("Expanded '{}'", snippet_name)
+}
+
+/// 固定長のワイド文字列の領域に文字列を書き込む（入りきらない部分は切り捨てる）
+/// 
+/// # 引数
+/// * `dest` - 書き込み先（末尾のNUL文字の分を残す）
+/// * `text` - 書き込む文字列
+pub fn copy_to_wide_buffer(dest: &mut [u16], text: &str) {
+    let Some(capacity) = dest.len().checked_sub(1) else {
+        return;
+    };
+    let mut length = 0;
+    for c in text.chars() {
+        let mut units = [0u16; 2];
+        let units = c.encode_utf16(&mut units);
+        // サロゲートペアの途中で切らない
+        if length + units.len() > capacity {
+            break;
+        }
+        dest[length..length + units.len()].copy_from_slice(units);
+        length += units.len();
+    }
+    dest[length] = 0;
+}
+
+/// 通知領域にアイコンを追加して通知を表示し、一定時間後にアイコンを削除する
+unsafe fn show_balloon(message: &str, icon: NOTIFY_ICON_INFOTIP_FLAGS) -> bool {
+    let Ok(instance) = GetModuleHandleW(None) else {
+        return false;
+    };
+    
+    // 通知領域のアイコンの持ち主になるメッセージ専用ウィンドウ
+    let hwnd = CreateWindowExW(
+        WINDOW_EX_STYLE::default(),
+        w!("STATIC"),
+        w!(""),
+        WINDOW_STYLE::default(),
+        0,
+        0,
+        0,
+        0,
+        HWND_MESSAGE,
+        None,
+        instance,
+        None,
+    );
+    if hwnd == HWND(0) {
+        return false;
+    }
+    
+    let mut data = NOTIFYICONDATAW {
+        cbSize: std::mem::size_of::<NOTIFYICONDATAW>() as u32,
+        hWnd: hwnd,
+        uID: 1,
+        uFlags: NIF_ICON | NIF_TIP | NIF_INFO,
+        hIcon: LoadIconW(None, IDI_APPLICATION).unwrap_or_default(),
+        // 通知音は設定に応じて別に鳴らす
+        dwInfoFlags: NOTIFY_ICON_INFOTIP_FLAGS(icon.0 | NIIF_NOSOUND.0),
+        ..Default::default()
+    };
+    copy_to_wide_buffer(&mut data.szTip, BALLOON_TITLE);
+    copy_to_wide_buffer(&mut data.szInfoTitle, BALLOON_TITLE);
+    copy_to_wide_buffer(&mut data.szInfo, message);
+    
+    let shown = Shell_NotifyIconW(NIM_ADD, &data).as_bool();
+    if shown {
+        thread::sleep(BALLOON_DURATION);
+        let _ = Shell_NotifyIconW(NIM_DELETE, &data);
+    }
+    
+    let _ = DestroyWindow(hwnd);
+    shown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_copy_to_wide_buffer_terminates_with_nul() {
+        let mut dest = [0xFFFFu16; 8];
+        copy_to_wide_buffer(&mut dest, "abc");
+        assert_eq!(&dest[..4], &[b'a' as u16, b'b' as u16, b'c' as u16, 0]);
+    }
+    
+    #[test]
+    fn test_copy_to_wide_buffer_truncates_long_text() {
+        let mut dest = [0u16; 4];
+        copy_to_wide_buffer(&mut dest, "abcdef");
+        assert_eq!(dest, [b'a' as u16, b'b' as u16, b'c' as u16, 0]);
+        
+        // サロゲートペアの途中では切らない
+        let mut dest = [0u16; 3];
+        copy_to_wide_buffer(&mut dest, "a😀");
+        assert_eq!(dest, [b'a' as u16, 0, 0]);
+    }
+}
@@ -0,0 +1,372 @@
+use std::cell::{Cell, RefCell};
+use std::thread::{self, JoinHandle};
+use crossbeam_channel::{Receiver, Sender};
+use windows::core::w;
+use windows::Win32::Foundation::{COLORREF, HWND, LPARAM, LRESULT, POINT, RECT, SIZE, WPARAM};
+use windows::Win32::Graphics::Gdi::{
+    BeginPaint, ClientToScreen, CreateSolidBrush, DeleteObject, DrawTextW, EndPaint, FillRect, GetDC,
+    GetMonitorInfoW, GetStockObject, GetTextExtentPoint32W, InvalidateRect, MonitorFromPoint, ReleaseDC,
+    SelectObject, SetBkMode, SetTextColor, DEFAULT_GUI_FONT, DT_CENTER, DT_SINGLELINE, DT_VCENTER,
+    MONITORINFO, MONITOR_DEFAULTTONEAREST, PAINTSTRUCT, TRANSPARENT,
+};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::System::Threading::GetCurrentThreadId;
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, GetCursorPos, GetGUIThreadInfo,
+    GetMessageW, KillTimer, PeekMessageW, PostThreadMessageW, RegisterClassW, SetLayeredWindowAttributes,
+    SetTimer, SetWindowPos, ShowWindow, GUITHREADINFO, HWND_TOPMOST, LWA_ALPHA, MSG, PM_NOREMOVE,
+    SWP_NOACTIVATE, SWP_SHOWWINDOW, SW_HIDE, WM_APP, WM_PAINT, WM_QUIT, WM_TIMER, WNDCLASSW,
+    WS_EX_LAYERED, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_EX_TRANSPARENT, WS_POPUP,
+};
+
+/// オーバーレイを表示するスレッドメッセージ
+const WM_SHOW_OVERLAY: u32 = WM_APP + 1;
+/// フェード用タイマーのID
+const FADE_TIMER_ID: usize = 1;
+/// フェード用タイマーの間隔（ミリ秒）
+const FADE_INTERVAL_MS: u32 = 40;
+/// フェードを開始するまでのタイマー回数（約0.8秒）
+const HOLD_TICKS: u32 = 20;
+/// 表示開始時の不透明度
+const INITIAL_ALPHA: u8 = 230;
+/// 1回のタイマーで下げる不透明度
+const FADE_STEP: u8 = 23;
+/// 文字列の周りの余白（ピクセル）
+const PADDING: i32 = 8;
+/// キャレットやカーソルからの距離（ピクセル）
+const ANCHOR_OFFSET: i32 = 4;
+/// マウスカーソルの大きさの目安（ピクセル）
+const CURSOR_SIZE: i32 = 20;
+
+thread_local! {
+    /// 表示中の文字列（UTF-16）
+    static OVERLAY_TEXT: RefCell<Vec<u16>> = const { RefCell::new(Vec::new()) };
+    /// フェード開始までの残りタイマー回数
+    static HOLD_REMAINING: Cell<u32> = const { Cell::new(0) };
+    /// 現在の不透明度
+    static CURRENT_ALPHA: Cell<u8> = const { Cell::new(0) };
+}
+
+/// オーバーレイの表示位置を計算する
+/// 
+/// キャレットの位置が取得できた場合はキャレットの下に、取得できない場合はマウスカーソルの右下に表示する。
+/// 画面の下端や右端からはみ出す場合は、画面内に収まるように位置をずらす。
+/// 
+/// # 引数
+/// * `caret` - キャレットの左下のスクリーン座標（取得できない場合はNone）
+/// * `cursor` - マウスカーソルのスクリーン座標
+/// * `size` - オーバーレイの幅と高さ
+/// * `work_area` - 表示先モニターの作業領域（left, top, right, bottom）
+/// 
+/// # 戻り値
+/// オーバーレイの左上のスクリーン座標
+pub fn compute_overlay_position(
+    caret: Option<(i32, i32)>,
+    cursor: (i32, i32),
+    size: (i32, i32),
+    work_area: (i32, i32, i32, i32),
+) -> (i32, i32) {
+    let (width, height) = size;
+    let (left, top, right, bottom) = work_area;
+    
+    // 基準点と、上に表示する場合に避ける高さ
+    let (anchor_x, anchor_y, anchor_height) = match caret {
+        Some((x, y)) => (x, y + ANCHOR_OFFSET, 0),
+        None => (cursor.0 + ANCHOR_OFFSET, cursor.1 + CURSOR_SIZE, CURSOR_SIZE),
+    };
+    
+    // 下にはみ出す場合は基準点の上に表示する
+    let mut y = anchor_y;
+    if y + height > bottom {
+        y = anchor_y - anchor_height - height - ANCHOR_OFFSET * 2;
+    }
+    let y = y.clamp(top, (bottom - height).max(top));
+    let x = anchor_x.clamp(left, (right - width).max(left));
+    
+    (x, y)
+}
+
+/// 展開時にスニペット名を表示するオーバーレイ
+/// 
+/// 専用スレッドで最前面・クリック透過のポップアップウィンドウを管理し、
+/// 表示要求を受け取るとキャレット付近に表示してからフェードアウトさせる
+#[derive(Debug)]
+pub struct ExpansionOverlay {
+    /// メッセージループを実行しているスレッドのID
+    thread_id: u32,
+    /// メッセージループのスレッド
+    handle: Option<JoinHandle<()>>,
+    /// 表示する文字列の送信器
+    sender: Sender<String>,
+}
+
+impl ExpansionOverlay {
+    /// オーバーレイのウィンドウを作成してメッセージループを開始する
+    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let (thread_id_sender, thread_id_receiver) = crossbeam_channel::bounded(1);
+        
+        let handle = thread::spawn(move || {
+            run_message_loop(receiver, thread_id_sender);
+        });
+        
+        // ウィンドウの作成に失敗した場合はスレッドが終了し、受信がエラーになる
+        let thread_id = thread_id_receiver.recv()?;
+        
+        Ok(Self {
+            thread_id,
+            handle: Some(handle),
+            sender,
+        })
+    }
+    
+    /// 指定した文字列をオーバーレイに表示する
+    pub fn show(&self, text: &str) {
+        if self.sender.send(text.to_string()).is_ok() {
+            unsafe {
+                let _ = PostThreadMessageW(self.thread_id, WM_SHOW_OVERLAY, WPARAM(0), LPARAM(0));
+            }
+        }
+    }
+}
+
+// 共有参照から使うのは `show` だけで、スレッドのハンドルには触れないため、
+// 置換エンジンの `catch_unwind` 内で参照しても不整合な状態は観測されない
+impl std::panic::RefUnwindSafe for ExpansionOverlay {}
+
+impl Drop for ExpansionOverlay {
+    fn drop(&mut self) {
+        // メッセージループを終了させる
+        unsafe {
+            let _ = PostThreadMessageW(self.thread_id, WM_QUIT, WPARAM(0), LPARAM(0));
+        }
+        
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// オーバーレイのウィンドウを作成し、WM_QUITを受け取るまでメッセージを処理する
+fn run_message_loop(receiver: Receiver<String>, thread_id_sender: Sender<u32>) {
+    unsafe {
+        let Some(hwnd) = create_overlay_window() else {
+            log::error!("Failed to create expansion overlay window");
+            return;
+        };
+        
+        // PostThreadMessageWを受け取れるようにメッセージキューを作成する
+        let mut msg = MSG::default();
+        let _ = PeekMessageW(&mut msg, HWND(0), 0, 0, PM_NOREMOVE);
+        let _ = thread_id_sender.send(GetCurrentThreadId());
+        
+        // GetMessageWはエラー時に-1を返すため、正の値の間だけループする
+        while GetMessageW(&mut msg, HWND(0), 0, 0).0 > 0 {
+            if msg.hwnd == HWND(0) && msg.message == WM_SHOW_OVERLAY {
+                // 連続して展開された場合は最後の表示要求だけを使う
+                if let Some(text) = receiver.try_iter().last() {
+                    show_overlay(hwnd, &text);
+                }
+            } else {
+                DispatchMessageW(&msg);
+            }
+        }
+        
+        let _ = DestroyWindow(hwnd);
+    }
+    
+    log::debug!("Expansion overlay message loop finished");
+}
+
+/// 最前面に表示され、クリックを透過するポップアップウィンドウを作成する
+unsafe fn create_overlay_window() -> Option<HWND> {
+    let instance = GetModuleHandleW(None).ok()?;
+    let class_name = w!("SwiftTypeExpansionOverlay");
+    
+    let window_class = WNDCLASSW {
+        lpfnWndProc: Some(overlay_window_proc),
+        hInstance: instance,
+        lpszClassName: class_name,
+        ..Default::default()
+    };
+    RegisterClassW(&window_class);
+    
+    let hwnd = CreateWindowExW(
+        WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_LAYERED | WS_EX_TRANSPARENT | WS_EX_NOACTIVATE,
+        class_name,
+        w!(""),
+        WS_POPUP,
+        0,
+        0,
+        0,
+        0,
+        None,
+        None,
+        instance,
+        None,
+    );
+    
+    if hwnd.0 == 0 {
+        None
+    } else {
+        Some(hwnd)
+    }
+}
+
+/// キャレット付近にオーバーレイを表示し、フェードアウトを開始する
+unsafe fn show_overlay(hwnd: HWND, text: &str) {
+    let wide: Vec<u16> = text.encode_utf16().collect();
+    
+    // 文字列の大きさからウィンドウの大きさを決める
+    let mut text_size = SIZE::default();
+    let hdc = GetDC(hwnd);
+    let old_font = SelectObject(hdc, GetStockObject(DEFAULT_GUI_FONT));
+    let _ = GetTextExtentPoint32W(hdc, &wide, &mut text_size);
+    SelectObject(hdc, old_font);
+    ReleaseDC(hwnd, hdc);
+    let size = (text_size.cx + PADDING * 2, text_size.cy + PADDING);
+    
+    let caret = get_caret_position();
+    let mut cursor = POINT::default();
+    let _ = GetCursorPos(&mut cursor);
+    
+    // 基準点があるモニターの作業領域に収める
+    let anchor = caret.map(|(x, y)| POINT { x, y }).unwrap_or(cursor);
+    let mut monitor_info = MONITORINFO {
+        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    let _ = GetMonitorInfoW(MonitorFromPoint(anchor, MONITOR_DEFAULTTONEAREST), &mut monitor_info);
+    let work = monitor_info.rcWork;
+    
+    let (x, y) = compute_overlay_position(
+        caret,
+        (cursor.x, cursor.y),
+        size,
+        (work.left, work.top, work.right, work.bottom),
+    );
+    
+    OVERLAY_TEXT.with(|overlay_text| *overlay_text.borrow_mut() = wide);
+    HOLD_REMAINING.with(|hold| hold.set(HOLD_TICKS));
+    CURRENT_ALPHA.with(|alpha| alpha.set(INITIAL_ALPHA));
+    
+    let _ = SetLayeredWindowAttributes(hwnd, COLORREF(0), INITIAL_ALPHA, LWA_ALPHA);
+    let _ = SetWindowPos(hwnd, HWND_TOPMOST, x, y, size.0, size.1, SWP_NOACTIVATE | SWP_SHOWWINDOW);
+    let _ = InvalidateRect(hwnd, None, true);
+    SetTimer(hwnd, FADE_TIMER_ID, FADE_INTERVAL_MS, None);
+}
+
+/// フォアグラウンドウィンドウのキャレットの左下の位置を取得する
+/// 
+/// キャレットを持たないアプリケーションではNoneを返す
+unsafe fn get_caret_position() -> Option<(i32, i32)> {
+    let mut info = GUITHREADINFO {
+        cbSize: std::mem::size_of::<GUITHREADINFO>() as u32,
+        ..Default::default()
+    };
+    
+    // スレッドIDに0を指定するとフォアグラウンドのスレッドの情報を取得する
+    if !GetGUIThreadInfo(0, &mut info).as_bool() || info.hwndCaret.0 == 0 {
+        return None;
+    }
+    
+    let mut point = POINT {
+        x: info.rcCaret.left,
+        y: info.rcCaret.bottom,
+    };
+    if ClientToScreen(info.hwndCaret, &mut point).as_bool() {
+        Some((point.x, point.y))
+    } else {
+        None
+    }
+}
+
+/// オーバーレイのウィンドウプロシージャ
+extern "system" fn overlay_window_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    unsafe {
+        match msg {
+            WM_PAINT => {
+                let mut paint = PAINTSTRUCT::default();
+                let hdc = BeginPaint(hwnd, &mut paint);
+                
+                let mut rect = RECT {
+                    left: 0,
+                    top: 0,
+                    right: paint.rcPaint.right.max(paint.rcPaint.left),
+                    bottom: paint.rcPaint.bottom.max(paint.rcPaint.top),
+                };
+                let brush = CreateSolidBrush(COLORREF(0x00303030));
+                FillRect(hdc, &rect, brush);
+                let _ = DeleteObject(brush);
+                
+                let old_font = SelectObject(hdc, GetStockObject(DEFAULT_GUI_FONT));
+                SetBkMode(hdc, TRANSPARENT);
+                SetTextColor(hdc, COLORREF(0x00FFFFFF));
+                OVERLAY_TEXT.with(|text| {
+                    DrawTextW(hdc, &mut text.borrow_mut(), &mut rect, DT_CENTER | DT_VCENTER | DT_SINGLELINE);
+                });
+                SelectObject(hdc, old_font);
+                
+                let _ = EndPaint(hwnd, &paint);
+                LRESULT(0)
+            }
+            WM_TIMER if wparam.0 == FADE_TIMER_ID => {
+                let hold = HOLD_REMAINING.with(|hold| hold.get());
+                if hold > 0 {
+                    HOLD_REMAINING.with(|remaining| remaining.set(hold - 1));
+                    return LRESULT(0);
+                }
+                
+                let alpha = CURRENT_ALPHA.with(|alpha| alpha.get()).saturating_sub(FADE_STEP);
+                CURRENT_ALPHA.with(|current| current.set(alpha));
+                
+                if alpha == 0 {
+                    let _ = KillTimer(hwnd, FADE_TIMER_ID);
+                    ShowWindow(hwnd, SW_HIDE);
+                } else {
+                    let _ = SetLayeredWindowAttributes(hwnd, COLORREF(0), alpha, LWA_ALPHA);
+                }
+                LRESULT(0)
+            }
+            _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const WORK_AREA: (i32, i32, i32, i32) = (0, 0, 1920, 1040);
+    
+    #[test]
+    fn test_position_below_caret() {
+        let position = compute_overlay_position(Some((100, 200)), (500, 500), (120, 24), WORK_AREA);
+        assert_eq!(position, (100, 200 + ANCHOR_OFFSET));
+    }
+    
+    #[test]
+    fn test_position_falls_back_to_cursor() {
+        let position = compute_overlay_position(None, (500, 500), (120, 24), WORK_AREA);
+        assert_eq!(position, (500 + ANCHOR_OFFSET, 500 + CURSOR_SIZE));
+    }
+    
+    #[test]
+    fn test_position_flips_above_at_bottom_edge() {
+        let position = compute_overlay_position(Some((100, 1030)), (0, 0), (120, 24), WORK_AREA);
+        assert!(position.1 + 24 <= 1030);
+        
+        let position = compute_overlay_position(None, (100, 1030), (120, 24), WORK_AREA);
+        assert!(position.1 + 24 <= 1030);
+    }
+    
+    #[test]
+    fn test_position_clamped_to_work_area() {
+        let position = compute_overlay_position(Some((1900, 200)), (0, 0), (120, 24), WORK_AREA);
+        assert_eq!(position.0, 1920 - 120);
+        
+        // 別のモニター（負の座標）でも作業領域内に収める
+        let position = compute_overlay_position(Some((-3000, 200)), (0, 0), (120, 24), (-1920, 0, 0, 1040));
+        assert_eq!(position.0, -1920);
+    }
+}
@@ -3,6 +3,7 @@ use egui::{self, Ui};
 // use std::sync::{Arc, Mutex};
 
 use crate::config::{ConfigManager, Settings};
+use crate::config::settings::Hotkey;
 
 /// 設定画面を描画する
 /// 
@@ -13,7 +14,6 @@ use crate::config::{ConfigManager, Settings};
 /// 
 /// # 戻り値
 /// 設定が変更されたかどうか
-#[allow(dead_code)]
 pub fn render_settings_view(
     ui: &mut Ui,
     settings: &mut Settings,
@@ -43,8 +43,14 @@ pub fn render_settings_view(
     ui.add_space(10.0);
     
     // ホットキーの設定
-    ui.label("Toggle Hotkey: Not implemented yet");
-    ui.label("Open Window Hotkey: Not implemented yet");
+    ui.horizontal(|ui| {
+        ui.label("Toggle Hotkey:");
+        changed |= render_hotkey_editor(ui, "toggle_hotkey", &mut settings.toggle_hotkey);
+    });
+    ui.horizontal(|ui| {
+        ui.label("Open Window Hotkey:");
+        changed |= render_hotkey_editor(ui, "open_window_hotkey", &mut settings.open_window_hotkey);
+    });
     
     ui.separator();
     
@@ -60,73 +66,175 @@ pub fn render_settings_view(
 }
 
 /// ホットキーエディタを描画する
-#[allow(dead_code)]
-fn render_hotkey_editor(ui: &mut Ui, hotkey: &mut Option<crate::config::settings::Hotkey>) -> bool {
+/// 
+/// [Set Hotkey] をクリックすると次に押されたキーの組み合わせを記録する。
+/// 他のアプリケーションのショートカットを奪わないよう、F1〜F20以外のキーにはCtrlかAltが必要。
+/// WinキーはWindowsに先に処理されるため記録できない
+/// 
+/// # 引数
+/// * `ui` - EGUIのUIコンテキスト
+/// * `id_source` - 記録中の状態を保持するためのID
+/// * `hotkey` - 編集するホットキー
+/// 
+/// # 戻り値
+/// ホットキーが変更されたかどうか
+pub fn render_hotkey_editor(ui: &mut Ui, id_source: &str, hotkey: &mut Option<Hotkey>) -> bool {
+    let capture_id = ui.make_persistent_id(("hotkey_capture", id_source));
+    let mut capturing = ui.data(|data| data.get_temp::<bool>(capture_id)).unwrap_or(false);
     let mut changed = false;
     
     ui.horizontal(|ui| {
-        if let Some(key) = hotkey {
-            // 修飾キーの設定
-            let mut ctrl = key.modifiers & 1 != 0;
-            let mut alt = key.modifiers & 2 != 0;
-            let mut shift = key.modifiers & 4 != 0;
-            let mut win = key.modifiers & 8 != 0;
+        if capturing {
+            ui.label("Press a key combination (Esc to cancel)...");
             
-            if ui.checkbox(&mut ctrl, "Ctrl").changed() {
-                if ctrl {
-                    key.modifiers |= 1;
-                } else {
-                    key.modifiers &= !1;
-                }
-                changed = true;
-            }
-            
-            if ui.checkbox(&mut alt, "Alt").changed() {
-                if alt {
-                    key.modifiers |= 2;
-                } else {
-                    key.modifiers &= !2;
-                }
-                changed = true;
-            }
+            // 押されたキーを取得（キーリピートは無視する）
+            let pressed = ui.input(|input| {
+                input.events.iter().find_map(|event| match event {
+                    egui::Event::Key { key, pressed: true, repeat: false, modifiers } => Some((*key, *modifiers)),
+                    _ => None,
+                })
+            });
             
-            if ui.checkbox(&mut shift, "Shift").changed() {
-                if shift {
-                    key.modifiers |= 4;
-                } else {
-                    key.modifiers &= !4;
+            match pressed {
+                Some((egui::Key::Escape, _)) => capturing = false,
+                Some((key, modifiers)) => {
+                    if let Some(captured) = hotkey_from_key(key, modifiers) {
+                        *hotkey = Some(captured);
+                        changed = true;
+                        capturing = false;
+                    }
                 }
-                changed = true;
+                None => {}
             }
             
-            if ui.checkbox(&mut win, "Win").changed() {
-                if win {
-                    key.modifiers |= 8;
-                } else {
-                    key.modifiers &= !8;
-                }
-                changed = true;
+            if ui.button("Cancel").clicked() {
+                capturing = false;
             }
-            
-            // キーコードの表示
-            ui.label(format!("Key: {}", key.key_code));
         } else {
+            match hotkey {
+                Some(key) => ui.monospace(key.to_string()),
+                None => ui.weak("Not set"),
+            };
+            
             if ui.button("Set Hotkey").clicked() {
-                *hotkey = Some(crate::config::settings::Hotkey {
-                    modifiers: 0,
-                    key_code: 0,
-                });
-                changed = true;
+                capturing = true;
             }
-        }
-        
-        if hotkey.is_some() {
-            if ui.button("Clear").clicked() {
+            
+            if hotkey.is_some() && ui.button("Clear").clicked() {
                 *hotkey = None;
                 changed = true;
             }
         }
     });
     
+    ui.data_mut(|data| data.insert_temp(capture_id, capturing));
     changed
-} 
\ No newline at end of file
+}
+
+/// 押されたキーからホットキーを作成する
+/// 
+/// # 戻り値
+/// ホットキー（ホットキーにできないキーや、修飾キーが足りない場合はNone）
+fn hotkey_from_key(key: egui::Key, modifiers: egui::Modifiers) -> Option<Hotkey> {
+    let key_code = virtual_key_code(key)?;
+    let is_function_key = (0x70..=0x83).contains(&key_code);
+    if !is_function_key && !modifiers.ctrl && !modifiers.alt {
+        return None;
+    }
+    
+    let mut bits = 0;
+    if modifiers.ctrl {
+        bits |= Hotkey::CTRL;
+    }
+    if modifiers.alt {
+        bits |= Hotkey::ALT;
+    }
+    if modifiers.shift {
+        bits |= Hotkey::SHIFT;
+    }
+    
+    Some(Hotkey { modifiers: bits, key_code })
+}
+
+/// EGUIのキーをWindowsの仮想キーコードに変換する
+fn virtual_key_code(key: egui::Key) -> Option<u32> {
+    use egui::Key;
+    
+    let code = match key {
+        Key::ArrowDown => 0x28,
+        Key::ArrowLeft => 0x25,
+        Key::ArrowRight => 0x27,
+        Key::ArrowUp => 0x26,
+        Key::Escape => return None,
+        Key::Tab => 0x09,
+        Key::Backspace => 0x08,
+        Key::Enter => 0x0D,
+        Key::Space => 0x20,
+        Key::Insert => 0x2D,
+        Key::Delete => 0x2E,
+        Key::Home => 0x24,
+        Key::End => 0x23,
+        Key::PageUp => 0x21,
+        Key::PageDown => 0x22,
+        Key::Minus => 0xBD,
+        Key::PlusEquals => 0xBB,
+        Key::Num0 => 0x30,
+        Key::Num1 => 0x31,
+        Key::Num2 => 0x32,
+        Key::Num3 => 0x33,
+        Key::Num4 => 0x34,
+        Key::Num5 => 0x35,
+        Key::Num6 => 0x36,
+        Key::Num7 => 0x37,
+        Key::Num8 => 0x38,
+        Key::Num9 => 0x39,
+        Key::A => 0x41,
+        Key::B => 0x42,
+        Key::C => 0x43,
+        Key::D => 0x44,
+        Key::E => 0x45,
+        Key::F => 0x46,
+        Key::G => 0x47,
+        Key::H => 0x48,
+        Key::I => 0x49,
+        Key::J => 0x4A,
+        Key::K => 0x4B,
+        Key::L => 0x4C,
+        Key::M => 0x4D,
+        Key::N => 0x4E,
+        Key::O => 0x4F,
+        Key::P => 0x50,
+        Key::Q => 0x51,
+        Key::R => 0x52,
+        Key::S => 0x53,
+        Key::T => 0x54,
+        Key::U => 0x55,
+        Key::V => 0x56,
+        Key::W => 0x57,
+        Key::X => 0x58,
+        Key::Y => 0x59,
+        Key::Z => 0x5A,
+        Key::F1 => 0x70,
+        Key::F2 => 0x71,
+        Key::F3 => 0x72,
+        Key::F4 => 0x73,
+        Key::F5 => 0x74,
+        Key::F6 => 0x75,
+        Key::F7 => 0x76,
+        Key::F8 => 0x77,
+        Key::F9 => 0x78,
+        Key::F10 => 0x79,
+        Key::F11 => 0x7A,
+        Key::F12 => 0x7B,
+        Key::F13 => 0x7C,
+        Key::F14 => 0x7D,
+        Key::F15 => 0x7E,
+        Key::F16 => 0x7F,
+        Key::F17 => 0x80,
+        Key::F18 => 0x81,
+        Key::F19 => 0x82,
+        Key::F20 => 0x83,
+    };
+    
+    Some(code)
+}
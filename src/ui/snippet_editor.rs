@@ -1,5 +1,8 @@
 use egui::{self, Ui};
-use crate::config::settings::{Snippet, SnippetType};
+use crate::config::settings::{Delivery, Snippet, SnippetType, MAX_PRE_DELAY_MS};
+use crate::replacement::formatter::preview_dynamic_content;
+use crate::replacement::keystrokes::parse_keystrokes;
+use crate::utils;
 
 /// キーワードのバリデーション
 /// 
@@ -13,16 +16,41 @@ fn validate_keyword(keyword: &str) -> bool {
     !keyword.contains('=') && !keyword.contains(';') && !keyword.contains(',')
 }
 
+/// スニペットエディタの操作結果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EditorResponse {
+    /// スニペットの内容が変更されたかどうか
+    pub changed: bool,
+    /// 保存ボタンが押されたかどうか
+    pub save_clicked: bool,
+    /// 展開を試すボタンが押されたかどうか
+    pub test_clicked: bool,
+    /// エディタ内で展開結果を確かめるボタンが押されたかどうか
+    pub try_clicked: bool,
+}
+
 /// スニペットエディタを描画する
 /// 
 /// # 引数
 /// * `ui` - EGUIのUIコンテキスト
 /// * `snippet` - 編集対象のスニペット
+/// * `locale` - 動的コンテンツのプレビューに使うロケール
+/// * `date_format` - プレビューで書式を省略した{date}に使う書式
+/// * `sandbox_output` - エディタ内で展開を確かめた結果（まだ確かめていない場合はNone）
 /// 
 /// # 戻り値
-/// スニペットが編集されたかどうか
-pub fn render_snippet_editor(ui: &mut Ui, snippet: &mut Snippet) -> bool {
+/// エディタの操作結果
+pub fn render_snippet_editor(
+    ui: &mut Ui,
+    snippet: &mut Snippet,
+    locale: &str,
+    date_format: &str,
+    sandbox_output: Option<&str>,
+) -> EditorResponse {
     let mut edited = false;
+    let mut save_clicked = false;
+    let mut test_clicked = false;
+    let mut try_clicked = false;
     
     ui.horizontal(|ui| {
         ui.label("Name:");
@@ -57,6 +85,34 @@ pub fn render_snippet_editor(ui: &mut Ui, snippet: &mut Snippet) -> bool {
         edited |= ui.text_edit_singleline(&mut snippet.category).changed();
     });
     
+    ui.label("Description:");
+    edited |= ui.add(
+        egui::TextEdit::multiline(&mut snippet.description)
+            .desired_rows(2)
+            .hint_text("Notes about what this snippet is for (shown when hovering it in the list)"),
+    ).changed();
+    
+    ui.horizontal(|ui| {
+        ui.label("Only in apps:");
+        
+        // 入力途中のカンマが消えないよう、編集中はテキストをそのまま保持する
+        let id = ui.make_persistent_id(("allowed_apps", snippet.id));
+        let mut apps_text = ui.data_mut(|data| data.get_temp::<String>(id))
+            .unwrap_or_else(|| snippet.allowed_apps.join(", "));
+        let response = ui.text_edit_singleline(&mut apps_text)
+            .on_hover_text("Comma-separated process names such as Code.exe, notepad. Leave empty to expand in every app");
+        
+        if response.changed() {
+            snippet.allowed_apps = utils::parse_app_list(&apps_text);
+            edited = true;
+        }
+        if response.has_focus() {
+            ui.data_mut(|data| data.insert_temp(id, apps_text));
+        } else {
+            ui.data_mut(|data| data.remove::<String>(id));
+        }
+    });
+    
     ui.horizontal(|ui| {
         ui.label("Type:");
         
@@ -73,7 +129,32 @@ pub fn render_snippet_editor(ui: &mut Ui, snippet: &mut Snippet) -> bool {
         }
     });
     
-    ui.label("Content:");
+    ui.horizontal(|ui| {
+        ui.label("Delivery:");
+        for delivery in Delivery::ALL {
+            edited |= ui.radio_value(&mut snippet.delivery, delivery, delivery.label()).changed();
+        }
+    }).response.on_hover_text("Auto types short text and pastes long text. Use Type for apps that reject paste, such as terminals");
+    
+    // キーワードを削除する前の待機時間（指定しない場合は展開の速さの設定に従う）
+    ui.horizontal(|ui| {
+        let mut custom_delay = snippet.pre_delay_ms.is_some();
+        if ui.checkbox(&mut custom_delay, "Wait before expanding:").changed() {
+            snippet.pre_delay_ms = custom_delay.then_some(snippet.pre_delay_ms.unwrap_or(500));
+            edited = true;
+        }
+        if let Some(delay) = snippet.pre_delay_ms.as_mut() {
+            edited |= ui.add(egui::DragValue::new(delay).clamp_range(0..=MAX_PRE_DELAY_MS).speed(10.0).suffix(" ms"))
+                .changed();
+        }
+    }).response.on_hover_text("Replaces the wait from the expansion speed setting before the keyword is deleted. Use it for slow targets such as remote desktops");
+    
+    ui.horizontal(|ui| {
+        ui.label("Content:");
+        try_clicked = ui.add_enabled(!snippet.content.is_empty(), egui::Button::new("Try here"))
+            .on_hover_text("Shows what the snippet would expand to in another app, without typing anything")
+            .clicked();
+    });
     let text_height = if snippet.content.contains('\n') { 120.0 } else { 80.0 };
     let response = ui.text_edit_multiline(&mut snippet.content);
     let text_edit_height = response.rect.height();
@@ -82,13 +163,30 @@ pub fn render_snippet_editor(ui: &mut Ui, snippet: &mut Snippet) -> bool {
     }
     edited |= response.changed();
     
+    // エディタ内で確かめた展開結果（本文と区別できるよう、編集できない欄に表示する）
+    if let Some(output) = sandbox_output {
+        ui.label("Expands to:");
+        let mut output = output;
+        ui.add(
+            egui::TextEdit::multiline(&mut output)
+                .font(egui::TextStyle::Monospace)
+                .desired_rows(2)
+                .desired_width(f32::INFINITY),
+        );
+    }
+    
     // 動的コンテンツのヘルプ
     if snippet.snippet_type == SnippetType::Dynamic {
         ui.separator();
         ui.label("Dynamic Content Format:");
-        ui.label("Use {date:format} for date and time:");
+        ui.label("Use {date:format} for date and time, or {date} for the default date format:");
         
         ui.horizontal(|ui| {
+            if ui.button("Date (default)").clicked() {
+                snippet.content += "{date}";
+                edited = true;
+            }
+            
             if ui.button("Date (YYYY/MM/DD)").clicked() {
                 snippet.content += "{date:yyyy/MM/dd}";
                 edited = true;
@@ -103,15 +201,70 @@ pub fn render_snippet_editor(ui: &mut Ui, snippet: &mut Snippet) -> bool {
                 snippet.content += "{date:HH:mm:ss}";
                 edited = true;
             }
+            
+            if ui.button("Clipboard").clicked() {
+                snippet.content += "{clipboard}";
+                edited = true;
+            }
+            
+            if ui.button("UUID").clicked() {
+                snippet.content += "{uuid}";
+                edited = true;
+            }
+        });
+        
+        // 展開したときの内容のプレビュー（時刻が進むよう1秒ごとに描画し直す）
+        ui.label("Preview:");
+        let preview = preview_dynamic_content(&snippet.content, locale, date_format, None);
+        egui::Frame::group(ui.style()).show(ui, |ui| {
+            ui.add(egui::Label::new(egui::RichText::new(preview).monospace()).wrap(true));
         });
+        ui.ctx().request_repaint_after(std::time::Duration::from_secs(1));
     }
     
     ui.separator();
     
+    edited |= ui.checkbox(&mut snippet.send_as_keystrokes, "Send as keystrokes")
+        .on_hover_text("Type the content key by key. Use {key:ctrl+a}, {key:enter} or {key:ctrl+shift+end} to send key combinations. Windows key combinations, Alt+F4, Ctrl+Alt+Del and Ctrl+Shift+Esc are not allowed.")
+        .changed();
+    
+    edited |= ui.checkbox(&mut snippet.word_boundary, "Expand only at word start")
+        .on_hover_text("Do not expand when the keyword is typed right after letters or digits, e.g. 'test1' inside 'contest1'.")
+        .changed();
+    
+    edited |= ui.checkbox(&mut snippet.keep_trigger, "Keep keyword")
+        .on_hover_text("Leave the typed keyword in place and insert the content after it.")
+        .changed();
+    
+    edited |= ui.checkbox(&mut snippet.match_case, "Match case of keyword")
+        .on_hover_text("Typing the keyword as 'Sig' capitalizes the first letter, and 'SIG' expands in all caps.")
+        .changed();
+    
+    if snippet.snippet_type == SnippetType::Static {
+        edited |= ui.checkbox(&mut snippet.process_escapes, "Process escape sequences")
+            .on_hover_text("Expand \\t to a tab, \\n to a new line and \\\\ to a backslash. Tabs are sent with the Tab key when typed.")
+            .changed();
+    }
+    
+    // キー入力として送信する場合は、本文の記述を検証して結果を表示する
+    if snippet.send_as_keystrokes {
+        if let Err(e) = parse_keystrokes(&snippet.content) {
+            ui.colored_label(egui::Color32::RED, format!("⚠ {}", e));
+        }
+    }
+    
     ui.horizontal(|ui| {
-        ui.checkbox(&mut snippet.enabled, "Enabled");
-        edited |= ui.button("Save").clicked();
+        edited |= ui.checkbox(&mut snippet.enabled, "Enabled").changed();
+        save_clicked = ui.button("Save").clicked();
+        test_clicked = ui.add_enabled(!snippet.content.is_empty(), egui::Button::new("Test"))
+            .on_hover_text("Types the content into the window you used before SwiftType, without saving")
+            .clicked();
     });
     
-    edited
+    EditorResponse {
+        changed: edited,
+        save_clicked,
+        test_clicked,
+        try_clicked,
+    }
 }
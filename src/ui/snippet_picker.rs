@@ -0,0 +1,239 @@
+use std::cmp::Reverse;
+use egui::Context;
+
+use crate::config::settings::Snippet;
+
+/// 一覧に表示する候補の最大数
+const MAX_RESULTS: usize = 10;
+
+/// 連続して一致した文字の加点
+const CONSECUTIVE_BONUS: i32 = 3;
+/// 単語の先頭で一致した文字の加点
+const WORD_START_BONUS: i32 = 2;
+/// キーワードで一致した場合の加点（名前や説明より優先する）
+const KEYWORD_BONUS: i32 = 5;
+
+/// スニペットの検索ウィンドウの操作結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickerOutcome {
+    /// 選んだスニペットを挿入する
+    Insert(u64),
+    /// 挿入せずに閉じる
+    Cancel,
+}
+
+/// 検索語に曖昧に一致するスニペットを選んで挿入するウィンドウ
+#[derive(Debug)]
+pub struct SnippetPicker {
+    /// 挿入先のウィンドウ（ウィンドウを開く前に前面にあったウィンドウのハンドル）
+    target_window: isize,
+    /// 入力中の検索語
+    query: String,
+    /// 選択中の候補の位置
+    selected: usize,
+}
+
+impl SnippetPicker {
+    /// 検索ウィンドウを作成する
+    /// 
+    /// # 引数
+    /// * `target_window` - 挿入先のウィンドウのハンドル
+    pub fn new(target_window: isize) -> Self {
+        Self {
+            target_window,
+            query: String::new(),
+            selected: 0,
+        }
+    }
+    
+    /// 挿入先のウィンドウのハンドルを取得する
+    pub fn target_window(&self) -> isize {
+        self.target_window
+    }
+    
+    /// 検索ウィンドウを描画する
+    /// 
+    /// # 引数
+    /// * `ctx` - EGUIのコンテキスト
+    /// * `snippets` - 検索するスニペット
+    /// 
+    /// # 戻り値
+    /// 挿入または取り消しが選ばれた場合はその結果
+    pub fn show(&mut self, ctx: &Context, snippets: &[Snippet]) -> Option<PickerOutcome> {
+        let results: Vec<&Snippet> = rank_snippets(snippets, &self.query)
+            .into_iter()
+            .take(MAX_RESULTS)
+            .collect();
+        
+        // 上下キーで候補を選ぶ
+        if ctx.input(|i| i.key_pressed(egui::Key::ArrowDown)) {
+            self.selected += 1;
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::ArrowUp)) {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        self.selected = self.selected.min(results.len().saturating_sub(1));
+        
+        let mut outcome = None;
+        
+        egui::Window::new("Insert snippet")
+            .collapsible(false)
+            .resizable(false)
+            .title_bar(false)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 40.0])
+            .show(ctx, |ui| {
+                ui.set_width(420.0);
+                
+                let previous_query = self.query.clone();
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text("Search snippets...")
+                        .desired_width(f32::INFINITY),
+                );
+                // 入力中は常に検索欄にフォーカスを置く
+                response.request_focus();
+                if self.query != previous_query {
+                    self.selected = 0;
+                }
+                
+                ui.separator();
+                
+                if results.is_empty() {
+                    ui.weak("No matching snippets");
+                }
+                for (index, snippet) in results.iter().enumerate() {
+                    let label = if snippet.keyword.is_empty() {
+                        snippet.name.clone()
+                    } else {
+                        format!("{}  ({})", snippet.name, snippet.keyword)
+                    };
+                    if ui.selectable_label(index == self.selected, label).clicked() {
+                        outcome = Some(PickerOutcome::Insert(snippet.id));
+                    }
+                }
+            });
+        
+        // Enterで選択中の候補を挿入し、Escで閉じる
+        if outcome.is_none() {
+            if ctx.input(|i| i.key_pressed(egui::Key::Enter)) {
+                outcome = results.get(self.selected).map(|snippet| PickerOutcome::Insert(snippet.id));
+            } else if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+                outcome = Some(PickerOutcome::Cancel);
+            }
+        }
+        
+        outcome
+    }
+}
+
+/// 検索語の文字が順番どおりに含まれていれば、一致の度合いを点数にする
+/// 
+/// 大文字と小文字は区別しない。連続して一致した文字や単語の先頭で一致した文字ほど点数が高い
+/// 
+/// # 引数
+/// * `query` - 検索語
+/// * `text` - 検索対象の文字列
+/// 
+/// # 戻り値
+/// 点数（一致しない場合はNone、検索語が空の場合は0）
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let query: Vec<char> = query.trim().to_lowercase().chars().filter(|c| !c.is_whitespace()).collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+    
+    let mut score = 0;
+    let mut matched = 0;
+    let mut previous_matched = false;
+    let mut previous_char: Option<char> = None;
+    
+    for c in text.to_lowercase().chars() {
+        if matched < query.len() && c == query[matched] {
+            score += 1;
+            if previous_matched {
+                score += CONSECUTIVE_BONUS;
+            }
+            if previous_char.is_none_or(|previous| !previous.is_alphanumeric()) {
+                score += WORD_START_BONUS;
+            }
+            matched += 1;
+            previous_matched = true;
+        } else {
+            previous_matched = false;
+        }
+        previous_char = Some(c);
+    }
+    
+    (matched == query.len()).then_some(score)
+}
+
+/// 有効なスニペットのうち検索語に一致するものを、一致の度合いが高い順に並べる
+/// 
+/// キーワード、名前、説明のうち最も点数の高いものを使う。同じ点数の場合は元の順序を保つ
+/// 
+/// # 引数
+/// * `snippets` - 検索するスニペット
+/// * `query` - 検索語
+pub fn rank_snippets<'a>(snippets: &'a [Snippet], query: &str) -> Vec<&'a Snippet> {
+    let mut scored: Vec<(i32, &Snippet)> = snippets.iter()
+        .filter(|snippet| snippet.enabled)
+        .filter_map(|snippet| {
+            let keyword = fuzzy_score(query, &snippet.keyword).map(|score| score + KEYWORD_BONUS);
+            let name = fuzzy_score(query, &snippet.name);
+            let description = fuzzy_score(query, &snippet.description);
+            [keyword, name, description].into_iter().flatten().max().map(|score| (score, snippet))
+        })
+        .collect();
+    
+    scored.sort_by_key(|(score, _)| Reverse(*score));
+    scored.into_iter().map(|(_, snippet)| snippet).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::settings::SnippetType;
+    
+    fn snippet(name: &str, keyword: &str) -> Snippet {
+        Snippet::new(
+            name.to_string(),
+            keyword.to_string(),
+            String::new(),
+            SnippetType::Static,
+            String::new(),
+        )
+    }
+    
+    #[test]
+    fn test_fuzzy_score_matches_subsequence() {
+        assert!(fuzzy_score("esig", "Email Signature").is_some());
+        assert!(fuzzy_score("ESIG", "email signature").is_some());
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        assert_eq!(fuzzy_score("gis", "Email Signature"), None);
+        
+        // 連続した一致や単語の先頭での一致を優先する
+        assert!(fuzzy_score("sig", "Signature") > fuzzy_score("sig", "s_i_g"));
+        assert!(fuzzy_score("ad", "Address") > fuzzy_score("ad", "road"));
+    }
+    
+    #[test]
+    fn test_rank_snippets_orders_by_score() {
+        let mut disabled = snippet("Signature draft", "sigd");
+        disabled.enabled = false;
+        let snippets = vec![
+            snippet("Date", ";date"),
+            snippet("Email Signature", ";sig"),
+            snippet("Shipping info", ";ship"),
+            disabled,
+        ];
+        
+        let names: Vec<&str> = rank_snippets(&snippets, "sig")
+            .into_iter()
+            .map(|snippet| snippet.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Email Signature", "Shipping info"]);
+        
+        // 検索語が空の場合は有効なスニペットをすべて元の順序で返す
+        assert_eq!(rank_snippets(&snippets, "").len(), 3);
+    }
+}
@@ -1,28 +1,129 @@
 use std::sync::{Arc, Mutex};
 use tray_icon::{
-    menu::{Menu, MenuItem, PredefinedMenuItem, MenuEvent},
+    menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem, MenuEvent, Submenu},
     TrayIcon, TrayIconBuilder, TrayEvent,
 };
 use crossbeam_channel::Receiver;
 use std::io::Cursor;
+use chrono::Duration;
 
 use crate::config::Settings;
+use crate::replacement::ReplacementEngine;
 
-// メニュー項目のインデックス定数
-const MENU_INDEX_SHOW: usize = 0;
-const MENU_INDEX_ENABLED: usize = 1;
-const MENU_INDEX_EXIT: usize = 3; // セパレータがあるため3番目
+/// トレイメニューの項目のID
+/// 
+/// IDはメニュー項目の作成時に割り当てられ、メニュー内の位置とは関係がないため、作成時に記録しておく
+#[derive(Debug, Clone, Copy)]
+struct MenuIds {
+    /// ウィンドウを表示する項目
+    show: u32,
+    /// 展開の有効/無効を切り替える項目
+    enabled: u32,
+    /// 一時停止を解除する項目
+    resume: u32,
+    /// アプリケーションを終了する項目
+    exit: u32,
+}
+
+/// 作成したトレイメニューの項目
+struct TrayMenu {
+    /// 固定の項目のID
+    ids: MenuIds,
+    /// 展開の有効/無効を切り替えるメニュー項目（チェックで現在の状態を示す）
+    enabled_item: CheckMenuItem,
+    /// 一時停止を解除するメニュー項目（一時停止中だけ選択できる）
+    resume_item: MenuItem,
+    /// 一時停止する項目のIDと、一時停止する時間（分）
+    pause_items: Vec<(u32, i64)>,
+    /// スニペットを挿入する項目のIDと、挿入するスニペットのID
+    snippet_items: Vec<(u32, u64)>,
+}
+
+/// [Pause] に表示する一時停止の時間（表示名と分）
+const PAUSE_DURATIONS: [(&str, i64); 3] = [("15 minutes", 15), ("30 minutes", 30), ("1 hour", 60)];
+
+/// メニューに表示するスニペット（IDと表示名）
+type SnippetEntries = Vec<(u64, String)>;
+
+/// 設定からメニューに表示するスニペットを取得する（有効なスニペットだけを設定の順に並べる）
+fn snippet_entries(settings: &Settings) -> SnippetEntries {
+    settings.snippets.iter()
+        .filter(|snippet| snippet.enabled)
+        .map(|snippet| {
+            let label = if snippet.name.trim().is_empty() { &snippet.keyword } else { &snippet.name };
+            (snippet.id, label.clone())
+        })
+        .collect()
+}
+
+/// トレイメニューを作成する
+/// 
+/// # 引数
+/// * `enabled` - 展開が有効かどうか
+/// * `paused` - 展開を一時停止中かどうか
+/// * `snippets` - [Insert snippet] に表示するスニペット
+fn build_menu(enabled: bool, paused: bool, snippets: &[(u64, String)]) -> (Menu, TrayMenu) {
+    let tray_menu = Menu::new();
+    
+    // メニュー項目を作成（シンプルな英語テキストに変更）
+    let show_item = MenuItem::new("Show", true, None);
+    let insert_menu = Submenu::new("Insert snippet", !snippets.is_empty());
+    let enabled_item = CheckMenuItem::new("Enabled", true, enabled, None);
+    let pause_menu = Submenu::new("Pause", true);
+    let resume_item = MenuItem::new("Resume", paused, None);
+    let exit_item = MenuItem::new("Exit", true, None);
+    
+    // キーワードを入力せずに挿入できるスニペットの一覧
+    let mut snippet_items = Vec::with_capacity(snippets.len());
+    for (snippet_id, label) in snippets {
+        let item = MenuItem::new(label, true, None);
+        insert_menu.append(&item);
+        snippet_items.push((item.id(), *snippet_id));
+    }
+    
+    // 一時停止する時間の一覧
+    let mut pause_items = Vec::with_capacity(PAUSE_DURATIONS.len());
+    for (label, minutes) in PAUSE_DURATIONS {
+        let item = MenuItem::new(label, true, None);
+        pause_menu.append(&item);
+        pause_items.push((item.id(), minutes));
+    }
+    
+    // メニューに項目を追加
+    tray_menu.append(&show_item);
+    tray_menu.append(&insert_menu);
+    tray_menu.append(&enabled_item);
+    tray_menu.append(&pause_menu);
+    tray_menu.append(&resume_item);
+    tray_menu.append(&PredefinedMenuItem::separator());
+    tray_menu.append(&exit_item);
+    
+    let ids = MenuIds {
+        show: show_item.id(),
+        enabled: enabled_item.id(),
+        resume: resume_item.id(),
+        exit: exit_item.id(),
+    };
+    
+    (tray_menu, TrayMenu { ids, enabled_item, resume_item, pause_items, snippet_items })
+}
 
 /// トレイアイコンの状態
 pub struct TrayIconState {
     /// トレイアイコン
-    _tray_icon: TrayIcon,
+    tray_icon: TrayIcon,
     /// メニューイベントの受信器
     menu_channel: Receiver<MenuEvent>,
     /// トレイアイコンイベントの受信器
     tray_channel: Receiver<TrayEvent>,
+    /// メニューの項目
+    menu: TrayMenu,
+    /// メニューに表示しているスニペット（変更されたらメニューを作り直す）
+    menu_snippets: SnippetEntries,
     /// アプリケーションの設定
     settings: Arc<Mutex<Settings>>,
+    /// スニペットを挿入する置換エンジン
+    replacement_engine: Arc<Mutex<ReplacementEngine>>,
     /// ウィンドウが表示されているかどうか
     pub show_window: Arc<Mutex<bool>>,
     /// アプリケーションを終了するかどうか
@@ -31,20 +132,16 @@ pub struct TrayIconState {
 
 impl TrayIconState {
     /// 新しいトレイアイコンを作成する
-    pub fn new(settings: Arc<Mutex<Settings>>) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn new(
+        settings: Arc<Mutex<Settings>>,
+        replacement_engine: Arc<Mutex<ReplacementEngine>>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
         // トレイアイコンのメニューを作成
-        let tray_menu = Menu::new();
-        
-        // メニュー項目を作成（シンプルな英語テキストに変更）
-        let show_item = MenuItem::new("Show", true, None);
-        let enabled_item = MenuItem::new("Enable", true, None);
-        let exit_item = MenuItem::new("Exit", true, None);
-        
-        // メニューに項目を追加（インデックス順に追加）
-        tray_menu.append(&show_item);      // インデックス 0
-        tray_menu.append(&enabled_item);   // インデックス 1
-        tray_menu.append(&PredefinedMenuItem::separator()); // インデックス 2
-        tray_menu.append(&exit_item);      // インデックス 3
+        let (enabled, paused, menu_snippets) = match settings.lock() {
+            Ok(settings) => (settings.enabled, settings.is_paused(), snippet_entries(&settings)),
+            Err(_) => (true, false, Vec::new()),
+        };
+        let (tray_menu, menu) = build_menu(enabled, paused, &menu_snippets);
         
         // アイコンデータを作成（デフォルトアイコン）
         let icon = {
@@ -78,40 +175,59 @@ impl TrayIconState {
         let tray_receiver = TrayEvent::receiver().clone();
         
         Ok(Self {
-            _tray_icon: tray_icon,
+            tray_icon,
             menu_channel: menu_receiver,
             tray_channel: tray_receiver,
+            menu,
+            menu_snippets,
             settings,
+            replacement_engine,
             show_window: Arc::new(Mutex::new(true)), // 初期状態ではウィンドウを表示
             should_exit: Arc::new(Mutex::new(false)),
         })
     }
     
     /// トレイアイコンのイベントを処理する
-    pub fn process_events(&mut self) {
+    /// 
+    /// # 戻り値
+    /// 展開の有効と無効の切り替えが選ばれた場合は切り替え後の状態
+    /// （バッファのクリアなども必要なため、切り替えは呼び出し元で行う）
+    pub fn process_events(&mut self) -> Option<bool> {
+        let mut enabled_request = None;
+        
         // メニューイベントを処理
         if let Ok(event) = self.menu_channel.try_recv() {
             log::debug!("Tray menu event received: {:?}", event);
-            // メニューイベントからインデックス（ID）を取得して処理
-            let index = event.id as usize;
-            
-            match index {
-                MENU_INDEX_SHOW => { // Show
-                    if let Ok(mut show_window) = self.show_window.lock() {
-                        *show_window = true;
-                    }
+            // 作成時に記録したIDでクリックされた項目を判別する
+            let ids = self.menu.ids;
+            if event.id == ids.show {
+                if let Ok(mut show_window) = self.show_window.lock() {
+                    *show_window = true;
+                }
+            } else if event.id == ids.enabled {
+                if let Ok(settings) = self.settings.lock() {
+                    enabled_request = Some(!settings.enabled);
                 }
-                MENU_INDEX_ENABLED => { // Enabled
-                    if let Ok(mut settings) = self.settings.lock() {
-                        settings.enabled = !settings.enabled;
-                    }
+            } else if event.id == ids.resume {
+                if let Ok(mut settings) = self.settings.lock() {
+                    settings.resume();
+                    log::info!("Expansion resumed");
                 }
-                MENU_INDEX_EXIT => { // Exit
-                    if let Ok(mut should_exit) = self.should_exit.lock() {
-                        *should_exit = true;
-                    }
+            } else if let Some(&(_, minutes)) = self.menu.pause_items.iter().find(|(id, _)| *id == event.id) {
+                if let Ok(mut settings) = self.settings.lock() {
+                    settings.pause_for(Duration::minutes(minutes));
+                    log::info!("Expansion paused for {} minutes", minutes);
                 }
-                _ => {}
+            } else if event.id == ids.exit {
+                if let Ok(mut should_exit) = self.should_exit.lock() {
+                    *should_exit = true;
+                }
+            } else if let Some(&(_, snippet_id)) = self.menu.snippet_items.iter().find(|(id, _)| *id == event.id) {
+                if let Ok(engine) = self.replacement_engine.lock() {
+                    engine.insert_snippet(snippet_id);
+                }
+            } else {
+                log::debug!("Unknown tray menu item: {}", event.id);
             }
         }
         
@@ -120,6 +236,55 @@ impl TrayIconState {
             log::debug!("Tray icon event received: {:?}", event);
             // 必要に応じてトレイアイコンイベントを処理することができます
         }
+        
+        // スニペットが追加・変更された場合はメニューを作り直す
+        self.sync_snippet_menu();
+        
+        // 設定タブやホットキーで切り替えた場合も含めて、チェックを現在の状態に合わせる
+        self.sync_enabled_item();
+        self.sync_resume_item();
+        
+        enabled_request
+    }
+    
+    /// メニューに表示しているスニペットが設定と異なれば、メニューを作り直す
+    fn sync_snippet_menu(&mut self) {
+        let (enabled, paused, snippets) = match self.settings.lock() {
+            Ok(settings) => (settings.enabled, settings.is_paused(), snippet_entries(&settings)),
+            Err(_) => return,
+        };
+        if snippets == self.menu_snippets {
+            return;
+        }
+        
+        log::debug!("Snippets changed, rebuilding the tray menu");
+        let (tray_menu, menu) = build_menu(enabled, paused, &snippets);
+        self.tray_icon.set_menu(Some(Box::new(tray_menu)));
+        self.menu = menu;
+        self.menu_snippets = snippets;
+    }
+    
+    /// 有効/無効のメニュー項目のチェックを設定に合わせる
+    /// 
+    /// クリックしたときにメニュー側でもチェックが切り替わるため、常に設定の値で上書きする
+    fn sync_enabled_item(&self) {
+        let Ok(settings) = self.settings.lock() else {
+            return;
+        };
+        if self.menu.enabled_item.is_checked() != settings.enabled {
+            self.menu.enabled_item.set_checked(settings.enabled);
+        }
+    }
+    
+    /// 一時停止を解除するメニュー項目を、一時停止中だけ選択できるようにする
+    fn sync_resume_item(&self) {
+        let Ok(settings) = self.settings.lock() else {
+            return;
+        };
+        let paused = settings.is_paused();
+        if self.menu.resume_item.is_enabled() != paused {
+            self.menu.resume_item.set_enabled(paused);
+        }
     }
 }
 
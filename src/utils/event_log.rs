@@ -0,0 +1,170 @@
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+use chrono::{DateTime, Local};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// アプリ内のログに保持する件数
+pub const MAX_LOG_ENTRIES: usize = 200;
+
+/// アプリ内のログに記録する最も詳細なレベル
+/// 
+/// デバッグログはキー入力のたびに出力され、すぐに古い記録が押し出されるため記録しない
+pub const EVENT_LOG_LEVEL: Level = Level::Info;
+
+/// アプリ内のログの1件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    /// 記録した時刻
+    pub time: DateTime<Local>,
+    /// ログのレベル
+    pub level: Level,
+    /// ログのメッセージ
+    pub message: String,
+}
+
+/// 最近のログを一定件数だけ保持するリングバッファ
+#[derive(Debug)]
+pub struct EventLog {
+    /// 記録したログ（新しいものが末尾）
+    entries: Mutex<VecDeque<LogEntry>>,
+    /// 保持する件数
+    capacity: usize,
+}
+
+impl EventLog {
+    /// 新しいログを作成する
+    /// 
+    /// # 引数
+    /// * `capacity` - 保持する件数（超えた場合は古いものから捨てる）
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+    
+    /// ログを追加する
+    pub fn push(&self, entry: LogEntry) {
+        if let Ok(mut entries) = self.entries.lock() {
+            while entries.len() >= self.capacity.max(1) {
+                entries.pop_front();
+            }
+            entries.push_back(entry);
+        }
+    }
+    
+    /// 記録したログを古い順に取得する
+    /// 
+    /// # 引数
+    /// * `level` - 取得する最も詳細なレベル（`LevelFilter::Warn` の場合は警告とエラーだけ）
+    pub fn entries(&self, level: LevelFilter) -> Vec<LogEntry> {
+        self.entries.lock()
+            .map(|entries| entries.iter().filter(|entry| entry.level <= level).cloned().collect())
+            .unwrap_or_default()
+    }
+    
+    /// 記録したログをすべて消去する
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+}
+
+/// アプリ内のログ（ロガーの初期化後に使える）
+static EVENT_LOG: OnceLock<EventLog> = OnceLock::new();
+
+/// アプリ内のログを取得する
+/// 
+/// # 戻り値
+/// アプリ内のログ（ロガーが初期化されていない場合はNone）
+pub fn event_log() -> Option<&'static EventLog> {
+    EVENT_LOG.get()
+}
+
+/// コンソールへの出力に加えて、アプリ内のログにも記録するロガー
+struct EventLogger {
+    /// コンソールへ出力するロガー（`RUST_LOG` の設定に従う）
+    console: env_logger::Logger,
+    /// アプリ内のログ
+    event_log: &'static EventLog,
+}
+
+impl Log for EventLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= EVENT_LOG_LEVEL || self.console.enabled(metadata)
+    }
+    
+    fn log(&self, record: &Record) {
+        if self.console.matches(record) {
+            self.console.log(record);
+        }
+        
+        if record.level() <= EVENT_LOG_LEVEL {
+            self.event_log.push(LogEntry {
+                time: Local::now(),
+                level: record.level(),
+                message: record.args().to_string(),
+            });
+        }
+    }
+    
+    fn flush(&self) {
+        self.console.flush();
+    }
+}
+
+/// ロガーを初期化する
+/// 
+/// `env_logger` と同じくコンソールへ出力し、情報以上のレベルのログをアプリ内のログにも記録する
+pub fn init() {
+    let console = env_logger::Builder::from_default_env().build();
+    let max_level = console.filter().max(EVENT_LOG_LEVEL.to_level_filter());
+    let event_log = EVENT_LOG.get_or_init(|| EventLog::new(MAX_LOG_ENTRIES));
+    
+    if log::set_boxed_logger(Box::new(EventLogger { console, event_log })).is_ok() {
+        log::set_max_level(max_level);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn entry(level: Level, message: &str) -> LogEntry {
+        LogEntry {
+            time: Local::now(),
+            level,
+            message: message.to_string(),
+        }
+    }
+    
+    #[test]
+    fn test_event_log_drops_oldest_entries() {
+        let event_log = EventLog::new(3);
+        for i in 0..5 {
+            event_log.push(entry(Level::Info, &i.to_string()));
+        }
+        
+        let messages: Vec<String> = event_log.entries(LevelFilter::Trace)
+            .into_iter()
+            .map(|entry| entry.message)
+            .collect();
+        assert_eq!(messages, vec!["2", "3", "4"]);
+    }
+    
+    #[test]
+    fn test_event_log_filters_by_level() {
+        let event_log = EventLog::new(10);
+        event_log.push(entry(Level::Info, "expanded"));
+        event_log.push(entry(Level::Warn, "slow"));
+        event_log.push(entry(Level::Error, "failed"));
+        
+        assert_eq!(event_log.entries(LevelFilter::Info).len(), 3);
+        assert_eq!(event_log.entries(LevelFilter::Warn).len(), 2);
+        assert_eq!(event_log.entries(LevelFilter::Error)[0].message, "failed");
+        
+        event_log.clear();
+        assert!(event_log.entries(LevelFilter::Trace).is_empty());
+    }
+}
@@ -1,5 +1,10 @@
+pub mod event_log;
+
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 
+use crate::config::settings::{AutoStartMethod, WindowGeometry};
+
 /// アプリケーションの終了ハンドラ
 /// 
 /// # 引数
@@ -15,6 +20,59 @@ pub fn check_should_exit(should_exit: &Arc<Mutex<bool>>) -> bool {
     }
 }
 
+/// システムのDPIスケールを取得する
+/// 
+/// # 戻り値
+/// 96DPIを1.0とした拡大率
+pub fn get_system_dpi_scale() -> f32 {
+    use windows::Win32::UI::HiDpi::GetDpiForSystem;
+    
+    let dpi = unsafe { GetDpiForSystem() };
+    if dpi == 0 {
+        log::warn!("Failed to get system DPI, assuming 96");
+        return 1.0;
+    }
+    
+    log::debug!("System DPI: {}", dpi);
+    dpi as f32 / 96.0
+}
+
+/// 画面上にあるか確認するウィンドウ上端の高さ（タイトルバーをつかめるかどうかで判断する）
+const TITLE_BAR_HEIGHT: f32 = 32.0;
+
+/// 保存したウィンドウの位置が画面の外にある場合は、プライマリモニターに収まるよう調整する
+/// 
+/// 外部モニターを外した場合などに、見えない位置にウィンドウを開かないようにする
+/// 
+/// # 引数
+/// * `geometry` - 保存したウィンドウの位置とサイズ（物理ピクセル）
+/// 
+/// # 戻り値
+/// 表示するウィンドウの位置とサイズ
+pub fn fit_window_on_screen(geometry: WindowGeometry) -> WindowGeometry {
+    use windows::Win32::Foundation::RECT;
+    use windows::Win32::Graphics::Gdi::{MonitorFromRect, MONITOR_DEFAULTTONULL};
+    use windows::Win32::UI::WindowsAndMessaging::{GetSystemMetrics, SM_CXSCREEN, SM_CYSCREEN};
+    
+    // タイトルバーがいずれかのモニターに入っていれば、そのまま使う
+    let title_bar = RECT {
+        left: geometry.x as i32,
+        top: geometry.y as i32,
+        right: (geometry.x + geometry.width) as i32,
+        bottom: (geometry.y + TITLE_BAR_HEIGHT) as i32,
+    };
+    if unsafe { MonitorFromRect(&title_bar, MONITOR_DEFAULTTONULL) }.0 != 0 {
+        return geometry;
+    }
+    
+    let (width, height) = unsafe { (GetSystemMetrics(SM_CXSCREEN), GetSystemMetrics(SM_CYSCREEN)) };
+    if width <= 0 || height <= 0 {
+        return geometry;
+    }
+    log::info!("Saved window position is off-screen, moving it to the primary monitor");
+    geometry.clamped_to(width as f32, height as f32)
+}
+
 /// 既知のテキスト置換ツールのプロセス名リスト
 const CONFLICTING_TOOLS: &[&str] = &[
     "PhraseExpress.exe",
@@ -28,73 +86,273 @@ const CONFLICTING_TOOLS: &[&str] = &[
     "TyperTask.exe",
 ];
 
+/// 実行中の競合ツール
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictingTool {
+    /// プロセス名
+    pub name: String,
+    /// プロセスID（同じツールが複数実行中の場合は最初に見つかったもの）
+    pub pid: u32,
+}
+
+/// 起動時に確認する競合ツールの一覧を作成する
+/// 
+/// 既知のツールに設定で追加したツールを加え、警告しないツールを除く
+/// 
+/// # 引数
+/// * `extra_tools` - 設定で追加したツールのプロセス名
+/// * `ignored_tools` - 警告しないツールのプロセス名（".exe" は省略してもよい）
+/// 
+/// # 戻り値
+/// 重複を除いたツールのプロセス名の一覧
+pub fn conflict_tool_list(extra_tools: &[String], ignored_tools: &[String]) -> Vec<String> {
+    let mut tools: Vec<String> = Vec::new();
+    for tool in CONFLICTING_TOOLS.iter().map(|tool| tool.to_string()).chain(extra_tools.iter().cloned()) {
+        if !app_list_contains(ignored_tools, &tool) && !app_list_contains(&tools, &tool) {
+            tools.push(tool);
+        }
+    }
+    tools
+}
+
 /// 競合する可能性のあるテキスト置換ツールが実行中かどうかをチェックする
 /// 
+/// # 引数
+/// * `tools` - 確認するツールのプロセス名（".exe" は省略してもよい）
+/// 
 /// # 戻り値
-/// 見つかった競合ツールのリスト
-pub fn check_conflicting_tools() -> Vec<String> {
+/// 見つかった競合ツールのリスト（同じツールは1回だけ含む）
+pub fn check_conflicting_tools(tools: &[String]) -> Vec<ConflictingTool> {
+    find_conflicting_tools(&running_processes(), tools)
+}
+
+/// 実行中のプロセスから競合ツールを探す
+/// 
+/// # 引数
+/// * `processes` - 実行中のプロセスのIDとプロセス名
+/// * `tools` - 確認するツールのプロセス名（".exe" は省略してもよい）
+/// 
+/// # 戻り値
+/// 見つかった競合ツールのリスト（複数のプロセスがある場合も1回だけ含む）
+pub fn find_conflicting_tools(processes: &[(u32, String)], tools: &[String]) -> Vec<ConflictingTool> {
+    let mut found_tools: Vec<ConflictingTool> = Vec::new();
+    for (pid, process_name) in processes {
+        let already_found = found_tools.iter().any(|tool| tool.name.eq_ignore_ascii_case(process_name));
+        if !already_found && app_list_contains(tools, process_name) {
+            found_tools.push(ConflictingTool { name: process_name.clone(), pid: *pid });
+        }
+    }
+    found_tools
+}
+
+/// プロセスIDの一覧を取得するバッファの最初の要素数
+const INITIAL_PROCESS_BUFFER_LEN: usize = 1024;
+
+/// プロセスIDの一覧を取得するバッファの最大の要素数（これ以上は拡張しない）
+const MAX_PROCESS_BUFFER_LEN: usize = 1024 * 64;
+
+/// 実行中のプロセスのIDとプロセス名を取得する
+/// 
+/// 名前を取得できないプロセス（権限が足りないシステムのプロセスなど）は含まない
+fn running_processes() -> Vec<(u32, String)> {
+    running_process_ids()
+        .into_iter()
+        .filter(|&pid| pid != 0)
+        .filter_map(|pid| process_name(pid).map(|name| (pid, name)))
+        .collect()
+}
+
+/// 実行中のプロセスのIDを取得する
+/// 
+/// バッファが一杯になった場合は、取りこぼしがないよう大きなバッファで取得し直す
+fn running_process_ids() -> Vec<u32> {
     use windows::Win32::System::ProcessStatus::EnumProcesses;
+    
+    let mut processes = vec![0u32; INITIAL_PROCESS_BUFFER_LEN];
+    loop {
+        let buffer_size = (processes.len() * std::mem::size_of::<u32>()) as u32;
+        let mut needed: u32 = 0;
+        
+        // プロセスIDのリストを取得
+        if !unsafe { EnumProcesses(processes.as_mut_ptr(), buffer_size, &mut needed) }.as_bool() {
+            log::warn!("Failed to enumerate processes");
+            return Vec::new();
+        }
+        
+        // 書き込まれたサイズがバッファと同じ場合は、入りきらなかったプロセスがある可能性がある
+        if needed < buffer_size || processes.len() >= MAX_PROCESS_BUFFER_LEN {
+            processes.truncate(needed as usize / std::mem::size_of::<u32>());
+            return processes;
+        }
+        processes.resize(processes.len() * 2, 0);
+    }
+}
+
+/// プロセスIDからプロセス名（実行ファイル名）を取得する
+/// 
+/// # 戻り値
+/// "Code.exe" のようなプロセス名（プロセスを開けない場合や取得できない場合はNone）
+fn process_name(pid: u32) -> Option<String> {
     use windows::Win32::System::Threading::{OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ};
     use windows::Win32::System::ProcessStatus::GetModuleBaseNameW;
     use windows::Win32::Foundation::CloseHandle;
     
-    let mut found_tools = Vec::new();
-    
     unsafe {
-        let mut processes = [0u32; 1024];
-        let mut needed: u32 = 0;
+        // プロセスを開く
+        let process = OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_VM_READ, false, pid).ok()?;
         
-        // プロセスIDのリストを取得
-        let enum_result = EnumProcesses(processes.as_mut_ptr(), (processes.len() * std::mem::size_of::<u32>()) as u32, &mut needed);
-        if enum_result.as_bool() {
-            let count = needed as usize / std::mem::size_of::<u32>();
-            
-            for i in 0..count {
-                if processes[i] != 0 {
-                    // プロセスを開く
-                    let process = OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_VM_READ, false, processes[i]);
-                    
-                    if let Ok(process) = process {
-                        let mut name_buf = [0u16; 260]; // MAX_PATH
-                        
-                        // プロセス名を取得
-                        let name_result = GetModuleBaseNameW(process, None, &mut name_buf);
-                        if name_result != 0 {
-                            let len = name_buf.iter().position(|&c| c == 0).unwrap_or(name_buf.len());
-                            let process_name = String::from_utf16_lossy(&name_buf[..len]);
-                            
-                            // 既知の競合ツールとマッチするか確認
-                            for tool in CONFLICTING_TOOLS {
-                                if process_name.eq_ignore_ascii_case(tool) {
-                                    found_tools.push(process_name.clone());
-                                    break;
-                                }
-                            }
-                        }
-                        
-                        // プロセスハンドルを閉じる
-                        CloseHandle(process);
-                    }
-                }
-            }
+        // プロセス名を取得
+        let mut name_buf = [0u16; 260]; // MAX_PATH
+        let name_result = GetModuleBaseNameW(process, None, &mut name_buf);
+        
+        // 名前を取得できたかどうかにかかわらず、プロセスハンドルを閉じる
+        if !CloseHandle(process).as_bool() {
+            log::debug!("Failed to close the handle of process {}", pid);
+        }
+        
+        if name_result == 0 {
+            return None;
         }
+        let len = name_buf.iter().position(|&c| c == 0).unwrap_or(name_buf.len());
+        Some(String::from_utf16_lossy(&name_buf[..len]))
     }
+}
+
+/// 前面のウィンドウのハンドルを取得する
+/// 
+/// # 戻り値
+/// ウィンドウのハンドル（前面のウィンドウがない場合は0）
+pub fn foreground_window() -> isize {
+    use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
     
-    found_tools
+    unsafe { GetForegroundWindow() }.0
+}
+
+/// 最後に調べた前面のウィンドウと、そのプロセス名
+static FOREGROUND_PROCESS: Mutex<Option<(isize, Option<String>)>> = Mutex::new(None);
+
+/// 前面のウィンドウのプロセス名（実行ファイル名）を取得する
+/// 
+/// キーボードフックからキー入力ごとに呼ばれるため、前面のウィンドウが変わるまでは
+/// プロセスを開き直さずに前回の結果を返す
+/// 
+/// # 戻り値
+/// "Code.exe" のようなプロセス名（取得できない場合はNone）
+pub fn get_foreground_process_name() -> Option<String> {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::GetWindowThreadProcessId;
+    
+    let hwnd = foreground_window();
+    if hwnd == 0 {
+        return None;
+    }
+    
+    cached_process_name(&FOREGROUND_PROCESS, hwnd, || {
+        let mut process_id: u32 = 0;
+        unsafe { GetWindowThreadProcessId(HWND(hwnd), Some(&mut process_id)) };
+        if process_id == 0 {
+            return None;
+        }
+        process_name(process_id)
+    })
+}
+
+/// ウィンドウのプロセス名を、ウィンドウが変わったときだけ調べ直す
+/// 
+/// # 引数
+/// * `cache` - 最後に調べたウィンドウとプロセス名
+/// * `hwnd` - 現在のウィンドウのハンドル
+/// * `lookup` - プロセス名を調べる関数
+/// 
+/// # 戻り値
+/// ウィンドウのプロセス名（取得できない場合はNone）
+fn cached_process_name(
+    cache: &Mutex<Option<(isize, Option<String>)>>,
+    hwnd: isize,
+    lookup: impl FnOnce() -> Option<String>,
+) -> Option<String> {
+    let Ok(mut cache) = cache.lock() else {
+        return lookup();
+    };
+    match cache.as_ref() {
+        Some((cached_hwnd, name)) if *cached_hwnd == hwnd => name.clone(),
+        _ => {
+            let name = lookup();
+            *cache = Some((hwnd, name.clone()));
+            name
+        }
+    }
+}
+
+/// Windowsのアプリのテーマがライトかどうかを取得する
+/// 
+/// [個人用設定]-[色] の「既定のアプリ モード」をレジストリから読み取る
+/// 
+/// # 戻り値
+/// ライトならtrue、ダークならfalse（取得できない場合はNone）
+pub fn apps_use_light_theme() -> Option<bool> {
+    use windows::core::w;
+    use windows::Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD};
+    
+    let mut value: u32 = 0;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            w!("Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize"),
+            w!("AppsUseLightTheme"),
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut value as *mut u32 as *mut std::ffi::c_void),
+            Some(&mut size),
+        )
+    };
+    
+    if result.is_err() {
+        log::debug!("Failed to read the system theme: {:?}", result);
+        return None;
+    }
+    Some(value != 0)
+}
+
+/// プロセス名がアプリの一覧に含まれるかどうか
+/// 
+/// 大文字と小文字は区別せず、一覧の名前は ".exe" を省略してもよい
+/// 
+/// # 引数
+/// * `apps` - "code.exe" や "Code" のようなアプリの一覧
+/// * `process_name` - 前面のプロセス名
+pub fn app_list_contains(apps: &[String], process_name: &str) -> bool {
+    let base_name = |name: &str| {
+        let name = name.trim().to_lowercase();
+        name.strip_suffix(".exe").map(str::to_string).unwrap_or(name)
+    };
+    let process_name = base_name(process_name);
+    apps.iter().any(|app| base_name(app) == process_name)
+}
+
+/// カンマ区切りのアプリの一覧を分割する
+pub fn parse_app_list(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|app| !app.is_empty())
+        .map(str::to_string)
+        .collect()
 }
 
 /// 自動起動の設定
 /// 
+/// 有効にする場合は指定した方法で登録し、二重に起動しないよう、もう一方の方法の登録は削除する。
+/// 無効にする場合は、どちらの方法で登録したかに関係なく両方の登録を削除する
+/// 
 /// # 引数
 /// * `enable` - 有効にするかどうか
+/// * `method` - 自動起動の登録方法
 /// 
 /// # 戻り値
 /// 成功したかどうか
-pub fn set_auto_startup(enable: bool) -> Result<(), Box<dyn std::error::Error>> {
+pub fn set_auto_startup(enable: bool, method: AutoStartMethod) -> Result<(), Box<dyn std::error::Error>> {
     use std::env;
-    use windows::Win32::UI::Shell::SHGetFolderPathW;
-    use windows::Win32::UI::Shell::CSIDL_STARTUP;
-    use windows::Win32::Foundation::MAX_PATH;
     
     // 実行ファイルのパスを取得
     let exe_path = match env::current_exe() {
@@ -107,6 +365,36 @@ pub fn set_auto_startup(enable: bool) -> Result<(), Box<dyn std::error::Error>>
     
     log::debug!("Executable path: {:?}", exe_path);
     
+    let use_shortcut = enable && method == AutoStartMethod::StartupFolder;
+    let use_registry = enable && method == AutoStartMethod::Registry;
+    
+    // 使わない方法の登録を先に削除してから登録する
+    if !use_shortcut {
+        set_startup_shortcut(&exe_path, false)?;
+    }
+    if !use_registry {
+        set_run_registry_value(&exe_path, false)?;
+    }
+    if use_shortcut {
+        set_startup_shortcut(&exe_path, true)?;
+    }
+    if use_registry {
+        set_run_registry_value(&exe_path, true)?;
+    }
+    
+    Ok(())
+}
+
+/// スタートアップフォルダのショートカットを作成または削除する
+/// 
+/// # 引数
+/// * `exe_path` - 実行ファイルのパス
+/// * `enable` - 作成するかどうか（falseの場合は削除する）
+fn set_startup_shortcut(exe_path: &Path, enable: bool) -> Result<(), Box<dyn std::error::Error>> {
+    use windows::Win32::UI::Shell::SHGetFolderPathW;
+    use windows::Win32::UI::Shell::CSIDL_STARTUP;
+    use windows::Win32::Foundation::MAX_PATH;
+    
     // スタートアップフォルダのパスを取得
     let mut path_buf = [0u16; MAX_PATH as usize];
     let startup_folder = unsafe {
@@ -131,7 +419,7 @@ pub fn set_auto_startup(enable: bool) -> Result<(), Box<dyn std::error::Error>>
     log::debug!("Startup folder: {}", startup_folder);
     
     // ショートカットファイルのパス
-    let shortcut_path = std::path::Path::new(&startup_folder).join("SwiftType.lnk");
+    let shortcut_path = Path::new(&startup_folder).join("SwiftType.lnk");
     log::debug!("Shortcut path: {:?}", shortcut_path);
     
     if enable {
@@ -165,8 +453,87 @@ pub fn set_auto_startup(enable: bool) -> Result<(), Box<dyn std::error::Error>>
     Ok(())
 }
 
+/// 自動起動を登録するレジストリのキー（HKEY_CURRENT_USER 以下）
+const RUN_KEY: &str = "Software\\Microsoft\\Windows\\CurrentVersion\\Run";
+
+/// 自動起動を登録するレジストリの値の名前
+const RUN_VALUE_NAME: &str = "SwiftType";
+
+/// レジストリの `Run` キーに登録するコマンドライン
+/// 
+/// 空白を含むパスが途中で区切られないよう、実行ファイルのパスを引用符で囲む
+/// 
+/// # 引数
+/// * `exe_path` - 実行ファイルのパス
+pub fn run_command_line(exe_path: &Path) -> String {
+    format!("\"{}\"", exe_path.display())
+}
+
+/// レジストリの `Run` キーに自動起動を登録または削除する
+/// 
+/// # 引数
+/// * `exe_path` - 実行ファイルのパス
+/// * `enable` - 登録するかどうか（falseの場合は削除する）
+fn set_run_registry_value(exe_path: &Path, enable: bool) -> Result<(), Box<dyn std::error::Error>> {
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::{ERROR_ACCESS_DENIED, ERROR_FILE_NOT_FOUND};
+    use windows::Win32::System::Registry::{RegDeleteKeyValueW, RegSetKeyValueW, HKEY_CURRENT_USER, REG_SZ};
+    
+    let key_w = windows_to_wide(RUN_KEY);
+    let value_name_w = windows_to_wide(RUN_VALUE_NAME);
+    
+    if enable {
+        let command_line = run_command_line(exe_path);
+        let data_w = windows_to_wide(&command_line);
+        let result = unsafe {
+            RegSetKeyValueW(
+                HKEY_CURRENT_USER,
+                PCWSTR(key_w.as_ptr()),
+                PCWSTR(value_name_w.as_ptr()),
+                REG_SZ.0,
+                Some(data_w.as_ptr() as *const std::ffi::c_void),
+                (data_w.len() * std::mem::size_of::<u16>()) as u32,
+            )
+        };
+        
+        if result == ERROR_ACCESS_DENIED {
+            log::error!("Access denied while writing HKCU\\{}\\{}", RUN_KEY, RUN_VALUE_NAME);
+            return Err(format!(
+                "Access to HKCU\\{} was denied. Startup entries may be blocked by policy; try the startup folder shortcut instead",
+                RUN_KEY,
+            ).into());
+        }
+        if result.is_err() {
+            log::error!("Failed to write auto-startup registry value: {:?}", result);
+            return Err(format!("Failed to write HKCU\\{}\\{} (error {})", RUN_KEY, RUN_VALUE_NAME, result.0).into());
+        }
+        
+        log::info!("Auto-startup registry value set to {}", command_line);
+    } else {
+        let result = unsafe {
+            RegDeleteKeyValueW(HKEY_CURRENT_USER, PCWSTR(key_w.as_ptr()), PCWSTR(value_name_w.as_ptr()))
+        };
+        
+        if result == ERROR_FILE_NOT_FOUND {
+            log::debug!("Auto-startup registry value doesn't exist, nothing to remove");
+        } else if result == ERROR_ACCESS_DENIED {
+            log::error!("Access denied while removing HKCU\\{}\\{}", RUN_KEY, RUN_VALUE_NAME);
+            return Err(format!("Access to HKCU\\{} was denied while removing the startup entry", RUN_KEY).into());
+        } else if result.is_err() {
+            log::error!("Failed to remove auto-startup registry value: {:?}", result);
+            return Err(format!("Failed to remove HKCU\\{}\\{} (error {})", RUN_KEY, RUN_VALUE_NAME, result.0).into());
+        } else {
+            log::info!("Auto-startup registry value removed successfully");
+        }
+    }
+    
+    Ok(())
+}
+
 /// Windowsショートカットを作成する
 /// 
+/// リンク先は1つのパスとして保存されるため、空白を含むパスも引用符で囲まずに設定する
+/// 
 /// # 引数
 /// * `target_path` - ターゲットファイルのパス
 /// * `shortcut_path` - ショートカットファイルのパス
@@ -253,4 +620,63 @@ fn create_shortcut(target_path: &str, shortcut_path: &str) -> Result<(), Box<dyn
 /// 文字列をワイド文字列に変換する
 fn windows_to_wide(s: &str) -> Vec<u16> {
     s.encode_utf16().chain(std::iter::once(0)).collect()
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_run_command_line_quotes_paths_with_spaces() {
+        let exe_path = Path::new("C:\\Program Files\\SwiftType\\swifttype.exe");
+        assert_eq!(run_command_line(exe_path), "\"C:\\Program Files\\SwiftType\\swifttype.exe\"");
+    }
+    
+    #[test]
+    fn test_cached_process_name_looks_up_once_per_window() {
+        let cache = Mutex::new(None);
+        let lookups = std::cell::Cell::new(0);
+        let lookup = |name: &str| {
+            lookups.set(lookups.get() + 1);
+            Some(name.to_string())
+        };
+        
+        assert_eq!(cached_process_name(&cache, 100, || lookup("Code.exe")), Some("Code.exe".to_string()));
+        assert_eq!(cached_process_name(&cache, 100, || lookup("other.exe")), Some("Code.exe".to_string()));
+        assert_eq!(lookups.get(), 1);
+        
+        // ウィンドウが変わると調べ直す
+        assert_eq!(cached_process_name(&cache, 200, || lookup("notepad.exe")), Some("notepad.exe".to_string()));
+        assert_eq!(lookups.get(), 2);
+    }
+    
+    #[test]
+    fn test_find_conflicting_tools_reports_each_tool_once() {
+        let processes = vec![
+            (4, "System".to_string()),
+            (120, "AutoHotkey.exe".to_string()),
+            (300, "notepad.exe".to_string()),
+            (512, "autohotkey.exe".to_string()),
+            (640, "Breevy.exe".to_string()),
+        ];
+        let tools = vec!["AutoHotkey.exe".to_string(), "breevy".to_string()];
+        
+        assert_eq!(find_conflicting_tools(&processes, &tools), vec![
+            ConflictingTool { name: "AutoHotkey.exe".to_string(), pid: 120 },
+            ConflictingTool { name: "Breevy.exe".to_string(), pid: 640 },
+        ]);
+        assert!(find_conflicting_tools(&processes, &[]).is_empty());
+    }
+    
+    #[test]
+    fn test_conflict_tool_list_extends_and_ignores() {
+        let extra = vec!["MyExpander.exe".to_string(), "breevy".to_string()];
+        let ignored = vec!["AutoHotkey".to_string()];
+        let tools = conflict_tool_list(&extra, &ignored);
+        
+        assert!(tools.iter().any(|tool| tool == "MyExpander.exe"));
+        assert!(!tools.iter().any(|tool| tool == "AutoHotkey.exe"));
+        // 既知のツールと同じものは重複して追加しない
+        assert_eq!(tools.iter().filter(|tool| tool.to_lowercase().starts_with("breevy")).count(), 1);
+    }
+}
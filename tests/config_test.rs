@@ -0,0 +1,585 @@
+use swifttype::config::{ConfigManager, ImportSummary, Settings, AHK_IMPORT_CATEGORY};
+use swifttype::config::settings::{Snippet, SnippetType, UNCATEGORIZED_CATEGORY};
+use swifttype::config::symbols::{STARTER_SYMBOLS, SYMBOL_CATEGORY};
+use swifttype::config::stats::{ExpansionStats, STATS_FILE_NAME, STATS_RETENTION_DAYS};
+use chrono::{Duration, Local, TimeZone};
+use temp_dir::TempDir;
+
+#[test]
+fn test_parse_settings_valid() {
+    // デフォルト設定をシリアライズしたものは有効
+    let json = serde_json::to_string_pretty(&Settings::default()).unwrap();
+    let settings = ConfigManager::parse_settings(&json).expect("valid settings should parse");
+    assert_eq!(settings.snippets.len(), Settings::default().snippets.len());
+}
+
+#[test]
+fn test_parse_settings_sanitizes_keywords() {
+    let mut settings = Settings::default();
+    settings.snippets[0].keyword = "a=b".to_string();
+    let json = serde_json::to_string(&settings).unwrap();
+    
+    let parsed = ConfigManager::parse_settings(&json).unwrap();
+    assert_eq!(parsed.snippets[0].keyword, "a_b");
+}
+
+#[test]
+fn test_parse_settings_invalid_json() {
+    // 3行目のカンマが抜けている
+    let json = "{\n  \"enabled\": true\n  \"snippets\": []\n}";
+    let err = ConfigManager::parse_settings(json).unwrap_err();
+    assert!(err.is_syntax());
+    assert_eq!(err.line(), 3);
+}
+
+#[test]
+fn test_parse_settings_invalid_schema() {
+    // JSONとしては正しいが、設定として必要な項目がない
+    let json = "{\n  \"enabled\": \"yes\"\n}";
+    let err = ConfigManager::parse_settings(json).unwrap_err();
+    assert!(err.is_data());
+    assert_eq!(err.line(), 2);
+}
+
+fn test_manager(dir: &TempDir) -> ConfigManager {
+    ConfigManager::with_path(dir.child("settings.json")).unwrap()
+}
+
+fn snippet(name: &str, keyword: &str) -> Snippet {
+    Snippet::new(
+        name.to_string(),
+        keyword.to_string(),
+        "content".to_string(),
+        SnippetType::Static,
+        "Test".to_string(),
+    )
+}
+
+#[test]
+fn test_default_snippets_have_unique_ids() {
+    let dir = TempDir::new().unwrap();
+    let manager = test_manager(&dir);
+    
+    let mut ids: Vec<u64> = manager.get_settings().snippets.iter().map(|s| s.id).collect();
+    assert!(ids.iter().all(|&id| id != 0));
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), manager.get_settings().snippets.len());
+}
+
+#[test]
+fn test_add_snippet_assigns_id_and_persists() {
+    let dir = TempDir::new().unwrap();
+    let mut manager = test_manager(&dir);
+    
+    let id = manager.add_snippet(snippet("Greeting", "hi;")).unwrap();
+    let added = manager.get_settings().snippets.iter().find(|s| s.id == id).unwrap();
+    assert_eq!(added.keyword, "hi_");
+    
+    // 設定ファイルから読み直しても残っている
+    let reloaded = test_manager(&dir);
+    assert!(reloaded.get_settings().snippets.iter().any(|s| s.id == id && s.name == "Greeting"));
+    assert!(!dir.child("settings.json.tmp").exists());
+}
+
+#[test]
+fn test_add_snippet_warns_duplicate_keyword() {
+    let dir = TempDir::new().unwrap();
+    let mut manager = test_manager(&dir);
+    let count = manager.get_settings().snippets.len();
+    
+    // 重複したキーワードは保存でき、重複の組として報告される
+    manager.add_snippet(snippet("First", "dup")).unwrap();
+    manager.add_snippet(snippet("Second", "dup")).unwrap();
+    // 大文字と小文字を区別しない設定では大文字のキーワードも重複とみなす
+    manager.add_snippet(snippet("Third", "DUP")).unwrap();
+    assert_eq!(
+        manager.get_settings().find_keyword_conflicts(),
+        vec![(count, count + 1), (count, count + 2), (count + 1, count + 2)],
+    );
+    
+    assert!(manager.add_snippet(snippet("", "noname")).is_err());
+    assert!(manager.add_snippet(snippet("Empty", "")).is_err());
+    assert_eq!(manager.get_settings().snippets.len(), count + 3);
+}
+
+#[test]
+fn test_update_snippet() {
+    let dir = TempDir::new().unwrap();
+    let mut manager = test_manager(&dir);
+    let first = manager.add_snippet(snippet("First", "one")).unwrap();
+    manager.add_snippet(snippet("Second", "two")).unwrap();
+    
+    // 自分自身のキーワードはそのまま保存できる
+    manager.update_snippet(first, snippet("Renamed", "one")).unwrap();
+    let updated = manager.get_settings().snippets.iter().find(|s| s.id == first).unwrap();
+    assert_eq!(updated.name, "Renamed");
+    assert_eq!(updated.keyword, "one");
+    
+    // 他のスニペットのキーワードにも変更できる（保存後に重複として警告する）
+    manager.update_snippet(first, snippet("Renamed", "two")).unwrap();
+    assert_eq!(manager.get_settings().find_keyword_conflicts().len(), 1);
+    assert!(manager.update_snippet(9999, snippet("Missing", "missing")).is_err());
+}
+
+#[test]
+fn test_remove_snippet() {
+    let dir = TempDir::new().unwrap();
+    let mut manager = test_manager(&dir);
+    let id = manager.add_snippet(snippet("Temporary", "tmp")).unwrap();
+    
+    let removed = manager.remove_snippet(id).unwrap();
+    assert_eq!(removed.name, "Temporary");
+    assert!(manager.remove_snippet(id).is_err());
+    
+    let reloaded = test_manager(&dir);
+    assert!(reloaded.get_settings().snippets.iter().all(|s| s.id != id));
+}
+
+fn snippet_in(name: &str, keyword: &str, category: &str) -> Snippet {
+    let mut snippet = snippet(name, keyword);
+    snippet.category = category.to_string();
+    snippet
+}
+
+/// サンプルのスニペットを持つ設定マネージャーを作成する
+fn manager_with_categories(dir: &TempDir) -> ConfigManager {
+    let mut manager = test_manager(dir);
+    let mut settings = manager.get_settings().clone();
+    settings.snippets = vec![
+        snippet_in("Hello", "hello", "Greetings"),
+        snippet_in("Bye", "bye", "Greetings"),
+        snippet_in("Address", "addr", "Personal"),
+    ];
+    settings.category_snippet_types.insert("Greetings".to_string(), SnippetType::Dynamic);
+    manager.update_settings(settings).unwrap();
+    manager
+}
+
+fn categories_of(manager: &ConfigManager) -> Vec<&str> {
+    manager.get_settings().snippets.iter().map(|s| s.category.as_str()).collect()
+}
+
+#[test]
+fn test_rename_category_updates_all_snippets() {
+    let dir = TempDir::new().unwrap();
+    let mut manager = manager_with_categories(&dir);
+    
+    let count = manager.rename_category("Greetings", " Salutations ").unwrap();
+    assert_eq!(count, 2);
+    assert_eq!(categories_of(&manager), vec!["Salutations", "Salutations", "Personal"]);
+    assert_eq!(manager.get_settings().default_snippet_type("Salutations"), Some(SnippetType::Dynamic));
+    assert_eq!(manager.get_settings().default_snippet_type("Greetings"), None);
+    
+    // 設定ファイルにも反映されている
+    let reloaded = test_manager(&dir);
+    assert_eq!(categories_of(&reloaded), vec!["Salutations", "Salutations", "Personal"]);
+    
+    assert!(manager.rename_category("Personal", "  ").is_err());
+    assert_eq!(categories_of(&manager), vec!["Salutations", "Salutations", "Personal"]);
+}
+
+#[test]
+fn test_rename_category_merges_into_existing() {
+    let dir = TempDir::new().unwrap();
+    let mut manager = manager_with_categories(&dir);
+    
+    manager.rename_category("Personal", "Greetings").unwrap();
+    assert_eq!(manager.get_settings().categories(), vec!["Greetings".to_string()]);
+}
+
+#[test]
+fn test_delete_category_reassigns_snippets() {
+    let dir = TempDir::new().unwrap();
+    let mut manager = manager_with_categories(&dir);
+    
+    let count = manager.delete_category("Greetings").unwrap();
+    assert_eq!(count, 2);
+    assert_eq!(categories_of(&manager), vec![UNCATEGORIZED_CATEGORY, UNCATEGORIZED_CATEGORY, "Personal"]);
+    assert_eq!(manager.get_settings().snippets.len(), 3);
+    assert_eq!(manager.get_settings().default_snippet_type("Greetings"), None);
+    
+    let reloaded = test_manager(&dir);
+    assert_eq!(categories_of(&reloaded), vec![UNCATEGORIZED_CATEGORY, UNCATEGORIZED_CATEGORY, "Personal"]);
+}
+
+#[test]
+fn test_set_category_snippet_type() {
+    let dir = TempDir::new().unwrap();
+    let mut manager = manager_with_categories(&dir);
+    
+    manager.set_category_snippet_type("Personal", Some(SnippetType::Dynamic)).unwrap();
+    assert_eq!(manager.get_settings().default_snippet_type("Personal"), Some(SnippetType::Dynamic));
+    
+    manager.set_category_snippet_type("Personal", None).unwrap();
+    assert_eq!(manager.get_settings().default_snippet_type("Personal"), None);
+}
+
+#[test]
+fn test_import_ahk_skips_duplicates() {
+    let dir = TempDir::new().unwrap();
+    let mut manager = test_manager(&dir);
+    
+    let script_path = dir.child("hotstrings.ahk");
+    std::fs::write(
+        &script_path,
+        "::btw::by the way\n:*:a=b::equals\n::sig::duplicate of a default snippet\n::btw::again\n::two words::invalid\n",
+    ).unwrap();
+    
+    let summary = manager.import_ahk(&script_path).unwrap();
+    assert_eq!(summary, ImportSummary { imported: 2, skipped_duplicates: 2, skipped_invalid: 1 });
+    
+    let imported: Vec<(&str, &str)> = manager.get_settings().snippets.iter()
+        .filter(|s| s.category == AHK_IMPORT_CATEGORY)
+        .map(|s| (s.keyword.as_str(), s.content.as_str()))
+        .collect();
+    assert_eq!(imported, vec![("btw", "by the way"), ("a_b", "equals")]);
+    
+    // 保存されていることを確認
+    let reloaded = ConfigManager::with_path(dir.child("settings.json")).unwrap();
+    assert_eq!(reloaded.get_settings().snippets.len(), manager.get_settings().snippets.len());
+}
+
+#[test]
+fn test_export_import_snippets_round_trip() {
+    let dir = TempDir::new().unwrap();
+    let mut source = test_manager(&dir);
+    let mut custom = snippet("Custom", "cst");
+    custom.allowed_apps = vec!["Code.exe".to_string()];
+    custom.word_boundary = false;
+    source.add_snippet(custom).unwrap();
+    
+    let export_path = dir.child("snippets.json");
+    source.export_snippets(&export_path).unwrap();
+    
+    // 自動起動などの設定は書き出さない
+    let exported = std::fs::read_to_string(&export_path).unwrap();
+    assert!(exported.trim_start().starts_with('['));
+    assert!(!exported.contains("start_with_system"));
+    
+    let other_dir = TempDir::new().unwrap();
+    let mut target = test_manager(&other_dir);
+    let mut empty = target.get_settings().clone();
+    empty.snippets.clear();
+    target.update_settings(empty).unwrap();
+    
+    let summary = target.import_snippets(&export_path).unwrap();
+    assert_eq!(summary.imported, source.get_settings().snippets.len());
+    assert_eq!(target.get_settings().snippets, source.get_settings().snippets);
+    
+    // 同じファイルをもう一度読み込むと、すべてのキーワードが重複する
+    let count = source.get_settings().snippets.len();
+    assert_eq!(
+        target.import_snippets(&export_path).unwrap(),
+        ImportSummary { imported: 0, skipped_duplicates: count, skipped_invalid: 0 },
+    );
+}
+
+#[test]
+fn test_import_csv() {
+    let dir = TempDir::new().unwrap();
+    let mut manager = test_manager(&dir);
+    
+    let csv_path = dir.child("snippets.csv");
+    std::fs::write(
+        &csv_path,
+        "name,keyword,content,category,type\n\
+         Address,addr,\"1-2-3 Chiyoda\nTokyo\",Templates,Static\n\
+         Blank,,ignored,Templates,Static\n\
+         Semicolon,a;b,text,Templates,\n",
+    ).unwrap();
+    
+    let summary = manager.import_csv(&csv_path).unwrap();
+    assert_eq!(summary, ImportSummary { imported: 2, skipped_duplicates: 0, skipped_invalid: 1 });
+    
+    let snippets = &manager.get_settings().snippets;
+    let address = snippets.iter().find(|s| s.keyword == "addr").unwrap();
+    assert_eq!(address.content, "1-2-3 Chiyoda\nTokyo");
+    assert!(snippets.iter().any(|s| s.keyword == "a_b"));
+}
+
+#[test]
+fn test_duplicate_snippet_avoids_keyword_collisions() {
+    let dir = TempDir::new().unwrap();
+    let mut manager = test_manager(&dir);
+    let id = manager.add_snippet(snippet("Greeting", "hi")).unwrap();
+    
+    let first = manager.duplicate_snippet(id).unwrap();
+    let second = manager.duplicate_snippet(id).unwrap();
+    
+    let find = |id: u64| manager.get_settings().snippets.iter().find(|s| s.id == id).unwrap();
+    assert_eq!(find(first).name, "Greeting (copy)");
+    assert_eq!(find(first).keyword, "hi_copy");
+    assert_eq!(find(first).content, "content");
+    assert_eq!(find(second).keyword, "hi_copy2");
+    assert!(manager.duplicate_snippet(9999).is_err());
+}
+
+#[test]
+fn test_reload_from_disk_applies_external_changes() {
+    let dir = TempDir::new().unwrap();
+    let mut manager = test_manager(&dir);
+    
+    // 自分で保存した内容は変更として扱わない
+    manager.save().unwrap();
+    assert!(manager.reload_from_disk().unwrap().is_none());
+    
+    // 手動で編集された設定は、読み込み時と同じ変換を適用して反映する
+    let mut edited = manager.get_settings().clone();
+    edited.enabled = !edited.enabled;
+    edited.snippets[0].keyword = "a=b".to_string();
+    std::fs::write(dir.child("settings.json"), serde_json::to_string_pretty(&edited).unwrap()).unwrap();
+    
+    let reloaded = manager.reload_from_disk().unwrap().expect("edited settings should be reloaded");
+    assert_eq!(reloaded.enabled, edited.enabled);
+    assert_eq!(reloaded.snippets[0].keyword, "a_b");
+    assert_eq!(manager.get_settings().enabled, edited.enabled);
+    
+    // 不正なJSONの場合はエラーになり、設定は変更しない
+    std::fs::write(dir.child("settings.json"), "{").unwrap();
+    assert!(manager.reload_from_disk().is_err());
+    assert_eq!(manager.get_settings().enabled, edited.enabled);
+}
+
+#[test]
+fn test_backup_and_restore() {
+    let dir = TempDir::new().unwrap();
+    let mut manager = test_manager(&dir);
+    let original_count = manager.get_settings().snippets.len();
+    
+    let backup_path = manager.backup().unwrap();
+    let file_name = backup_path.file_name().unwrap().to_str().unwrap().to_string();
+    assert!(file_name.starts_with("settings.") && file_name.ends_with(".json.bak"), "{}", file_name);
+    assert_eq!(backup_path.parent(), Some(dir.path()));
+    let backup_content = std::fs::read_to_string(&backup_path).unwrap();
+    
+    manager.add_snippet(snippet("Added", "added")).unwrap();
+    assert_eq!(manager.get_settings().snippets.len(), original_count + 1);
+    
+    // 同じ秒のうちに復元しても、復元前のバックアップで元のバックアップを上書きしない
+    let restored = manager.restore(&backup_path).unwrap();
+    assert_eq!(restored.snippets.len(), original_count);
+    assert_eq!(manager.get_settings().snippets.len(), original_count);
+    assert_eq!(std::fs::read_to_string(&backup_path).unwrap(), backup_content);
+}
+
+#[test]
+fn test_backups_in_the_same_second_get_unique_names() {
+    let dir = TempDir::new().unwrap();
+    let manager = test_manager(&dir);
+    
+    let first = manager.backup().unwrap();
+    let second = manager.backup().unwrap();
+    let third = manager.backup().unwrap();
+    
+    assert_ne!(first, second);
+    assert_ne!(second, third);
+    assert_ne!(first, third);
+    for path in [&first, &second, &third] {
+        assert!(path.exists());
+    }
+}
+
+#[test]
+fn test_restore_migrates_old_backups() {
+    let dir = TempDir::new().unwrap();
+    let mut manager = test_manager(&dir);
+    
+    // 古い形式のバックアップ（日本語のタイトルや特殊文字を含むキーワード）
+    let mut old_settings = Settings::default();
+    old_settings.snippets[0].name = "現在時刻".to_string();
+    old_settings.snippets[0].keyword = "t=now".to_string();
+    let backup_path = dir.child("old.json.bak");
+    std::fs::write(&backup_path, serde_json::to_string(&old_settings).unwrap()).unwrap();
+    
+    let restored = manager.restore(&backup_path).unwrap();
+    assert_eq!(restored.snippets[0].name, "Current Time");
+    assert_eq!(restored.snippets[0].keyword, "t_now");
+}
+
+#[test]
+fn test_restore_rejects_invalid_backup() {
+    let dir = TempDir::new().unwrap();
+    let mut manager = test_manager(&dir);
+    let before = manager.get_settings().snippets.len();
+    
+    let backup_path = dir.child("broken.json.bak");
+    std::fs::write(&backup_path, "{ \"enabled\": true").unwrap();
+    
+    assert!(manager.restore(&backup_path).is_err());
+    assert_eq!(manager.get_settings().snippets.len(), before);
+}
+
+#[test]
+fn test_settings_without_profiles_use_default_profile() {
+    // プロファイルがなかった頃の設定ファイル
+    let mut value = serde_json::to_value(Settings::default()).unwrap();
+    value.as_object_mut().unwrap().remove("active_profile");
+    value.as_object_mut().unwrap().remove("profiles");
+    
+    let settings = ConfigManager::parse_settings(&value.to_string()).unwrap();
+    assert_eq!(settings.active_profile, "Default");
+    assert_eq!(settings.snippets.len(), Settings::default().snippets.len());
+    assert!(settings.profiles.is_empty());
+}
+
+#[test]
+fn test_switch_profile_persists() {
+    let dir = TempDir::new().unwrap();
+    let mut manager = test_manager(&dir);
+    let default_count = manager.get_settings().snippets.len();
+    
+    manager.switch_profile("Work").unwrap();
+    manager.add_snippet(snippet("Meeting", "mtg")).unwrap();
+    
+    // 別のプロファイルのキーワードとは重複してもよい
+    manager.switch_profile("Default").unwrap();
+    manager.add_snippet(snippet("Meeting", "mtg")).unwrap();
+    
+    let reloaded = test_manager(&dir);
+    let settings = reloaded.get_settings();
+    assert_eq!(settings.active_profile, "Default");
+    assert_eq!(settings.snippets.len(), default_count + 1);
+    assert_eq!(settings.profiles["Work"].len(), 1);
+    
+    assert!(manager.switch_profile("  ").is_err());
+}
+
+#[test]
+fn test_snippet_ids_are_unique_across_profiles() {
+    let dir = TempDir::new().unwrap();
+    let mut manager = test_manager(&dir);
+    
+    manager.switch_profile("Work").unwrap();
+    let work_id = manager.add_snippet(snippet("Meeting", "mtg")).unwrap();
+    
+    // 保管中のプロファイルのIDとも重複しない
+    manager.switch_profile("Default").unwrap();
+    let default_id = manager.add_snippet(snippet("Meeting", "mtg")).unwrap();
+    assert!(default_id > work_id);
+    
+    // 手動の編集で重複したIDは、読み込み時に振り直す
+    let mut settings = manager.get_settings().clone();
+    settings.profiles.get_mut("Work").unwrap()[0].id = default_id;
+    let parsed = ConfigManager::parse_settings(&serde_json::to_string(&settings).unwrap()).unwrap();
+    let ids: Vec<u64> = parsed.snippets.iter()
+        .chain(parsed.profiles.values().flatten())
+        .map(|s| s.id)
+        .collect();
+    let unique: std::collections::HashSet<u64> = ids.iter().copied().collect();
+    assert_eq!(unique.len(), ids.len());
+    assert_eq!(parsed.snippets.iter().find(|s| s.keyword == "mtg").unwrap().id, default_id);
+}
+
+#[test]
+fn test_settings_without_close_to_tray_minimize_to_tray() {
+    // 閉じるボタンの動作を選べなかった頃の設定ファイル
+    let mut value = serde_json::to_value(Settings::default()).unwrap();
+    value.as_object_mut().unwrap().remove("close_to_tray");
+    
+    let settings = ConfigManager::parse_settings(&value.to_string()).unwrap();
+    assert!(settings.close_to_tray);
+}
+
+#[test]
+fn test_set_snippets_enabled_returns_previous_states() {
+    let dir = TempDir::new().unwrap();
+    let mut manager = test_manager(&dir);
+    let ids: Vec<u64> = manager.get_settings().snippets.iter().map(|s| s.id).collect();
+    assert!(manager.get_settings().snippets.iter().all(|s| s.enabled));
+    
+    let states: Vec<(u64, bool)> = ids.iter().map(|&id| (id, false)).collect();
+    let previous = manager.set_snippets_enabled(&states).unwrap();
+    assert_eq!(previous.len(), ids.len());
+    assert!(manager.get_settings().snippets.iter().all(|s| !s.enabled));
+    
+    // 変更前の状態を渡すと元に戻る
+    manager.set_snippets_enabled(&previous).unwrap();
+    assert!(manager.get_settings().snippets.iter().all(|s| s.enabled));
+    
+    // 変更がなければ何も返さない
+    assert!(manager.set_snippets_enabled(&previous).unwrap().is_empty());
+}
+
+#[test]
+fn test_add_starter_symbols_skips_existing_keywords() {
+    let dir = TempDir::new().unwrap();
+    let mut manager = test_manager(&dir);
+    manager.add_snippet(snippet("My shrug", ":shrug:")).unwrap();
+    let count = manager.get_settings().snippets.len();
+    
+    let summary = manager.add_starter_symbols().unwrap();
+    assert_eq!(summary.imported, STARTER_SYMBOLS.len() - 1);
+    assert_eq!(summary.skipped_duplicates, 1);
+    assert_eq!(manager.get_settings().snippets.len(), count + STARTER_SYMBOLS.len() - 1);
+    
+    let thumbs_up = manager.get_settings().snippets.iter()
+        .find(|snippet| snippet.keyword == ":thumbsup:")
+        .cloned()
+        .unwrap();
+    assert_eq!(thumbs_up.content, "👍");
+    assert_eq!(thumbs_up.category, SYMBOL_CATEGORY);
+    
+    // 2回目はすべて追加済み
+    let summary = manager.add_starter_symbols().unwrap();
+    assert_eq!(summary.imported, 0);
+}
+
+#[test]
+fn test_expansion_stats_counts_per_day_and_snippet() {
+    let now = Local.with_ymd_and_hms(2024, 5, 10, 12, 0, 0).unwrap();
+    let mut stats = ExpansionStats::default();
+    stats.record(1, "Signature", now);
+    stats.record(2, "Date", now);
+    stats.record(1, "Signature", now - Duration::days(1));
+    stats.record(3, "Address", now - Duration::days(3));
+    
+    assert_eq!(stats.total(), 4);
+    assert_eq!(
+        stats.top_snippets(2),
+        vec![("Signature".to_string(), 2), ("Address".to_string(), 1)]
+    );
+    
+    let daily = stats.daily_counts(now.date_naive(), 3);
+    let counts: Vec<usize> = daily.iter().map(|(_, count)| *count).collect();
+    assert_eq!(counts, vec![0, 1, 2]);
+    assert_eq!(daily[2].0, now.date_naive());
+}
+
+#[test]
+fn test_expansion_stats_purges_old_records_on_load() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.child(STATS_FILE_NAME);
+    let now = Local.with_ymd_and_hms(2024, 5, 10, 12, 0, 0).unwrap();
+    
+    let mut stats = ExpansionStats::load(&path, now);
+    assert_eq!(stats.total(), 0);
+    stats.record(2, "Recent", now - Duration::days(1));
+    stats.record(1, "Old", now - Duration::days(STATS_RETENTION_DAYS + 1));
+    stats.save().unwrap();
+    
+    let stats = ExpansionStats::load(&path, now);
+    assert_eq!(stats.top_snippets(10), vec![("Recent".to_string(), 1)]);
+    
+    // 削除した結果はファイルにも保存される
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert!(!saved.contains("Old"));
+}
+
+#[test]
+fn test_expansion_stats_purges_old_records_while_recording() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.child(STATS_FILE_NAME);
+    let now = Local.with_ymd_and_hms(2024, 5, 10, 12, 0, 0).unwrap();
+    
+    // 起動したまま保持する日数を過ぎた記録は、次の展開で削除される
+    let mut stats = ExpansionStats::load(&path, now);
+    stats.record(1, "Old", now);
+    stats.record(2, "Recent", now + Duration::days(STATS_RETENTION_DAYS + 1));
+    assert_eq!(stats.top_snippets(10), vec![("Recent".to_string(), 1)]);
+    
+    // 一時ファイルを残さずに保存する
+    stats.save().unwrap();
+    assert!(std::fs::read_to_string(&path).unwrap().contains("Recent"));
+    assert!(!path.with_extension("json.tmp").exists());
+}
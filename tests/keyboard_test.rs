@@ -1,4 +1,4 @@
-use swifttype::keyboard::KeyboardState;
+use swifttype::keyboard::{Key, KeyTranslation, KeyTranslator, KeyboardState, Modifiers};
 
 #[test]
 fn test_keyboard_state() {
@@ -55,4 +55,268 @@ fn test_replace_keyword() {
     let replaced = keyboard_state.replace_keyword("not_found", "");
     assert!(!replaced);
     assert_eq!(keyboard_state.get_buffer(), "hello ");
-} 
\ No newline at end of file
+}
+
+#[test]
+fn test_pop_char_on_empty_buffer() {
+    let mut keyboard_state = KeyboardState::new(10);
+    
+    // バックスペースの押し続けを想定して、空のバッファから何度も削除してもパニックしない
+    for _ in 0..100 {
+        assert_eq!(keyboard_state.pop_char(), None);
+    }
+    assert_eq!(keyboard_state.get_buffer(), "");
+    
+    // 削除後も通常通り文字を追加できる
+    keyboard_state.add_char('a');
+    assert_eq!(keyboard_state.get_buffer(), "a");
+}
+
+#[test]
+fn test_pop_char_to_empty() {
+    let mut keyboard_state = KeyboardState::new(10);
+    for c in "abc".chars() {
+        keyboard_state.add_char(c);
+    }
+    
+    assert_eq!(keyboard_state.pop_char(), Some('c'));
+    assert_eq!(keyboard_state.get_buffer(), "ab");
+    assert_eq!(keyboard_state.pop_char(), Some('b'));
+    assert_eq!(keyboard_state.pop_char(), Some('a'));
+    assert_eq!(keyboard_state.get_buffer(), "");
+    
+    // 空になった後のキーリピートは無視される
+    assert_eq!(keyboard_state.pop_char(), None);
+    assert_eq!(keyboard_state.get_buffer(), "");
+} 
+
+#[test]
+fn test_set_buffer_size_keeps_latest_chars() {
+    let mut keyboard_state = KeyboardState::new(10);
+    for c in "abcdefghij".chars() {
+        keyboard_state.add_char(c);
+    }
+    
+    // 小さくすると、新しく入力された文字だけが残る
+    keyboard_state.set_buffer_size(4);
+    assert_eq!(keyboard_state.buffer_size(), 4);
+    assert_eq!(keyboard_state.get_buffer(), "ghij");
+    
+    keyboard_state.add_char('k');
+    assert_eq!(keyboard_state.get_buffer(), "hijk");
+    
+    // 大きくしても内容は変わらない
+    keyboard_state.set_buffer_size(8);
+    assert_eq!(keyboard_state.get_buffer(), "hijk");
+}
+
+#[test]
+fn test_to_char_letter_case() {
+    let shift = Modifiers { shift: true, ..Default::default() };
+    let caps_lock = Modifiers { caps_lock: true, ..Default::default() };
+    let both = Modifiers { shift: true, caps_lock: true, ..Default::default() };
+    let key_t = Key::from_virtual_key(0x54);
+    
+    assert_eq!(key_t.to_char(Modifiers::default()), Some('t'));
+    assert_eq!(key_t.to_char(shift), Some('T'));
+    assert_eq!(key_t.to_char(caps_lock), Some('T'));
+    // CAPSLOCK中にSHIFTを押すと小文字になる
+    assert_eq!(key_t.to_char(both), Some('t'));
+    
+    // アルファベット以外はCAPSLOCKの影響を受けない
+    assert_eq!(Key::from_virtual_key(0x31).to_char(caps_lock), Some('1'));
+}
+
+#[test]
+fn test_process_key_event_buffers_uppercase() {
+    let mut keyboard_state = KeyboardState::new(10);
+    let shift = Modifiers { shift: true, ..Default::default() };
+    
+    // WM_KEYDOWNで「Test1」を入力
+    keyboard_state.process_key_event(0x0100, 0x54, shift);
+    for vk in [0x45, 0x53, 0x54, 0x31] {
+        keyboard_state.process_key_event(0x0100, vk, Modifiers::default());
+    }
+    // WM_KEYUPは無視される
+    keyboard_state.process_key_event(0x0101, 0x54, shift);
+    
+    assert_eq!(keyboard_state.get_buffer(), "Test1");
+}
+
+#[test]
+fn test_to_char_shifted_symbols() {
+    let shift = Modifiers { shift: true, ..Default::default() };
+    let shifted = |vk: u32| Key::from_virtual_key(vk).to_char(shift);
+    
+    // US配列のSHIFT+数字と記号
+    assert_eq!(shifted(0x31), Some('!'));
+    assert_eq!(shifted(0x33), Some('#'));
+    assert_eq!(shifted(0x30), Some(')'));
+    assert_eq!(shifted(0xBA), Some(':'));
+    assert_eq!(shifted(0xBF), Some('?'));
+    assert_eq!(shifted(0xDE), Some('"'));
+    
+    // SHIFTなしは従来どおり
+    assert_eq!(Key::from_virtual_key(0x31).to_char(Modifiers::default()), Some('1'));
+    assert_eq!(Key::from_virtual_key(0xBA).to_char(Modifiers::default()), Some(';'));
+    
+    // テンキーはSHIFTの影響を受けない
+    assert_eq!(shifted(0x61), Some('1'));
+}
+
+
+#[test]
+fn test_buffer_cleared_when_foreground_window_changes() {
+    let mut keyboard_state = KeyboardState::new(10);
+    
+    // 最初のウィンドウでは記録するだけ
+    assert!(!keyboard_state.track_foreground_window(100));
+    keyboard_state.add_char('t');
+    keyboard_state.add_char('e');
+    
+    // 同じウィンドウでの入力ではクリアしない
+    assert!(!keyboard_state.track_foreground_window(100));
+    assert_eq!(keyboard_state.get_buffer(), "te");
+    
+    // 別のウィンドウに切り替えるとクリアする
+    assert!(keyboard_state.track_foreground_window(200));
+    assert_eq!(keyboard_state.get_buffer(), "");
+    keyboard_state.add_char('s');
+    assert!(!keyboard_state.track_foreground_window(200));
+    assert_eq!(keyboard_state.get_buffer(), "s");
+}
+
+
+#[test]
+fn test_backspace_removes_last_char() {
+    let mut keyboard_state = KeyboardState::new(10);
+    
+    // "test" と入力して2回バックスペース
+    for vk in [0x54, 0x45, 0x53, 0x54, 0x08, 0x08] {
+        keyboard_state.process_key_event(0x0100, vk, Modifiers::default());
+    }
+    assert_eq!(keyboard_state.get_buffer(), "te");
+    
+    // 続けて入力すると消した位置から追加される
+    keyboard_state.process_key_event(0x0100, 0x58, Modifiers::default());
+    assert_eq!(keyboard_state.get_buffer(), "tex");
+    
+    // WM_KEYUPのバックスペースは無視される
+    keyboard_state.process_key_event(0x0101, 0x08, Modifiers::default());
+    assert_eq!(keyboard_state.get_buffer(), "tex");
+}
+
+#[test]
+fn test_backspace_on_empty_buffer() {
+    let mut keyboard_state = KeyboardState::new(10);
+    
+    keyboard_state.process_key_event(0x0100, 0x41, Modifiers::default());
+    for _ in 0..5 {
+        keyboard_state.process_key_event(0x0100, 0x08, Modifiers::default());
+    }
+    assert_eq!(keyboard_state.get_buffer(), "");
+    
+    keyboard_state.process_key_event(0x0100, 0x42, Modifiers::default());
+    assert_eq!(keyboard_state.get_buffer(), "b");
+}
+
+#[test]
+fn test_shortcut_keys_are_not_buffered() {
+    let mut keyboard_state = KeyboardState::new(10);
+    let shortcut = Modifiers { shortcut: true, ..Default::default() };
+    let shift = Modifiers { shift: true, ..Default::default() };
+    
+    // 従来どおり、設定しなければCTRLなどを押しながらの入力もバッファに加える
+    keyboard_state.process_key_event(0x0100, 0x43, shortcut);
+    assert_eq!(keyboard_state.get_buffer(), "c");
+    
+    // CTRL+Cなどのショートカットは無視し、SHIFTを押しながらの入力は文字として扱う
+    keyboard_state.clear_buffer();
+    keyboard_state.set_ignore_shortcut_keys(true);
+    keyboard_state.process_key_event(0x0100, 0x43, shortcut);
+    keyboard_state.process_key_event(0x0100, 0x41, shift);
+    keyboard_state.process_key_event(0x0100, 0x56, shortcut);
+    keyboard_state.process_key_event(0x0100, 0x42, Modifiers::default());
+    assert_eq!(keyboard_state.get_buffer(), "Ab");
+}
+
+/// キーボード配列を再現するための変換（テスト用）
+#[derive(Debug)]
+struct StubLayout(fn(Key, Modifiers) -> KeyTranslation);
+
+impl KeyTranslator for StubLayout {
+    fn translate(&self, key: Key, modifiers: Modifiers) -> KeyTranslation {
+        (self.0)(key, modifiers)
+    }
+}
+
+/// AZERTY配列の一部を再現する（それ以外のキーは変換できない）
+fn azerty(key: Key, modifiers: Modifiers) -> KeyTranslation {
+    match (key.0, modifiers.shift, modifiers.alt_gr) {
+        (0x51, false, false) => KeyTranslation::Char('a'),
+        (0x31, false, false) => KeyTranslation::Char('&'),
+        (0x31, true, false) => KeyTranslation::Char('1'),
+        (0x30, false, true) => KeyTranslation::Char('@'),
+        (0xDD, false, false) => KeyTranslation::DeadKey,
+        (0x0D, _, _) => KeyTranslation::Char('\r'),
+        _ => KeyTranslation::Unknown,
+    }
+}
+
+#[test]
+fn test_modifiers_key_state() {
+    let state = Modifiers { shift: true, caps_lock: true, ..Default::default() }.key_state();
+    assert_eq!(state[0x10], 0x80);
+    assert_eq!(state[0x14], 0x01);
+    assert_eq!(state[0x11], 0);
+    
+    // AltGrはCTRLとALTを同時に押した状態になる
+    let state = Modifiers { alt_gr: true, shortcut: true, ..Default::default() }.key_state();
+    assert_eq!(state[0x11], 0x80);
+    assert_eq!(state[0x12], 0x80);
+    assert_eq!(state[0x10], 0);
+    
+    // ショートカットのCTRL・ALTは含めない
+    let state = Modifiers { shortcut: true, ..Default::default() }.key_state();
+    assert!(state.iter().all(|&value| value == 0));
+}
+
+#[test]
+fn test_key_translator_uses_layout_characters() {
+    let mut keyboard_state = KeyboardState::new(10);
+    keyboard_state.set_key_translator(Box::new(StubLayout(azerty)));
+    let shift = Modifiers { shift: true, ..Default::default() };
+    
+    for vk in [0x51, 0x31] {
+        keyboard_state.process_key_event(0x0100, vk, Modifiers::default());
+    }
+    keyboard_state.process_key_event(0x0100, 0x31, shift);
+    // 配列で変換できないキーはUS配列として変換する
+    keyboard_state.process_key_event(0x0100, 0x42, Modifiers::default());
+    assert_eq!(keyboard_state.get_buffer(), "a&1b");
+    
+    // 制御キーは配列によらず同じ扱い（エンターでバッファをクリアする）
+    keyboard_state.process_key_event(0x0100, 0x0D, Modifiers::default());
+    assert_eq!(keyboard_state.get_buffer(), "");
+}
+
+#[test]
+fn test_key_translator_alt_gr_and_dead_keys() {
+    let mut keyboard_state = KeyboardState::new(10);
+    keyboard_state.set_key_translator(Box::new(StubLayout(azerty)));
+    keyboard_state.set_ignore_shortcut_keys(true);
+    let alt_gr = Modifiers { alt_gr: true, shortcut: true, ..Default::default() };
+    
+    // AltGrで入力した記号は、ショートカットのキーを無視する設定でも文字として扱う
+    keyboard_state.process_key_event(0x0100, 0x51, Modifiers::default());
+    keyboard_state.process_key_event(0x0100, 0x30, alt_gr);
+    assert_eq!(keyboard_state.get_buffer(), "a@");
+    
+    // 配列で文字が割り当てられていないAltGrの組み合わせは無視する
+    keyboard_state.process_key_event(0x0100, 0x43, alt_gr);
+    assert_eq!(keyboard_state.get_buffer(), "a@");
+    
+    // デッドキーは次のキーと組み合わせた文字がわからないため、バッファを捨てる
+    keyboard_state.process_key_event(0x0100, 0xDD, Modifiers::default());
+    assert_eq!(keyboard_state.get_buffer(), "");
+}
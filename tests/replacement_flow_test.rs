@@ -0,0 +1,1029 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use swifttype::config::Settings;
+use swifttype::config::settings::{Delivery, Snippet, SnippetType, MAX_PRE_DELAY_MS};
+use swifttype::keyboard::{KeyboardState, Modifiers};
+use swifttype::replacement::{InsertMethod, ReplacementEngine, ReplacementOutcome, ABORT_COOLDOWN};
+use swifttype::replacement::backend::InputBackend;
+use swifttype::replacement::clipboard_history::{record_clipboard_text, ClipboardHistory};
+use swifttype::replacement::form::FieldFormRequest;
+use swifttype::replacement::keystrokes::KeyCombo;
+
+/// モックのバックエンドが受け取った操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum InputEvent {
+    Backspaces(usize),
+    Typed(String),
+    Pasted(String),
+    Key(KeyCombo),
+    KeyPresses(u16, usize),
+    ResetModifiers,
+}
+
+/// 受け取った操作を記録するバックエンド（待機はしない）
+#[derive(Debug, Clone, Default)]
+struct MockBackend {
+    events: Arc<Mutex<Vec<InputEvent>>>,
+    waits: Arc<Mutex<Vec<Duration>>>,
+    fail_backspace: bool,
+    fail_type: bool,
+    fail_paste: bool,
+    foreground_app: Option<String>,
+    clipboard_watch: Option<Arc<ClipboardWatch>>,
+}
+
+/// 貼り付けの間にクリップボードの監視が読み取った場合を再現する
+#[derive(Debug)]
+struct ClipboardWatch {
+    history: ClipboardHistory,
+    busy: Mutex<Option<Arc<AtomicBool>>>,
+}
+
+impl MockBackend {
+    fn events(&self) -> Vec<InputEvent> {
+        self.events.lock().unwrap().clone()
+    }
+    
+    fn waits(&self) -> Vec<Duration> {
+        self.waits.lock().unwrap().clone()
+    }
+    
+    fn record(&self, event: InputEvent) {
+        self.events.lock().unwrap().push(event);
+    }
+}
+
+impl InputBackend for MockBackend {
+    fn send_backspaces(&self, count: usize, _is_short_keyword: bool) -> bool {
+        self.record(InputEvent::Backspaces(count));
+        !self.fail_backspace
+    }
+    
+    fn type_text(&self, text: &str) -> bool {
+        self.record(InputEvent::Typed(text.to_string()));
+        !self.fail_type
+    }
+    
+    fn paste_text(&self, text: &str) -> bool {
+        self.record(InputEvent::Pasted(text.to_string()));
+        if let Some(watch) = &self.clipboard_watch {
+            let busy = watch.busy.lock().unwrap().clone();
+            record_clipboard_text(&watch.history, busy.as_deref(), text);
+        }
+        !self.fail_paste
+    }
+    
+    fn send_key_combo(&self, combo: KeyCombo) -> bool {
+        self.record(InputEvent::Key(combo));
+        true
+    }
+    
+    fn send_key_presses(&self, vk: u16, count: usize) -> bool {
+        self.record(InputEvent::KeyPresses(vk, count));
+        true
+    }
+    
+    fn foreground_process_name(&self) -> Option<String> {
+        self.foreground_app.clone()
+    }
+    
+    fn reset_modifier_keys(&self) -> bool {
+        self.record(InputEvent::ResetModifiers);
+        true
+    }
+    
+    fn wait(&self, duration: Duration) {
+        self.waits.lock().unwrap().push(duration);
+    }
+}
+
+/// テスト用のスニペットを1つ登録したエンジンを作成する
+fn engine_with(snippet: Snippet, backend: &MockBackend) -> ReplacementEngine {
+    let settings = Settings {
+        snippets: vec![snippet],
+        enabled: true,
+        ..Default::default()
+    };
+    
+    engine_with_settings(settings, backend)
+}
+
+fn engine_with_settings(settings: Settings, backend: &MockBackend) -> ReplacementEngine {
+    ReplacementEngine::with_backend(Arc::new(Mutex::new(settings)), Box::new(backend.clone()))
+}
+
+fn static_snippet(keyword: &str, content: &str) -> Snippet {
+    Snippet::new(
+        "Test".to_string(),
+        keyword.to_string(),
+        content.to_string(),
+        SnippetType::Static,
+        "Test".to_string(),
+    )
+}
+
+/// ワーカーが実行中の処理を終えるまで待つ
+fn wait_for_worker(busy: &AtomicBool) {
+    let started = Instant::now();
+    while busy.load(Ordering::SeqCst) {
+        assert!(started.elapsed() < Duration::from_secs(5), "worker did not finish");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// バッファから置換を検出して実行する
+fn expand(engine: &ReplacementEngine, buffer: &str) -> bool {
+    let replacement = engine.find_replacement(buffer).expect("keyword should match");
+    engine.perform_replacement(&replacement).succeeded()
+}
+
+#[test]
+fn test_direct_input_path() {
+    let backend = MockBackend::default();
+    let engine = engine_with(static_snippet("sig", "Best regards"), &backend);
+    
+    assert!(expand(&engine, "hello sig"));
+    assert_eq!(backend.events(), vec![
+        InputEvent::Backspaces(3),
+        InputEvent::Typed("Best regards".to_string()),
+    ]);
+}
+
+#[test]
+fn test_backspaces_count_characters_of_long_and_multibyte_keywords() {
+    // 20文字を超えるキーワードもすべて削除する
+    let long_keyword = "abcdefghijklmnopqrstuvwxy";
+    assert_eq!(long_keyword.len(), 25);
+    let backend = MockBackend::default();
+    let engine = engine_with(static_snippet(long_keyword, "long"), &backend);
+    
+    assert!(expand(&engine, long_keyword));
+    assert_eq!(backend.events(), vec![
+        InputEvent::Backspaces(25),
+        InputEvent::Typed("long".to_string()),
+    ]);
+    
+    // 日本語のキーワードはバイト数ではなく文字数だけ削除する
+    let backend = MockBackend::default();
+    let engine = engine_with(static_snippet("おつかれさ", "お疲れさまです"), &backend);
+    
+    assert!(expand(&engine, "おつかれさ"));
+    assert_eq!(backend.events(), vec![
+        InputEvent::Backspaces(5),
+        InputEvent::Typed("お疲れさまです".to_string()),
+    ]);
+    assert_eq!(engine.check_for_replacements("おつかれさ").map(|(_, length)| length), Some(5));
+}
+
+#[test]
+fn test_clipboard_fallback_when_direct_input_fails() {
+    let backend = MockBackend { fail_type: true, ..Default::default() };
+    let engine = engine_with(static_snippet("sig", "Best regards"), &backend);
+    
+    assert!(expand(&engine, "sig"));
+    assert_eq!(backend.events(), vec![
+        InputEvent::Backspaces(3),
+        InputEvent::Typed("Best regards".to_string()),
+        InputEvent::Pasted("Best regards".to_string()),
+    ]);
+}
+
+#[test]
+fn test_long_text_uses_clipboard() {
+    let long_text = "a".repeat(51);
+    let backend = MockBackend::default();
+    let engine = engine_with(static_snippet("long", &long_text), &backend);
+    
+    assert!(expand(&engine, "long"));
+    assert_eq!(backend.events(), vec![
+        InputEvent::Backspaces(4),
+        InputEvent::Pasted(long_text),
+    ]);
+}
+
+#[test]
+fn test_backspace_failure_inserts_nothing() {
+    let backend = MockBackend { fail_backspace: true, ..Default::default() };
+    let engine = engine_with(static_snippet("sig", "Best regards"), &backend);
+    
+    assert!(!expand(&engine, "sig"));
+    assert_eq!(backend.events(), vec![InputEvent::Backspaces(3)]);
+}
+
+#[test]
+fn test_all_insert_methods_fail() {
+    let backend = MockBackend { fail_type: true, fail_paste: true, ..Default::default() };
+    let engine = engine_with(static_snippet("sig", "Best regards"), &backend);
+    
+    assert!(!expand(&engine, "sig"));
+    assert_eq!(backend.events(), vec![
+        InputEvent::Backspaces(3),
+        InputEvent::Typed("Best regards".to_string()),
+        InputEvent::Pasted("Best regards".to_string()),
+    ]);
+}
+
+#[test]
+fn test_keystroke_snippet_sends_tokens_in_order() {
+    let mut snippet = static_snippet("sel", "{key:ctrl+a}Hi{key:enter}");
+    snippet.send_as_keystrokes = true;
+    let backend = MockBackend::default();
+    let engine = engine_with(snippet, &backend);
+    
+    assert!(expand(&engine, "sel"));
+    assert_eq!(backend.events(), vec![
+        InputEvent::Backspaces(3),
+        InputEvent::Key(KeyCombo { ctrl: true, vk: 0x41, ..Default::default() }),
+        InputEvent::Typed("Hi".to_string()),
+        InputEvent::Key(KeyCombo { vk: 0x0D, ..Default::default() }),
+    ]);
+}
+
+#[test]
+fn test_invalid_keystroke_snippet_sends_nothing() {
+    let mut snippet = static_snippet("bad", "{key:alt+f4}");
+    snippet.send_as_keystrokes = true;
+    let backend = MockBackend::default();
+    let engine = engine_with(snippet, &backend);
+    
+    assert!(!expand(&engine, "bad"));
+    assert!(backend.events().is_empty());
+}
+
+#[test]
+fn test_cursor_marker_moves_caret_back() {
+    let backend = MockBackend::default();
+    let engine = engine_with(static_snippet("dear", "Dear {cursor},\n様"), &backend);
+    
+    assert!(expand(&engine, "dear"));
+    assert_eq!(backend.events(), vec![
+        InputEvent::Backspaces(4),
+        InputEvent::Typed("Dear ,\n様".to_string()),
+        InputEvent::KeyPresses(0x25, 3),
+    ]);
+}
+
+#[test]
+fn test_field_values_are_filled_before_expansion() {
+    let backend = MockBackend::default();
+    let engine = engine_with(static_snippet("reply", "Hi {field:Name}, #{field:Ticket}{cursor} ({field:Name})"), &backend);
+    
+    let replacement = engine.find_replacement("reply").unwrap();
+    assert_eq!(replacement.fields, vec!["Name".to_string(), "Ticket".to_string()]);
+    
+    let values: HashMap<String, String> = [
+        ("Name".to_string(), "Sato".to_string()),
+        ("Ticket".to_string(), "42".to_string()),
+    ].into();
+    assert!(engine.perform_replacement(&replacement.with_field_values(&values)).succeeded());
+    assert_eq!(backend.events(), vec![
+        InputEvent::Backspaces(5),
+        InputEvent::Typed("Hi Sato, #42 (Sato)".to_string()),
+        InputEvent::KeyPresses(0x25, 7),
+    ]);
+}
+
+#[test]
+fn test_field_form_completion_runs_on_worker() {
+    let backend = MockBackend::default();
+    let engine = Arc::new(Mutex::new(engine_with(static_snippet("reply", "Hi {field:Name}"), &backend)));
+    ReplacementEngine::start_worker(&engine);
+    let busy = engine.lock().unwrap().worker_busy_flag().expect("worker should be running");
+    
+    let request = FieldFormRequest {
+        replacement: engine.lock().unwrap().find_replacement("reply").unwrap(),
+        target_window: 0,
+    };
+    let values: HashMap<String, String> = [("Name".to_string(), "Sato".to_string())].into();
+    
+    // 展開の間は実行中のフラグが立ち、キーボードフックは入力を捨てる
+    {
+        let locked = engine.lock().unwrap();
+        assert!(locked.complete_field_form(&request, &values));
+        assert!(busy.load(Ordering::SeqCst));
+    }
+    wait_for_worker(&busy);
+    
+    assert_eq!(backend.events(), vec![
+        InputEvent::Backspaces(5),
+        InputEvent::Typed("Hi Sato".to_string()),
+    ]);
+    // フォーカスを戻した後、待ってから入力する
+    assert!(backend.waits().contains(&Duration::from_millis(300)));
+}
+
+#[test]
+fn test_allowed_apps_limit_expansion() {
+    let mut snippet = static_snippet("cmt", "// TODO: ");
+    snippet.allowed_apps = vec!["code".to_string(), "devenv.exe".to_string()];
+    
+    let in_app = |app: Option<&str>| {
+        let backend = MockBackend { foreground_app: app.map(str::to_string), ..Default::default() };
+        engine_with(snippet.clone(), &backend).find_replacement("cmt").is_some()
+    };
+    
+    // 大文字と小文字を区別せず、".exe" の有無も問わない
+    assert!(in_app(Some("Code.exe")));
+    assert!(in_app(Some("DEVENV.EXE")));
+    assert!(!in_app(Some("notepad.exe")));
+    assert!(!in_app(None));
+}
+
+#[test]
+fn test_disabled_apps_suppress_expansion() {
+    let settings = Settings {
+        disabled_apps: vec!["KeePass.exe".to_string()],
+        ..Default::default()
+    };
+    
+    let disabled_in = |app: Option<&str>| {
+        let backend = MockBackend { foreground_app: app.map(str::to_string), ..Default::default() };
+        engine_with_settings(settings.clone(), &backend).is_disabled_in_foreground_app()
+    };
+    
+    assert!(disabled_in(Some("keepass.EXE")));
+    assert!(!disabled_in(Some("notepad.exe")));
+    assert!(!disabled_in(None));
+}
+
+#[test]
+fn test_successful_expansion_counts_usage() {
+    let mut settings = Settings {
+        snippets: vec![static_snippet("sig", "Best regards")],
+        ..Default::default()
+    };
+    settings.snippets[0].id = 7;
+    let settings = Arc::new(Mutex::new(settings));
+    let usage_count = || settings.lock().unwrap().snippets[0].usage_count;
+    
+    let backend = MockBackend::default();
+    let engine = ReplacementEngine::with_backend(Arc::clone(&settings), Box::new(backend));
+    assert!(expand(&engine, "sig"));
+    assert!(expand(&engine, "sig"));
+    assert_eq!(usage_count(), 2);
+    // 設定ファイルへの保存はUIスレッドに任せる
+    assert!(engine.usage_changed_flag().load(Ordering::SeqCst));
+    
+    // 失敗した展開は数えない
+    let failing = MockBackend { fail_backspace: true, ..Default::default() };
+    let engine = ReplacementEngine::with_backend(Arc::clone(&settings), Box::new(failing));
+    assert!(!expand(&engine, "sig"));
+    assert_eq!(usage_count(), 2);
+    assert!(!engine.usage_changed_flag().load(Ordering::SeqCst));
+}
+
+#[test]
+fn test_undo_last_expansion() {
+    let backend = MockBackend::default();
+    let engine = engine_with(static_snippet("dear", "Dear {cursor},\r\n様"), &backend);
+    
+    assert!(expand(&engine, "dear"));
+    backend.events.lock().unwrap().clear();
+    
+    assert!(engine.undo_last_expansion());
+    assert_eq!(backend.events(), vec![
+        InputEvent::ResetModifiers,
+        InputEvent::KeyPresses(0x27, 3),
+        InputEvent::KeyPresses(0x08, 8),
+        InputEvent::Typed("dear".to_string()),
+    ]);
+    
+    // 取り消しは1回だけ
+    assert!(!engine.undo_last_expansion());
+}
+
+#[test]
+fn test_undo_unavailable_after_typing_or_keystrokes() {
+    let backend = MockBackend::default();
+    let engine = engine_with(static_snippet("sig", "Best regards"), &backend);
+    assert!(expand(&engine, "sig"));
+    engine.clear_last_expansion();
+    assert!(!engine.undo_last_expansion());
+    
+    let mut snippet = static_snippet("sel", "{key:ctrl+a}");
+    snippet.send_as_keystrokes = true;
+    let engine = engine_with(snippet, &backend);
+    assert!(expand(&engine, "sel"));
+    assert!(!engine.undo_last_expansion());
+}
+
+#[test]
+fn test_worker_runs_replacements_one_at_a_time() {
+    let backend = MockBackend::default();
+    let engine = Arc::new(Mutex::new(engine_with(static_snippet("sig", "Best regards"), &backend)));
+    ReplacementEngine::start_worker(&engine);
+    
+    let busy = engine.lock().unwrap().worker_busy_flag().expect("worker should be running");
+    let replacement = engine.lock().unwrap().find_replacement("sig").unwrap();
+    
+    // ワーカーがエンジンを使えないようにロックしたまま依頼し、実行中の依頼は受け付けないことを確認する
+    {
+        let locked = engine.lock().unwrap();
+        assert!(locked.queue_replacement(replacement.clone()));
+        assert!(busy.load(Ordering::SeqCst));
+        assert!(!locked.queue_replacement(replacement.clone()));
+    }
+    
+    wait_for_worker(&busy);
+    
+    assert_eq!(backend.events(), vec![
+        InputEvent::Backspaces(3),
+        InputEvent::Typed("Best regards".to_string()),
+    ]);
+}
+
+#[test]
+fn test_worker_runs_undo_and_test_expansion() {
+    let backend = MockBackend::default();
+    let snippet = static_snippet("sig", "Best regards");
+    let engine = Arc::new(Mutex::new(engine_with(snippet.clone(), &backend)));
+    ReplacementEngine::start_worker(&engine);
+    let busy = engine.lock().unwrap().worker_busy_flag().expect("worker should be running");
+    
+    // 展開のテストも実行中のフラグを立ててワーカーで入力し、結果を送り返す
+    let (sender, receiver) = crossbeam_channel::bounded(1);
+    {
+        let locked = engine.lock().unwrap();
+        assert!(locked.queue_test_expansion(snippet.clone(), sender));
+        assert!(busy.load(Ordering::SeqCst));
+        assert!(!locked.queue_undo());
+    }
+    assert_eq!(receiver.recv_timeout(Duration::from_secs(5)).unwrap(), Some(InsertMethod::Typed));
+    wait_for_worker(&busy);
+    
+    // 取り消しもワーカーで実行する
+    assert!(expand(&engine.lock().unwrap(), "sig"));
+    backend.events.lock().unwrap().clear();
+    {
+        let locked = engine.lock().unwrap();
+        assert!(locked.queue_undo());
+        assert!(busy.load(Ordering::SeqCst));
+    }
+    wait_for_worker(&busy);
+    assert_eq!(backend.events().last(), Some(&InputEvent::Typed("sig".to_string())));
+}
+
+#[test]
+fn test_insert_snippet_without_keyword() {
+    let backend = MockBackend::default();
+    let snippet = static_snippet("sig", "Best regards");
+    let snippet_id = snippet.id;
+    let engine = engine_with(snippet, &backend);
+    
+    // キーワードを入力していないため、バックスペースは送らずに挿入だけを行う
+    assert!(engine.insert_snippet(snippet_id));
+    assert_eq!(backend.events(), vec![
+        InputEvent::Typed("Best regards".to_string()),
+    ]);
+    
+    assert!(!engine.insert_snippet(snippet_id + 1));
+}
+
+#[test]
+fn test_only_active_profile_snippets_expand() {
+    let backend = MockBackend::default();
+    let mut settings = Settings {
+        enabled: true,
+        snippets: vec![static_snippet("sig", "Best regards")],
+        ..Default::default()
+    };
+    settings.profiles.insert("Work".to_string(), vec![static_snippet("mtg", "Let's meet")]);
+    let settings = Arc::new(Mutex::new(settings));
+    let engine = ReplacementEngine::with_backend(Arc::clone(&settings), Box::new(backend.clone()));
+    
+    assert!(engine.find_replacement("mtg").is_none());
+    assert!(engine.find_replacement("sig").is_some());
+    
+    // 切り替えた後は再起動せずに新しいプロファイルのスニペットだけが展開される
+    settings.lock().unwrap().switch_profile("Work");
+    assert!(engine.find_replacement("mtg").is_some());
+    assert!(engine.find_replacement("sig").is_none());
+}
+
+#[test]
+fn test_test_expansion_reports_insert_method() {
+    let backend = MockBackend::default();
+    let snippet = static_snippet("sig", "Best regards");
+    let engine = engine_with(snippet.clone(), &backend);
+    
+    // キーワードは入力されていないため、バックスペースは送らない
+    assert_eq!(engine.test_expansion(&snippet), Some(InsertMethod::Typed));
+    assert_eq!(backend.events(), vec![InputEvent::Typed("Best regards".to_string())]);
+    
+    let backend = MockBackend { fail_type: true, ..Default::default() };
+    let engine = engine_with(snippet.clone(), &backend);
+    assert_eq!(engine.test_expansion(&snippet), Some(InsertMethod::Pasted));
+    
+    let backend = MockBackend { fail_type: true, fail_paste: true, ..Default::default() };
+    let engine = engine_with(snippet.clone(), &backend);
+    assert_eq!(engine.test_expansion(&snippet), None);
+}
+
+#[test]
+fn test_terminator_is_removed_with_keyword() {
+    let backend = MockBackend::default();
+    let settings = Settings {
+        snippets: vec![static_snippet("sig", "Best regards")],
+        require_terminator: true,
+        ..Default::default()
+    };
+    let engine = engine_with_settings(settings, &backend);
+    
+    assert!(expand(&engine, "hello sig "));
+    assert_eq!(backend.events(), vec![
+        InputEvent::Backspaces(4),
+        InputEvent::Typed("Best regards".to_string()),
+    ]);
+}
+
+#[test]
+fn test_kept_terminator_is_typed_after_expansion() {
+    let backend = MockBackend::default();
+    let settings = Settings {
+        snippets: vec![static_snippet("dear", "Dear {cursor}様")],
+        require_terminator: true,
+        keep_terminator: true,
+        terminator_chars: " .".to_string(),
+        ..Default::default()
+    };
+    let engine = engine_with_settings(settings, &backend);
+    
+    // カーソル位置へ戻るときは、入力し直した区切り文字の分も戻る
+    assert!(expand(&engine, "dear."));
+    assert_eq!(backend.events(), vec![
+        InputEvent::Backspaces(5),
+        InputEvent::Typed("Dear 様".to_string()),
+        InputEvent::Typed(".".to_string()),
+        InputEvent::KeyPresses(0x25, 2),
+    ]);
+}
+
+#[test]
+fn test_no_expansion_while_paused() {
+    let backend = MockBackend::default();
+    let mut settings = Settings {
+        snippets: vec![static_snippet("sig", "Best regards")],
+        ..Default::default()
+    };
+    settings.pause_for(chrono::Duration::minutes(30));
+    let settings = Arc::new(Mutex::new(settings));
+    let engine = ReplacementEngine::with_backend(Arc::clone(&settings), Box::new(backend.clone()));
+    
+    assert!(engine.find_replacement("hello sig").is_none());
+    
+    // 期限が過ぎると次の確認で再開する
+    settings.lock().unwrap().paused_until = Some(chrono::Local::now() - chrono::Duration::seconds(1));
+    assert!(engine.find_replacement("hello sig").is_some());
+    assert_eq!(settings.lock().unwrap().paused_until, None);
+}
+
+#[test]
+fn test_abort_keyword_resets_state_and_stops_expansion() {
+    let backend = MockBackend::default();
+    let engine = engine_with(static_snippet("sig", "Best regards"), &backend);
+    
+    assert!(!engine.check_abort("hello sig"));
+    assert!(engine.check_abort("oops xxstop"));
+    assert_eq!(backend.events(), vec![InputEvent::ResetModifiers]);
+    
+    // 中止した直後は展開しない
+    assert!(engine.find_replacement("hello sig").is_none());
+    std::thread::sleep(ABORT_COOLDOWN);
+    assert!(engine.find_replacement("hello sig").is_some());
+}
+
+#[test]
+fn test_empty_abort_keyword_is_ignored() {
+    let backend = MockBackend::default();
+    let settings = Settings {
+        snippets: vec![static_snippet("sig", "Best regards")],
+        abort_keyword: String::new(),
+        ..Default::default()
+    };
+    let engine = engine_with_settings(settings, &backend);
+    
+    assert!(!engine.check_abort("oops xxstop"));
+    assert!(!engine.check_abort(""));
+    assert!(backend.events().is_empty());
+}
+
+#[test]
+fn test_dry_run_records_without_typing() {
+    let backend = MockBackend::default();
+    let settings = Settings {
+        snippets: vec![static_snippet("sig", "Best regards")],
+        dry_run: true,
+        ..Default::default()
+    };
+    let settings = Arc::new(Mutex::new(settings));
+    let engine = ReplacementEngine::with_backend(Arc::clone(&settings), Box::new(backend.clone()));
+    
+    let replacement = engine.find_replacement("hello sig").unwrap();
+    assert_eq!(engine.perform_replacement(&replacement), ReplacementOutcome::Recorded);
+    assert!(backend.events().is_empty());
+    
+    // 記録だけのため展開の通知はせず、使用回数も増やさない
+    assert!(engine.run_replacement(&replacement));
+    assert!(backend.events().is_empty());
+    assert_eq!(settings.lock().unwrap().snippets[0].usage_count, 0);
+    
+    let events = engine.dry_run_log();
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].trigger, "sig");
+    assert_eq!(events[0].backspaces, 3);
+    assert_eq!(events[0].text, "Best regards");
+    
+    // 入力していないため取り消しの対象にもならない
+    assert!(!engine.undo_last_expansion());
+    assert!(backend.events().is_empty());
+}
+
+#[test]
+fn test_longest_overlapping_keyword_wins_regardless_of_order() {
+    let backend = MockBackend::default();
+    let mut short = static_snippet("st", "short");
+    short.word_boundary = false;
+    let long = static_snippet("test", "long");
+    
+    for snippets in [vec![short.clone(), long.clone()], vec![long.clone(), short.clone()]] {
+        let settings = Settings {
+            snippets,
+            ..Default::default()
+        };
+        let engine = engine_with_settings(settings, &backend);
+        
+        let replacement = engine.find_replacement("test").unwrap();
+        assert_eq!(replacement.keyword, "test");
+        assert_eq!(replacement.text, "long");
+    }
+}
+
+#[test]
+fn test_keyword_that_starts_a_longer_one_needs_a_terminator() {
+    let backend = MockBackend::default();
+    let short = static_snippet("te", "short");
+    let long = static_snippet("test", "long");
+    
+    // 区切り文字が不要な場合は、"test" を入力し終える前に "te" が展開される
+    let settings = Settings { snippets: vec![short.clone(), long.clone()], ..Default::default() };
+    let engine = engine_with_settings(settings, &backend);
+    assert_eq!(engine.find_replacement("te").unwrap().keyword, "te");
+    
+    // 区切り文字が必要な場合は、入力し終えたキーワードが展開される
+    let settings = Settings {
+        snippets: vec![short, long],
+        require_terminator: true,
+        ..Default::default()
+    };
+    let engine = engine_with_settings(settings, &backend);
+    assert!(engine.find_replacement("te").is_none());
+    assert_eq!(engine.find_replacement("test ").unwrap().text, "long");
+    assert_eq!(engine.find_replacement("te ").unwrap().text, "short");
+}
+
+#[test]
+fn test_keywords_of_equal_length_follow_list_order() {
+    let backend = MockBackend::default();
+    let mut first = static_snippet("sig", "first");
+    first.name = "First".to_string();
+    let mut second = static_snippet("sig", "second");
+    second.name = "Second".to_string();
+    
+    let settings = Settings {
+        snippets: vec![first, second],
+        ..Default::default()
+    };
+    let engine = engine_with_settings(settings, &backend);
+    
+    assert_eq!(engine.find_replacement("sig").unwrap().snippet_name, "First");
+}
+
+#[test]
+fn test_reset_modifier_keys_goes_through_backend() {
+    let backend = MockBackend::default();
+    let engine = engine_with(static_snippet("sig", "Best regards"), &backend);
+    
+    assert!(engine.reset_modifier_keys());
+    assert_eq!(backend.events(), vec![InputEvent::ResetModifiers]);
+}
+
+/// 入力方法を指定したスニペット
+fn snippet_with_delivery(keyword: &str, content: &str, delivery: Delivery) -> Snippet {
+    let mut snippet = static_snippet(keyword, content);
+    snippet.delivery = delivery;
+    snippet
+}
+
+#[test]
+fn test_type_delivery_types_long_text() {
+    let long_text = "a".repeat(80);
+    let backend = MockBackend::default();
+    let engine = engine_with(snippet_with_delivery("long", &long_text, Delivery::Type), &backend);
+    
+    assert!(expand(&engine, "long"));
+    assert_eq!(backend.events(), vec![
+        InputEvent::Backspaces(4),
+        InputEvent::Typed(long_text),
+    ]);
+}
+
+#[test]
+fn test_type_delivery_does_not_fall_back_to_paste() {
+    let backend = MockBackend { fail_type: true, ..Default::default() };
+    let engine = engine_with(snippet_with_delivery("sig", "Best regards", Delivery::Type), &backend);
+    
+    assert!(!expand(&engine, "sig"));
+    assert_eq!(backend.events(), vec![
+        InputEvent::Backspaces(3),
+        InputEvent::Typed("Best regards".to_string()),
+    ]);
+}
+
+#[test]
+fn test_paste_delivery_pastes_short_text() {
+    let backend = MockBackend::default();
+    let engine = engine_with(snippet_with_delivery("sig", "Best regards", Delivery::Paste), &backend);
+    
+    assert!(expand(&engine, "sig"));
+    assert_eq!(backend.events(), vec![
+        InputEvent::Backspaces(3),
+        InputEvent::Pasted("Best regards".to_string()),
+    ]);
+}
+
+#[test]
+fn test_accented_keyword_sends_one_backspace_per_character() {
+    let backend = MockBackend::default();
+    let engine = engine_with(static_snippet("café", "Café au lait"), &backend);
+    
+    assert!(expand(&engine, "un café"));
+    assert_eq!(backend.events()[0], InputEvent::Backspaces(4));
+    assert_eq!(engine.check_for_replacements("café").map(|(_, length)| length), Some(4));
+}
+
+#[test]
+fn test_typed_trigger_is_taken_by_characters_when_case_differs() {
+    let backend = MockBackend::default();
+    let engine = engine_with(static_snippet("straße", "Hauptstraße 1"), &backend);
+    
+    // "ẞ" は小文字の "ß" とバイト数が異なる
+    let replacement = engine.find_replacement("die STRAẞE").unwrap();
+    assert_eq!(replacement.trigger, "STRAẞE");
+    assert_eq!(replacement.keyword_length, 6);
+}
+
+#[test]
+fn test_keep_trigger_sends_no_backspaces() {
+    let backend = MockBackend::default();
+    let mut snippet = static_snippet("fn", "() {}");
+    snippet.keep_trigger = true;
+    let engine = engine_with(snippet, &backend);
+    
+    assert!(expand(&engine, "fn"));
+    assert_eq!(backend.events(), vec![InputEvent::Typed("() {}".to_string())]);
+    
+    // 取り消してもキーワードは入力し直さない
+    backend.events.lock().unwrap().clear();
+    assert!(engine.undo_last_expansion());
+    assert_eq!(backend.events(), vec![
+        InputEvent::ResetModifiers,
+        InputEvent::KeyPresses(0x08, 5),
+    ]);
+}
+
+#[test]
+fn test_expansion_over_max_length_is_blocked() {
+    let backend = MockBackend::default();
+    let settings = Settings {
+        snippets: vec![static_snippet("sig", "Best regards")],
+        max_expansion_len: 5,
+        ..Default::default()
+    };
+    let engine = engine_with_settings(settings, &backend);
+    
+    // キーワードを削除する前にやめるため、何も入力しない
+    assert!(!expand(&engine, "hello sig"));
+    assert!(backend.events().is_empty());
+}
+
+#[test]
+fn test_enter_key_terminates_keyword_by_default() {
+    let backend = MockBackend::default();
+    let settings = Settings {
+        snippets: vec![static_snippet("sig", "Best regards")],
+        require_terminator: true,
+        ..Default::default()
+    };
+    let mut state = KeyboardState::new(32);
+    state.set_newline_terminator(settings.newline_terminates_keyword());
+    let engine = engine_with_settings(settings, &backend);
+    
+    // デフォルトの区切り文字は空白と改行だけ
+    assert!(engine.find_replacement("hello sig.").is_none());
+    assert!(engine.find_replacement("hello sig/").is_none());
+    assert!(engine.find_replacement("hello sig\t").is_some());
+    
+    // "sig" と入力してエンター（VK_RETURN）を押すと、改行の前のキーワードが一致する
+    for vk in [0x53, 0x49, 0x47, 0x0D] {
+        state.process_key_event(0x0100, vk, Modifiers::default());
+    }
+    assert!(state.should_check_replacement());
+    let found = engine.find_replacement(&state.get_keyword_candidate()).expect("Enter should terminate the keyword");
+    assert_eq!(found.text, "Best regards");
+    
+    // 次の行を入力すると、前の行の文字は捨てられる
+    state.process_key_event(0x0100, 0x41, Modifiers::default());
+    assert_eq!(state.get_keyword_candidate(), "a");
+}
+
+#[test]
+fn test_custom_terminators() {
+    let backend = MockBackend::default();
+    let settings = Settings {
+        snippets: vec![static_snippet("abbr", "abbreviation")],
+        require_terminator: true,
+        terminator_chars: "./".to_string(),
+        ..Default::default()
+    };
+    let engine = engine_with_settings(settings, &backend);
+    
+    // 空白を含まない場合は、空白を入力しても展開しない
+    assert!(engine.find_replacement("abbr ").is_none());
+    assert!(engine.find_replacement("abbr-").is_none());
+    
+    let replacement = engine.find_replacement("path abbr/").unwrap();
+    assert_eq!(replacement.trigger, "abbr/");
+    assert_eq!(replacement.keyword_length, 5);
+    assert!(engine.find_replacement("abbr.").is_some());
+}
+
+#[test]
+fn test_escape_sequences_in_static_snippet() {
+    let mut snippet = static_snippet("tbl", r"Name\tPrice\nApple\t100");
+    snippet.process_escapes = true;
+    let backend = MockBackend::default();
+    let engine = engine_with(snippet, &backend);
+    
+    assert!(expand(&engine, "tbl"));
+    assert_eq!(backend.events(), vec![
+        InputEvent::Backspaces(3),
+        InputEvent::Typed("Name\tPrice\nApple\t100".to_string()),
+    ]);
+    
+    // 有効にしない場合はそのまま入力する
+    let backend = MockBackend::default();
+    let engine = engine_with(static_snippet("tbl", r"a\tb"), &backend);
+    
+    assert!(expand(&engine, "tbl"));
+    assert_eq!(backend.events(), vec![
+        InputEvent::Backspaces(3),
+        InputEvent::Typed(r"a\tb".to_string()),
+    ]);
+}
+
+#[test]
+fn test_snippet_pre_delay_overrides_timing_profile() {
+    // 指定しない場合は展開の速さの設定（Safe）の待機時間
+    let backend = MockBackend::default();
+    let engine = engine_with(static_snippet("sig", "Best regards"), &backend);
+    assert!(expand(&engine, "sig"));
+    assert_eq!(backend.waits().first(), Some(&Duration::from_millis(200)));
+    
+    let mut snippet = static_snippet("sig", "Best regards");
+    snippet.pre_delay_ms = Some(1234);
+    let backend = MockBackend::default();
+    let engine = engine_with(snippet, &backend);
+    assert!(expand(&engine, "sig"));
+    assert_eq!(backend.waits().first(), Some(&Duration::from_millis(1234)));
+    
+    // 上限を超える値は上限まで待機する
+    let mut snippet = static_snippet("sig", "Best regards");
+    snippet.pre_delay_ms = Some(60_000);
+    let backend = MockBackend::default();
+    let engine = engine_with(snippet, &backend);
+    assert!(expand(&engine, "sig"));
+    assert_eq!(backend.waits().first(), Some(&Duration::from_millis(MAX_PRE_DELAY_MS)));
+}
+
+#[test]
+fn test_trim_trailing_whitespace_before_paste() {
+    let mut snippet = static_snippet("addr", "1-2-3 Chiyoda\n  Tokyo \n");
+    snippet.delivery = Delivery::Paste;
+    
+    let settings = Settings {
+        snippets: vec![snippet.clone()],
+        enabled: true,
+        trim_trailing_whitespace: true,
+        ..Default::default()
+    };
+    let backend = MockBackend::default();
+    let engine = engine_with_settings(settings, &backend);
+    
+    // 末尾の改行と空白だけを取り除き、途中の改行や字下げは残す
+    assert!(expand(&engine, "addr"));
+    assert_eq!(backend.events(), vec![
+        InputEvent::Backspaces(4),
+        InputEvent::Pasted("1-2-3 Chiyoda\n  Tokyo".to_string()),
+    ]);
+    
+    // 有効にしない場合はそのまま貼り付ける
+    let backend = MockBackend::default();
+    let engine = engine_with(snippet, &backend);
+    assert!(expand(&engine, "addr"));
+    assert_eq!(backend.events(), vec![
+        InputEvent::Backspaces(4),
+        InputEvent::Pasted("1-2-3 Chiyoda\n  Tokyo \n".to_string()),
+    ]);
+}
+
+#[test]
+fn test_expand_keyword_without_typing_it() {
+    let settings = Settings {
+        snippets: vec![static_snippet("xsig", "Best regards")],
+        enabled: true,
+        require_terminator: true,
+        ..Default::default()
+    };
+    let backend = MockBackend::default();
+    let engine = engine_with_settings(settings, &backend);
+    
+    // キーワードは入力されていないため削除せず、区切り文字がなくても展開する
+    assert_eq!(engine.expand_keyword(" xsig\n"), Ok("Best regards".to_string()));
+    assert_eq!(backend.events(), vec![InputEvent::Typed("Best regards".to_string())]);
+    
+    // キーワードの一部だけの一致や、存在しないキーワードは展開しない
+    assert!(engine.expand_keyword("sig").is_err());
+    assert!(engine.expand_keyword("xxsig").is_err());
+    assert_eq!(backend.events().len(), 1);
+}
+
+#[test]
+fn test_expand_keyword_returns_the_text_it_types() {
+    let mut snippet = static_snippet("xid", "{uuid}");
+    snippet.snippet_type = SnippetType::Dynamic;
+    let backend = MockBackend::default();
+    let engine = engine_with(snippet, &backend);
+    
+    // 返すテキストと入力するテキストは、同じ1回の展開から作る
+    let text = engine.expand_keyword("xid").unwrap();
+    assert_eq!(backend.events(), vec![InputEvent::Typed(text)]);
+}
+
+#[test]
+fn test_expand_keyword_waits_for_abort_cooldown() {
+    let settings = Settings {
+        snippets: vec![static_snippet("xsig", "Best regards")],
+        abort_keyword: "xstop".to_string(),
+        ..Default::default()
+    };
+    let backend = MockBackend::default();
+    let engine = engine_with_settings(settings, &backend);
+    
+    assert!(engine.check_abort("xstop"));
+    assert!(engine.expand_keyword("xsig").is_err());
+    assert!(backend.events().iter().all(|event| !matches!(event, InputEvent::Typed(_))));
+}
+
+#[test]
+fn test_expand_keyword_respects_allowed_apps() {
+    let mut snippet = static_snippet("xsig", "Best regards");
+    snippet.allowed_apps = vec!["notepad.exe".to_string()];
+    
+    // 展開するアプリが前面にある場合は展開する
+    let backend = MockBackend { foreground_app: Some("Notepad.exe".to_string()), ..Default::default() };
+    let engine = engine_with(snippet.clone(), &backend);
+    assert_eq!(engine.expand_keyword("xsig"), Ok("Best regards".to_string()));
+    
+    let backend = MockBackend { foreground_app: Some("chrome.exe".to_string()), ..Default::default() };
+    let engine = engine_with(snippet, &backend);
+    assert!(engine.expand_keyword("xsig").is_err());
+    assert!(backend.events().is_empty());
+}
+
+#[test]
+fn test_worker_paste_is_not_recorded_in_clipboard_history() {
+    let watch = Arc::new(ClipboardWatch { history: ClipboardHistory::new(10), busy: Mutex::new(None) });
+    let backend = MockBackend { clipboard_watch: Some(Arc::clone(&watch)), ..Default::default() };
+    let snippet = snippet_with_delivery("reply", "Hi {field:Name}", Delivery::Paste);
+    let engine = Arc::new(Mutex::new(engine_with(snippet, &backend)));
+    ReplacementEngine::start_worker(&engine);
+    let busy = engine.lock().unwrap().worker_busy_flag().expect("worker should be running");
+    *watch.busy.lock().unwrap() = Some(Arc::clone(&busy));
+    assert!(watch.history.push("copied by the user"));
+    
+    // キーボードフックを経由しない入力フォームからの展開でも、一時的な貼り付けは履歴に残らない
+    let request = FieldFormRequest {
+        replacement: engine.lock().unwrap().find_replacement("reply").unwrap(),
+        target_window: 0,
+    };
+    let values: HashMap<String, String> = [("Name".to_string(), "Sato".to_string())].into();
+    assert!(engine.lock().unwrap().complete_field_form(&request, &values));
+    wait_for_worker(&busy);
+    
+    assert!(backend.events().contains(&InputEvent::Pasted("Hi Sato".to_string())));
+    assert_eq!(watch.history.get(1).as_deref(), Some("copied by the user"));
+    assert_eq!(watch.history.get(2), None);
+}
@@ -1,16 +1,17 @@
 use std::sync::{Arc, Mutex};
 use swifttype::config::Settings;
-use swifttype::config::settings::{Snippet, SnippetType};
-use swifttype::replacement::ReplacementEngine;
+use swifttype::config::settings::{Snippet, SnippetType, DEFAULT_DATE_FORMAT};
+use swifttype::keyboard::KeyboardState;
+use swifttype::replacement::{expand_snippet_text, match_snippet, ReplacementEngine};
 use swifttype::replacement::formatter::format_dynamic_content;
 
 #[test]
 fn test_format_dynamic_content() {
-    let result = format_dynamic_content("Today is {date:yyyy/MM/dd}");
+    let result = format_dynamic_content("Today is {date:yyyy/MM/dd}", "en-US", DEFAULT_DATE_FORMAT, None);
     assert!(result.starts_with("Today is "));
     assert!(result.contains("/"));
     
-    let result = format_dynamic_content("Plain text without format");
+    let result = format_dynamic_content("Plain text without format", "en-US", DEFAULT_DATE_FORMAT, None);
     assert_eq!(result, "Plain text without format");
 }
 
@@ -46,14 +47,289 @@ fn test_check_for_replacements() {
     // キーワードが含まれている場合
     let result = engine.check_for_replacements("This is a test1");
     assert!(result.is_some());
-    let (keyword, replacement) = result.unwrap();
-    assert_eq!(keyword, "test1");
+    let (replacement, keyword_length) = result.unwrap();
     assert_eq!(replacement, "Replacement 1");
+    assert_eq!(keyword_length, "test1".len());
     
     // 別のキーワードが含まれている場合
     let result = engine.check_for_replacements("Another test2");
     assert!(result.is_some());
-    let (keyword, replacement) = result.unwrap();
-    assert_eq!(keyword, "test2");
+    let (replacement, keyword_length) = result.unwrap();
     assert_eq!(replacement, "Replacement 2");
-} 
\ No newline at end of file
+    assert_eq!(keyword_length, "test2".len());
+}
+
+/// キーワードと内容の組からスニペットを登録した設定を作成する（保存時の置き換えを経由しない）
+/// 
+/// `configure` で設定や登録したスニペットを変更できる
+fn settings_with(snippets: &[(&str, &str)], configure: impl FnOnce(&mut Settings)) -> Settings {
+    let mut settings = Settings {
+        enabled: true,
+        snippets: snippets.iter()
+            .map(|(keyword, content)| Snippet::new(
+                keyword.to_string(),
+                keyword.to_string(),
+                content.to_string(),
+                SnippetType::Static,
+                "Test".to_string(),
+            ))
+            .collect(),
+        ..Default::default()
+    };
+    configure(&mut settings);
+    settings
+}
+
+/// キーワードと内容の組からスニペットを登録したエンジンを作成する
+fn engine_with(snippets: &[(&str, &str)], configure: impl FnOnce(&mut Settings)) -> ReplacementEngine {
+    ReplacementEngine::new(Arc::new(Mutex::new(settings_with(snippets, configure))))
+}
+
+#[test]
+fn test_uppercase_keyword_case_sensitive() {
+    let engine = engine_with(&[("USA", "United States of America")], |settings| settings.case_sensitive = true);
+    
+    // 大文字で入力した場合のみ展開される
+    let result = engine.check_for_replacements("the USA");
+    assert_eq!(result, Some(("United States of America".to_string(), 3)));
+    
+    assert!(engine.check_for_replacements("the usa").is_none());
+    assert!(engine.check_for_replacements("the Usa").is_none());
+}
+
+#[test]
+fn test_uppercase_keyword_case_insensitive() {
+    let engine = engine_with(&[("USA", "United States of America")], |settings| settings.case_sensitive = false);
+    
+    // 大文字と小文字を区別しない場合はどちらでも展開される
+    let result = engine.check_for_replacements("the USA");
+    assert_eq!(result, Some(("United States of America".to_string(), 3)));
+    
+    let result = engine.check_for_replacements("the usa");
+    assert_eq!(result, Some(("United States of America".to_string(), 3)));
+} 
+
+#[test]
+fn test_word_boundary_required_before_keyword() {
+    let engine = engine_with(&[("test1", "Replacement 1")], |settings| settings.snippets[0].word_boundary = true);
+    
+    // 単語の途中では展開しない
+    assert!(engine.check_for_replacements("contest1").is_none());
+    assert!(engine.check_for_replacements("my_test1").is_none());
+    
+    // 空白、記号、バッファの先頭、日本語の直後では展開する
+    assert!(engine.check_for_replacements(" test1").is_some());
+    assert!(engine.check_for_replacements("(test1").is_some());
+    assert!(engine.check_for_replacements("test1").is_some());
+    assert!(engine.check_for_replacements("日本語test1").is_some());
+}
+
+#[test]
+fn test_word_boundary_disabled() {
+    let engine = engine_with(&[("test1", "Replacement 1")], |settings| settings.snippets[0].word_boundary = false);
+    assert!(engine.check_for_replacements("contest1").is_some());
+}
+
+#[test]
+fn test_match_case_follows_typed_keyword() {
+    let engine = engine_with(&[("sig", "best regards, taro")], |settings| settings.snippets[0].match_case = true);
+    
+    let expand = |buffer: &str| engine.check_for_replacements(buffer).map(|(text, _)| text);
+    assert_eq!(expand("so sig"), Some("best regards, taro".to_string()));
+    assert_eq!(expand("so Sig"), Some("Best regards, taro".to_string()));
+    assert_eq!(expand("so SIG"), Some("BEST REGARDS, TARO".to_string()));
+}
+
+#[test]
+fn test_nested_snippets() {
+    let engine = engine_with(&[
+        ("sig", "Regards,\n{snippet:name}"),
+        ("name", "Taro ({snippet:title})"),
+        ("title", "Engineer"),
+    ], |_| {});
+    
+    let result = engine.check_for_replacements(" sig").map(|(text, _)| text);
+    assert_eq!(result, Some("Regards,\nTaro (Engineer)".to_string()));
+}
+
+#[test]
+fn test_nested_snippet_cycles_are_not_expanded() {
+    let engine = engine_with(&[
+        ("self", "x{snippet:self}"),
+        ("ping", "ping {snippet:pong}"),
+        ("pong", "pong {snippet:ping}"),
+        ("lost", "[{snippet:missing}]"),
+    ], |_| {});
+    
+    let expand = |buffer: &str| engine.check_for_replacements(buffer).map(|(text, _)| text);
+    assert_eq!(expand(" self"), Some("x{snippet:self}".to_string()));
+    assert_eq!(expand(" ping"), Some("ping pong {snippet:ping}".to_string()));
+    assert_eq!(expand(" lost"), Some("[{snippet:missing}]".to_string()));
+}
+
+#[test]
+fn test_nested_snippet_depth_is_limited() {
+    // n0 -> n1 -> ... -> n11 と深さの上限を超えて参照する
+    let contents: Vec<(String, String)> = (0..12)
+        .map(|i| (format!("n{}", i), format!("{}{{snippet:n{}}}", i, i + 1)))
+        .collect();
+    let snippets: Vec<(&str, &str)> = contents.iter().map(|(k, c)| (k.as_str(), c.as_str())).collect();
+    let engine = engine_with(&snippets, |_| {});
+    
+    let (text, _) = engine.check_for_replacements(" n0").unwrap();
+    assert_eq!(text, "0123456789{snippet:n10}");
+}
+
+/// キーボードフックと同じ手順で1文字ずつ入力し、展開された内容を返す
+fn type_text(state: &mut KeyboardState, engine: &ReplacementEngine, text: &str) -> Vec<String> {
+    let mut expansions = Vec::new();
+    for c in text.chars() {
+        state.add_char(c);
+        if state.should_check_replacement() {
+            if let Some((replacement, _)) = engine.check_for_replacements(&state.get_keyword_candidate()) {
+                state.keyword_replaced();
+                expansions.push(replacement);
+            }
+        }
+    }
+    expansions
+}
+
+#[test]
+fn test_back_to_back_keywords_expand_in_sequence() {
+    let engine = engine_with(&[
+        ("ddate", "DATE"),
+        ("ttime", "TIME"),
+        // 前のキーワードの残り（"e"）と次の入力（"tt"）が結合すると一致してしまうキーワード
+        ("ett", "TRAP"),
+    ], |_| {});
+    let mut state = KeyboardState::new(100);
+    
+    let expansions = type_text(&mut state, &engine, "ddatettime");
+    
+    assert_eq!(expansions, vec!["DATE".to_string(), "TIME".to_string()]);
+    assert_eq!(state.get_buffer(), "");
+    
+    // 展開直後は新しい文字が入力されるまで再チェックしない
+    assert!(!state.should_check_replacement());
+    state.add_char('x');
+    state.add_char('y');
+    assert!(state.should_check_replacement());
+}
+
+/// 区切り文字が必要な設定（空白に加えてピリオドも区切り文字にする）
+fn require_terminator(settings: &mut Settings) {
+    settings.require_terminator = true;
+    settings.terminator_chars = " \t\n.".to_string();
+}
+
+#[test]
+fn test_require_terminator_expands_after_terminator() {
+    let engine = engine_with(&[("test1", "Replacement 1")], require_terminator);
+    
+    // 区切り文字の分も削除する
+    let result = engine.check_for_replacements("This is a test1 ");
+    assert_eq!(result, Some(("Replacement 1".to_string(), "test1 ".len())));
+    
+    let result = engine.check_for_replacements("(test1.");
+    assert_eq!(result, Some(("Replacement 1".to_string(), "test1.".len())));
+    
+    let result = engine.check_for_replacements("test1\t");
+    assert_eq!(result, Some(("Replacement 1".to_string(), "test1\t".len())));
+}
+
+#[test]
+fn test_require_terminator_does_not_expand_without_terminator() {
+    let engine = engine_with(&[("test1", "Replacement 1")], require_terminator);
+    
+    assert!(engine.check_for_replacements("This is a test1").is_none());
+    // アンダースコアは単語の一部なので区切り文字にならない
+    assert!(engine.check_for_replacements("test1_").is_none());
+    // 区切り文字の前がキーワードでない場合
+    assert!(engine.check_for_replacements("test12 ").is_none());
+}
+
+#[test]
+fn test_typed_special_characters_match_saved_keyword() {
+    let engine = engine_with(&[("a_b", "expanded a_b")], |_| {});
+    
+    // 保存したキーワードでは特殊文字が `_` に置き換わっている
+    for typed in ["a=b", "a;b", "a,b", "a_b"] {
+        let result = engine.check_for_replacements(typed);
+        assert_eq!(result, Some(("expanded a_b".to_string(), 3)), "typed: {}", typed);
+    }
+}
+
+#[test]
+fn test_distinct_symbols_do_not_cross_match() {
+    let engine = engine_with(&[("a,b", "expanded a,b"), ("a-b", "expanded a-b")], |_| {});
+    
+    // 置き換えられていないキーワードに、別の記号で入力した文字列は一致しない
+    assert!(engine.check_for_replacements("a;b").is_none());
+    assert!(engine.check_for_replacements("a=b").is_none());
+    assert_eq!(engine.check_for_replacements("a-b"), Some(("expanded a-b".to_string(), 3)));
+}
+
+#[test]
+fn test_match_snippet_without_engine() {
+    let settings = settings_with(&[("sig", "Best regards"), ("addr", "1-2-3 Chiyoda")], |_| {});
+    
+    let result = match_snippet(&settings, "hello sig").unwrap();
+    assert_eq!(result.text, "Best regards");
+    assert_eq!(result.keyword, "sig");
+    assert_eq!(result.keyword_length, 3);
+    
+    assert!(match_snippet(&settings, "hello si").is_none());
+    assert!(match_snippet(&settings, "design").is_none());
+}
+
+#[test]
+fn test_match_snippet_respects_disabled_settings_and_apps() {
+    let mut settings = settings_with(&[("sig", "Best regards")], |_| {});
+    settings.enabled = false;
+    assert!(match_snippet(&settings, "sig").is_none());
+    
+    // 前面のアプリを取得しないため、アプリを限定したスニペットは一致しない
+    settings.enabled = true;
+    settings.snippets[0].allowed_apps = vec!["notepad.exe".to_string()];
+    assert!(match_snippet(&settings, "sig").is_none());
+}
+
+#[test]
+fn test_match_snippet_counts_terminator_in_keyword_length() {
+    let settings = settings_with(&[("おつ", "お疲れさまです")], |settings| settings.require_terminator = true);
+    
+    assert!(match_snippet(&settings, "おつ").is_none());
+    let result = match_snippet(&settings, "おつ ").unwrap();
+    assert_eq!(result.keyword, "おつ");
+    assert_eq!(result.keyword_length, 3);
+}
+
+#[test]
+fn test_doubled_braces_are_not_nested_snippets_cursor_or_fields() {
+    let settings = settings_with(&[
+        ("use", "use {{snippet:sig}} and {{cursor}}x"),
+        ("ask", "{{field:name}} {{date:yyyy}}"),
+        ("sig", "Best regards"),
+    ], |settings| {
+        settings.snippets[0].snippet_type = SnippetType::Dynamic;
+        settings.snippets[1].snippet_type = SnippetType::Dynamic;
+    });
+    
+    // 波括弧を2つ重ねたトークンは、入れ子のスニペットやカーソル位置として扱わずに文字どおり入力する
+    let result = match_snippet(&settings, "use").unwrap();
+    assert_eq!(result.text, "use {snippet:sig} and {cursor}x");
+    assert_eq!(expand_snippet_text(&settings.snippets[0], &settings), "use {snippet:sig} and {cursor}x");
+    
+    // 入力フィールドとしても扱わない
+    let result = match_snippet(&settings, "ask").unwrap();
+    assert_eq!(result.text, "{field:name} {date:yyyy}");
+}
+
+#[test]
+fn test_expand_snippet_text_resolves_nested_snippets() {
+    let settings = settings_with(&[("hi", "Hello, {snippet:nm}"), ("nm", "Taro")], |_| {});
+    
+    // キー入力はせず、ほかのアプリで展開した場合と同じテキストを返す
+    assert_eq!(expand_snippet_text(&settings.snippets[0], &settings), "Hello, Taro");
+}
\ No newline at end of file